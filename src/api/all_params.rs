@@ -1,3 +1,4 @@
+use crate::db::GeoBoundingBox;
 use std::error::Error;
 use serde_with::skip_serializing_none;
 use serde::{Serialize,Deserialize};
@@ -21,6 +22,10 @@ pub struct AllParameters {
     pub param_last_checkuuid: Option<String>,
     #[serde(rename = "iid")]
     pub param_last_clickuuid: Option<String>,
+    #[serde(rename = "lei")]
+    pub param_last_event_id: Option<String>,
+    #[serde(rename = "q")]
+    pub param_query: Option<String>,
     #[serde(rename = "na")]
     pub param_name: Option<String>,
     #[serde(rename = "nx")]
@@ -67,8 +72,21 @@ pub struct AllParameters {
     pub param_is_https: Option<bool>,
     #[serde(rename = "of")]
     pub param_offset: u32,
+    /// None if the client did not limit the list
     #[serde(rename = "li")]
-    pub param_limit: u32,
+    pub param_limit: Option<u32>,
+    #[serde(rename = "cu")]
+    pub param_cursor: Option<String>,
+    #[serde(rename = "ev")]
+    pub param_envelope: bool,
+    #[serde(rename = "ic")]
+    pub param_includecount: bool,
+    #[serde(rename = "fi")]
+    pub param_fields: Option<String>,
+    #[serde(rename = "va")]
+    pub param_variables: Option<String>,
+    #[serde(rename = "on")]
+    pub param_operation_name: Option<String>,
     #[serde(rename = "se")]
     pub param_seconds: u32,
     #[serde(rename = "up")]
@@ -77,10 +95,73 @@ pub struct AllParameters {
     pub param_geo_lat: Option<f64>,
     #[serde(rename = "go")]
     pub param_geo_long: Option<f64>,
+    #[serde(rename = "gd")]
+    pub param_geo_distance: Option<f64>,
+    #[serde(rename = "gai")]
+    pub param_geo_lat_min: Option<f64>,
+    #[serde(rename = "gaa")]
+    pub param_geo_lat_max: Option<f64>,
+    #[serde(rename = "goi")]
+    pub param_geo_long_min: Option<f64>,
+    #[serde(rename = "goa")]
+    pub param_geo_long_max: Option<f64>,
+    #[serde(rename = "fo")]
+    pub param_force: bool,
+    #[serde(rename = "sk")]
+    pub param_secret: Option<String>,
+    #[serde(rename = "wh")]
+    pub param_events: Option<String>,
+    #[serde(rename = "su")]
+    pub param_stationuuid: Option<String>,
 }
 
 impl AllParameters {
     pub fn to_string(&self) -> Result<String, Box<dyn Error>> {
         Ok(serde_json::to_string(&self)?)
     }
+
+    /// Limit for the database, lists without a limit are only capped
+    pub fn limit(&self) -> u32 {
+        self.param_limit.unwrap_or(999999)
+    }
+
+    /// Coordinates have to be on the earth and distances positive. A bounding box can cross the 180th meridian
+    /// with geo_long_min bigger than geo_long_max, but not the poles.
+    pub fn check_geo(&self) -> Result<(), String> {
+        let latitudes = [("geo_lat", self.param_geo_lat), ("geo_lat_min", self.param_geo_lat_min), ("geo_lat_max", self.param_geo_lat_max)];
+        let longitudes = [("geo_long", self.param_geo_long), ("geo_long_min", self.param_geo_long_min), ("geo_long_max", self.param_geo_long_max)];
+        for (name, value) in latitudes {
+            if let Some(value) = value {
+                if !(-90.0..=90.0).contains(&value) {
+                    return Err(format!("{} has to be between -90 and 90", name));
+                }
+            }
+        }
+        for (name, value) in longitudes {
+            if let Some(value) = value {
+                if !(-180.0..=180.0).contains(&value) {
+                    return Err(format!("{} has to be between -180 and 180", name));
+                }
+            }
+        }
+        if let (Some(south), Some(north)) = (self.param_geo_lat_min, self.param_geo_lat_max) {
+            if south > north {
+                return Err(String::from("geo_lat_min can not be bigger than geo_lat_max"));
+            }
+        }
+        if let Some(distance) = self.param_geo_distance {
+            if !distance.is_finite() || distance < 0.0 {
+                return Err(String::from("geo_distance has to be a positive number of meters"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Bounding box filter, only if all 4 edges are given
+    pub fn geo_bbox(&self) -> Option<GeoBoundingBox> {
+        match (self.param_geo_lat_min, self.param_geo_long_min, self.param_geo_lat_max, self.param_geo_long_max) {
+            (Some(south), Some(west), Some(north), Some(east)) => Some(GeoBoundingBox { south, west, north, east }),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,57 @@
+use crate::config::Config;
+
+/// Permissions of a request, decided by the token in the header "Authorization: Bearer <token>"
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiAuth {
+    Anonymous,
+    /// Named api key from the config, may edit stations
+    ApiKey(String),
+    /// Admin token from the config, may also delete stations and review new ones
+    Admin,
+}
+
+impl ApiAuth {
+    pub fn from_header(header: Option<&str>, config: &Config) -> ApiAuth {
+        let token = match header.and_then(|header| header.trim().strip_prefix("Bearer ")) {
+            Some(token) => token.trim(),
+            None => return ApiAuth::Anonymous,
+        };
+        if token.is_empty() {
+            return ApiAuth::Anonymous;
+        }
+        if let Some(ref admin_token) = config.admin_token {
+            if constant_time_eq(token, admin_token) {
+                return ApiAuth::Admin;
+            }
+        }
+        for api_key in config.api_keys.iter() {
+            if constant_time_eq(token, &api_key.key) {
+                return ApiAuth::ApiKey(api_key.name.clone());
+            }
+        }
+        ApiAuth::Anonymous
+    }
+
+    pub fn can_edit(&self) -> bool {
+        !matches!(self, ApiAuth::Anonymous)
+    }
+
+    /// Who did a change, for logs
+    pub fn name(&self) -> &str {
+        match self {
+            ApiAuth::Anonymous => "anonymous",
+            ApiAuth::ApiKey(name) => name,
+            ApiAuth::Admin => "admin",
+        }
+    }
+}
+
+/// Comparison that does not stop at the first difference, so the time does not leak how much of a token was right
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -1,11 +1,25 @@
+use std::error::Error;
 use std::fs::File;
+use std::io::Write;
+
+/// Writes a response body piece by piece, called in its own thread while the client reads
+pub type StreamProducer = Box<dyn FnOnce(&mut dyn Write) -> Result<(), Box<dyn Error>> + Send>;
 
 pub enum ApiResponse {
     Text(String),
+    /// Page of a list with the link to the next page and the count of all results, if they are known
+    Paged(String, Option<String>, Option<u64>),
+    /// Body that is written while it is sent, its entity tag is only known afterwards
+    Stream(StreamProducer),
+    /// Server-Sent Events, sent as they are written
+    Events(StreamProducer),
     File(String, File),
     ServerError(String),
     NotFound,
     UnknownContentType,
-    //ParameterError(String),
+    ParameterError(String),
     Locked(String),
+    Unauthorized(String),
+    Forbidden(String),
+    Unavailable(String),
 }
@@ -6,12 +6,12 @@ use std::time::{Duration, SystemTime};
 
 #[derive(Debug)]
 pub struct Item {
-    value: String,
+    value: Vec<u8>,
     expire: SystemTime,
 }
 
 impl Item {
-    pub fn new(value: String, expire: u16) -> Self {
+    pub fn new(value: Vec<u8>, expire: u16) -> Self {
         Item {
             value,
             expire: SystemTime::now() + Duration::new(expire.into(), 0),
@@ -32,7 +32,7 @@ impl BuiltinCache {
             cache: Arc::new(Mutex::new(HashMap::new())),
         }
     }
-    pub fn get(&self, key: &str) -> Option<String> {
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
         trace!("GET {}", key);
         let locked = self.cache.lock();
         match locked {
@@ -56,13 +56,13 @@ impl BuiltinCache {
             }
         }
     }
-    pub fn set(&mut self, key: &str, value: &str) {
+    pub fn set(&mut self, key: &str, value: &[u8]) {
         trace!("SET {}", key);
         let locked = self.cache.lock();
         match locked {
             Ok(mut locked) => {
                 locked.remove(key);
-                locked.insert(key.to_string(), Item::new(value.into(), self.ttl));
+                locked.insert(key.to_string(), Item::new(value.to_vec(), self.ttl));
             }
             Err(err) => {
                 error!("Unable to lock counter for set: {}", err);
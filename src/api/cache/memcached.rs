@@ -12,17 +12,17 @@ impl MemcachedCache {
     pub fn new(cache_url: String, ttl: u16) -> Self {
         MemcachedCache { cache_url, ttl }
     }
-    fn get_internal(&self, key: &str) -> Result<Option<String>, Box<dyn Error>> {
+    fn get_internal(&self, key: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
         let client = memcache::Client::connect(self.cache_url.clone())?;
         let result = client.get(key)?;
         Ok(result)
     }
-    fn set_internal(&mut self, key: &str, value: &str, expire: u16) -> Result<(), Box<dyn Error>> {
+    fn set_internal(&mut self, key: &str, value: &[u8], expire: u16) -> Result<(), Box<dyn Error>> {
         let client = memcache::Client::connect(self.cache_url.clone())?;
         client.set(key, value, expire.into())?;
         Ok(())
     }
-    pub fn get(&self, key: &str) -> Option<String> {
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
         trace!("GET {}", key);
         let result = self.get_internal(key);
         match result {
@@ -33,7 +33,7 @@ impl MemcachedCache {
             }
         }
     }
-    pub fn set(&mut self, key: &str, value: &str) {
+    pub fn set(&mut self, key: &str, value: &[u8]) {
         trace!("SET {} {}", key.len(), key);
         let result = self.set_internal(key, value, self.ttl);
         if let Err(err) = result {
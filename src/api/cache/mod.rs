@@ -2,9 +2,87 @@ mod builtin;
 mod memcached;
 mod redis;
 
+use super::conditional;
+use chrono::DateTime;
+use chrono::TimeZone;
+use chrono::Utc;
 use std::sync::Arc;
 use std::sync::Mutex;
 
+/// Cached response body with the time it was created, the body may be compressed
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheItem {
+    pub body: Vec<u8>,
+    pub modified: DateTime<Utc>,
+    /// Entity tag of the body, computed once when the item is created
+    pub etag: String,
+    /// Link to the next page of a paginated list
+    pub next: Option<String>,
+    /// Count of all results of a list, if it was requested with includecount
+    pub total_count: Option<u64>,
+}
+
+impl CacheItem {
+    pub fn new(body: Vec<u8>) -> Self {
+        CacheItem {
+            etag: conditional::etag(&body),
+            body,
+            modified: Utc::now(),
+            next: None,
+            total_count: None,
+        }
+    }
+
+    pub fn with_next(mut self, next: Option<String>) -> Self {
+        self.next = next;
+        self
+    }
+
+    pub fn with_total_count(mut self, total_count: Option<u64>) -> Self {
+        self.total_count = total_count;
+        self
+    }
+
+    /// Stored as "<unix timestamp> etag=<tag>[ next=<link>][ total=<count>]\n<body>" so every cache backend can keep it as a single value.
+    /// Links are urls and tags are hex digits, neither contains spaces.
+    fn encode(&self) -> Vec<u8> {
+        let mut header = self.modified.timestamp().to_string();
+        header.push_str(&format!(" etag={}", self.etag));
+        if let Some(ref next) = self.next {
+            header.push_str(&format!(" next={}", next));
+        }
+        if let Some(total_count) = self.total_count {
+            header.push_str(&format!(" total={}", total_count));
+        }
+        header.push('\n');
+        let mut value = header.into_bytes();
+        value.extend_from_slice(&self.body);
+        value
+    }
+
+    fn decode(mut value: Vec<u8>) -> Option<Self> {
+        let split = value.iter().position(|byte| *byte == b'\n')?;
+        let header = std::str::from_utf8(&value[..split]).ok()?;
+        let mut tokens = header.split(' ');
+        let modified = Utc.timestamp_opt(tokens.next()?.parse().ok()?, 0).single()?;
+        let mut etag = None;
+        let mut next = None;
+        let mut total_count = None;
+        for token in tokens {
+            match token.split_once('=') {
+                Some(("etag", tag)) => etag = Some(tag.to_string()),
+                Some(("next", link)) => next = Some(link.to_string()),
+                Some(("total", count)) => total_count = Some(count.parse().ok()?),
+                _ => return None,
+            }
+        }
+        let body = value.split_off(split + 1);
+        // items of older versions have no tag yet
+        let etag = etag.unwrap_or_else(|| conditional::etag(&body));
+        Some(CacheItem { body, modified, etag, next, total_count })
+    }
+}
+
 pub enum GenericCacheType {
     None,
     BuiltIn,
@@ -33,7 +111,8 @@ impl GenericCache {
             }
         }
     }
-    pub fn set(&mut self, key: &str, value: &str) {
+    pub fn set(&mut self, key: &str, item: &CacheItem) {
+        let value = &item.encode();
         match self {
             GenericCache::None => {}
             GenericCache::BuiltIn(builtin) => {
@@ -55,7 +134,10 @@ impl GenericCache {
             }
         };
     }
-    pub fn get(&self, key: &str) -> Option<String> {
+    pub fn get(&self, key: &str) -> Option<CacheItem> {
+        self.get_raw(key).and_then(CacheItem::decode)
+    }
+    fn get_raw(&self, key: &str) -> Option<Vec<u8>> {
         match self {
             GenericCache::None => None,
             GenericCache::BuiltIn(builtin) => {
@@ -83,6 +165,9 @@ impl GenericCache {
             }
         }
     }
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self, GenericCache::None)
+    }
     pub fn needs_cleanup(&self) -> bool {
         if let GenericCache::BuiltIn(_) = self {
             return true;
@@ -90,3 +175,29 @@ impl GenericCache {
         return false;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::CacheItem;
+
+    #[test]
+    fn encode_cache_item() {
+        let item = CacheItem::new(b"[{\"name\":\"a\nb\"}]".to_vec());
+        let decoded = CacheItem::decode(item.encode()).unwrap();
+        assert_eq!(decoded.body, item.body);
+        assert_eq!(decoded.modified.timestamp(), item.modified.timestamp());
+        assert_eq!(decoded.etag, item.etag);
+        assert_eq!(CacheItem::decode(b"no timestamp".to_vec()), None);
+        assert_eq!(CacheItem::decode(b"1700000000\n[]".to_vec()).unwrap().etag, CacheItem::new(b"[]".to_vec()).etag);
+
+        let item = CacheItem::new(b"[]".to_vec()).with_next(Some(String::from("/json/stations?cursor=6e")));
+        let decoded = CacheItem::decode(item.encode()).unwrap();
+        assert_eq!(decoded.next, item.next);
+        assert_eq!(decoded.total_count, None);
+
+        let item = CacheItem::new(b"[]".to_vec()).with_total_count(Some(120));
+        let decoded = CacheItem::decode(item.encode()).unwrap();
+        assert_eq!(decoded.next, None);
+        assert_eq!(decoded.total_count, Some(120));
+    }
+}
@@ -13,7 +13,7 @@ impl RedisCache {
     pub fn new(cache_url: String, ttl: u16) -> Self {
         RedisCache { cache_url, ttl }
     }
-    fn get_internal(&self, key: &str) -> Result<Option<String>, Box<dyn Error>> {
+    fn get_internal(&self, key: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
         let client = redis::Client::open(self.cache_url.clone())?;
         let mut con = client.get_connection()?;
         let result = con.get(key);
@@ -22,14 +22,14 @@ impl RedisCache {
             Err(_) => Ok(None),
         }
     }
-    fn set_internal(&mut self, key: &str, value: &str, expire: u16) -> Result<(), Box<dyn Error>> {
+    fn set_internal(&mut self, key: &str, value: &[u8], expire: u16) -> Result<(), Box<dyn Error>> {
         let client = redis::Client::open(self.cache_url.clone())?;
         let mut con = client.get_connection()?;
         let expire: usize = expire.into();
         con.set_ex(key, value, expire)?;
         Ok(())
     }
-    pub fn get(&self, key: &str) -> Option<String> {
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
         trace!("GET {}", key);
         let result = self.get_internal(key);
         match result {
@@ -40,7 +40,7 @@ impl RedisCache {
             }
         }
     }
-    pub fn set(&mut self, key: &str, value: &str) {
+    pub fn set(&mut self, key: &str, value: &[u8]) {
         trace!("SET {} {}", key.len(), key);
         let result = self.set_internal(key, value, self.ttl);
         if let Err(err) = result {
@@ -0,0 +1,142 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+/// Smaller responses are sent uncompressed, the overhead is not worth it
+pub const MIN_COMPRESS_SIZE: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Gzip,
+    Brotli,
+}
+
+impl ContentEncoding {
+    /// Value for the header Content-Encoding
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Brotli => "br",
+        }
+    }
+
+    /// Best encoding the client accepts by its header Accept-Encoding,
+    /// brotli is preferred over gzip if the client has no preference
+    pub fn from_accept_encoding(header: Option<&str>) -> Option<ContentEncoding> {
+        let mut best: Option<(ContentEncoding, f32)> = None;
+        for entry in header?.split(',') {
+            let mut parts = entry.split(';');
+            let name = parts.next().unwrap_or("").trim().to_lowercase();
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .next()
+                .map(|q| q.trim().parse::<f32>().unwrap_or(0.0))
+                .unwrap_or(1.0);
+            let encoding = match name.as_str() {
+                "br" | "*" => ContentEncoding::Brotli,
+                "gzip" | "x-gzip" => ContentEncoding::Gzip,
+                _ => continue,
+            };
+            if quality <= 0.0 {
+                continue;
+            }
+            let better = match best {
+                Some((best_encoding, best_quality)) => quality > best_quality || (quality == best_quality && encoding == ContentEncoding::Brotli && best_encoding != encoding),
+                None => true,
+            };
+            if better {
+                best = Some((encoding, quality));
+            }
+        }
+        best.map(|(encoding, _)| encoding)
+    }
+
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        let mut encoder = Encoder::new(Some(*self), Vec::new());
+        encoder.write_all(data)?;
+        encoder.finish()
+    }
+}
+
+/// Writer that compresses everything with the given encoding, or passes it through unchanged
+pub enum Encoder<W: Write> {
+    Identity(W),
+    Gzip(GzEncoder<W>),
+    Brotli(Box<brotli::CompressorWriter<W>>),
+}
+
+impl<W: Write> Encoder<W> {
+    pub fn new(encoding: Option<ContentEncoding>, writer: W) -> Self {
+        match encoding {
+            None => Encoder::Identity(writer),
+            Some(ContentEncoding::Gzip) => Encoder::Gzip(GzEncoder::new(writer, Compression::default())),
+            // quality 5 is a lot faster than the maximum of 11 and still better than gzip
+            Some(ContentEncoding::Brotli) => Encoder::Brotli(Box::new(brotli::CompressorWriter::new(writer, 4096, 5, 22))),
+        }
+    }
+
+    /// Writes the end of the compressed stream
+    pub fn finish(self) -> Result<W, std::io::Error> {
+        match self {
+            Encoder::Identity(writer) => Ok(writer),
+            Encoder::Gzip(encoder) => encoder.finish(),
+            Encoder::Brotli(mut encoder) => {
+                encoder.flush()?;
+                Ok(encoder.into_inner())
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Encoder::Identity(writer) => writer.write(buf),
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            Encoder::Brotli(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Encoder::Identity(writer) => writer.flush(),
+            Encoder::Gzip(encoder) => encoder.flush(),
+            Encoder::Brotli(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ContentEncoding;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn negotiate_encoding() {
+        assert_eq!(ContentEncoding::from_accept_encoding(None), None);
+        assert_eq!(ContentEncoding::from_accept_encoding(Some("identity")), None);
+        assert_eq!(ContentEncoding::from_accept_encoding(Some("gzip, deflate")), Some(ContentEncoding::Gzip));
+        assert_eq!(ContentEncoding::from_accept_encoding(Some("gzip, deflate, br")), Some(ContentEncoding::Brotli));
+        assert_eq!(ContentEncoding::from_accept_encoding(Some("br;q=0.5, gzip")), Some(ContentEncoding::Gzip));
+        assert_eq!(ContentEncoding::from_accept_encoding(Some("br;q=0, gzip;q=0")), None);
+        assert_eq!(ContentEncoding::from_accept_encoding(Some("*")), Some(ContentEncoding::Brotli));
+    }
+
+    #[test]
+    fn compress_roundtrip() {
+        let data = "radio ".repeat(1000).into_bytes();
+
+        let gzip = ContentEncoding::Gzip.compress(&data).unwrap();
+        assert!(gzip.len() < data.len());
+        let mut decoded = vec![];
+        GzDecoder::new(&gzip[..]).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, data);
+
+        let br = ContentEncoding::Brotli.compress(&data).unwrap();
+        assert!(br.len() < data.len());
+        let mut decoded = vec![];
+        brotli::Decompressor::new(&br[..], 4096).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, data);
+    }
+}
@@ -0,0 +1,84 @@
+use chrono::DateTime;
+use chrono::Utc;
+use sha2::Digest;
+use sha2::Sha256;
+
+/// Strong entity tag of a response body, the same body always gets the same tag on every server
+pub fn etag(body: &[u8]) -> String {
+    tag(&Sha256::digest(body))
+}
+
+/// Same tag as etag() for a body that is hashed piece by piece while it is written
+pub struct EtagHasher(Sha256);
+
+impl EtagHasher {
+    pub fn new() -> Self {
+        EtagHasher(Sha256::new())
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    pub fn finish(self) -> String {
+        tag(&self.0.finalize())
+    }
+}
+
+fn tag(hash: &[u8]) -> String {
+    let hex: String = hash.iter().take(16).map(|byte| format!("{:02x}", byte)).collect();
+    format!("\"{}\"", hex)
+}
+
+/// Date format of the headers Last-Modified and If-Modified-Since
+pub fn http_date(date: &DateTime<Utc>) -> String {
+    date.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Decides if the client already has the current response (RFC 7232).
+/// If-None-Match wins over If-Modified-Since if both are sent.
+pub fn is_not_modified(if_none_match: Option<&str>, if_modified_since: Option<&str>, etag: &str, modified: &DateTime<Utc>) -> bool {
+    if let Some(if_none_match) = if_none_match {
+        return if_none_match.split(',').map(|tag| tag.trim()).any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag);
+    }
+    if let Some(if_modified_since) = if_modified_since {
+        if let Ok(since) = DateTime::parse_from_rfc2822(if_modified_since) {
+            return modified.timestamp() <= since.timestamp();
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::etag;
+    use super::EtagHasher;
+    use super::http_date;
+    use super::is_not_modified;
+    use chrono::TimeZone;
+    use chrono::Utc;
+
+    #[test]
+    fn conditional_headers() {
+        let tag = etag(b"[]");
+        assert_eq!(tag, etag(b"[]"));
+        assert_ne!(tag, etag(b"[{}]"));
+        assert_eq!(tag.len(), 34);
+        let mut hasher = EtagHasher::new();
+        hasher.update(b"[");
+        hasher.update(b"]");
+        assert_eq!(hasher.finish(), tag);
+
+        let modified = Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+        assert_eq!(http_date(&modified), "Sun, 18 Oct 2026 12:00:00 GMT");
+
+        assert!(is_not_modified(Some(&tag), None, &tag, &modified));
+        assert!(is_not_modified(Some(&format!("\"other\", W/{}", tag)), None, &tag, &modified));
+        assert!(is_not_modified(Some("*"), None, &tag, &modified));
+        assert!(!is_not_modified(Some("\"other\""), Some("Sun, 18 Oct 2026 12:00:00 GMT"), &tag, &modified));
+        assert!(is_not_modified(None, Some("Sun, 18 Oct 2026 12:00:00 GMT"), &tag, &modified));
+        assert!(!is_not_modified(None, Some("Sun, 18 Oct 2026 11:59:59 GMT"), &tag, &modified));
+        assert!(!is_not_modified(None, Some("yesterday"), &tag, &modified));
+        assert!(!is_not_modified(None, None, &tag, &modified));
+    }
+}
@@ -0,0 +1,76 @@
+use crate::api::ApiResponse;
+use crate::db::models::WebhookItem;
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// Registered webhook, the secret is never sent back
+#[derive(PartialEq, Serialize, Deserialize, Debug)]
+pub struct ApiWebhook {
+    pub webhookuuid: String,
+    pub owner: String,
+    pub url: String,
+    pub events: String,
+    pub stationuuid: String,
+    pub tag: String,
+    pub countrycode: String,
+    pub creation_iso8601: Option<DateTime<Utc>>,
+}
+
+impl From<WebhookItem> for ApiWebhook {
+    fn from(item: WebhookItem) -> Self {
+        ApiWebhook {
+            webhookuuid: item.webhookuuid,
+            owner: item.owner,
+            url: item.url,
+            events: item.events,
+            stationuuid: item.stationuuid,
+            tag: item.tag,
+            countrycode: item.countrycode,
+            creation_iso8601: item.creation_iso8601,
+        }
+    }
+}
+
+impl ApiWebhook {
+    fn serialize_webhooks(webhooks: &[ApiWebhook]) -> std::io::Result<String> {
+        let mut xml = xml_writer::XmlWriter::new(Vec::new());
+        xml.begin_elem("result")?;
+        for webhook in webhooks {
+            xml.begin_elem("webhook")?;
+            xml.attr_esc("webhookuuid", &webhook.webhookuuid)?;
+            xml.attr_esc("owner", &webhook.owner)?;
+            xml.attr_esc("url", &webhook.url)?;
+            xml.attr_esc("events", &webhook.events)?;
+            xml.attr_esc("stationuuid", &webhook.stationuuid)?;
+            xml.attr_esc("tag", &webhook.tag)?;
+            xml.attr_esc("countrycode", &webhook.countrycode)?;
+            if let Some(creation_iso8601) = webhook.creation_iso8601 {
+                xml.attr_esc("creation_iso8601", &creation_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+            }
+            xml.end_elem()?;
+        }
+        xml.end_elem()?;
+        xml.close()?;
+        xml.flush()?;
+        Ok(String::from_utf8(xml.into_inner()).unwrap_or("encoding error".to_string()))
+    }
+
+    pub fn get_response(list: Vec<ApiWebhook>, format: &str) -> Result<ApiResponse, Box<dyn Error>> {
+        Ok(match format {
+            "json" => ApiResponse::Text(serde_json::to_string(&list)?),
+            "xml" => ApiResponse::Text(ApiWebhook::serialize_webhooks(&list)?),
+            _ => ApiResponse::UnknownContentType,
+        })
+    }
+
+    pub fn get_single_response(self, format: &str) -> Result<ApiResponse, Box<dyn Error>> {
+        Ok(match format {
+            "json" => ApiResponse::Text(serde_json::to_string(&self)?),
+            "xml" => ApiResponse::Text(ApiWebhook::serialize_webhooks(&[self])?),
+            _ => ApiResponse::UnknownContentType,
+        })
+    }
+}
@@ -1,30 +1,41 @@
 mod api_config;
 mod api_language;
 mod api_streaming_server;
+mod api_webhook;
 mod api_country;
 mod result_message;
 mod station_add_result;
 mod station_check_step;
+mod station_check_source;
 mod station_check;
 mod station_click;
 mod station_history;
+mod station_now_playing;
+mod station_pending;
+mod station_recheck;
 mod station;
 mod status;
 
 pub use self::api_config::ApiConfig as ApiConfig;
 pub use self::api_language::ApiLanguage as ApiLanguage;
 pub use self::api_streaming_server::ApiStreamingServer as ApiStreamingServer;
+pub use self::api_webhook::ApiWebhook;
 pub use self::api_country::ApiCountry as ApiCountry;
 pub use self::result_message::ResultMessage;
 pub use self::station_add_result::StationAddResult;
 pub use self::station_check_step::StationCheckStep;
+pub use self::station_check_source::StationCheckSource;
 pub use self::station_check::StationCheck;
 pub use self::station_check::StationCheckV0;
 pub use self::station_click::StationClick;
 pub use self::station_click::StationClickV0;
 pub use self::station_history::StationHistoryCurrent;
 pub use self::station_history::StationHistoryV0;
+pub use self::station_now_playing::StationNowPlaying;
+pub use self::station_pending::StationPending;
+pub use self::station_recheck::StationRecheck;
 pub use self::station::Station;
 pub use self::station::StationCachedInfo;
+pub use self::station::StationListWriter;
 pub use self::station::StationV0;
 pub use self::status::Status;
\ No newline at end of file
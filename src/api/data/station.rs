@@ -1,12 +1,15 @@
 use crate::api::api_response::ApiResponse;
 use crate::api::data::StationHistoryCurrent;
 use crate::db::models::DbStationItem;
+use crate::db::StationFields;
 use std::error::Error;
+use std::io::Write;
 use chrono::NaiveDateTime;
 use chrono::DateTime;
 use chrono::Utc;
 use chrono::SecondsFormat;
 use serde::{Serialize,Deserialize};
+use serde_json::json;
 
 #[derive(PartialEq, Eq, Serialize, Deserialize)]
 pub struct StationCachedInfo {
@@ -61,7 +64,7 @@ pub struct StationV0 {
     pub clicktrend: String,
 }
 
-#[derive(PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub struct Station {
     pub changeuuid: String,
     pub stationuuid: String,
@@ -99,6 +102,13 @@ pub struct Station {
     pub geo_lat: Option<f64>,
     pub geo_long: Option<f64>,
     pub has_extended_info: Option<bool>,
+    /// Title from the ICY metadata of the stream
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub now_playing: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distance: Option<f64>,
 }
 
 impl Station {
@@ -113,90 +123,18 @@ impl Station {
     }
 
     pub fn serialize_to_csv(entries: Vec<Station>) -> Result<String, Box<dyn Error>> {
-        let mut wtr = csv::Writer::from_writer(Vec::new());
-
+        let mut list_writer = StationListWriter::new("csv", Vec::new(), None)?.ok_or("format can not be written")?;
         for entry in entries {
-            wtr.serialize(entry)?;
+            list_writer.write(entry)?;
         }
-        
-        wtr.flush()?;
-        let x: Vec<u8> = wtr.into_inner()?;
-        Ok(String::from_utf8(x).unwrap_or("encoding error".to_string()))
+        Ok(String::from_utf8(list_writer.finish()?).unwrap_or("encoding error".to_string()))
     }
 
     pub fn serialize_station_list(entries: Vec<Station>) -> std::io::Result<String> {
         let mut xml = xml_writer::XmlWriter::new(Vec::new());
         xml.begin_elem("result")?;
         for entry in entries {
-            xml.begin_elem("station")?;
-            xml.attr_esc("changeuuid", &entry.changeuuid)?;
-            xml.attr_esc("stationuuid", &entry.stationuuid)?;
-            if let Some(serveruuid) = entry.serveruuid {
-                xml.attr_esc("serveruuid", &serveruuid)?;
-            }
-            xml.attr_esc("name", &entry.name)?;
-            xml.attr_esc("url", &entry.url)?;
-            xml.attr_esc("url_resolved", &entry.url_resolved)?;
-            xml.attr_esc("homepage", &entry.homepage)?;
-            xml.attr_esc("favicon", &entry.favicon)?;
-            xml.attr_esc("tags", &entry.tags)?;
-            xml.attr_esc("country", &entry.country)?;
-            xml.attr_esc("countrycode", &entry.countrycode)?;
-            if let Some(iso_3166_2) = entry.iso_3166_2 {
-                xml.attr_esc("iso_3166_2", &iso_3166_2)?;
-            }
-            xml.attr_esc("state", &entry.state)?;
-            xml.attr_esc("language", &entry.language)?;
-            if let Some(languagecodes) = entry.languagecodes {
-                xml.attr_esc("languagecodes", &languagecodes)?;
-            }
-            let station_votes_str = format!("{}", entry.votes);
-            xml.attr_esc("votes", &station_votes_str)?;
-            xml.attr_esc("lastchangetime", &entry.lastchangetime)?;
-            if let Some(lastchangetime_iso8601) = &entry.lastchangetime_iso8601 {
-                xml.attr_esc("lastchangetime_iso8601", &lastchangetime_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
-            }
-            xml.attr_esc("codec", &entry.codec)?;
-            let station_bitrate = format!("{}", entry.bitrate);
-            xml.attr_esc("bitrate", &station_bitrate)?;
-            let station_hls = format!("{}", entry.hls);
-            xml.attr_esc("hls", &station_hls)?;
-            let station_lastcheckok = format!("{}", entry.lastcheckok);
-            xml.attr_esc("lastcheckok", &station_lastcheckok)?;
-            xml.attr_esc("lastchecktime", &entry.lastchecktime)?;
-            if let Some(lastchecktime_iso8601) = &entry.lastchecktime_iso8601 {
-                xml.attr_esc("lastchecktime_iso8601", &lastchecktime_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
-            }
-            xml.attr_esc("lastcheckoktime", &entry.lastcheckoktime)?;
-            if let Some(lastcheckoktime_iso8601) = &entry.lastcheckoktime_iso8601 {
-                xml.attr_esc("lastcheckoktime_iso8601", &lastcheckoktime_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
-            }
-            xml.attr_esc("lastlocalchecktime", &entry.lastlocalchecktime)?;
-            if let Some(lastlocalchecktime_iso8601) = &entry.lastlocalchecktime_iso8601 {
-                xml.attr_esc("lastlocalchecktime_iso8601", &lastlocalchecktime_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
-            }
-            xml.attr_esc("clicktimestamp", &entry.clicktimestamp)?;
-            if let Some(clicktimestamp_iso8601) = &entry.clicktimestamp_iso8601 {
-                xml.attr_esc("clicktimestamp_iso8601", &clicktimestamp_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
-            }
-            let station_clickcount = format!("{}", entry.clickcount);
-            xml.attr_esc("clickcount", &station_clickcount)?;
-            let station_clicktrend = format!("{}", entry.clicktrend);
-            xml.attr_esc("clicktrend", &station_clicktrend)?;
-            if let Some(ssl_error) = entry.ssl_error {
-                let station_ssl_error = format!("{}", ssl_error);
-                xml.attr_esc("ssl_error", &station_ssl_error)?;
-            }
-            if let Some(geo_lat) = &entry.geo_lat {
-                xml.attr_esc("geo_lat", &geo_lat.to_string())?;
-            }
-            if let Some(geo_long) = &entry.geo_long {
-                xml.attr_esc("geo_long", &geo_long.to_string())?;
-            }
-            if let Some(has_extended_info) = &entry.has_extended_info {
-                xml.attr_esc("has_extended_info", &has_extended_info.to_string())?;
-            }
-            xml.end_elem()?;
+            Station::write_xml_station(&mut xml, entry)?;
         }
         xml.end_elem()?;
         xml.close()?;
@@ -204,26 +142,102 @@ impl Station {
         Ok(String::from_utf8(xml.into_inner()).unwrap_or("encoding error".to_string()))
     }
 
+    fn write_xml_station<W: Write>(xml: &mut xml_writer::XmlWriter<W>, entry: Station) -> std::io::Result<()> {
+        xml.begin_elem("station")?;
+        xml.attr_esc("changeuuid", &entry.changeuuid)?;
+        xml.attr_esc("stationuuid", &entry.stationuuid)?;
+        if let Some(serveruuid) = entry.serveruuid {
+            xml.attr_esc("serveruuid", &serveruuid)?;
+        }
+        xml.attr_esc("name", &entry.name)?;
+        xml.attr_esc("url", &entry.url)?;
+        xml.attr_esc("url_resolved", &entry.url_resolved)?;
+        xml.attr_esc("homepage", &entry.homepage)?;
+        xml.attr_esc("favicon", &entry.favicon)?;
+        xml.attr_esc("tags", &entry.tags)?;
+        xml.attr_esc("country", &entry.country)?;
+        xml.attr_esc("countrycode", &entry.countrycode)?;
+        if let Some(iso_3166_2) = entry.iso_3166_2 {
+            xml.attr_esc("iso_3166_2", &iso_3166_2)?;
+        }
+        xml.attr_esc("state", &entry.state)?;
+        xml.attr_esc("language", &entry.language)?;
+        if let Some(languagecodes) = entry.languagecodes {
+            xml.attr_esc("languagecodes", &languagecodes)?;
+        }
+        let station_votes_str = format!("{}", entry.votes);
+        xml.attr_esc("votes", &station_votes_str)?;
+        xml.attr_esc("lastchangetime", &entry.lastchangetime)?;
+        if let Some(lastchangetime_iso8601) = &entry.lastchangetime_iso8601 {
+            xml.attr_esc("lastchangetime_iso8601", &lastchangetime_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+        }
+        xml.attr_esc("codec", &entry.codec)?;
+        let station_bitrate = format!("{}", entry.bitrate);
+        xml.attr_esc("bitrate", &station_bitrate)?;
+        let station_hls = format!("{}", entry.hls);
+        xml.attr_esc("hls", &station_hls)?;
+        let station_lastcheckok = format!("{}", entry.lastcheckok);
+        xml.attr_esc("lastcheckok", &station_lastcheckok)?;
+        xml.attr_esc("lastchecktime", &entry.lastchecktime)?;
+        if let Some(lastchecktime_iso8601) = &entry.lastchecktime_iso8601 {
+            xml.attr_esc("lastchecktime_iso8601", &lastchecktime_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+        }
+        xml.attr_esc("lastcheckoktime", &entry.lastcheckoktime)?;
+        if let Some(lastcheckoktime_iso8601) = &entry.lastcheckoktime_iso8601 {
+            xml.attr_esc("lastcheckoktime_iso8601", &lastcheckoktime_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+        }
+        xml.attr_esc("lastlocalchecktime", &entry.lastlocalchecktime)?;
+        if let Some(lastlocalchecktime_iso8601) = &entry.lastlocalchecktime_iso8601 {
+            xml.attr_esc("lastlocalchecktime_iso8601", &lastlocalchecktime_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+        }
+        xml.attr_esc("clicktimestamp", &entry.clicktimestamp)?;
+        if let Some(clicktimestamp_iso8601) = &entry.clicktimestamp_iso8601 {
+            xml.attr_esc("clicktimestamp_iso8601", &clicktimestamp_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+        }
+        let station_clickcount = format!("{}", entry.clickcount);
+        xml.attr_esc("clickcount", &station_clickcount)?;
+        let station_clicktrend = format!("{}", entry.clicktrend);
+        xml.attr_esc("clicktrend", &station_clicktrend)?;
+        if let Some(ssl_error) = entry.ssl_error {
+            let station_ssl_error = format!("{}", ssl_error);
+            xml.attr_esc("ssl_error", &station_ssl_error)?;
+        }
+        if let Some(geo_lat) = &entry.geo_lat {
+            xml.attr_esc("geo_lat", &geo_lat.to_string())?;
+        }
+        if let Some(geo_long) = &entry.geo_long {
+            xml.attr_esc("geo_long", &geo_long.to_string())?;
+        }
+        if let Some(has_extended_info) = &entry.has_extended_info {
+            xml.attr_esc("has_extended_info", &has_extended_info.to_string())?;
+        }
+        if let Some(now_playing) = &entry.now_playing {
+            xml.attr_esc("now_playing", now_playing)?;
+        }
+        if let Some(score) = &entry.score {
+            xml.attr_esc("score", &score.to_string())?;
+        }
+        if let Some(distance) = &entry.distance {
+            xml.attr_esc("distance", &distance.to_string())?;
+        }
+        xml.end_elem()?;
+        Ok(())
+    }
+
     pub fn serialize_to_m3u(list: Vec<Station>, use_cached_url: bool) -> String {
         let mut j = String::with_capacity(200 * list.len());
         j.push_str("#EXTM3U\r\n");
         for item in list {
-            j.push_str("#RADIOBROWSERUUID:");
-            j.push_str(&item.stationuuid);
-            j.push_str("\r\n");
-            j.push_str("#EXTINF:1,");
-            j.push_str(&item.name);
-            j.push_str("\r\n");
-            if use_cached_url {
-                j.push_str(&item.url_resolved);
-            } else {
-                j.push_str(&item.url);
-            }
-            j.push_str("\r\n\r\n");
+            j.push_str(&Station::m3u_entry(&item, use_cached_url));
         }
         j
     }
 
+    fn m3u_entry(item: &Station, use_cached_url: bool) -> String {
+        let url = if use_cached_url { &item.url_resolved } else { &item.url };
+        format!("#RADIOBROWSERUUID:{}\r\n#EXTINF:1,{}\r\n{}\r\n\r\n", item.stationuuid, item.name, url)
+    }
+
     pub fn serialize_to_pls(list: Vec<Station>, use_cached_url: bool) -> String {
         let mut j = String::with_capacity(200 * list.len());
         j.push_str("[playlist]\r\n");
@@ -396,6 +410,107 @@ impl Station {
         j
     }
 
+    /// Station fields as flat key value pairs for geo formats
+    fn properties(&self) -> Result<serde_json::Map<String, serde_json::Value>, Box<dyn Error>> {
+        match serde_json::to_value(self)? {
+            serde_json::Value::Object(map) => Ok(map),
+            _ => Ok(serde_json::Map::new()),
+        }
+    }
+
+    /// Values of the requested fields in their order, null for fields the station does not have
+    fn field_values(&self, fields: &StationFields) -> Result<Vec<(&'static str, serde_json::Value)>, Box<dyn Error>> {
+        let mut properties = self.properties()?;
+        Ok(fields
+            .names()
+            .iter()
+            .map(|name| (*name, properties.remove(*name).unwrap_or(serde_json::Value::Null)))
+            .collect())
+    }
+
+    /// JSON object with only the requested fields
+    pub fn field_map(&self, fields: &StationFields) -> Result<serde_json::Map<String, serde_json::Value>, Box<dyn Error>> {
+        Ok(self.field_values(fields)?.into_iter().map(|(name, value)| (name.to_string(), value)).collect())
+    }
+
+    fn write_xml_station_fields<W: Write>(xml: &mut xml_writer::XmlWriter<W>, entry: Station, fields: &StationFields) -> Result<(), Box<dyn Error>> {
+        xml.begin_elem("station")?;
+        for (name, value) in entry.field_values(fields)? {
+            if let Some(value) = field_text(&value) {
+                xml.attr_esc(name, &value)?;
+            }
+        }
+        xml.end_elem()?;
+        Ok(())
+    }
+
+    /// GeoJSON FeatureCollection, stations without location are left out
+    /// DOCS from: https://datatracker.ietf.org/doc/html/rfc7946
+    pub fn serialize_to_geojson(list: Vec<Station>) -> Result<String, Box<dyn Error>> {
+        let mut features = Vec::with_capacity(list.len());
+        for entry in list {
+            if let (Some(geo_lat), Some(geo_long)) = (entry.geo_lat, entry.geo_long) {
+                features.push(json!({
+                    "type": "Feature",
+                    "id": entry.stationuuid,
+                    "geometry": {
+                        "type": "Point",
+                        "coordinates": [geo_long, geo_lat],
+                    },
+                    "properties": entry.properties()?,
+                }));
+            }
+        }
+        Ok(serde_json::to_string(&json!({
+            "type": "FeatureCollection",
+            "features": features,
+        }))?)
+    }
+
+    /// KML document with a placemark for every station with location
+    /// DOCS from: https://developers.google.com/kml/documentation/kmlreference
+    pub fn serialize_to_kml(list: Vec<Station>) -> Result<String, Box<dyn Error>> {
+        let mut xml = xml_writer::XmlWriter::new(Vec::new());
+        xml.dtd("UTF-8")?;
+        xml.begin_elem("kml")?;
+        xml.attr_esc("xmlns", "http://www.opengis.net/kml/2.2")?;
+        xml.begin_elem("Document")?;
+        for entry in list {
+            if let (Some(geo_lat), Some(geo_long)) = (entry.geo_lat, entry.geo_long) {
+                xml.begin_elem("Placemark")?;
+                xml.attr_esc("id", &entry.stationuuid)?;
+                xml.elem_text("name", &entry.name)?;
+                xml.elem_text("description", &entry.url)?;
+                xml.begin_elem("ExtendedData")?;
+                for (key, value) in entry.properties()? {
+                    let value = match field_text(&value) {
+                        Some(value) => value,
+                        None => continue,
+                    };
+                    xml.begin_elem("Data")?;
+                    xml.attr_esc("name", &key)?;
+                    xml.elem_text("value", &value)?;
+                    xml.end_elem()?;
+                }
+                xml.end_elem()?;
+                xml.begin_elem("Point")?;
+                xml.elem_text("coordinates", &format!("{},{}", geo_long, geo_lat))?;
+                xml.end_elem()?;
+                xml.end_elem()?;
+            }
+        }
+        xml.end_elem()?;
+        xml.end_elem()?;
+        xml.close()?;
+        xml.flush()?;
+        Ok(String::from_utf8(xml.into_inner()).unwrap_or("encoding error".to_string()))
+    }
+
+    /// Formats that StationListWriter can write
+    pub fn can_stream(format: &str) -> bool {
+        matches!(format, "json" | "csv" | "xml" | "m3u")
+    }
+
     pub fn get_response(list: Vec<Station>, format: &str) -> Result<ApiResponse, Box<dyn Error>> {
         Ok(match format {
             "csv" => ApiResponse::Text(Station::serialize_to_csv(list)?),
@@ -405,9 +520,117 @@ impl Station {
             "pls" => ApiResponse::Text(Station::serialize_to_pls(list, false)),
             "xspf" => ApiResponse::Text(Station::serialize_to_xspf(list)?),
             "ttl" => ApiResponse::Text(Station::serialize_to_ttl(list)),
+            "geojson" => ApiResponse::Text(Station::serialize_to_geojson(list)?),
+            "kml" => ApiResponse::Text(Station::serialize_to_kml(list)?),
             _ => ApiResponse::UnknownContentType,
         })
     }
+
+    /// Same as get_response(), but json, csv and xml only contain the requested fields
+    pub fn get_response_fields(list: Vec<Station>, format: &str, fields: Option<&StationFields>) -> Result<ApiResponse, Box<dyn Error>> {
+        let fields = match fields {
+            Some(fields) if matches!(format, "json" | "csv" | "xml") => fields,
+            _ => return Station::get_response(list, format),
+        };
+        let mut list_writer = StationListWriter::new(format, Vec::new(), Some(fields.clone()))?.ok_or("format can not be written")?;
+        for station in list {
+            list_writer.write(station)?;
+        }
+        Ok(ApiResponse::Text(String::from_utf8(list_writer.finish()?)?))
+    }
+}
+
+/// Text of a field value for csv and xml, None for null
+fn field_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(value) => Some(value.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+/// Writes a list of stations one by one, for responses that are streamed instead of built in memory
+pub struct StationListWriter<W: Write> {
+    output: StationListOutput<W>,
+    fields: Option<StationFields>,
+}
+
+enum StationListOutput<W: Write> {
+    Json(W, bool),
+    Csv(csv::Writer<W>, StationFields),
+    Xml(xml_writer::XmlWriter<'static, W>),
+    M3u(W),
+}
+
+impl<W: Write> StationListWriter<W> {
+    /// None for formats that can not be streamed.
+    /// With fields json, csv and xml only contain the requested fields of the stations.
+    pub fn new(format: &str, mut writer: W, fields: Option<StationFields>) -> Result<Option<Self>, Box<dyn Error>> {
+        let output = match format {
+            "json" => {
+                writer.write_all(b"[")?;
+                StationListOutput::Json(writer, true)
+            }
+            "csv" => {
+                // every row needs the same columns, stations leave out score, distance and now_playing if they have none
+                let csv_fields = fields.clone().unwrap_or_else(StationFields::all);
+                let mut csv_writer = csv::Writer::from_writer(writer);
+                csv_writer.write_record(csv_fields.names())?;
+                StationListOutput::Csv(csv_writer, csv_fields)
+            }
+            "xml" => {
+                let mut xml = xml_writer::XmlWriter::new(writer);
+                xml.begin_elem("result")?;
+                StationListOutput::Xml(xml)
+            }
+            "m3u" => {
+                writer.write_all(b"#EXTM3U\r\n")?;
+                StationListOutput::M3u(writer)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(StationListWriter { output, fields }))
+    }
+
+    pub fn write(&mut self, station: Station) -> Result<(), Box<dyn Error>> {
+        match (&mut self.output, &self.fields) {
+            (StationListOutput::Json(writer, first), fields) => {
+                if !*first {
+                    writer.write_all(b",")?;
+                }
+                *first = false;
+                match fields {
+                    Some(fields) => serde_json::to_writer(writer, &station.field_map(fields)?)?,
+                    None => serde_json::to_writer(writer, &station)?,
+                }
+            }
+            (StationListOutput::Csv(writer, fields), _) => {
+                let values = station.field_values(fields)?;
+                writer.write_record(values.iter().map(|(_, value)| field_text(value).unwrap_or_default()))?;
+            }
+            (StationListOutput::Xml(xml), Some(fields)) => Station::write_xml_station_fields(xml, station, fields)?,
+            (StationListOutput::Xml(xml), None) => Station::write_xml_station(xml, station)?,
+            (StationListOutput::M3u(writer), _) => writer.write_all(Station::m3u_entry(&station, false).as_bytes())?,
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<W, Box<dyn Error>> {
+        Ok(match self.output {
+            StationListOutput::Json(mut writer, _) => {
+                writer.write_all(b"]")?;
+                writer
+            }
+            StationListOutput::Csv(writer, _) => writer.into_inner().map_err(|err| err.into_error())?,
+            StationListOutput::Xml(mut xml) => {
+                xml.end_elem()?;
+                xml.close()?;
+                xml.flush()?;
+                xml.into_inner()
+            }
+            StationListOutput::M3u(writer) => writer,
+        })
+    }
 }
 
 impl From<&StationHistoryCurrent> for Station {
@@ -453,6 +676,9 @@ impl From<&StationHistoryCurrent> for Station {
             geo_lat: item.geo_lat,
             geo_long: item.geo_long,
             has_extended_info: None,
+            now_playing: None,
+            score: None,
+            distance: None,
         }
     }
 }
@@ -496,6 +722,9 @@ impl From<DbStationItem> for Station {
             geo_lat: item.geo_lat,
             geo_long: item.geo_long,
             has_extended_info: item.has_extended_info,
+            now_playing: item.now_playing,
+            score: item.score,
+            distance: item.distance,
         }
     }
 }
@@ -552,6 +781,9 @@ impl From<StationV0> for Station {
             geo_lat: None,
             geo_long: None,
             has_extended_info: None,
+            now_playing: None,
+            score: None,
+            distance: None,
         }
     }
 }
@@ -1,12 +1,24 @@
 use crate::api::api_response::ApiResponse;
+use crate::db::models::DuplicateReason;
+use crate::db::models::StationDuplicate;
 use std::error::Error;
 use serde::{Serialize,Deserialize};
 
+#[derive(Serialize, Deserialize)]
+pub struct StationAddDuplicate {
+    stationuuid: String,
+    name: String,
+    url: String,
+    reason: String,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct StationAddResult {
     ok: bool,
     message: String,
     uuid: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    duplicates: Vec<StationAddDuplicate>,
 }
 
 impl StationAddResult {
@@ -15,6 +27,48 @@ impl StationAddResult {
             ok: true,
             message: "added station successfully".to_string(),
             uuid: stationuuid,
+            duplicates: vec![],
+        }
+    }
+
+    pub fn new_pending(stationuuid: String) -> StationAddResult {
+        StationAddResult {
+            ok: true,
+            message: "added station to the review queue".to_string(),
+            uuid: stationuuid,
+            duplicates: vec![],
+        }
+    }
+
+    /// Stations with the same url are the same station, its uuid is returned.
+    /// A submission with the same url in the review queue can not be added twice either.
+    /// Similar names are only a hint that can be overridden with force.
+    pub fn new_duplicate(duplicates: Vec<StationDuplicate>) -> StationAddResult {
+        let existing = duplicates
+            .iter()
+            .find(|duplicate| duplicate.reason != DuplicateReason::Name)
+            .map(|duplicate| duplicate.stationuuid.clone());
+        let pending = duplicates.iter().all(|duplicate| duplicate.reason == DuplicateReason::Pending);
+        let message = if pending {
+            "station with this url waits in the review queue"
+        } else if existing.is_some() {
+            "station with this url already exists"
+        } else {
+            "possible duplicate, use force=true to add the station anyway"
+        };
+        StationAddResult {
+            ok: false,
+            message: message.to_string(),
+            uuid: existing.unwrap_or_default(),
+            duplicates: duplicates
+                .into_iter()
+                .map(|duplicate| StationAddDuplicate {
+                    stationuuid: duplicate.stationuuid,
+                    name: duplicate.name,
+                    url: duplicate.url,
+                    reason: duplicate.reason.as_str().to_string(),
+                })
+                .collect(),
         }
     }
 
@@ -23,6 +77,7 @@ impl StationAddResult {
             ok: false,
             message: err.to_string(),
             uuid: "".to_string(),
+            duplicates: vec![],
         }
     }
 
@@ -34,6 +89,14 @@ impl StationAddResult {
         xml.attr_esc("message", &self.ok.to_string())?;
         xml.attr_esc("uuid", &self.uuid)?;
         xml.end_elem()?;
+        for duplicate in self.duplicates.iter() {
+            xml.begin_elem("duplicate")?;
+            xml.attr_esc("stationuuid", &duplicate.stationuuid)?;
+            xml.attr_esc("name", &duplicate.name)?;
+            xml.attr_esc("url", &duplicate.url)?;
+            xml.attr_esc("reason", &duplicate.reason)?;
+            xml.end_elem()?;
+        }
         xml.end_elem()?;
         xml.close()?;
         xml.flush()?;
@@ -47,6 +110,13 @@ impl StationAddResult {
         }
     }
 
+    pub fn from_pending(result: Result<String, Box<dyn Error>>) -> StationAddResult {
+        match result {
+            Ok(res) => StationAddResult::new_pending(res),
+            Err(err) => StationAddResult::new_err(&err.to_string()),
+        }
+    }
+
     pub fn get_response(&self, format: &str) -> Result<ApiResponse, Box<dyn Error>> {
         Ok(match format {
             "json" => ApiResponse::Text(serde_json::to_string(&self)?),
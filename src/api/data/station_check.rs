@@ -6,6 +6,7 @@ use crate::api::api_response::ApiResponse;
 use crate::db::models::StationCheckItem;
 use std::convert::TryFrom;
 use std::error::Error;
+use std::io::Write;
 use serde::{Serialize,Deserialize};
 
 #[derive(PartialEq, Eq, Serialize, Deserialize)]
@@ -53,6 +54,10 @@ pub struct StationCheck {
     pub ssl_error: Option<u8>,
     pub geo_lat: Option<f64>,
     pub geo_long: Option<f64>,
+
+    pub audio_bitrate: Option<u32>,
+    pub audio_sampling: Option<u32>,
+    pub audio_liveness: Option<String>,
 }
 
 impl StationCheck {
@@ -87,6 +92,10 @@ impl StationCheck {
         ssl_error: u8,
         geo_lat: Option<f64>,
         geo_long: Option<f64>,
+
+        audio_bitrate: Option<u32>,
+        audio_sampling: Option<u32>,
+        audio_liveness: Option<String>,
     ) -> Self {
         StationCheck {
             stationuuid,
@@ -119,6 +128,10 @@ impl StationCheck {
             ssl_error: Some(ssl_error),
             geo_lat,
             geo_long,
+
+            audio_bitrate,
+            audio_sampling,
+            audio_liveness,
         }
     }
 
@@ -138,43 +151,7 @@ impl StationCheck {
         let mut xml = xml_writer::XmlWriter::new(Vec::new());
         xml.begin_elem("result")?;
         for entry in entries {
-            xml.begin_elem("check")?;
-            xml.attr_esc("stationuuid", &entry.stationuuid)?;
-            xml.attr_esc("checkuuid", &entry.checkuuid)?;
-            xml.attr_esc("source", &entry.source)?;
-            xml.attr_esc("codec", &entry.codec)?;
-            xml.attr_esc("bitrate", &entry.bitrate.to_string())?;
-            xml.attr_esc("hls", &entry.hls.to_string())?;
-            xml.attr_esc("ok", &entry.ok.to_string())?;
-            xml.attr_esc("urlcache", &entry.urlcache)?;
-            if let Some(timestamp_iso8601) = entry.timestamp_iso8601 {
-                xml.attr_esc("timestamp_iso8601", &timestamp_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
-            }
-            xml.attr_esc("timestamp", &entry.timestamp)?;
-
-            xml.attr_esc("metainfo_overrides_database", &entry.metainfo_overrides_database.unwrap_or(0).to_string())?;
-            xml.attr_esc("public", &entry.public.unwrap_or(0).to_string())?;
-            xml.attr_esc("name", &entry.name.unwrap_or_default())?;
-            xml.attr_esc("description", &entry.description.unwrap_or_default())?;
-            xml.attr_esc("tags", &entry.tags.unwrap_or_default())?;
-            xml.attr_esc("homepage", &entry.homepage.unwrap_or_default())?;
-            xml.attr_esc("loadbalancer", &entry.loadbalancer.unwrap_or_default())?;
-            xml.attr_esc("favicon", &entry.favicon.unwrap_or_default())?;
-            xml.attr_esc("countrycode", &entry.countrycode.unwrap_or_default())?;
-
-            xml.attr_esc("countrysubdivisioncode", &entry.countrysubdivisioncode.unwrap_or_default())?;
-            xml.attr_esc("server_software", &entry.server_software.unwrap_or_default())?;
-            xml.attr_esc("sampling", &entry.sampling.unwrap_or(0).to_string())?;
-            xml.attr_esc("timing_ms", &entry.timing_ms.unwrap_or(0).to_string())?;
-            xml.attr_esc("languagecodes", &entry.languagecodes.unwrap_or_default())?;
-            xml.attr_esc("ssl_error", &entry.ssl_error.unwrap_or(0).to_string())?;
-            if let Some(geo_lat) = &entry.geo_lat {
-                xml.attr_esc("geo_lat", &geo_lat.to_string())?;
-            }
-            if let Some(geo_long) = &entry.geo_long {
-                xml.attr_esc("geo_long", &geo_long.to_string())?;
-            }
-            xml.end_elem()?;
+            StationCheck::write_xml_check(&mut xml, entry)?;
         }
         xml.end_elem()?;
         xml.close()?;
@@ -182,6 +159,56 @@ impl StationCheck {
         Ok(String::from_utf8(xml.into_inner()).unwrap_or("encoding error".to_string()))
     }
 
+    pub fn write_xml_check<W: Write>(xml: &mut xml_writer::XmlWriter<W>, entry: StationCheck) -> std::io::Result<()> {
+        xml.begin_elem("check")?;
+        xml.attr_esc("stationuuid", &entry.stationuuid)?;
+        xml.attr_esc("checkuuid", &entry.checkuuid)?;
+        xml.attr_esc("source", &entry.source)?;
+        xml.attr_esc("codec", &entry.codec)?;
+        xml.attr_esc("bitrate", &entry.bitrate.to_string())?;
+        xml.attr_esc("hls", &entry.hls.to_string())?;
+        xml.attr_esc("ok", &entry.ok.to_string())?;
+        xml.attr_esc("urlcache", &entry.urlcache)?;
+        if let Some(timestamp_iso8601) = entry.timestamp_iso8601 {
+            xml.attr_esc("timestamp_iso8601", &timestamp_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+        }
+        xml.attr_esc("timestamp", &entry.timestamp)?;
+
+        xml.attr_esc("metainfo_overrides_database", &entry.metainfo_overrides_database.unwrap_or(0).to_string())?;
+        xml.attr_esc("public", &entry.public.unwrap_or(0).to_string())?;
+        xml.attr_esc("name", &entry.name.unwrap_or_default())?;
+        xml.attr_esc("description", &entry.description.unwrap_or_default())?;
+        xml.attr_esc("tags", &entry.tags.unwrap_or_default())?;
+        xml.attr_esc("homepage", &entry.homepage.unwrap_or_default())?;
+        xml.attr_esc("loadbalancer", &entry.loadbalancer.unwrap_or_default())?;
+        xml.attr_esc("favicon", &entry.favicon.unwrap_or_default())?;
+        xml.attr_esc("countrycode", &entry.countrycode.unwrap_or_default())?;
+
+        xml.attr_esc("countrysubdivisioncode", &entry.countrysubdivisioncode.unwrap_or_default())?;
+        xml.attr_esc("server_software", &entry.server_software.unwrap_or_default())?;
+        xml.attr_esc("sampling", &entry.sampling.unwrap_or(0).to_string())?;
+        xml.attr_esc("timing_ms", &entry.timing_ms.unwrap_or(0).to_string())?;
+        xml.attr_esc("languagecodes", &entry.languagecodes.unwrap_or_default())?;
+        xml.attr_esc("ssl_error", &entry.ssl_error.unwrap_or(0).to_string())?;
+        if let Some(geo_lat) = &entry.geo_lat {
+            xml.attr_esc("geo_lat", &geo_lat.to_string())?;
+        }
+        if let Some(geo_long) = &entry.geo_long {
+            xml.attr_esc("geo_long", &geo_long.to_string())?;
+        }
+        if let Some(audio_bitrate) = &entry.audio_bitrate {
+            xml.attr_esc("audio_bitrate", &audio_bitrate.to_string())?;
+        }
+        if let Some(audio_sampling) = &entry.audio_sampling {
+            xml.attr_esc("audio_sampling", &audio_sampling.to_string())?;
+        }
+        if let Some(audio_liveness) = &entry.audio_liveness {
+            xml.attr_esc("audio_liveness", audio_liveness)?;
+        }
+        xml.end_elem()?;
+        Ok(())
+    }
+
     pub fn get_response(list: Vec<StationCheck>, format: &str) -> Result<ApiResponse, Box<dyn Error>> {
         Ok(match format {
             "csv" => ApiResponse::Text(StationCheck::serialize_station_checks_csv(list)?),
@@ -231,6 +258,10 @@ impl TryFrom<StationCheckV0> for StationCheck {
             ssl_error: None,
             geo_lat: None,
             geo_long: None,
+
+            audio_bitrate: None,
+            audio_sampling: None,
+            audio_liveness: None,
         })
     }
 }
@@ -268,6 +299,10 @@ impl From<StationCheckItem> for StationCheck {
             if item.ssl_error { 1 } else { 0 },
             item.geo_lat,
             item.geo_long,
+
+            item.audio_bitrate,
+            item.audio_sampling,
+            item.audio_liveness,
         )
     }
 }
\ No newline at end of file
@@ -0,0 +1,96 @@
+use crate::api::api_response::ApiResponse;
+use crate::db::models::CheckConsensus;
+use crate::db::models::StationCheckItem;
+use chrono::DateTime;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::error::Error;
+
+/// Latest check of a station from one source, which shows whether the station is reachable from there
+#[derive(PartialEq, Eq, Serialize, Deserialize)]
+pub struct StationCheckSource {
+    pub stationuuid: String,
+    pub source: String,
+    pub checkuuid: String,
+    pub ok: u8,
+    pub ssl_error: u8,
+    pub timestamp_iso8601: Option<DateTime<Utc>>,
+    pub timestamp: String,
+    /// 1 if the check is part of the consensus that decides whether the station is working
+    pub consensus: u8,
+}
+
+impl StationCheckSource {
+    /// Sources ordered by their latest check, the most recent first
+    pub fn from_checks(mut checks: Vec<StationCheckItem>, consensus: &CheckConsensus) -> Vec<StationCheckSource> {
+        checks.sort_by_key(|check| Reverse(check.check_id));
+        let now = Utc::now();
+        let mut recent = 0;
+        checks
+            .into_iter()
+            .map(|item| {
+                // checks that are too old do not take a position among the sources
+                let position = recent;
+                let age = item.check_time_iso8601.map(|time| (now - time).num_seconds()).unwrap_or(i64::MAX);
+                let is_recent = consensus.is_recent(position, age);
+                if is_recent {
+                    recent += 1;
+                }
+                StationCheckSource {
+                    stationuuid: item.station_uuid,
+                    source: item.source,
+                    checkuuid: item.check_uuid,
+                    ok: if item.check_ok { 1 } else { 0 },
+                    ssl_error: if item.ssl_error { 1 } else { 0 },
+                    timestamp_iso8601: item.check_time_iso8601,
+                    timestamp: item.check_time,
+                    consensus: if is_recent && consensus.includes(position) { 1 } else { 0 },
+                }
+            })
+            .collect()
+    }
+
+    pub fn serialize_check_sources_csv(entries: Vec<StationCheckSource>) -> Result<String, Box<dyn Error>> {
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+
+        for entry in entries {
+            wtr.serialize(entry)?;
+        }
+        wtr.flush()?;
+        let x: Vec<u8> = wtr.into_inner()?;
+        Ok(String::from_utf8(x).unwrap_or("encoding error".to_string()))
+    }
+
+    pub fn serialize_check_sources(entries: Vec<StationCheckSource>) -> std::io::Result<String> {
+        let mut xml = xml_writer::XmlWriter::new(Vec::new());
+        xml.begin_elem("result")?;
+        for entry in entries {
+            xml.begin_elem("checksource")?;
+            xml.attr_esc("stationuuid", &entry.stationuuid)?;
+            xml.attr_esc("source", &entry.source)?;
+            xml.attr_esc("checkuuid", &entry.checkuuid)?;
+            xml.attr_esc("ok", &entry.ok.to_string())?;
+            xml.attr_esc("ssl_error", &entry.ssl_error.to_string())?;
+            if let Some(timestamp_iso8601) = entry.timestamp_iso8601 {
+                xml.attr_esc("timestamp_iso8601", &timestamp_iso8601.to_string())?;
+            }
+            xml.attr_esc("timestamp", &entry.timestamp)?;
+            xml.attr_esc("consensus", &entry.consensus.to_string())?;
+            xml.end_elem()?;
+        }
+        xml.end_elem()?;
+        xml.close()?;
+        xml.flush()?;
+        Ok(String::from_utf8(xml.into_inner()).unwrap_or("encoding error".to_string()))
+    }
+
+    pub fn get_response(list: Vec<StationCheckSource>, format: &str) -> Result<ApiResponse, Box<dyn Error>> {
+        Ok(match format {
+            "csv" => ApiResponse::Text(StationCheckSource::serialize_check_sources_csv(list)?),
+            "json" => ApiResponse::Text(serde_json::to_string(&list)?),
+            "xml" => ApiResponse::Text(StationCheckSource::serialize_check_sources(list)?),
+            _ => ApiResponse::UnknownContentType,
+        })
+    }
+}
@@ -4,6 +4,7 @@ use chrono::DateTime;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
+use std::io::Write;
 
 #[derive(PartialEq, Eq, Serialize, Deserialize)]
 pub struct StationCheckStep {
@@ -35,22 +36,7 @@ impl StationCheckStep {
         let mut xml = xml_writer::XmlWriter::new(Vec::new());
         xml.begin_elem("result")?;
         for entry in entries {
-            xml.begin_elem("checkstep")?;
-            xml.attr_esc("stepuuid", &entry.stepuuid)?;
-            if let Some(parent_stepuuid) = entry.parent_stepuuid {
-                xml.attr_esc("parent_stepuuid", &parent_stepuuid)?;
-            }
-            xml.attr_esc("checkuuid", &entry.checkuuid)?;
-            xml.attr_esc("stationuuid", &entry.stationuuid)?;
-            xml.attr_esc("url", &entry.url)?;
-            if let Some(urltype) = entry.urltype {
-                xml.attr_esc("urltype", &urltype)?;
-            }
-            if let Some(error) = entry.error {
-                xml.attr_esc("error", &error)?;
-            }
-            xml.attr_esc("creation_iso8601", &entry.creation_iso8601.to_string())?;
-            xml.end_elem()?;
+            StationCheckStep::write_xml_check_step(&mut xml, entry)?;
         }
         xml.end_elem()?;
         xml.close()?;
@@ -58,6 +44,26 @@ impl StationCheckStep {
         Ok(String::from_utf8(xml.into_inner()).unwrap_or("encoding error".to_string()))
     }
 
+    pub fn write_xml_check_step<W: Write>(xml: &mut xml_writer::XmlWriter<W>, entry: StationCheckStep) -> std::io::Result<()> {
+        xml.begin_elem("checkstep")?;
+        xml.attr_esc("stepuuid", &entry.stepuuid)?;
+        if let Some(parent_stepuuid) = entry.parent_stepuuid {
+            xml.attr_esc("parent_stepuuid", &parent_stepuuid)?;
+        }
+        xml.attr_esc("checkuuid", &entry.checkuuid)?;
+        xml.attr_esc("stationuuid", &entry.stationuuid)?;
+        xml.attr_esc("url", &entry.url)?;
+        if let Some(urltype) = entry.urltype {
+            xml.attr_esc("urltype", &urltype)?;
+        }
+        if let Some(error) = entry.error {
+            xml.attr_esc("error", &error)?;
+        }
+        xml.attr_esc("creation_iso8601", &entry.creation_iso8601.to_string())?;
+        xml.end_elem()?;
+        Ok(())
+    }
+
     pub fn get_response(
         list: Vec<StationCheckStep>,
         format: &str,
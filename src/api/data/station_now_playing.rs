@@ -0,0 +1,59 @@
+use crate::api::api_response::ApiResponse;
+use crate::db::models::NowPlayingItem;
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// Title a station played when it was last read from its ICY metadata
+#[derive(PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct StationNowPlaying {
+    pub stationuuid: String,
+    /// None if the stream has no metadata or was not read yet
+    pub title: Option<String>,
+    pub timestamp_iso8601: Option<DateTime<Utc>>,
+}
+
+impl StationNowPlaying {
+    pub fn new(stationuuid: &str, item: Option<NowPlayingItem>) -> Self {
+        match item {
+            Some(item) => StationNowPlaying {
+                stationuuid: item.stationuuid,
+                title: Some(item.title),
+                timestamp_iso8601: item.updatetime_iso8601,
+            },
+            None => StationNowPlaying {
+                stationuuid: stationuuid.to_string(),
+                title: None,
+                timestamp_iso8601: None,
+            },
+        }
+    }
+
+    fn serialize_now_playing(&self) -> std::io::Result<String> {
+        let mut xml = xml_writer::XmlWriter::new(Vec::new());
+        xml.begin_elem("result")?;
+        xml.begin_elem("nowplaying")?;
+        xml.attr_esc("stationuuid", &self.stationuuid)?;
+        if let Some(title) = &self.title {
+            xml.attr_esc("title", title)?;
+        }
+        if let Some(timestamp_iso8601) = self.timestamp_iso8601 {
+            xml.attr_esc("timestamp_iso8601", &timestamp_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+        }
+        xml.end_elem()?;
+        xml.end_elem()?;
+        xml.close()?;
+        xml.flush()?;
+        Ok(String::from_utf8(xml.into_inner()).unwrap_or("encoding error".to_string()))
+    }
+
+    pub fn get_response(self, format: &str) -> Result<ApiResponse, Box<dyn Error>> {
+        Ok(match format {
+            "json" => ApiResponse::Text(serde_json::to_string(&self)?),
+            "xml" => ApiResponse::Text(self.serialize_now_playing()?),
+            _ => ApiResponse::UnknownContentType,
+        })
+    }
+}
@@ -0,0 +1,90 @@
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use crate::api::api_response::ApiResponse;
+use crate::db::models::StationAddItem;
+use std::error::Error;
+use serde::{Serialize,Deserialize};
+
+/// Station in the review queue
+#[derive(PartialEq, Serialize, Deserialize)]
+pub struct StationPending {
+    pub stationuuid: String,
+    pub name: String,
+    pub url: String,
+    pub homepage: String,
+    pub favicon: String,
+    pub country: String,
+    pub countrycode: String,
+    pub state: String,
+    pub language: String,
+    pub languagecodes: String,
+    pub tags: String,
+    pub geo_lat: Option<f64>,
+    pub geo_long: Option<f64>,
+    pub creation_iso8601: Option<DateTime<Utc>>,
+}
+
+impl StationPending {
+    pub fn serialize_pending_stations(entries: Vec<StationPending>) -> std::io::Result<String> {
+        let mut xml = xml_writer::XmlWriter::new(Vec::new());
+        xml.begin_elem("result")?;
+        for entry in entries {
+            xml.begin_elem("station")?;
+            xml.attr_esc("stationuuid", &entry.stationuuid)?;
+            xml.attr_esc("name", &entry.name)?;
+            xml.attr_esc("url", &entry.url)?;
+            xml.attr_esc("homepage", &entry.homepage)?;
+            xml.attr_esc("favicon", &entry.favicon)?;
+            xml.attr_esc("country", &entry.country)?;
+            xml.attr_esc("countrycode", &entry.countrycode)?;
+            xml.attr_esc("state", &entry.state)?;
+            xml.attr_esc("language", &entry.language)?;
+            xml.attr_esc("languagecodes", &entry.languagecodes)?;
+            xml.attr_esc("tags", &entry.tags)?;
+            if let Some(geo_lat) = entry.geo_lat {
+                xml.attr_esc("geo_lat", &geo_lat.to_string())?;
+            }
+            if let Some(geo_long) = entry.geo_long {
+                xml.attr_esc("geo_long", &geo_long.to_string())?;
+            }
+            if let Some(creation_iso8601) = entry.creation_iso8601 {
+                xml.attr_esc("creation_iso8601", &creation_iso8601.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+            }
+            xml.end_elem()?;
+        }
+        xml.end_elem()?;
+        xml.close()?;
+        xml.flush()?;
+        Ok(String::from_utf8(xml.into_inner()).unwrap_or("encoding error".to_string()))
+    }
+
+    pub fn get_response(list: Vec<StationPending>, format: &str) -> Result<ApiResponse, Box<dyn Error>> {
+        Ok(match format {
+            "json" => ApiResponse::Text(serde_json::to_string(&list)?),
+            "xml" => ApiResponse::Text(StationPending::serialize_pending_stations(list)?),
+            _ => ApiResponse::UnknownContentType,
+        })
+    }
+}
+
+impl From<StationAddItem> for StationPending {
+    fn from(item: StationAddItem) -> Self {
+        StationPending {
+            stationuuid: item.stationuuid,
+            name: item.name,
+            url: item.url,
+            homepage: item.homepage,
+            favicon: item.favicon,
+            country: item.country,
+            countrycode: item.countrycode,
+            state: item.state,
+            language: item.language,
+            languagecodes: item.languagecodes,
+            tags: item.tags,
+            geo_lat: item.geo_lat,
+            geo_long: item.geo_long,
+            creation_iso8601: item.creation_iso8601,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use crate::api::api_response::ApiResponse;
+use crate::api::data::StationCheck;
+use crate::api::data::StationCheckStep;
+use serde::Serialize;
+use std::error::Error;
+
+/// Check of a station on request, with the steps that lead from the station url to the stream
+#[derive(Serialize)]
+pub struct StationRecheck {
+    pub check: StationCheck,
+    pub steps: Vec<StationCheckStep>,
+}
+
+impl StationRecheck {
+    fn serialize_recheck(self) -> std::io::Result<String> {
+        let mut xml = xml_writer::XmlWriter::new(Vec::new());
+        xml.begin_elem("result")?;
+        StationCheck::write_xml_check(&mut xml, self.check)?;
+        for step in self.steps {
+            StationCheckStep::write_xml_check_step(&mut xml, step)?;
+        }
+        xml.end_elem()?;
+        xml.close()?;
+        xml.flush()?;
+        Ok(String::from_utf8(xml.into_inner()).unwrap_or("encoding error".to_string()))
+    }
+
+    pub fn get_response(self, format: &str) -> Result<ApiResponse, Box<dyn Error>> {
+        Ok(match format {
+            "json" => ApiResponse::Text(serde_json::to_string(&self)?),
+            "xml" => ApiResponse::Text(self.serialize_recheck()?),
+            _ => ApiResponse::UnknownContentType,
+        })
+    }
+}
@@ -0,0 +1,196 @@
+use crate::api::api_response::StreamProducer;
+use crate::api::data::StationCheck;
+use crate::api::data::StationClick;
+use crate::api::data::StationHistoryCurrent;
+use crate::db::DbConnection;
+use serde::Serialize;
+use std::error::Error;
+use std::io::Write;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+/// Time between two looks into the database for new records
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Changes, checks and clicks that are read at once
+const BATCH_SIZE: u32 = 1000;
+/// Records of each kind that are sent again at most when a client continues at an older position
+const MAX_REPLAY: u32 = 5 * BATCH_SIZE;
+/// Milliseconds the browser waits before it reconnects
+const RETRY_MILLIS: u32 = 10000;
+
+/// Clients that are connected at the moment
+static CLIENTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Position in the changes, checks and clicks. It is the id of every event in the form
+/// "changeuuid,checkuuid,clickuuid", so Last-Event-ID continues after the last event
+/// the client has seen.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventPosition {
+    pub change: Option<String>,
+    pub check: Option<String>,
+    pub click: Option<String>,
+}
+
+impl EventPosition {
+    /// Kinds without a uuid, with an unknown one or with one that is more than MAX_REPLAY records
+    /// behind continue after the newest record, instead of sending the whole history again
+    pub fn clamp<A: DbConnection>(self, connection: &A) -> Result<EventPosition, Box<dyn Error>> {
+        let replayable = |count: usize| count < MAX_REPLAY as usize;
+        let change = match self.change {
+            Some(uuid) if replayable(connection.get_changes(None, Some(uuid.clone()), MAX_REPLAY)?.len()) => Some(uuid),
+            _ => connection.get_last_changeuuid()?,
+        };
+        let check = match self.check {
+            Some(uuid) if replayable(connection.get_checks(None, Some(uuid.clone()), 0, true, MAX_REPLAY)?.len()) => Some(uuid),
+            _ => connection.get_last_checkuuid()?,
+        };
+        let click = match self.click {
+            Some(uuid) if replayable(connection.get_clicks(None, Some(uuid.clone()), 0, MAX_REPLAY)?.len()) => Some(uuid),
+            _ => connection.get_last_clickuuid()?,
+        };
+        Ok(EventPosition { change, check, click })
+    }
+
+    pub fn parse(id: &str) -> Option<EventPosition> {
+        let parts: Vec<Option<String>> = id
+            .split(',')
+            .map(|part| part.trim().to_lowercase())
+            .map(|part| if part.is_empty() { None } else { Some(part) })
+            .collect();
+        match parts.as_slice() {
+            [change, check, click] => Some(EventPosition {
+                change: change.clone(),
+                check: check.clone(),
+                click: click.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    fn id(&self) -> String {
+        format!(
+            "{},{},{}",
+            self.change.as_deref().unwrap_or(""),
+            self.check.as_deref().unwrap_or(""),
+            self.click.as_deref().unwrap_or("")
+        )
+    }
+}
+
+/// Place of a connected client, given back when the stream ends
+struct ClientSlot;
+
+impl ClientSlot {
+    fn take(max_clients: usize) -> Option<ClientSlot> {
+        CLIENTS
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |clients| if clients < max_clients { Some(clients + 1) } else { None })
+            .ok()
+            .map(|_| ClientSlot)
+    }
+}
+
+impl Drop for ClientSlot {
+    fn drop(&mut self) {
+        CLIENTS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Stream of the records that are inserted after the position, None if too many clients are connected.
+/// Records show up like in the lists with lastchangeuuid, lastcheckuuid and lastclickuuid,
+/// which is about a minute after they were inserted.
+pub fn stream<A>(connection: A, position: EventPosition, max_clients: usize) -> Option<StreamProducer>
+where
+    A: DbConnection + Send + 'static,
+{
+    let slot = ClientSlot::take(max_clients)?;
+    Some(Box::new(move |writer| {
+        let _slot = slot;
+        match write_events(&connection, position, writer) {
+            Err(err) if is_disconnect(err.as_ref()) => {
+                debug!("Event client disconnected");
+                Ok(())
+            }
+            result => result,
+        }
+    }))
+}
+
+fn write_events<A: DbConnection>(connection: &A, position: EventPosition, writer: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    let mut position = position.clamp(connection)?;
+    write!(writer, "retry: {}\n\n", RETRY_MILLIS)?;
+    writer.flush()?;
+    loop {
+        let mut sent = 0;
+        for change in connection.get_changes(None, position.change.clone(), BATCH_SIZE)? {
+            position.change = Some(change.changeuuid.clone());
+            write_event(writer, "change", &position, &StationHistoryCurrent::from(change))?;
+            sent += 1;
+        }
+        for check in connection.get_checks(None, position.check.clone(), 0, true, BATCH_SIZE)? {
+            position.check = Some(check.check_uuid.clone());
+            write_event(writer, "check", &position, &StationCheck::from(check))?;
+            sent += 1;
+        }
+        for click in connection.get_clicks(None, position.click.clone(), 0, BATCH_SIZE)? {
+            position.click = Some(click.clickuuid.clone());
+            write_event(writer, "click", &position, &StationClick::from(click))?;
+            sent += 1;
+        }
+        if sent == 0 {
+            // a comment, writing it fails if the client is gone
+            writer.write_all(b":\n\n")?;
+        }
+        writer.flush()?;
+        if sent == 0 {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+fn write_event<T: Serialize>(writer: &mut dyn Write, event: &str, position: &EventPosition, data: &T) -> Result<(), Box<dyn Error>> {
+    write!(writer, "id: {}\nevent: {}\ndata: {}\n\n", position.id(), event, serde_json::to_string(data)?)?;
+    Ok(())
+}
+
+fn is_disconnect(err: &(dyn Error + 'static)) -> bool {
+    err.downcast_ref::<std::io::Error>()
+        .map(|err| err.kind() == std::io::ErrorKind::BrokenPipe)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EventPosition;
+    use crate::db::DbConnection;
+    use crate::test_utils::sample_db;
+
+    #[test]
+    fn event_ids() {
+        let position = EventPosition {
+            change: Some(String::from("c-1")),
+            check: None,
+            click: Some(String::from("k-1")),
+        };
+        assert_eq!(position.id(), "c-1,,k-1");
+        assert_eq!(EventPosition::parse(&position.id()), Some(position));
+        assert_eq!(EventPosition::parse(",,"), Some(EventPosition::default()));
+        assert_eq!(EventPosition::parse("c-1"), None);
+    }
+
+    #[test]
+    fn clamp_positions() {
+        let conn = sample_db();
+        conn.advance_clock(61);
+        let newest = EventPosition {
+            change: conn.get_last_changeuuid().unwrap(),
+            check: conn.get_last_checkuuid().unwrap(),
+            click: conn.get_last_clickuuid().unwrap(),
+        };
+        assert_eq!(EventPosition::parse(",,").unwrap().clamp(&conn).unwrap(), newest);
+        let position = EventPosition::parse("c-jazz,,").unwrap().clamp(&conn).unwrap();
+        assert_eq!(position.change.as_deref(), Some("c-jazz"));
+        assert_eq!(position.check, newest.check);
+    }
+}
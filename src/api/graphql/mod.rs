@@ -0,0 +1,326 @@
+/// Parser for the executable part of GraphQL: operations, fragments, variables and directives
+mod parser;
+/// Types of the schema and their resolvers on top of DbConnection
+mod schema;
+
+use self::parser::Directive;
+use self::parser::Document;
+use self::parser::Selection;
+use self::schema::FieldType;
+use self::schema::Query;
+use crate::db::DbConnection;
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
+use std::cell::Cell;
+
+/// Limits for queries, checked before anything is read from the database
+pub struct Limits {
+    /// Maximum nesting of fields
+    pub max_depth: u32,
+    /// Maximum number of fields in the result, lists count with their limit
+    pub max_complexity: u64,
+}
+
+/// Answer of the query as GraphQL response with "data" and "errors".
+/// Variables are a JSON object, the operation name is only needed if the document has more than one operation.
+pub fn execute<A: DbConnection>(connection: &A, query: &str, variables: Option<&str>, operation_name: Option<&str>, limits: &Limits) -> Value {
+    let queries = match prepare(query, variables, operation_name, limits) {
+        Ok(queries) => queries,
+        Err(error) => return json!({ "errors": [error] }),
+    };
+    let mut data = Map::new();
+    let mut errors = vec![];
+    for query in queries {
+        match schema::resolve_root(connection, &query) {
+            Ok(value) => {
+                data.insert(query.key, value);
+            }
+            Err(err) => {
+                errors.push(json!({ "message": err.to_string(), "path": [query.key] }));
+                data.insert(query.key, Value::Null);
+            }
+        }
+    }
+    if errors.is_empty() {
+        json!({ "data": data })
+    } else {
+        json!({ "data": data, "errors": errors })
+    }
+}
+
+fn error(message: String) -> Value {
+    json!({ "message": message })
+}
+
+/// Resolved fields of the operation, after the query passed all checks
+fn prepare(query: &str, variables: Option<&str>, operation_name: Option<&str>, limits: &Limits) -> Result<Vec<Query>, Value> {
+    let document = parser::parse(query).map_err(|err| {
+        json!({
+            "message": format!("syntax error: {}", err.message),
+            "locations": [{ "line": err.line, "column": err.column }],
+        })
+    })?;
+    let operation = match operation_name {
+        Some(name) => document.operations.iter().find(|operation| operation.name.as_deref() == Some(name)),
+        None if document.operations.len() == 1 => document.operations.first(),
+        None => return Err(error(String::from("operationName is required for documents with more than one operation"))),
+    }
+    .ok_or_else(|| error(String::from("unknown operation")))?;
+    if operation.kind != "query" {
+        return Err(error(format!("only queries are supported, not {}", operation.kind)));
+    }
+
+    let mut provided: Map<String, Value> = match variables.map(str::trim).filter(|variables| !variables.is_empty()) {
+        Some(variables) => match serde_json::from_str(variables) {
+            Ok(Value::Object(variables)) => variables,
+            Ok(Value::Null) => Map::new(),
+            _ => return Err(error(String::from("variables have to be a JSON object"))),
+        },
+        None => Map::new(),
+    };
+    let mut values = Map::new();
+    for definition in operation.variables.iter() {
+        let value = match (provided.remove(&definition.name), &definition.default) {
+            (Some(value), _) => value,
+            (None, Some(default)) => input_value(default, &Map::new()),
+            (None, None) if definition.required => return Err(error(format!("variable ${} is required", definition.name))),
+            (None, None) => Value::Null,
+        };
+        values.insert(definition.name.clone(), value);
+    }
+
+    let resolver = Resolver { document: &document, variables: &values, fields_left: Cell::new(limits.max_complexity) };
+    let mut fragments = vec![];
+    let queries = resolver.fields("Query", &operation.selection, &mut fragments).map_err(error)?;
+    let depth = depth(&queries);
+    if depth > limits.max_depth {
+        return Err(error(format!("query depth {} is more than the maximum of {}", depth, limits.max_depth)));
+    }
+    let complexity = complexity("Query", &queries);
+    if complexity > limits.max_complexity {
+        return Err(error(format!("query complexity {} is more than the maximum of {}", complexity, limits.max_complexity)));
+    }
+    Ok(queries)
+}
+
+/// Replaces variables with their values
+fn input_value(value: &parser::Value, variables: &Map<String, Value>) -> Value {
+    match value {
+        parser::Value::Null => Value::Null,
+        parser::Value::Int(value) => json!(value),
+        parser::Value::Float(value) => json!(value),
+        parser::Value::String(value) | parser::Value::Enum(value) => Value::String(value.clone()),
+        parser::Value::Boolean(value) => Value::Bool(*value),
+        parser::Value::List(list) => Value::Array(list.iter().map(|item| input_value(item, variables)).collect()),
+        parser::Value::Object(fields) => Value::Object(fields.iter().map(|(name, value)| (name.clone(), input_value(value, variables))).collect()),
+        parser::Value::Variable(name) => variables.get(name).cloned().unwrap_or(Value::Null),
+    }
+}
+
+/// Checks the selections against the schema and flattens fragments and directives
+struct Resolver<'a> {
+    document: &'a Document,
+    variables: &'a Map<String, Value>,
+    /// Fragments that spread other fragments more than once grow exponentially,
+    /// every field counts for the complexity, so there can not be more of them
+    fields_left: Cell<u64>,
+}
+
+impl<'a> Resolver<'a> {
+    fn variable(&self, value: &parser::Value) -> Result<Value, String> {
+        if let parser::Value::Variable(name) = value {
+            if !self.variables.contains_key(name) {
+                return Err(format!("variable ${} is not defined", name));
+            }
+        }
+        match value {
+            parser::Value::List(list) => list.iter().try_for_each(|item| self.variable(item).map(|_| ()))?,
+            parser::Value::Object(fields) => fields.iter().try_for_each(|(_, item)| self.variable(item).map(|_| ()))?,
+            _ => {}
+        }
+        Ok(input_value(value, self.variables))
+    }
+
+    /// False if @skip or @include leave the selection out
+    fn included(&self, directives: &[Directive]) -> Result<bool, String> {
+        for directive in directives {
+            let condition = directive
+                .arguments
+                .iter()
+                .find(|(name, _)| name == "if")
+                .map(|(_, value)| self.variable(value))
+                .transpose()?;
+            let condition = match condition {
+                Some(Value::Bool(condition)) => condition,
+                _ => return Err(format!("directive @{} needs the boolean argument 'if'", directive.name)),
+            };
+            match directive.name.as_str() {
+                "include" if !condition => return Ok(false),
+                "skip" if condition => return Ok(false),
+                "include" | "skip" => {}
+                name => return Err(format!("unknown directive @{}", name)),
+            }
+        }
+        Ok(true)
+    }
+
+    fn fields(&self, type_name: &str, selection: &[Selection], fragments: &mut Vec<String>) -> Result<Vec<Query>, String> {
+        let mut queries: Vec<Query> = vec![];
+        for item in selection {
+            let found = match item {
+                Selection::Field(field) => {
+                    if !self.included(&field.directives)? {
+                        continue;
+                    }
+                    vec![self.field(type_name, field, fragments)?]
+                }
+                Selection::FragmentSpread(name, directives) => {
+                    if !self.included(directives)? {
+                        continue;
+                    }
+                    let fragment = self
+                        .document
+                        .fragments
+                        .iter()
+                        .find(|fragment| &fragment.name == name)
+                        .ok_or_else(|| format!("unknown fragment '{}'", name))?;
+                    if fragment.type_condition != type_name {
+                        return Err(format!("fragment '{}' on type '{}' can not be used on type '{}'", name, fragment.type_condition, type_name));
+                    }
+                    if fragments.contains(name) {
+                        return Err(format!("fragment '{}' contains itself", name));
+                    }
+                    fragments.push(name.clone());
+                    let found = self.fields(type_name, &fragment.selection, fragments)?;
+                    fragments.pop();
+                    found
+                }
+                Selection::InlineFragment(type_condition, directives, selection) => {
+                    if !self.included(directives)? {
+                        continue;
+                    }
+                    if let Some(type_condition) = type_condition {
+                        if type_condition != type_name {
+                            return Err(format!("inline fragment on type '{}' can not be used on type '{}'", type_condition, type_name));
+                        }
+                    }
+                    self.fields(type_name, selection, fragments)?
+                }
+            };
+            // the same field can be selected more than once, e.g. by fragments
+            for query in found {
+                match queries.iter_mut().find(|existing| existing.key == query.key) {
+                    Some(existing) if existing.name != query.name || existing.arguments != query.arguments => {
+                        return Err(format!("'{}' is selected with different fields or arguments", query.key));
+                    }
+                    Some(existing) => {
+                        for child in query.selection {
+                            if !existing.selection.iter().any(|selected| selected.key == child.key) {
+                                existing.selection.push(child);
+                            }
+                        }
+                    }
+                    None => queries.push(query),
+                }
+            }
+        }
+        Ok(queries)
+    }
+
+    fn field(&self, type_name: &str, field: &parser::Field, fragments: &mut Vec<String>) -> Result<Query, String> {
+        match self.fields_left.get().checked_sub(1) {
+            Some(left) => self.fields_left.set(left),
+            None => return Err(String::from("query has too many fields after expanding its fragments")),
+        }
+        let key = field.alias.clone().unwrap_or_else(|| field.name.clone());
+        if field.name == "__typename" {
+            return Ok(Query { key, name: field.name.clone(), arguments: Map::new(), selection: vec![] });
+        }
+        let definition = schema::field(type_name, &field.name).ok_or_else(|| format!("unknown field '{}' on type '{}'", field.name, type_name))?;
+        let mut arguments = Map::new();
+        for (name, value) in field.arguments.iter() {
+            if !definition.arguments.contains(&name.as_str()) {
+                return Err(format!("unknown argument '{}' of field '{}'", name, field.name));
+            }
+            arguments.insert(name.clone(), self.variable(value)?);
+        }
+        let selection = match (&definition.field_type, field.selection.is_empty()) {
+            (FieldType::Scalar, true) => vec![],
+            (FieldType::Scalar, false) => return Err(format!("field '{}' of type '{}' has no fields to select", field.name, type_name)),
+            (FieldType::Object(_) | FieldType::List(_), true) => return Err(format!("field '{}' of type '{}' needs a selection of fields", field.name, type_name)),
+            (FieldType::Object(item_type) | FieldType::List(item_type), false) => self.fields(item_type, &field.selection, fragments)?,
+        };
+        Ok(Query { key, name: field.name.clone(), arguments, selection })
+    }
+}
+
+fn depth(queries: &[Query]) -> u32 {
+    queries.iter().map(|query| 1 + depth(&query.selection)).max().unwrap_or(0)
+}
+
+/// Every field counts once, the fields below lists as often as the list can have items
+fn complexity(type_name: &str, queries: &[Query]) -> u64 {
+    queries
+        .iter()
+        .map(|query| match schema::field(type_name, &query.name) {
+            Some(definition) => match definition.field_type {
+                FieldType::Scalar => 1,
+                FieldType::Object(item_type) => 1 + complexity(item_type, &query.selection),
+                FieldType::List(item_type) => {
+                    // an invalid limit fails when the list is read
+                    let limit = query.limit(definition.default_limit).unwrap_or(definition.default_limit);
+                    1u64.saturating_add(u64::from(limit).saturating_mul(complexity(item_type, &query.selection)))
+                }
+            },
+            None => 1,
+        })
+        .fold(0, u64::saturating_add)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::prepare;
+    use super::Limits;
+    use serde_json::Value;
+
+    const LIMITS: Limits = Limits { max_depth: 5, max_complexity: 1000 };
+
+    fn error(query: &str, variables: Option<&str>) -> String {
+        match prepare(query, variables, None, &LIMITS) {
+            Ok(_) => String::from("no error"),
+            Err(error) => error["message"].as_str().unwrap_or("").to_string(),
+        }
+    }
+
+    #[test]
+    fn prepare_queries() {
+        let query = "query($uuid: String!, $steps: Boolean = true) {
+            page: station(uuid: $uuid) { name ...parts checksteps @include(if: $steps) { url children { url } } }
+        }
+        fragment parts on Station { name checks(limit: 3) { ok } }";
+        let queries = prepare(query, Some(r#"{"uuid": "abc"}"#), None, &LIMITS).unwrap();
+        assert_eq!(queries[0].key, "page");
+        assert_eq!(queries[0].arguments["uuid"], Value::String(String::from("abc")));
+        let names: Vec<&str> = queries[0].selection.iter().map(|query| query.key.as_str()).collect();
+        assert_eq!(names, vec!["name", "checks", "checksteps"]);
+
+        assert!(error(query, None).contains("$uuid"));
+        assert!(error("{ station(uuid: \"a\") { password } }", None).contains("password"));
+        assert!(error("{ station(id: \"a\") { name } }", None).contains("'id'"));
+        assert!(error("{ station(uuid: \"a\") }", None).contains("selection"));
+        assert!(error("{ stations { name(x: 1) } }", None).contains("'x'"));
+        assert!(error("{ stations { ...a } } fragment a on Station { ...a }", None).contains("itself"));
+        assert!(error("mutation { stations { name } }", None).contains("mutation"));
+        assert!(error("{ stations { name } } { tags { name } }", None).contains("operationName"));
+        assert!(error("{ stations { name @skip } }", None).contains("@skip"));
+        assert_eq!(error("{ stations(limit: 100) { name votes checks(limit: 4) { ok } } }", None), "no error");
+        assert!(error("{ stations(limit: 100) { name checks(limit: 10) { ok } } }", None).contains("complexity"));
+        assert!(error("{ stations { checksteps { children { children { children { children { url } } } } } } }", None).contains("depth"));
+        assert!(error("{ stations(uuids: [\"a\"], limit: 1000) { checksteps(limit: 2) { url } } }", None).contains("complexity"));
+        let bomb = "{ tags { ...a } } fragment a on Tag { ...b ...b } fragment b on Tag { ...c ...c } fragment c on Tag { ...d ...d }
+            fragment d on Tag { ...e ...e } fragment e on Tag { ...f ...f } fragment f on Tag { ...g ...g } fragment g on Tag { ...h ...h }
+            fragment h on Tag { ...i ...i } fragment i on Tag { ...j ...j } fragment j on Tag { ...k ...k } fragment k on Tag { name }";
+        assert!(error(bomb, None).contains("too many fields"));
+    }
+}
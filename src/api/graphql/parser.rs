@@ -0,0 +1,555 @@
+use std::fmt;
+
+/// Selection sets, lists and objects can not be nested deeper, the parser would run out of stack before
+const MAX_NESTING: usize = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Int(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    Enum(String),
+    List(Vec<Value>),
+    Object(Vec<(String, Value)>),
+    Variable(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Directive {
+    pub name: String,
+    pub arguments: Vec<(String, Value)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Field {
+    pub alias: Option<String>,
+    pub name: String,
+    pub arguments: Vec<(String, Value)>,
+    pub directives: Vec<Directive>,
+    pub selection: Vec<Selection>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Selection {
+    Field(Field),
+    FragmentSpread(String, Vec<Directive>),
+    /// Type condition, directives and the selection
+    InlineFragment(Option<String>, Vec<Directive>, Vec<Selection>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct VariableDefinition {
+    pub name: String,
+    /// Variables with a non null type have to be set
+    pub required: bool,
+    pub default: Option<Value>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Operation {
+    /// "query", "mutation" or "subscription"
+    pub kind: String,
+    pub name: Option<String>,
+    pub variables: Vec<VariableDefinition>,
+    pub selection: Vec<Selection>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Fragment {
+    pub name: String,
+    pub type_condition: String,
+    pub selection: Vec<Selection>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Document {
+    pub operations: Vec<Operation>,
+    pub fragments: Vec<Fragment>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message, self.line, self.column)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Punctuator(char),
+    Spread,
+    Name(String),
+    Int(i64),
+    Float(f64),
+    String(String),
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Punctuator(c) => write!(f, "'{}'", c),
+            Token::Spread => write!(f, "'...'"),
+            Token::Name(name) => write!(f, "'{}'", name),
+            Token::Int(value) => write!(f, "{}", value),
+            Token::Float(value) => write!(f, "{}", value),
+            Token::String(value) => write!(f, "\"{}\"", value),
+            Token::End => write!(f, "end of query"),
+        }
+    }
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        ParseError { message, line: self.line, column: self.column }
+    }
+
+    /// Commas are insignificant in GraphQL, like whitespace and comments
+    fn skip_ignored(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                ' ' | '\t' | '\n' | '\r' | ',' | '\u{feff}' => {
+                    self.next_char();
+                }
+                '#' => {
+                    while let Some(c) = self.next_char() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Next token with its position
+    fn token(&mut self) -> Result<(Token, usize, usize), ParseError> {
+        self.skip_ignored();
+        let (line, column) = (self.line, self.column);
+        let c = match self.next_char() {
+            Some(c) => c,
+            None => return Ok((Token::End, line, column)),
+        };
+        let token = match c {
+            '!' | '$' | '(' | ')' | ':' | '=' | '@' | '[' | ']' | '{' | '|' | '}' => Token::Punctuator(c),
+            '.' => {
+                if self.next_char() != Some('.') || self.next_char() != Some('.') {
+                    return Err(self.error(String::from("expected '...'")));
+                }
+                Token::Spread
+            }
+            '"' => Token::String(self.string()?),
+            c if c == '_' || c.is_ascii_alphabetic() => {
+                let mut name = c.to_string();
+                while let Some(&c) = self.chars.peek() {
+                    if c != '_' && !c.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    name.push(c);
+                    self.next_char();
+                }
+                Token::Name(name)
+            }
+            c if c == '-' || c.is_ascii_digit() => self.number(c)?,
+            c => return Err(self.error(format!("unexpected character '{}'", c))),
+        };
+        Ok((token, line, column))
+    }
+
+    fn number(&mut self, first: char) -> Result<Token, ParseError> {
+        let mut text = first.to_string();
+        let mut float = false;
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                '0'..='9' => {}
+                '.' | 'e' | 'E' => float = true,
+                '+' | '-' if text.ends_with('e') || text.ends_with('E') => {}
+                _ => break,
+            }
+            text.push(c);
+            self.next_char();
+        }
+        let token = if float {
+            text.parse().map(Token::Float).ok()
+        } else {
+            text.parse().map(Token::Int).ok()
+        };
+        token.ok_or_else(|| self.error(format!("invalid number {}", text)))
+    }
+
+    /// String after the opening quote, block strings are not supported
+    fn string(&mut self) -> Result<String, ParseError> {
+        let mut value = String::new();
+        loop {
+            match self.next_char() {
+                None | Some('\n') => return Err(self.error(String::from("unterminated string"))),
+                Some('"') => return Ok(value),
+                Some('\\') => {
+                    let escaped = match self.next_char() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let hex: String = (0..4).filter_map(|_| self.next_char()).collect();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error(format!("invalid unicode escape '{}'", hex)))?
+                        }
+                        _ => return Err(self.error(String::from("invalid escape in string"))),
+                    };
+                    value.push(escaped);
+                }
+                Some(c) => value.push(c),
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    token: Token,
+    line: usize,
+    column: usize,
+    /// Current nesting of selection sets, values and types
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn advance(&mut self) -> Result<Token, ParseError> {
+        let (token, line, column) = self.lexer.token()?;
+        self.line = line;
+        self.column = column;
+        Ok(std::mem::replace(&mut self.token, token))
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, ParseError> {
+        Err(ParseError { message, line: self.line, column: self.column })
+    }
+
+    fn unexpected<T>(&self) -> Result<T, ParseError> {
+        self.error(format!("unexpected {}", self.token))
+    }
+
+    /// Parses one level deeper
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, ParseError>) -> Result<T, ParseError> {
+        if self.depth >= MAX_NESTING {
+            return self.error(format!("nesting is deeper than {}", MAX_NESTING));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn is(&self, c: char) -> bool {
+        self.token == Token::Punctuator(c)
+    }
+
+    /// Consumes the punctuator if it is the next token
+    fn skip(&mut self, c: char) -> Result<bool, ParseError> {
+        if self.is(c) {
+            self.advance()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseError> {
+        if self.skip(c)? {
+            Ok(())
+        } else {
+            self.error(format!("expected '{}', found {}", c, self.token))
+        }
+    }
+
+    fn name(&mut self) -> Result<String, ParseError> {
+        match self.token {
+            Token::Name(_) => match self.advance()? {
+                Token::Name(name) => Ok(name),
+                _ => unreachable!(),
+            },
+            _ => self.error(format!("expected name, found {}", self.token)),
+        }
+    }
+
+    fn document(&mut self) -> Result<Document, ParseError> {
+        let mut document = Document::default();
+        while self.token != Token::End {
+            match self.token {
+                Token::Punctuator('{') => document.operations.push(Operation {
+                    kind: String::from("query"),
+                    name: None,
+                    variables: vec![],
+                    selection: self.selection_set()?,
+                }),
+                Token::Name(ref name) if name == "fragment" => {
+                    self.advance()?;
+                    let name = self.name()?;
+                    match self.name()?.as_str() {
+                        "on" => {}
+                        _ => return self.error(String::from("expected 'on' after the fragment name")),
+                    }
+                    let type_condition = self.name()?;
+                    self.directives()?;
+                    let selection = self.selection_set()?;
+                    document.fragments.push(Fragment { name, type_condition, selection });
+                }
+                Token::Name(ref kind) if kind == "query" || kind == "mutation" || kind == "subscription" => {
+                    let kind = self.name()?;
+                    let name = match self.token {
+                        Token::Name(_) => Some(self.name()?),
+                        _ => None,
+                    };
+                    let variables = self.variable_definitions()?;
+                    self.directives()?;
+                    let selection = self.selection_set()?;
+                    document.operations.push(Operation { kind, name, variables, selection });
+                }
+                _ => return self.unexpected(),
+            }
+        }
+        Ok(document)
+    }
+
+    fn variable_definitions(&mut self) -> Result<Vec<VariableDefinition>, ParseError> {
+        let mut variables = vec![];
+        if !self.skip('(')? {
+            return Ok(variables);
+        }
+        while !self.skip(')')? {
+            self.expect('$')?;
+            let name = self.name()?;
+            self.expect(':')?;
+            let required = self.variable_type()?;
+            let default = if self.skip('=')? { Some(self.value(true)?) } else { None };
+            self.directives()?;
+            variables.push(VariableDefinition { name, required, default });
+        }
+        Ok(variables)
+    }
+
+    /// Types are only checked when the value is used, true if it is non null
+    fn variable_type(&mut self) -> Result<bool, ParseError> {
+        if self.skip('[')? {
+            self.nested(|parser| parser.variable_type())?;
+            self.expect(']')?;
+        } else {
+            self.name()?;
+        }
+        self.skip('!')
+    }
+
+    fn directives(&mut self) -> Result<Vec<Directive>, ParseError> {
+        let mut directives = vec![];
+        while self.skip('@')? {
+            let name = self.name()?;
+            let arguments = self.arguments()?;
+            directives.push(Directive { name, arguments });
+        }
+        Ok(directives)
+    }
+
+    fn arguments(&mut self) -> Result<Vec<(String, Value)>, ParseError> {
+        let mut arguments = vec![];
+        if !self.skip('(')? {
+            return Ok(arguments);
+        }
+        while !self.skip(')')? {
+            let name = self.name()?;
+            self.expect(':')?;
+            arguments.push((name, self.value(false)?));
+        }
+        Ok(arguments)
+    }
+
+    fn selection_set(&mut self) -> Result<Vec<Selection>, ParseError> {
+        self.expect('{')?;
+        self.nested(|parser| {
+            let mut selection = vec![];
+            while !parser.skip('}')? {
+                selection.push(parser.selection()?);
+            }
+            if selection.is_empty() {
+                return parser.error(String::from("empty selection"));
+            }
+            Ok(selection)
+        })
+    }
+
+    fn selection(&mut self) -> Result<Selection, ParseError> {
+        if self.token == Token::Spread {
+            self.advance()?;
+            return match self.token {
+                Token::Name(ref name) if name != "on" => {
+                    let name = self.name()?;
+                    Ok(Selection::FragmentSpread(name, self.directives()?))
+                }
+                Token::Name(_) => {
+                    self.advance()?;
+                    let type_condition = self.name()?;
+                    let directives = self.directives()?;
+                    Ok(Selection::InlineFragment(Some(type_condition), directives, self.selection_set()?))
+                }
+                _ => {
+                    let directives = self.directives()?;
+                    Ok(Selection::InlineFragment(None, directives, self.selection_set()?))
+                }
+            };
+        }
+        let mut name = self.name()?;
+        let mut alias = None;
+        if self.skip(':')? {
+            alias = Some(name);
+            name = self.name()?;
+        }
+        let arguments = self.arguments()?;
+        let directives = self.directives()?;
+        let selection = if self.is('{') { self.selection_set()? } else { vec![] };
+        Ok(Selection::Field(Field { alias, name, arguments, directives, selection }))
+    }
+
+    /// Values in variable definitions have to be constant
+    fn value(&mut self, constant: bool) -> Result<Value, ParseError> {
+        let value = match self.token {
+            Token::Punctuator('$') if !constant => {
+                self.advance()?;
+                return Ok(Value::Variable(self.name()?));
+            }
+            Token::Punctuator('[') => {
+                self.advance()?;
+                return self.nested(|parser| {
+                    let mut list = vec![];
+                    while !parser.skip(']')? {
+                        list.push(parser.value(constant)?);
+                    }
+                    Ok(Value::List(list))
+                });
+            }
+            Token::Punctuator('{') => {
+                self.advance()?;
+                return self.nested(|parser| {
+                    let mut fields = vec![];
+                    while !parser.skip('}')? {
+                        let name = parser.name()?;
+                        parser.expect(':')?;
+                        fields.push((name, parser.value(constant)?));
+                    }
+                    Ok(Value::Object(fields))
+                });
+            }
+            Token::Int(value) => Value::Int(value),
+            Token::Float(value) => Value::Float(value),
+            Token::String(ref value) => Value::String(value.clone()),
+            Token::Name(ref name) => match name.as_str() {
+                "true" => Value::Boolean(true),
+                "false" => Value::Boolean(false),
+                "null" => Value::Null,
+                name => Value::Enum(name.to_string()),
+            },
+            _ => return self.unexpected(),
+        };
+        self.advance()?;
+        Ok(value)
+    }
+}
+
+pub fn parse(query: &str) -> Result<Document, ParseError> {
+    let mut parser = Parser {
+        lexer: Lexer { chars: query.chars().peekable(), line: 1, column: 1 },
+        token: Token::End,
+        line: 1,
+        column: 1,
+        depth: 0,
+    };
+    parser.advance()?;
+    parser.document()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_query() {
+        let document = parse(
+            r#"
+            # station page
+            query Page($uuid: String!, $limit: Int = 5) {
+                station(uuid: $uuid) {
+                    title: name
+                    checks(limit: $limit) { ...check @include(if: true) }
+                    ... on Station { votes }
+                }
+            }
+            fragment check on StationCheck { ok, codec }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(document.fragments[0].name, "check");
+        let operation = &document.operations[0];
+        assert_eq!(operation.name.as_deref(), Some("Page"));
+        assert!(operation.variables[0].required);
+        assert_eq!(operation.variables[1].default, Some(Value::Int(5)));
+        let station = match &operation.selection[0] {
+            Selection::Field(field) => field,
+            _ => panic!("no field"),
+        };
+        assert_eq!(station.arguments, vec![(String::from("uuid"), Value::Variable(String::from("uuid")))]);
+        match &station.selection[0] {
+            Selection::Field(field) => assert_eq!((field.alias.as_deref(), field.name.as_str()), (Some("title"), "name")),
+            _ => panic!("no field"),
+        }
+        assert!(matches!(&station.selection[2], Selection::InlineFragment(Some(name), _, _) if name == "Station"));
+
+        let document = parse(r#"{ stations(name: "a\"b", tagList: ["x", "y"], geo: {lat: -1.5e1}) { name } }"#).unwrap();
+        match &document.operations[0].selection[0] {
+            Selection::Field(field) => {
+                assert_eq!(field.arguments[0].1, Value::String(String::from("a\"b")));
+                assert_eq!(field.arguments[2].1, Value::Object(vec![(String::from("lat"), Value::Float(-15.0))]));
+            }
+            _ => panic!("no field"),
+        }
+
+        let error = parse("{ station(uuid: ) { name } }").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 17));
+        assert!(parse("{ }").is_err());
+        assert!(parse("{ name").is_err());
+        let deep = format!("{}name{}", "{ a ".repeat(100), " }".repeat(100));
+        assert!(parse(&deep).unwrap_err().message.contains("nesting"));
+        let deep = format!("{{ a(b: {}1{}) }}", "[".repeat(100_000), "]".repeat(100_000));
+        assert!(parse(&deep).unwrap_err().message.contains("nesting"));
+    }
+}
@@ -0,0 +1,336 @@
+use crate::api::data::ApiCountry;
+use crate::api::data::ApiStreamingServer;
+use crate::api::data::Station;
+use crate::api::data::StationCheck;
+use crate::api::data::StationCheckStep;
+use crate::api::data::StationClick;
+use crate::api::data::StationHistoryCurrent;
+use crate::db::DbConnection;
+use serde::Serialize;
+use serde_json::Map;
+use serde_json::Value;
+use std::error::Error;
+
+pub enum FieldType {
+    Scalar,
+    Object(&'static str),
+    List(&'static str),
+}
+
+pub struct FieldDef {
+    pub name: &'static str,
+    pub field_type: FieldType,
+    pub arguments: &'static [&'static str],
+    /// Number of items of lists without the argument "limit", also the factor of the list for the complexity
+    pub default_limit: u32,
+}
+
+const fn scalar(name: &'static str) -> FieldDef {
+    FieldDef { name, field_type: FieldType::Scalar, arguments: &[], default_limit: 1 }
+}
+
+const fn object(name: &'static str, type_name: &'static str, arguments: &'static [&'static str]) -> FieldDef {
+    FieldDef { name, field_type: FieldType::Object(type_name), arguments, default_limit: 1 }
+}
+
+const fn list(name: &'static str, type_name: &'static str, arguments: &'static [&'static str], default_limit: u32) -> FieldDef {
+    FieldDef { name, field_type: FieldType::List(type_name), arguments, default_limit }
+}
+
+const EXTRA_LIST: &[&str] = &["filter", "order", "reverse", "hidebroken", "offset", "limit"];
+
+const QUERY: &[FieldDef] = &[
+    object("station", "Station", &["uuid"]),
+    list("stations", "Station", &[
+        "uuids", "name", "nameExact", "country", "countryExact", "countrycode", "state", "stateExact", "language", "languageExact",
+        "tag", "tagExact", "tagList", "codec", "bitrateMin", "bitrateMax", "order", "reverse", "hidebroken", "offset", "limit",
+    ], 10),
+    list("tags", "Tag", EXTRA_LIST, 100),
+    list("countries", "Country", EXTRA_LIST, 100),
+    list("checks", "StationCheck", &["stationuuid", "lastcheckuuid", "seconds", "limit"], 100),
+    list("clicks", "StationClick", &["stationuuid", "lastclickuuid", "seconds", "limit"], 100),
+];
+
+const STATION: &[FieldDef] = &[
+    scalar("changeuuid"), scalar("stationuuid"), scalar("serveruuid"), scalar("name"), scalar("url"), scalar("url_resolved"),
+    scalar("homepage"), scalar("favicon"), scalar("tags"), scalar("country"), scalar("countrycode"), scalar("iso_3166_2"),
+    scalar("state"), scalar("language"), scalar("languagecodes"), scalar("votes"), scalar("lastchangetime"),
+    scalar("lastchangetime_iso8601"), scalar("codec"), scalar("bitrate"), scalar("hls"), scalar("lastcheckok"),
+    scalar("lastchecktime"), scalar("lastchecktime_iso8601"), scalar("lastcheckoktime"), scalar("lastcheckoktime_iso8601"),
+    scalar("lastlocalchecktime"), scalar("lastlocalchecktime_iso8601"), scalar("clicktimestamp"), scalar("clicktimestamp_iso8601"),
+    scalar("clickcount"), scalar("clicktrend"), scalar("ssl_error"), scalar("geo_lat"), scalar("geo_long"), scalar("has_extended_info"),
+    scalar("now_playing"),
+    list("checks", "StationCheck", &["limit"], 10),
+    list("checksteps", "StationCheckStep", &["limit"], 10),
+    list("clicks", "StationClick", &["seconds", "limit"], 10),
+    object("streamingserver", "StreamingServer", &[]),
+    list("history", "StationHistory", &["limit"], 10),
+];
+
+const STATION_CHECK: &[FieldDef] = &[
+    scalar("stationuuid"), scalar("checkuuid"), scalar("source"), scalar("codec"), scalar("bitrate"), scalar("hls"), scalar("ok"),
+    scalar("timestamp_iso8601"), scalar("timestamp"), scalar("urlcache"), scalar("metainfo_overrides_database"), scalar("public"),
+    scalar("name"), scalar("description"), scalar("tags"), scalar("countrycode"), scalar("homepage"), scalar("favicon"),
+    scalar("loadbalancer"), scalar("do_not_index"), scalar("countrysubdivisioncode"), scalar("server_software"), scalar("sampling"),
+    scalar("timing_ms"), scalar("languagecodes"), scalar("ssl_error"), scalar("geo_lat"), scalar("geo_long"),
+    scalar("audio_bitrate"), scalar("audio_sampling"), scalar("audio_liveness"),
+];
+
+const STATION_CHECK_STEP: &[FieldDef] = &[
+    scalar("stepuuid"), scalar("parent_stepuuid"), scalar("checkuuid"), scalar("stationuuid"), scalar("url"), scalar("urltype"),
+    scalar("error"), scalar("creation_iso8601"),
+    list("children", "StationCheckStep", &["limit"], 2),
+];
+
+const STATION_CLICK: &[FieldDef] = &[scalar("stationuuid"), scalar("clickuuid"), scalar("clicktimestamp_iso8601"), scalar("clicktimestamp")];
+
+const STATION_HISTORY: &[FieldDef] = &[
+    scalar("changeuuid"), scalar("stationuuid"), scalar("name"), scalar("url"), scalar("homepage"), scalar("favicon"), scalar("tags"),
+    scalar("country"), scalar("countrycode"), scalar("state"), scalar("language"), scalar("languagecodes"), scalar("votes"),
+    scalar("lastchangetime"), scalar("lastchangetime_iso8601"), scalar("geo_lat"), scalar("geo_long"),
+];
+
+const STREAMING_SERVER: &[FieldDef] = &[scalar("uuid"), scalar("url"), scalar("statusurl"), scalar("status"), scalar("error")];
+
+const TAG: &[FieldDef] = &[scalar("name"), scalar("stationcount")];
+
+const COUNTRY: &[FieldDef] = &[scalar("name"), scalar("iso_3166_1"), scalar("stationcount")];
+
+/// Types of the schema with their fields, queries start at "Query"
+const TYPES: &[(&str, &[FieldDef])] = &[
+    ("Query", QUERY),
+    ("Station", STATION),
+    ("StationCheck", STATION_CHECK),
+    ("StationCheckStep", STATION_CHECK_STEP),
+    ("StationClick", STATION_CLICK),
+    ("StationHistory", STATION_HISTORY),
+    ("StreamingServer", STREAMING_SERVER),
+    ("Tag", TAG),
+    ("Country", COUNTRY),
+];
+
+pub fn field(type_name: &str, name: &str) -> Option<&'static FieldDef> {
+    TYPES
+        .iter()
+        .find(|(name, _)| *name == type_name)
+        .and_then(|(_, fields)| fields.iter().find(|field| field.name == name))
+}
+
+/// Field of a query with fragments, directives and variables already resolved
+pub struct Query {
+    /// Name in the response, the alias if there is one
+    pub key: String,
+    pub name: String,
+    pub arguments: Map<String, Value>,
+    pub selection: Vec<Query>,
+}
+
+impl Query {
+    /// Number of items of a list, the same for the complexity and the database
+    pub fn limit(&self, default_limit: u32) -> Result<u32, Box<dyn Error>> {
+        self.number("limit", default_limit)
+    }
+
+    fn string(&self, name: &str) -> Result<Option<String>, Box<dyn Error>> {
+        match self.arguments.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(format!("argument '{}' of '{}' has to be a string", name, self.name).into()),
+        }
+    }
+
+    /// Lists of strings can also be given as one comma separated string
+    fn strings(&self, name: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        match self.arguments.get(name) {
+            None | Some(Value::Null) => Ok(vec![]),
+            Some(Value::String(value)) => Ok(value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect()),
+            Some(Value::Array(list)) => list
+                .iter()
+                .map(|item| item.as_str().map(String::from).ok_or_else(|| format!("argument '{}' of '{}' has to be a list of strings", name, self.name).into()))
+                .collect(),
+            Some(_) => Err(format!("argument '{}' of '{}' has to be a list of strings", name, self.name).into()),
+        }
+    }
+
+    fn boolean(&self, name: &str, default: bool) -> Result<bool, Box<dyn Error>> {
+        match self.arguments.get(name) {
+            None | Some(Value::Null) => Ok(default),
+            Some(Value::Bool(value)) => Ok(*value),
+            Some(_) => Err(format!("argument '{}' of '{}' has to be a boolean", name, self.name).into()),
+        }
+    }
+
+    fn number(&self, name: &str, default: u32) -> Result<u32, Box<dyn Error>> {
+        match self.arguments.get(name) {
+            None | Some(Value::Null) => Ok(default),
+            Some(Value::Number(value)) => value
+                .as_u64()
+                .and_then(|value| u32::try_from(value).ok())
+                .ok_or_else(|| format!("argument '{}' of '{}' has to be a positive integer", name, self.name).into()),
+            Some(_) => Err(format!("argument '{}' of '{}' has to be a positive integer", name, self.name).into()),
+        }
+    }
+}
+
+fn to_object<T: Serialize>(item: T) -> Result<Map<String, Value>, Box<dyn Error>> {
+    match serde_json::to_value(item)? {
+        Value::Object(object) => Ok(object),
+        _ => Ok(Map::new()),
+    }
+}
+
+fn select_list<A, T>(connection: &A, type_name: &str, list: Vec<T>, selection: &[Query]) -> Result<Value, Box<dyn Error>>
+where
+    A: DbConnection,
+    T: Serialize,
+{
+    let list = list
+        .into_iter()
+        .map(|item| select(connection, type_name, to_object(item)?, selection))
+        .collect::<Result<Vec<Value>, Box<dyn Error>>>()?;
+    Ok(Value::Array(list))
+}
+
+/// Selected fields of the object, lists and objects of other types are loaded from the database as needed
+pub fn select<A: DbConnection>(connection: &A, type_name: &str, object: Map<String, Value>, selection: &[Query]) -> Result<Value, Box<dyn Error>> {
+    let mut result = Map::new();
+    for query in selection {
+        let value = if query.name == "__typename" {
+            Value::String(type_name.to_string())
+        } else {
+            match field(type_name, &query.name).map(|field| &field.field_type) {
+                Some(FieldType::Scalar) => object.get(&query.name).cloned().unwrap_or(Value::Null),
+                // nested objects that are already part of the object, like the children of check steps
+                Some(FieldType::List(item_type)) if object.contains_key(&query.name) => {
+                    let default_limit = field(type_name, &query.name).map(|field| field.default_limit).unwrap_or(1);
+                    let list = object.get(&query.name).and_then(|list| list.as_array()).cloned().unwrap_or_default();
+                    let list = list
+                        .into_iter()
+                        .take(query.limit(default_limit)? as usize)
+                        .filter_map(|item| match item {
+                            Value::Object(item) => Some(item),
+                            _ => None,
+                        })
+                        .map(|item| select(connection, item_type, item, &query.selection));
+                    Value::Array(list.collect::<Result<Vec<Value>, Box<dyn Error>>>()?)
+                }
+                Some(_) => resolve(connection, type_name, &object, query)?,
+                None => return Err(format!("unknown field '{}' on type '{}'", query.name, type_name).into()),
+            }
+        };
+        result.insert(query.key.clone(), value);
+    }
+    Ok(Value::Object(result))
+}
+
+/// Fields of the root type Query
+pub fn resolve_root<A: DbConnection>(connection: &A, query: &Query) -> Result<Value, Box<dyn Error>> {
+    resolve(connection, "Query", &Map::new(), query)
+}
+
+fn resolve<A: DbConnection>(connection: &A, type_name: &str, parent: &Map<String, Value>, query: &Query) -> Result<Value, Box<dyn Error>> {
+    let default_limit = field(type_name, &query.name).map(|field| field.default_limit).unwrap_or(1);
+    let stationuuid = parent.get("stationuuid").and_then(|uuid| uuid.as_str()).unwrap_or("").to_string();
+    let selection = &query.selection;
+    match (type_name, query.name.as_str()) {
+        ("Query", "station") => {
+            let uuid = query.string("uuid")?.ok_or("argument 'uuid' of 'station' is missing")?;
+            match connection.get_station_by_uuid(&uuid)?.pop() {
+                Some(station) => select(connection, "Station", to_object(Station::from(station))?, selection),
+                None => Ok(Value::Null),
+            }
+        }
+        ("Query", "stations") => {
+            let uuids = query.strings("uuids")?;
+            let list = if !uuids.is_empty() {
+                let uuids = uuids.into_iter().skip(query.number("offset", 0)? as usize).take(query.limit(default_limit)? as usize).collect();
+                connection.get_stations_by_uuid(uuids)?
+            } else {
+                let order = query.string("order")?.unwrap_or_else(|| String::from("name"));
+                connection.get_stations_advanced(
+                    query.string("name")?, query.boolean("nameExact", false)?, query.string("country")?, query.boolean("countryExact", false)?,
+                    query.string("countrycode")?, query.string("state")?, query.boolean("stateExact", false)?, query.string("language")?,
+                    query.boolean("languageExact", false)?, query.string("tag")?, query.boolean("tagExact", false)?, query.strings("tagList")?,
+                    query.string("codec")?, query.number("bitrateMin", 0)?, query.number("bitrateMax", 1000000)?, None, None, None,
+                    None, None, None, None, &order, query.boolean("reverse", false)?, query.boolean("hidebroken", false)?,
+                    None, None, query.number("offset", 0)?, query.limit(default_limit)?,
+                )?
+            };
+            let list: Vec<Station> = list.into_iter().map(Station::from).collect();
+            select_list(connection, "Station", list, selection)
+        }
+        ("Query", "tags") => {
+            let list = connection.get_extra("TagCache", "TagName", query.string("filter")?, query.string("order")?.unwrap_or_else(|| String::from("name")),
+                query.boolean("reverse", false)?, query.boolean("hidebroken", false)?, query.number("offset", 0)?, query.limit(default_limit)?)?;
+            select_list(connection, "Tag", list, selection)
+        }
+        ("Query", "countries") => {
+            let list = connection.get_countries(query.string("filter")?, query.string("order")?.unwrap_or_else(|| String::from("name")),
+                query.boolean("reverse", false)?, query.boolean("hidebroken", false)?, query.number("offset", 0)?, query.limit(default_limit)?)?;
+            let list: Vec<ApiCountry> = list.into_iter().map(ApiCountry::from).collect();
+            select_list(connection, "Country", list, selection)
+        }
+        ("Query", "checks") => {
+            let stationuuid = query.string("stationuuid")?;
+            let include_history = stationuuid.is_some();
+            let list = connection.get_checks(stationuuid, query.string("lastcheckuuid")?, query.number("seconds", 0)?, include_history, query.limit(default_limit)?)?;
+            let list: Vec<StationCheck> = list.into_iter().map(StationCheck::from).collect();
+            select_list(connection, "StationCheck", list, selection)
+        }
+        ("Query", "clicks") => {
+            let list = connection.get_clicks(query.string("stationuuid")?, query.string("lastclickuuid")?, query.number("seconds", 0)?, query.limit(default_limit)?)?;
+            let list: Vec<StationClick> = list.into_iter().map(StationClick::from).collect();
+            select_list(connection, "StationClick", list, selection)
+        }
+        ("Station", "checks") => {
+            let list = connection.get_checks(Some(stationuuid), None, 0, true, query.limit(default_limit)?)?;
+            let list: Vec<StationCheck> = list.into_iter().map(StationCheck::from).collect();
+            select_list(connection, "StationCheck", list, selection)
+        }
+        ("Station", "checksteps") => {
+            let steps: Vec<StationCheckStep> = connection
+                .select_station_check_steps_by_stations(&[stationuuid], query.limit(default_limit)?)?
+                .into_iter()
+                .map(StationCheckStep::from)
+                .collect();
+            let steps = steps.into_iter().map(to_object).collect::<Result<Vec<_>, _>>()?;
+            let roots = step_tree(&steps, None);
+            let roots = roots.into_iter().map(|step| select(connection, "StationCheckStep", step, selection));
+            Ok(Value::Array(roots.collect::<Result<Vec<Value>, Box<dyn Error>>>()?))
+        }
+        ("Station", "clicks") => {
+            let list = connection.get_clicks(Some(stationuuid), None, query.number("seconds", 0)?, query.limit(default_limit)?)?;
+            let list: Vec<StationClick> = list.into_iter().map(StationClick::from).collect();
+            select_list(connection, "StationClick", list, selection)
+        }
+        ("Station", "streamingserver") => {
+            match connection.get_streaming_servers_by_station_uuids(vec![stationuuid], "url", false, 0, 1)?.pop() {
+                Some(server) => select(connection, "StreamingServer", to_object(ApiStreamingServer::from(server))?, selection),
+                None => Ok(Value::Null),
+            }
+        }
+        ("Station", "history") => {
+            let list = connection.get_changes(Some(stationuuid), None, query.limit(default_limit)?)?;
+            let list: Vec<StationHistoryCurrent> = list.into_iter().map(StationHistoryCurrent::from).collect();
+            select_list(connection, "StationHistory", list, selection)
+        }
+        _ => Err(format!("unknown field '{}' on type '{}'", query.name, type_name).into()),
+    }
+}
+
+/// Check steps below the parent step with their own children in the field "children"
+fn step_tree(steps: &[Map<String, Value>], parent: Option<&str>) -> Vec<Map<String, Value>> {
+    steps
+        .iter()
+        .filter(|step| step.get("parent_stepuuid").and_then(|uuid| uuid.as_str()) == parent)
+        .map(|step| {
+            let mut step = step.clone();
+            let children = match step.get("stepuuid").and_then(|uuid| uuid.as_str()) {
+                Some(uuid) => step_tree(steps, Some(uuid)),
+                None => vec![],
+            };
+            step.insert(String::from("children"), Value::Array(children.into_iter().map(Value::Object).collect()));
+            step
+        })
+        .collect()
+}
@@ -11,19 +11,35 @@ mod api_error;
 mod api_response;
 mod cache;
 mod all_params;
+mod api_auth;
+mod rate_limit;
+mod conditional;
+mod compression;
+mod stream;
+mod pagination;
+mod routes;
+mod openapi;
+mod graphql;
+mod events;
 
 //use std::thread::JoinHandle;
 //use rouille::Server;
 use crate::api::data::ApiCountry;
 use crate::api::data::ApiLanguage;
 use all_params::AllParameters;
+use api_auth::ApiAuth;
+use pagination::Pagination;
+use rate_limit::RateLimitKind;
+use rate_limit::RateLimiter;
 use prometheus_exporter::RegistryLinks;
 
 use api_response::ApiResponse;
+use api_response::StreamProducer;
 
 use std::error::Error;
 use std::convert::TryInto;
 use std::thread;
+use std::sync::Mutex;
 use std::time::Duration;
 use api_error::ApiError;
 
@@ -33,25 +49,40 @@ use crate::api::data::ApiStreamingServer;
 use crate::api::data::ResultMessage;
 use crate::api::data::StationCachedInfo;
 use crate::api::data::StationHistoryCurrent;
+use crate::api::data::StationPending;
 use crate::api::data::Station;
+use crate::api::data::StationListWriter;
 use crate::api::data::StationCheck;
 use crate::api::data::StationCheckStep;
+use crate::api::data::StationCheckSource;
+use crate::api::data::StationNowPlaying;
 use crate::api::data::StationAddResult;
 use crate::api::data::Status;
 use crate::api::data::StationClick;
 use crate::api::data::ApiConfig;
+use crate::api::data::ApiWebhook;
+use crate::api::data::StationRecheck;
 use crate::db::DbConnection;
+use crate::db::DbError;
+use crate::db::StationCursor;
+use crate::db::StationFields;
+use crate::db::nearest_radius;
+use crate::db::models::CheckConsensus;
 use crate::db::models::ExtraInfo;
 use crate::db::models::State;
 use crate::db::models::DbStationItem;
+use crate::db::models::DuplicateReason;
+use crate::db::models::StationCheckItem;
 use crate::api::rouille::Response;
 use crate::api::rouille::Request;
 use std;
 use self::dns_lookup::lookup_host;
 use self::dns_lookup::lookup_addr;
 
+use crate::check;
 use crate::config;
 use crate::config::Config;
+use crate::webhooks;
 
 use std::fs::File;
 use self::serde_json::value::Map;
@@ -94,7 +125,7 @@ fn dns_resolve(format : &str) -> Result<ApiResponse, Box<dyn Error>> {
 
     match format {
         "json" => Ok(ApiResponse::Text(serde_json::to_string(&list)?)),
-        _ => Ok(ApiResponse::NotFound)
+        _ => Ok(ApiResponse::UnknownContentType)
     }
 }
 
@@ -131,6 +162,253 @@ fn encode_message(status: Result<String, Box<dyn Error>>, format : &str) -> Resu
     })
 }
 
+fn add_station<A>(connection_new: &A, all_params: AllParameters, moderation: bool, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
+    let mut duplicates = connection_new.find_duplicate_stations(
+        all_params.param_name.as_deref().unwrap_or_default(),
+        all_params.param_url.as_deref().unwrap_or_default(),
+        all_params.param_countrycode.as_deref().unwrap_or_default(),
+    )?;
+    // force only overrides the hint of a similar name, a known url is always the same station
+    if all_params.param_force {
+        duplicates.retain(|duplicate| duplicate.reason != DuplicateReason::Name);
+    }
+    if !duplicates.is_empty() {
+        return StationAddResult::new_duplicate(duplicates).get_response(format);
+    }
+    let result = if moderation {
+        StationAddResult::from_pending(connection_new.add_pending_station_opt(all_params.param_name, all_params.param_url, all_params.param_homepage, all_params.param_favicon, all_params.param_countrycode, all_params.param_state, all_params.param_language, all_params.param_language_codes, all_params.param_tags, all_params.param_geo_lat, all_params.param_geo_long))
+    } else {
+        StationAddResult::from(connection_new.add_station_opt(all_params.param_name, all_params.param_url, all_params.param_homepage, all_params.param_favicon, all_params.param_countrycode, all_params.param_state, all_params.param_language, all_params.param_language_codes, all_params.param_tags, all_params.param_geo_lat, all_params.param_geo_long))
+    };
+    result.get_response(format)
+}
+
+fn edit_station<A>(connection_new: &A, auth: &ApiAuth, stationuuid: &str, all_params: AllParameters, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
+    if !auth.can_edit() {
+        return Ok(ApiResponse::Unauthorized(String::from("editing stations needs an api key or the admin token")));
+    }
+    let result = connection_new.edit_station_opt(stationuuid, all_params.param_name, all_params.param_url, all_params.param_homepage, all_params.param_favicon,
+        all_params.param_countrycode, all_params.param_state, all_params.param_language, all_params.param_language_codes, all_params.param_tags,
+        all_params.param_geo_lat, all_params.param_geo_long, "EDIT");
+    if result.is_ok() {
+        info!("station {} edited by {}", stationuuid, auth.name());
+    }
+    encode_message(result.map(|_| String::from("changed station successfully")), format)
+}
+
+/// Response for requests without the admin token, None if the request has it
+fn admin_only(auth: &ApiAuth, action: &str) -> Option<ApiResponse> {
+    let message = format!("{} needs the admin token", action);
+    match auth {
+        ApiAuth::Admin => None,
+        ApiAuth::Anonymous => Some(ApiResponse::Unauthorized(message)),
+        ApiAuth::ApiKey(_) => Some(ApiResponse::Forbidden(message)),
+    }
+}
+
+fn delete_station<A>(connection_new: &A, auth: &ApiAuth, stationuuid: &str, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
+    if let Some(response) = admin_only(auth, "deleting stations") {
+        return Ok(response);
+    }
+    let result: Result<String, Box<dyn Error>> = match get_only_first_item(connection_new.get_station_by_uuid(stationuuid)?) {
+        Some(station) => connection_new.delete_stations(&[station.stationuuid]).map(|_| {
+            info!("station {} deleted by {}", stationuuid, auth.name());
+            String::from("deleted station successfully")
+        }),
+        None => Err(Box::new(DbError::EditStationError(String::from("station not found")))),
+    };
+    encode_message(result, format)
+}
+
+fn pending_stations<A>(connection_new: &A, auth: &ApiAuth, offset: u32, limit: u32, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
+    if let Some(response) = admin_only(auth, "the review queue") {
+        return Ok(response);
+    }
+    StationPending::get_response(connection_new.get_pending_stations(offset, limit)?.drain(..).map(|x| x.into()).collect(), format)
+}
+
+fn moderate_station<A>(connection_new: &A, auth: &ApiAuth, stationuuid: &str, approve: bool, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
+    if let Some(response) = admin_only(auth, "the review queue") {
+        return Ok(response);
+    }
+    let result = if approve {
+        connection_new.approve_pending_station(stationuuid).map(|_| String::from("approved station successfully"))
+    } else {
+        connection_new.reject_pending_station(stationuuid).map(|_| String::from("rejected station successfully"))
+    };
+    if let Ok(ref message) = result {
+        info!("{}: {}", message, stationuuid);
+    }
+    encode_message(result, format)
+}
+
+/// Owner whose webhooks the request may see and change, None for all of them
+fn webhook_owner(auth: &ApiAuth) -> Option<&str> {
+    match auth {
+        ApiAuth::ApiKey(name) => Some(name.as_str()),
+        _ => None,
+    }
+}
+
+fn list_webhooks<A>(connection_new: &A, auth: &ApiAuth, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
+    if !auth.can_edit() {
+        return Ok(ApiResponse::Unauthorized(String::from("webhooks need an api key or the admin token")));
+    }
+    ApiWebhook::get_response(connection_new.get_webhooks(webhook_owner(auth))?.drain(..).map(|x| x.into()).collect(), format)
+}
+
+fn add_webhook<A>(connection_new: &A, auth: &ApiAuth, all_params: AllParameters, private_targets: bool, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
+    if !auth.can_edit() {
+        return Ok(ApiResponse::Unauthorized(String::from("webhooks need an api key or the admin token")));
+    }
+    let webhook = webhooks::new_webhook(
+        auth.name(),
+        all_params.param_url.as_deref().unwrap_or_default(),
+        all_params.param_secret.as_deref().unwrap_or_default(),
+        all_params.param_events.as_deref().unwrap_or_default(),
+        all_params.param_stationuuid.as_deref().unwrap_or_default(),
+        all_params.param_tag.as_deref().unwrap_or_default(),
+        all_params.param_countrycode.as_deref().unwrap_or_default(),
+    );
+    let webhook = match webhook {
+        Ok(webhook) => webhook,
+        Err(message) => return Ok(ApiResponse::ParameterError(message)),
+    };
+    if let Err(message) = webhooks::target_addresses(&webhook.url, private_targets) {
+        return Ok(ApiResponse::ParameterError(message));
+    }
+    connection_new.add_webhook(&webhook)?;
+    info!("webhook {} to {} added by {}", webhook.webhookuuid, webhook.url, auth.name());
+    let added = connection_new
+        .get_webhooks(Some(auth.name()))?
+        .into_iter()
+        .find(|item| item.webhookuuid == webhook.webhookuuid)
+        .unwrap_or(webhook);
+    ApiWebhook::from(added).get_single_response(format)
+}
+
+fn delete_webhook<A>(connection_new: &A, auth: &ApiAuth, webhookuuid: &str, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
+    if !auth.can_edit() {
+        return Ok(ApiResponse::Unauthorized(String::from("webhooks need an api key or the admin token")));
+    }
+    let result = connection_new.delete_webhook(webhookuuid, webhook_owner(auth));
+    if result.is_ok() {
+        info!("webhook {} deleted by {}", webhookuuid, auth.name());
+    }
+    encode_message(result.map(|_| String::from("deleted webhook successfully")), format)
+}
+
+/// Last title of the station, without a title if none was read yet
+fn now_playing<A>(connection_new: &A, stationuuid: &str, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
+    if get_only_first_item(connection_new.get_station_by_uuid(stationuuid)?).is_none() {
+        return Ok(ApiResponse::NotFound);
+    }
+    StationNowPlaying::new(stationuuid, connection_new.get_now_playing(stationuuid)?).get_response(format)
+}
+
+/// Most stations that are checked on request at the same time
+const MAX_RECHECKS: usize = 4;
+/// Stations that were checked by this server in this time get that check instead of a new one
+const RECHECK_COOLDOWN_SECONDS: i64 = 5 * 60;
+/// Stations that are checked on request right now
+static RECHECKS: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+/// Place in RECHECKS of a station, given back when it is dropped
+struct RecheckSlot(String);
+
+impl RecheckSlot {
+    /// None if too many stations or this station are checked already
+    fn take(stationuuid: &str) -> Option<RecheckSlot> {
+        let mut running = RECHECKS.lock().unwrap_or_else(|err| err.into_inner());
+        if running.len() >= MAX_RECHECKS || running.iter().any(|uuid| uuid == stationuuid) {
+            return None;
+        }
+        running.push(stationuuid.to_string());
+        Some(RecheckSlot(stationuuid.to_string()))
+    }
+}
+
+impl Drop for RecheckSlot {
+    fn drop(&mut self) {
+        let mut running = RECHECKS.lock().unwrap_or_else(|err| err.into_inner());
+        running.retain(|uuid| uuid != &self.0);
+    }
+}
+
+/// Checks the station with the settings of the regular checks and answers with the stored check and its steps
+fn recheck_station<A>(connection_new: &A, config: &Config, stationuuid: &str, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection + Clone {
+    if !config.enable_check {
+        return Ok(ApiResponse::Locked(String::from("checks are not enabled on this server")));
+    }
+    let station = match get_only_first_item(connection_new.get_station_by_uuid(stationuuid)?) {
+        Some(station) => station,
+        None => return Ok(ApiResponse::NotFound),
+    };
+    let checked_recently = station
+        .lastlocalchecktime_iso8601
+        .map(|time| (chrono::Utc::now() - time).num_seconds() < RECHECK_COOLDOWN_SECONDS)
+        .unwrap_or(false);
+    if checked_recently {
+        let recent = connection_new
+            .get_checks(Some(stationuuid.to_string()), None, 0, false, 1000)?
+            .into_iter()
+            .find(|check| check.source == config.source);
+        if let Some(check) = recent {
+            return recheck_response(connection_new, stationuuid, check, format);
+        }
+    }
+    let _slot = match RecheckSlot::take(&station.stationuuid) {
+        Some(slot) => slot,
+        None => return Ok(ApiResponse::Unavailable(String::from("too many stations are checked on request right now"))),
+    };
+    let scheduler = check::create_scheduler(config);
+    let inserted = check::check_stations(
+        connection_new.clone(),
+        vec![station],
+        &config.source,
+        1,
+        config.tcp_timeout.as_secs(),
+        config.max_depth,
+        config.retries,
+        config.check_servers,
+        config.recheck_existing_favicon,
+        config.enable_extract_favicon,
+        config.favicon_size_min,
+        config.favicon_size_max,
+        config.favicon_size_optimum,
+        config.check_audio_duration,
+        config.check_now_playing,
+        scheduler.as_ref(),
+        &CheckConsensus::new(config.check_consensus_ok, config.check_consensus_sources, config.check_interval.as_secs()),
+    )?;
+    let checkuuid = match inserted.into_iter().next().and_then(|check| check.checkuuid) {
+        Some(checkuuid) => checkuuid,
+        // the station was deleted while it was checked
+        None => return Ok(ApiResponse::NotFound),
+    };
+    info!("station {} checked on request", stationuuid);
+    let check = connection_new
+        .get_checks(Some(stationuuid.to_string()), None, 0, false, 1000)?
+        .into_iter()
+        .find(|check| check.check_uuid == checkuuid);
+    match check {
+        Some(check) => recheck_response(connection_new, stationuuid, check, format),
+        None => Ok(ApiResponse::NotFound),
+    }
+}
+
+/// The check with its steps
+fn recheck_response<A>(connection_new: &A, stationuuid: &str, check: StationCheckItem, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
+    let checkuuid = check.check_uuid.clone();
+    let steps = connection_new
+        .select_station_check_steps_by_stations(&[stationuuid.to_string()], u32::MAX)?
+        .into_iter()
+        .filter(|step| step.checkuuid == checkuuid)
+        .map(|step| step.into())
+        .collect();
+    StationRecheck { check: check.into(), steps }.get_response(format)
+}
+
 fn encode_station_url<A>(connection_new: &A, station: Option<DbStationItem>, ip: &str, format : &str, seconds: u64, registry: RegistryLinks) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
     Ok(match station {
         Some(station) => {
@@ -290,8 +568,18 @@ pub fn start<A: 'static +  std::clone::Clone>(
             });
         }
 
+        let rate_limiter = RateLimiter::new(&config);
+        let rate_limiter_cleanup = rate_limiter.clone();
+        thread::spawn(move || {
+            loop{
+                trace!("Rate limiter cleanup run..");
+                rate_limiter_cleanup.cleanup();
+                thread::sleep(Duration::from_secs(60));
+            }
+        });
+
         rouille::start_server_with_pool(listen_str, Some(config.threads), move |request| {
-            handle_connection(&connection_new, request, config.clone(), registry.clone(), cache.clone())
+            handle_connection(&connection_new, request, config.clone(), registry.clone(), cache.clone(), &rate_limiter)
         });
     }
 }
@@ -385,7 +673,8 @@ fn handle_connection<A>(
     config: Config,
     registry: RegistryLinks,
     cache: cache::GenericCache,
-) -> rouille::Response where A: DbConnection {
+    rate_limiter: &RateLimiter,
+) -> rouille::Response where A: DbConnection + Clone + Send + 'static {
     let remote_ip: String = request.header("X-Forwarded-For").unwrap_or(&request.remote_addr().ip().to_string()).to_string();
     let referer: String = request.header("Referer").unwrap_or(&"-".to_string()).to_string();
     let user_agent: String = request.header("User-agent").unwrap_or(&"-".to_string()).to_string();
@@ -426,6 +715,9 @@ fn handle_connection<A>(
         log_to_file(&log_file, &line);
     };
     rouille::log_custom(request, log_ok, log_err, || {
+        if let Some(response) = check_rate_limit(request, rate_limiter, &registry) {
+            return add_cors(response);
+        }
         let timer = registry.timer.with_label_values(&[request.method()]).start_timer();
         let result = handle_cached_connection(connection_new, request, config, registry, cache);
         let r = match result {
@@ -441,18 +733,71 @@ fn handle_connection<A>(
     })
 }
 
+/// Response with status 429 if the client did too many requests to this endpoint
+fn check_rate_limit(request: &rouille::Request, rate_limiter: &RateLimiter, registry: &RegistryLinks) -> Option<rouille::Response> {
+    if request.method() == "OPTIONS" {
+        return None;
+    }
+    let kind = RateLimitKind::from_url(request.raw_url())?;
+    let client = rate_limiter.client_ip(request.header("X-Forwarded-For"), &request.remote_addr().ip());
+    match rate_limiter.check(kind, &client) {
+        Ok(_) => None,
+        Err(wait) => {
+            registry.rate_limited.with_label_values(&[kind.as_str()]).inc();
+            let retry_after = wait.as_secs() + if wait.subsec_nanos() > 0 { 1 } else { 0 };
+            Some(rouille::Response::text("too many requests")
+                .with_status_code(429)
+                .with_unique_header("Retry-After", retry_after.to_string()))
+        }
+    }
+}
+
+/// Sets the Content-Type by the format in the first part of the url
+fn with_content_type(result: rouille::Response, url_path: &str) -> rouille::Response {
+    let url_parts: Vec<&str> = url_path.split('/').collect();
+    if url_parts.len() > 1 {
+        let output_content_type_short = url_parts[1];
+        trace!("Parsed output content type: '{}'",output_content_type_short);
+        match output_content_type_short {
+            "html" => result.with_unique_header("Content-Type", "text/html"),
+            "" => result.with_unique_header("Content-Type", "text/html"),
+            "json" | "openapi.json" | "graphql" => result.with_unique_header("Content-Type", "application/json"),
+            "xml" => result.with_unique_header("Content-Type", "text/xml"),
+            "m3u" => result.with_unique_header("Content-Type", "audio/mpegurl").with_unique_header("Content-Disposition", r#"inline; filename="playlist.m3u""#),
+            "pls" => result.with_unique_header("Content-Type", "audio/x-scpls").with_unique_header("Content-Disposition", r#"inline; filename="playlist.pls""#),
+            "xspf" => result.with_unique_header("Content-Type", "application/xspf+xml").with_unique_header("Content-Disposition", r#"inline; filename="playlist.xspf""#),
+            "ttl" => result.with_unique_header("Content-Type", "text/turtle"),
+            "geojson" => result.with_unique_header("Content-Type", "application/geo+json"),
+            "kml" => result.with_unique_header("Content-Type", "application/vnd.google-earth.kml+xml").with_unique_header("Content-Disposition", r#"inline; filename="stations.kml""#),
+            _ => result,
+        }
+    }else{
+        result
+    }
+}
+
 fn handle_cached_connection<A>(
     connection_new: &A,
     request: &rouille::Request,
     config: Config,
     registry: RegistryLinks,
     mut cache: cache::GenericCache,
-) -> Result<rouille::Response, Box<dyn std::error::Error>> where A: DbConnection {
+) -> Result<rouille::Response, Box<dyn std::error::Error>> where A: DbConnection + Clone + Send + 'static {
     if request.method() == "OPTIONS" {
         return Ok(rouille::Response::empty_204());
     }
-    if request.method() != "POST" && request.method() != "GET" {
-        return Ok(rouille::Response::empty_404());
+    let path = request.raw_url().split('?').next().unwrap_or("");
+    let found = match routes::find(path) {
+        Some(found) => found,
+        None => return Ok(rouille::Response::empty_404()),
+    };
+    if !found.route.allows(request.method()) {
+        return Ok(rouille::Response::text("")
+            .with_status_code(405)
+            .with_unique_header("Allow", found.route.methods.join(", ")));
+    }
+    if !found.format_allowed() {
+        return Ok(rouille::Response::empty_406());
     }
 
     let header_host = request.header("X-Forwarded-Host").or(request.header("Host"));
@@ -484,7 +829,10 @@ fn handle_cached_connection<A>(
         param_last_changeuuid: ppp.get_string("lastchangeuuid").map(|item|item.to_lowercase()),
         param_last_checkuuid: ppp.get_string("lastcheckuuid").map(|item|item.to_lowercase()),
         param_last_clickuuid: ppp.get_string("lastclickuuid").map(|item|item.to_lowercase()),
+        // EventSource sends the header on reconnects, polyfills use the parameter
+        param_last_event_id: request.header("Last-Event-ID").map(String::from).or(ppp.get_string("lastEventId")),
     
+        param_query: ppp.get_string("query").filter(|query| !query.trim().is_empty()),
         param_name: ppp.get_string("name"),
         param_name_exact: ppp.get_bool("nameExact", false),
         param_country: ppp.get_string("country"),
@@ -510,250 +858,445 @@ fn handle_cached_connection<A>(
         param_is_https: ppp.get_bool_opt("is_https")?,
         
         param_offset: ppp.get_number("offset", 0),
-        param_limit: ppp.get_number("limit", 999999),
+        param_limit: ppp.get_number_opt("limit"),
+        param_cursor: ppp.get_string("cursor"),
+        param_envelope: ppp.get_bool("envelope", false),
+        param_includecount: ppp.get_bool("includecount", false),
+        param_fields: ppp.get_string("fields"),
+        param_variables: ppp.get_string("variables"),
+        param_operation_name: ppp.get_string("operationName"),
     
         param_seconds: ppp.get_number("seconds", 0),
         param_url: ppp.get_string("url"),
         param_geo_lat: ppp.get_double("geo_lat", None),
         param_geo_long: ppp.get_double("geo_long", None),
+        param_geo_distance: ppp.get_double("geo_distance", None),
+        param_geo_lat_min: ppp.get_double("geo_lat_min", None),
+        param_geo_lat_max: ppp.get_double("geo_lat_max", None),
+        param_geo_long_min: ppp.get_double("geo_long_min", None),
+        param_geo_long_max: ppp.get_double("geo_long_max", None),
+        param_force: ppp.get_bool("force", false),
+        param_secret: ppp.get_string("secret"),
+        param_events: ppp.get_string("events"),
+        param_stationuuid: ppp.get_string("stationuuid").map(|item|item.to_lowercase()),
     };
 
     let key = allparams.to_string()?;
     let cached_item = cache.get(&key);
-    let mut is_text = false;
-    let result: rouille::Response = match cached_item {
+    let cache_ttl = config.cache_ttl.as_secs();
+    // text responses with their max-age, everything else is sent as it is
+    let result: Result<(cache::CacheItem, Option<u64>), rouille::Response> = match cached_item {
         Some(cached_item) => {
             registry.cache_hits.inc();
-            is_text = true;
-            rouille::Response::text(cached_item)
+            let age = (chrono::Utc::now() - cached_item.modified).num_seconds().max(0) as u64;
+            Ok((cached_item, Some(cache_ttl.saturating_sub(age))))
         },
         None => {
             registry.cache_misses.inc();
-            let (do_cache, response) = do_api_calls(allparams, connection_new, config, registry, base_url, content_type, remote_ip)?;
+            let auth = ApiAuth::from_header(request.header("Authorization"), &config);
+            let do_cache = found.route.cacheable;
+            let response = do_api_calls(allparams, &found, connection_new, config, registry, base_url, content_type, remote_ip, auth)?;
+            let mut store = |item: cache::CacheItem| {
+                if do_cache {
+                    cache.set(&key, &item);
+                    (item, Some(cache_ttl))
+                }else{
+                    (item, None)
+                }
+            };
 
             match response {
                 ApiResponse::Text(text) => {
-                    is_text = true;
-                    if do_cache {
-                        cache.set(&key, &text);
-                        rouille::Response::text(text)
-                    }else{
-                        rouille::Response::text(text).with_no_cache()
+                    Ok(store(cache::CacheItem::new(text.into_bytes())))
+                },
+                ApiResponse::Paged(text, next, total_count) => {
+                    Ok(store(cache::CacheItem::new(text.into_bytes()).with_next(next).with_total_count(total_count)))
+                },
+                ApiResponse::Stream(producer) => {
+                    let encoding = compression::ContentEncoding::from_accept_encoding(request.header("Accept-Encoding"));
+                    let modified = chrono::Utc::now();
+                    // the body and its entity tag are collected while it is sent, the next requests get both from the cache
+                    let producer: StreamProducer = if do_cache && cache.is_enabled() {
+                        let mut cache = cache.clone();
+                        let key = key.clone();
+                        Box::new(move |writer| {
+                            let mut tee = stream::TeeWriter::new(writer);
+                            producer(&mut tee)?;
+                            let (body, etag) = tee.into_copy();
+                            cache.set(&key, &cache::CacheItem { body, modified, etag, next: None, total_count: None });
+                            Ok(())
+                        })
+                    } else {
+                        producer
+                    };
+                    let mut response = rouille::Response::text("");
+                    response.data = stream::stream_body(encoding, producer);
+                    if let Some(encoding) = encoding {
+                        response = response.with_unique_header("Content-Encoding", encoding.as_str());
                     }
+                    let response = with_content_type(response, &request.url())
+                        .with_unique_header("Vary", "Accept-Encoding");
+                    Err(if do_cache {
+                        response
+                            .with_unique_header("Last-Modified", conditional::http_date(&modified))
+                            .with_unique_header("Cache-Control", format!("public, max-age={}", cache_ttl))
+                    } else {
+                        response.with_no_cache()
+                    })
+                },
+                ApiResponse::Events(producer) => {
+                    let mut response = rouille::Response::text("")
+                        .with_unique_header("Content-Type", "text/event-stream")
+                        .with_no_cache();
+                    response.data = stream::stream_body(None, producer);
+                    Err(response)
                 },
                 ApiResponse::File(content_type, file) => {
-                    rouille::Response::from_file(content_type, file)
+                    Err(rouille::Response::from_file(content_type, file))
                 },
                 ApiResponse::NotFound => {
-                    rouille::Response::empty_404()
+                    Err(rouille::Response::empty_404())
                 },
                 ApiResponse::UnknownContentType => {
-                    rouille::Response::empty_406()
+                    Err(rouille::Response::empty_406())
                 },
                 ApiResponse::ServerError(msg) => {
-                    rouille::Response::text(msg).with_status_code(500)
+                    Err(rouille::Response::text(msg).with_status_code(500))
                 },
                 ApiResponse::Locked(msg) => {
-                    rouille::Response::text(msg).with_status_code(423)
+                    Err(rouille::Response::text(msg).with_status_code(423))
+                },
+                ApiResponse::Unauthorized(msg) => {
+                    Err(rouille::Response::text(msg).with_status_code(401).with_unique_header("WWW-Authenticate", "Bearer"))
+                },
+                ApiResponse::Forbidden(msg) => {
+                    Err(rouille::Response::text(msg).with_status_code(403))
+                },
+                ApiResponse::Unavailable(msg) => {
+                    Err(rouille::Response::text(msg).with_status_code(503).with_unique_header("Retry-After", "60"))
                 },
-                /*
                 ApiResponse::ParameterError(msg) => {
-                    rouille::Response::text(msg).with_status_code(400)
+                    Err(rouille::Response::text(msg).with_status_code(400))
                 },
-                */
             }
         }
     };
+    let (item, max_age) = match result {
+        Ok(text) => text,
+        Err(response) => return Ok(response),
+    };
 
-    if is_text {
-        let url_path = request.url();
-        let url_parts: Vec<&str> = url_path.split('/').collect();
-        let response = if url_parts.len() > 1 {
-            let output_content_type_short = url_parts[1];
-            trace!("Parsed output content type: '{}'",output_content_type_short);
-            match output_content_type_short {
-                "html" => result.with_unique_header("Content-Type", "text/html"),
-                "" => result.with_unique_header("Content-Type", "text/html"),
-                "json" => result.with_unique_header("Content-Type", "application/json"),
-                "xml" => result.with_unique_header("Content-Type", "text/xml"),
-                "m3u" => result.with_unique_header("Content-Type", "audio/mpegurl").with_unique_header("Content-Disposition", r#"inline; filename="playlist.m3u""#),
-                "pls" => result.with_unique_header("Content-Type", "audio/x-scpls").with_unique_header("Content-Disposition", r#"inline; filename="playlist.pls""#),
-                "xspf" => result.with_unique_header("Content-Type", "application/xspf+xml").with_unique_header("Content-Disposition", r#"inline; filename="playlist.xspf""#),
-                "ttl" => result.with_unique_header("Content-Type", "text/turtle"),
-                _ => result,
+    let mut etag = item.etag;
+    let modified = item.modified;
+    let next = item.next;
+    let total_count = item.total_count;
+    let mut body = item.body;
+    let encoding = compression::ContentEncoding::from_accept_encoding(request.header("Accept-Encoding"))
+        .filter(|_| body.len() >= compression::MIN_COMPRESS_SIZE);
+    if let Some(encoding) = encoding {
+        let key_encoded = format!("{}|{}", key, encoding.as_str());
+        etag = format!("{}-{}\"", etag.trim_end_matches('"'), encoding.as_str());
+        // the compressed variant has to belong to the same version of the uncompressed body
+        let cached_encoded = cache.get(&key_encoded).filter(|cached| cached.modified == modified);
+        body = match cached_encoded {
+            Some(cached_encoded) => cached_encoded.body,
+            None => {
+                let encoded = cache::CacheItem {
+                    body: encoding.compress(&body)?,
+                    modified,
+                    etag: etag.clone(),
+                    next: None,
+                    total_count: None,
+                };
+                if max_age.is_some() {
+                    cache.set(&key_encoded, &encoded);
+                }
+                encoded.body
             }
-        }else{
-            result
         };
+    }
 
-        Ok(response)
-    }else{
-        Ok(result)
+    let result = rouille::Response::from_data("text/plain; charset=utf-8", body);
+    let result = match encoding {
+        Some(encoding) => result.with_unique_header("Content-Encoding", encoding.as_str()),
+        None => result,
+    };
+    let response = with_content_type(result, &request.url())
+        .with_unique_header("Vary", "Accept-Encoding");
+    let response = match next {
+        Some(next) => response.with_unique_header("Link", format!("<{}>; rel=\"next\"", next)),
+        None => response,
+    };
+    let response = match total_count {
+        Some(total_count) => response.with_unique_header("X-Total-Count", total_count.to_string()),
+        None => response,
+    };
+    // only cacheable answers can be validated, a vote or a click has to reach the server every time
+    let max_age = match max_age {
+        Some(max_age) => max_age,
+        None => return Ok(response.with_no_cache()),
+    };
+    let mut response = response
+        .with_unique_header("ETag", etag.clone())
+        .with_unique_header("Last-Modified", conditional::http_date(&modified))
+        .with_unique_header("Cache-Control", format!("public, max-age={}", max_age));
+
+    if request.method() == "GET" && conditional::is_not_modified(request.header("If-None-Match"), request.header("If-Modified-Since"), &etag, &modified) {
+        response.status_code = 304;
+        response.data = rouille::ResponseBody::empty();
     }
+    Ok(response)
+}
+
+/// Lists of all stations are tens of megabytes, so they are streamed from the database
+/// instead of being built in memory
+fn stations_all<A>(connection_new: &A, all_params: &AllParameters, cursor: Option<&StationCursor>, pagination: &Pagination, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection + Clone + Send + 'static {
+    // the link to the next page is only known after the last station of the page
+    let paged = cursor.is_some() || all_params.param_envelope || all_params.param_includecount || all_params.param_limit.is_some();
+    if paged || !Station::can_stream(format) {
+        let list = connection_new.get_stations_by_all(&all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, pagination.fields(), cursor, all_params.param_offset, all_params.limit())?;
+        let total_count = if all_params.param_includecount {
+            let working = connection_new.get_station_count_working()?;
+            if all_params.param_hidebroken {
+                Some(working)
+            } else {
+                Some(working + connection_new.get_station_count_broken()?)
+            }
+        } else {
+            None
+        };
+        return pagination.response(list, total_count, format);
+    }
+    let connection = connection_new.clone();
+    let format = format.to_string();
+    let fields = pagination.fields().cloned();
+    let order = all_params.param_order.clone();
+    let reverse = all_params.param_reverse;
+    let hidebroken = all_params.param_hidebroken;
+    let offset = all_params.param_offset;
+    let limit = all_params.limit();
+    Ok(ApiResponse::Stream(Box::new(move |writer| {
+        let mut list_writer = StationListWriter::new(&format, writer, fields.clone())?.ok_or("format can not be streamed")?;
+        connection.for_each_station_by_all(&order, reverse, hidebroken, fields.as_ref(), offset, limit, &mut |station| list_writer.write(station.into()))?;
+        list_writer.finish()?;
+        Ok(())
+    })))
+}
+
+/// Stations with the search in the column, with their total count if includecount is set
+fn stations_by_column<A>(connection_new: &A, all_params: &AllParameters, cursor: Option<&StationCursor>, pagination: &Pagination, column: &str, search: &str, exact: bool, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
+    let list = connection_new.get_stations_by_column(column, search.to_string(), exact, &all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, pagination.fields(), cursor, all_params.param_offset, all_params.limit())?;
+    let total_count = if all_params.param_includecount {
+        Some(connection_new.count_stations_by_column(column, search.to_string(), exact, all_params.param_hidebroken)?)
+    } else {
+        None
+    };
+    pagination.response(list, total_count, format)
+}
+
+/// Stations with the search in one of the comma separated values of the column, with their total count if includecount is set
+fn stations_by_column_multiple<A>(connection_new: &A, all_params: &AllParameters, cursor: Option<&StationCursor>, pagination: &Pagination, column: &str, search: Option<String>, exact: bool, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
+    let total_count = if all_params.param_includecount {
+        Some(connection_new.count_stations_by_column_multiple(column, search.clone(), exact, all_params.param_hidebroken)?)
+    } else {
+        None
+    };
+    let list = connection_new.get_stations_by_column_multiple(column, search, exact, &all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, pagination.fields(), cursor, all_params.param_offset, all_params.limit())?;
+    pagination.response(list, total_count, format)
+}
+
+/// Number of stations that match the search, with geo_distance instead of the distance parameter
+fn count_stations_search<A>(connection_new: &A, all_params: &AllParameters, geo_distance: Option<f64>) -> Result<u64, Box<dyn Error>> where A: DbConnection {
+    let geo_bbox = all_params.geo_bbox();
+    match all_params.param_query {
+        Some(ref query) => connection_new.count_stations_search(query, all_params.param_name.clone(), all_params.param_name_exact, all_params.param_country.clone(),
+            all_params.param_country_exact, all_params.param_countrycode.clone(), all_params.param_state.clone(), all_params.param_state_exact, all_params.param_language.clone(), all_params.param_language_exact, all_params.param_tag.clone(),
+            all_params.param_tag_exact, all_params.param_tag_list.clone(), all_params.param_codec.clone(), all_params.param_bitrate_min, all_params.param_bitrate_max, all_params.param_has_geo_info, all_params.param_has_extended_info, all_params.param_is_https,
+            all_params.param_geo_lat, all_params.param_geo_long, geo_distance, geo_bbox, all_params.param_hidebroken),
+        None => connection_new.count_stations_advanced(all_params.param_name.clone(), all_params.param_name_exact, all_params.param_country.clone(),
+            all_params.param_country_exact, all_params.param_countrycode.clone(), all_params.param_state.clone(), all_params.param_state_exact, all_params.param_language.clone(), all_params.param_language_exact, all_params.param_tag.clone(),
+            all_params.param_tag_exact, all_params.param_tag_list.clone(), all_params.param_codec.clone(), all_params.param_bitrate_min, all_params.param_bitrate_max, all_params.param_has_geo_info, all_params.param_has_extended_info, all_params.param_is_https,
+            all_params.param_geo_lat, all_params.param_geo_long, geo_distance, geo_bbox, all_params.param_hidebroken),
+    }
+}
+
+/// Advanced search, or fuzzy search if there is a query, with the total count of results if includecount is set
+fn stations_search<A>(connection_new: &A, all_params: AllParameters, cursor: Option<&StationCursor>, pagination: &Pagination, format: &str) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection {
+    let geo_bbox = all_params.geo_bbox();
+    let limit = all_params.limit();
+    let total_count = if all_params.param_includecount {
+        Some(count_stations_search(connection_new, &all_params, all_params.param_geo_distance)?)
+    } else {
+        None
+    };
+    // the nearest stations of the page are in a smaller circle, only its bounding box has to be read from the database.
+    // Without a limit the whole list is wanted, counting in circles would only add scans.
+    let nearest = all_params.param_order == "distance" && !all_params.param_reverse && cursor.is_none() && all_params.param_limit.is_some()
+        && all_params.param_geo_lat.is_some() && all_params.param_geo_long.is_some();
+    let geo_distance = if nearest {
+        let wanted = u64::from(all_params.param_offset) + u64::from(limit);
+        nearest_radius(wanted, all_params.param_geo_distance, |radius| count_stations_search(connection_new, &all_params, Some(radius)))?
+    } else {
+        all_params.param_geo_distance
+    };
+    let list = match all_params.param_query {
+        Some(query) => connection_new.get_stations_search(&query, all_params.param_name, all_params.param_name_exact, all_params.param_country,
+            all_params.param_country_exact, all_params.param_countrycode, all_params.param_state, all_params.param_state_exact, all_params.param_language, all_params.param_language_exact, all_params.param_tag,
+            all_params.param_tag_exact, all_params.param_tag_list, all_params.param_codec, all_params.param_bitrate_min, all_params.param_bitrate_max, all_params.param_has_geo_info, all_params.param_has_extended_info, all_params.param_is_https,
+            all_params.param_geo_lat, all_params.param_geo_long, geo_distance, geo_bbox, &all_params.param_order,all_params.param_reverse,
+            all_params.param_hidebroken,cursor,all_params.param_offset,limit)?,
+        None => connection_new.get_stations_advanced(all_params.param_name, all_params.param_name_exact, all_params.param_country,
+            all_params.param_country_exact, all_params.param_countrycode, all_params.param_state, all_params.param_state_exact, all_params.param_language, all_params.param_language_exact, all_params.param_tag,
+            all_params.param_tag_exact, all_params.param_tag_list, all_params.param_codec, all_params.param_bitrate_min, all_params.param_bitrate_max, all_params.param_has_geo_info, all_params.param_has_extended_info, all_params.param_is_https,
+            all_params.param_geo_lat, all_params.param_geo_long, geo_distance, geo_bbox, &all_params.param_order,all_params.param_reverse,
+            all_params.param_hidebroken,pagination.fields(),cursor,all_params.param_offset,limit)?,
+    };
+    pagination.response(list, total_count, format)
 }
 
-fn do_api_calls<A>(all_params: AllParameters,
+fn do_api_calls<A>(mut all_params: AllParameters,
+    found: &routes::RouteMatch,
     connection_new: &A,
     config: Config,
     registry: RegistryLinks,
     base_url: String,
     content_type: &str,
     remote_ip: String,
-) -> Result<(bool, ApiResponse), Box<dyn Error>> where A: DbConnection {
-    use percent_encoding::percent_decode_str;
+    auth: ApiAuth,
+) -> Result<ApiResponse, Box<dyn Error>> where A: DbConnection + Clone + Send + 'static {
+    use routes::Handler;
     trace!("content_type: {}", content_type);
-    let parts : Vec<&str> = all_params.url.split('?').collect();
-    let items : Vec<String> = parts[0].split('/').map(|item| {
-        let x = percent_decode_str(item);
-        let y = x.decode_utf8_lossy();
-        y.into_owned()
-    }).collect();
-
-    if items.len() == 2 {
-        let file_name: &str = &items[1];
-        match file_name {
-            "metrics" => {
-                if config.prometheus_exporter {
-                    Ok((false, prometheus_exporter::render(connection_new, config.broken_stations_never_working_timeout.as_secs(), config.broken_stations_timeout.as_secs(), registry)?))
-                }else{
-                    Ok((true, ApiResponse::Locked("Exporter not enabled!".to_string())))
-                }
-            },
-            "favicon.ico" => Ok((true,send_file(&format!("{}/{}",config.static_files_dir,"favicon.ico"), "image/png"))),
-            "robots.txt" => Ok((true,send_file(&format!("{}/{}",config.static_files_dir,"robots.txt"), "text/plain"))),
-            "main.css" => Ok((true,send_file(&format!("{}/{}",config.static_files_dir,"main.css"),"text/css"))),
-            "" => {
-                let mut handlebars = Handlebars::new();
-                let y = handlebars.register_template_file("docs.hbs", &format!("{}/{}",config.static_files_dir,"docs.hbs"));
-                if y.is_ok() {
-                    let pkg_version = env!("CARGO_PKG_VERSION");
-                    let mut data = Map::new();
-                    data.insert(String::from("API_SERVER"), to_json(base_url));
-                    data.insert(String::from("SERVER_VERSION"), to_json(format!("{version}",version = pkg_version)));
-                    let rendered = handlebars.render("docs.hbs", &data)?;
-                    Ok((true, ApiResponse::Text(rendered)))
-                }else{
-                    error!("unable register template file: docs.hbs");
-                    Ok((false, ApiResponse::ServerError("unable to render docs".to_string())))
-                }
-            }
-            _ => Ok((false, ApiResponse::NotFound)),
+
+    // a cursor continues the list in the order it was created for
+    let cursor = match all_params.param_cursor {
+        Some(ref token) => match StationCursor::decode(token) {
+            Some(cursor) => Some(cursor),
+            None => return Ok(ApiResponse::ParameterError(String::from("invalid cursor"))),
+        },
+        None => None,
+    };
+    if let Some(ref cursor) = cursor {
+        if all_params.param_query.is_some() {
+            return Ok(ApiResponse::ParameterError(String::from("cursor can not be used with a fuzzy query")));
         }
-    } else if items.len() == 3 {
-        let format:&str = &items[1];
-        let command:&str = &items[2];
-        let filter : Option<String> = None;
-
-        match command {
-            "languages" => Ok((true,ApiLanguage::get_response(connection_new.get_extra("LanguageCache", "LanguageName", filter, all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?, format)?)),
-            "countries" => Ok((true,ApiCountry::get_response(connection_new.get_countries(filter, all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?, format)?)),
-            "countrycodes" => Ok((true,encode_extra(connection_new.get_1_n("CountryCode", filter, all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?, format, "countrycode")?)),
-            "states" => Ok((true,encode_states(connection_new.get_states(None, filter, all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?, format)?)),
-            "codecs" => Ok((true,encode_extra(connection_new.get_1_n("Codec", filter, all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?, format, "codec")?)),
-            "tags" => Ok((true,encode_extra(connection_new.get_extra("TagCache", "TagName", filter, all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?, format, "tag")?)),
-            "stations" => Ok((true,Station::get_response(connection_new.get_stations_by_all(&all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?.drain(..).map(|x|x.into()).collect(), format)?)),
-            "servers" => Ok((true,dns_resolve(format)?)),
-            "stats" => Ok((true,encode_status(get_status(connection_new)?, format, &config.static_files_dir))),
-            "checks" => Ok((true,StationCheck::get_response(connection_new.get_checks(None, all_params.param_last_checkuuid, all_params.param_seconds, false, all_params.param_limit)?.drain(..).map(|x|x.into()).collect(),format)?)),
-            "clicks" => Ok((true,StationClick::get_response(connection_new.get_clicks(None, all_params.param_last_clickuuid, all_params.param_seconds)?.drain(..).map(|x|x.into()).collect(),format)?)),
-            "checksteps" => Ok((true,StationCheckStep::get_response(connection_new.select_station_check_steps_by_stations(&all_params.param_uuids)?.drain(..).map(|x|x.into()).collect(), format)?)),
-            "add" => Ok((false,StationAddResult::from(connection_new.add_station_opt(all_params.param_name, all_params.param_url, all_params.param_homepage, all_params.param_favicon, all_params.param_countrycode, all_params.param_state, all_params.param_language, all_params.param_language_codes, all_params.param_tags, all_params.param_geo_lat, all_params.param_geo_long)).get_response(format)?)),
-            "config" => Ok((true,ApiConfig::get_response(config.into(),format)?)),
-            "streamingservers" => Ok((true,ApiStreamingServer::get_response(connection_new.get_streaming_servers(&all_params.param_order, all_params.param_reverse, all_params.param_offset, all_params.param_limit)?,format)?)),
-            _ => Ok((true,ApiResponse::NotFound)),
+        if !StationCursor::supports_order(&all_params.param_order) {
+            return Ok(ApiResponse::ParameterError(format!("cursor can not be used with order={}", all_params.param_order)));
         }
-    } else if items.len() == 4 {
-        let format:&str = &items[1];
-        let command:&str = &items[2];
-        let parameter:&str = &items[3];
-
-        // None => connection_new.get_1_n("Country", filter, param_order, param_reverse, param_hidebroken)?, format, "country")?,
-        match command {
-            "languages" => Ok((true,ApiLanguage::get_response(connection_new.get_extra("LanguageCache", "LanguageName", Some(String::from(parameter)), all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?, format)?)),
-            "countries" => Ok((true,ApiCountry::get_response(connection_new.get_countries(Some(String::from(parameter)), all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?, format)?)),
-            "countrycodes" => Ok((true,encode_extra(connection_new.get_1_n("CountryCode", Some(String::from(parameter)), all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?, format, "countrycode")?)),
-            "codecs" => Ok((true,encode_extra(connection_new.get_1_n("Codec", Some(String::from(parameter)), all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?, format, "codec")?)),
-            "tags" => Ok((true,encode_extra(connection_new.get_extra("TagCache", "TagName", Some(String::from(parameter)), all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?, format, "tag")?)),
-            "states" => Ok((true,encode_states(connection_new.get_states(None, Some(String::from(parameter)), all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?, format)?)),
-            "vote" => Ok((false,encode_message(connection_new.vote_for_station(&remote_ip, get_only_first_item(connection_new.get_station_by_uuid(parameter)?)), format)?)),
-            "url" => Ok((false,encode_station_url(connection_new, get_only_first_item(connection_new.get_station_by_uuid(parameter)?), &remote_ip, format, config.click_valid_timeout.as_secs(),registry)?)),
-            "stations" => {
-                match parameter {
-                    "topvote" => Ok((true,Station::get_response(connection_new.get_stations_topvote(all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                    "topclick" => Ok((true,Station::get_response(connection_new.get_stations_topclick(all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                    "lastclick" => Ok((true,Station::get_response(connection_new.get_stations_lastclick(all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                    "lastchange" => Ok((true,Station::get_response(connection_new.get_stations_lastchange(all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                    "broken" => Ok((true,Station::get_response(connection_new.get_stations_broken(all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                    "changed" => Ok((true,encode_changes(connection_new.get_changes(None, all_params.param_last_changeuuid, all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                    "byurl" => Ok((true,Station::get_response(connection_new.get_stations_by_column_multiple("Url", all_params.param_url,true,&all_params.param_order,all_params.param_reverse,
-                        all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                    "byserveruuid" => Ok((true,Station::get_response(connection_new.get_stations_by_server_uuids(all_params.param_uuids, &all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                    //"byserverurl" => Ok((true,Station::get_response(connection_new.get_stations_by_uuid(all_params.param_uuids)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                    "byuuid" => Ok((true,Station::get_response(connection_new.get_stations_by_uuid(all_params.param_uuids)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                    "search" => Ok((true,Station::get_response(connection_new.get_stations_advanced(all_params.param_name, all_params.param_name_exact, all_params.param_country,
-                        all_params.param_country_exact, all_params.param_countrycode, all_params.param_state, all_params.param_state_exact, all_params.param_language, all_params.param_language_exact, all_params.param_tag,
-                        all_params.param_tag_exact, all_params.param_tag_list, all_params.param_codec, all_params.param_bitrate_min, all_params.param_bitrate_max, all_params.param_has_geo_info, all_params.param_has_extended_info, all_params.param_is_https, &all_params.param_order,all_params.param_reverse,
-                        all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                    _ => Ok((true,ApiResponse::NotFound)),
-                }
-            },
-            "streamingservers" => {
-                match parameter {
-                    "byserveruuid" => Ok((true,ApiStreamingServer::get_response(connection_new.get_streaming_servers_by_uuids(all_params.param_uuids, &all_params.param_order, all_params.param_reverse, all_params.param_offset, all_params.param_limit)?,format)?)),
-                    "bystationuuid" => Ok((true,ApiStreamingServer::get_response(connection_new.get_streaming_servers_by_station_uuids(all_params.param_uuids, &all_params.param_order, all_params.param_reverse, all_params.param_offset, all_params.param_limit)?,format)?)),
-                    _ => Ok((true,ApiResponse::NotFound)),
-                }
-            },
-            "checks" => Ok((true,StationCheck::get_response(connection_new.get_checks(Some(parameter.to_string()), all_params.param_last_checkuuid, all_params.param_seconds, true, all_params.param_limit)?.drain(..).map(|x|x.into()).collect(), format)?)),
-            "clicks" => Ok((true,StationClick::get_response(connection_new.get_clicks(Some(parameter.to_string()), all_params.param_last_clickuuid, all_params.param_seconds)?.drain(..).map(|x|x.into()).collect(), format)?)),
-            _ => Ok((true,ApiResponse::NotFound)),
-        }
-    } else if items.len() == 5 {
-        let format:&str = &items[1];
-        let command:&str = &items[2];
-        let parameter:&str = &items[3];
-        let search:&str = &items[4];
-        if format == "v2" {
-            // deprecated
-            let format = command;
-            let command = parameter;
-            match command {
-                "url" => Ok((false,encode_station_url(connection_new, get_only_first_item(connection_new.get_station_by_uuid(search)?), &remote_ip, format, config.click_valid_timeout.as_secs(), registry)?)),
-                _ => Ok((false,ApiResponse::NotFound)),
+        all_params.param_order = cursor.order.clone();
+        all_params.param_reverse = cursor.reverse;
+    }
+    let fields = match all_params.param_fields {
+        Some(ref list) => match StationFields::parse(list) {
+            Ok(fields) => Some(fields),
+            Err(err) => return Ok(ApiResponse::ParameterError(format!("invalid fields: {}", err))),
+        },
+        None => None,
+    };
+    if let Err(message) = all_params.check_geo() {
+        return Ok(ApiResponse::ParameterError(message));
+    }
+    let limit = all_params.limit();
+    let pagination = Pagination::new(&all_params, fields);
+
+    let format = found.format();
+    let filter = found.param("filter").map(String::from);
+    let stationuuid = found.param("stationuuid");
+    let search = found.param("searchterm").unwrap_or("");
+    // lists of top stations have their limit in the path or as parameter
+    let top_limit = found.param("limit").map(|limit| limit.parse().unwrap_or(0)).unwrap_or(limit);
+
+    match found.route.handler {
+        Handler::Docs => {
+            let mut handlebars = Handlebars::new();
+            let y = handlebars.register_template_file("docs.hbs", &format!("{}/{}",config.static_files_dir,"docs.hbs"));
+            if y.is_ok() {
+                let pkg_version = env!("CARGO_PKG_VERSION");
+                let mut data = Map::new();
+                data.insert(String::from("API_SERVER"), to_json(base_url));
+                data.insert(String::from("SERVER_VERSION"), to_json(format!("{version}",version = pkg_version)));
+                let rendered = handlebars.render("docs.hbs", &data)?;
+                Ok(ApiResponse::Text(rendered))
+            }else{
+                error!("unable register template file: docs.hbs");
+                Ok(ApiResponse::ServerError("unable to render docs".to_string()))
             }
-        }else{
-            match command {
-                "states" => Ok((true,encode_states(connection_new.get_states(Some(String::from(parameter)), Some(String::from(search)), all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, all_params.param_limit)?, format)?)),
-                
-                "stations" => {
-                    match parameter {
-                        "topvote" => Ok((true,Station::get_response(connection_new.get_stations_topvote(all_params.param_hidebroken, all_params.param_offset, search.parse().unwrap_or(0))?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "topclick" => Ok((true,Station::get_response(connection_new.get_stations_topclick(all_params.param_hidebroken, all_params.param_offset, search.parse().unwrap_or(0))?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "lastclick" => Ok((true,Station::get_response(connection_new.get_stations_lastclick(all_params.param_hidebroken, all_params.param_offset, search.parse().unwrap_or(0))?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "lastchange" => Ok((true,Station::get_response(connection_new.get_stations_lastchange(all_params.param_hidebroken, all_params.param_offset, search.parse().unwrap_or(0))?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "broken" => Ok((true,Station::get_response(connection_new.get_stations_broken(all_params.param_offset, search.parse().unwrap_or(0))?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "byname" => Ok((true,Station::get_response(connection_new.get_stations_by_column("Name", search.to_string(),false,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "bynameexact" => Ok((true,Station::get_response(connection_new.get_stations_by_column("Name", search.to_string(),true,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "bycodec" => Ok((true,Station::get_response(connection_new.get_stations_by_column("Codec", search.to_string(),false,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "bycodecexact" => Ok((true,Station::get_response(connection_new.get_stations_by_column("Codec", search.to_string(),true,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "bycountry" => Ok((true,Station::get_response(connection_new.get_stations_by_column("Country", search.to_string(),false,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "bycountryexact" => Ok((true,Station::get_response(connection_new.get_stations_by_column("Country", search.to_string(),true,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "bycountrycodeexact" => Ok((true,Station::get_response(connection_new.get_stations_by_column("CountryCode", search.to_string(),true,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "bystate" => Ok((true,Station::get_response(connection_new.get_stations_by_column("Subcountry", search.to_string(),false,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "bystateexact" => Ok((true,Station::get_response(connection_new.get_stations_by_column("Subcountry", search.to_string(),true,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "bytag" => Ok((true,Station::get_response(connection_new.get_stations_by_column_multiple("Tags", Some(search.to_string()),false,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "bytagexact" => Ok((true,Station::get_response(connection_new.get_stations_by_column_multiple("Tags", Some(search.to_string()),true,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "bylanguage" => Ok((true,Station::get_response(connection_new.get_stations_by_column_multiple("Language", Some(search.to_string()),false,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "bylanguageexact" => Ok((true,Station::get_response(connection_new.get_stations_by_column_multiple("Language", Some(search.to_string()),true,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "byuuid" => Ok((true,Station::get_response(connection_new.get_stations_by_column("StationUuid", search.to_string(),true,&all_params.param_order,all_params.param_reverse,all_params.param_hidebroken,all_params.param_offset,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        "changed" => Ok((true,encode_changes(connection_new.get_changes(Some(search.to_string()),all_params.param_last_changeuuid,all_params.param_limit)?.drain(..).map(|x| x.into()).collect(), format)?)),
-                        _ => Ok((true,ApiResponse::NotFound)),
-                    }
+        },
+        Handler::OpenApi => Ok(ApiResponse::Text(serde_json::to_string(&openapi::spec(&base_url))?)),
+        Handler::Metrics => {
+            if config.prometheus_exporter {
+                Ok(prometheus_exporter::render(connection_new, config.broken_stations_never_working_timeout.as_secs(), config.broken_stations_timeout.as_secs(), (config.check_interval.as_secs() / 3600).max(1) as u32, registry)?)
+            }else{
+                Ok(ApiResponse::Locked("Exporter not enabled!".to_string()))
+            }
+        },
+        Handler::StaticFile(file_name, file_type) => Ok(send_file(&format!("{}/{}",config.static_files_dir,file_name), file_type)),
+        Handler::Languages => Ok(ApiLanguage::get_response(connection_new.get_extra("LanguageCache", "LanguageName", filter, all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, limit)?, format)?),
+        Handler::Countries => Ok(ApiCountry::get_response(connection_new.get_countries(filter, all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, limit)?, format)?),
+        Handler::CountryCodes => Ok(encode_extra(connection_new.get_1_n("CountryCode", filter, all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, limit)?, format, "countrycode")?),
+        Handler::States => Ok(encode_states(connection_new.get_states(found.param("country").map(String::from), filter, all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, limit)?, format)?),
+        Handler::Codecs => Ok(encode_extra(connection_new.get_1_n("Codec", filter, all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, limit)?, format, "codec")?),
+        Handler::Tags => Ok(encode_extra(connection_new.get_extra("TagCache", "TagName", filter, all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, limit)?, format, "tag")?),
+        Handler::Stations => stations_all(connection_new, &all_params, cursor.as_ref(), &pagination, format),
+        Handler::StationsTopVote => Ok(Station::get_response_fields(connection_new.get_stations_topvote(all_params.param_hidebroken, all_params.param_offset, top_limit)?.drain(..).map(|x| x.into()).collect(), format, pagination.fields())?),
+        Handler::StationsTopClick => Ok(Station::get_response_fields(connection_new.get_stations_topclick(all_params.param_hidebroken, all_params.param_offset, top_limit)?.drain(..).map(|x| x.into()).collect(), format, pagination.fields())?),
+        Handler::StationsLastClick => Ok(Station::get_response_fields(connection_new.get_stations_lastclick(all_params.param_hidebroken, all_params.param_offset, top_limit)?.drain(..).map(|x| x.into()).collect(), format, pagination.fields())?),
+        Handler::StationsLastChange => Ok(Station::get_response_fields(connection_new.get_stations_lastchange(all_params.param_hidebroken, all_params.param_offset, top_limit)?.drain(..).map(|x| x.into()).collect(), format, pagination.fields())?),
+        Handler::StationsBroken => Ok(Station::get_response_fields(connection_new.get_stations_broken(all_params.param_offset, top_limit)?.drain(..).map(|x| x.into()).collect(), format, pagination.fields())?),
+        Handler::StationChanges => Ok(encode_changes(connection_new.get_changes(stationuuid.map(String::from), all_params.param_last_changeuuid, limit)?.drain(..).map(|x| x.into()).collect(), format)?),
+        Handler::StationsByUrl => Ok(stations_by_column_multiple(connection_new, &all_params, cursor.as_ref(), &pagination, "Url", all_params.param_url.clone(), true, format)?),
+        Handler::StationsByServerUuid => Ok(Station::get_response_fields(connection_new.get_stations_by_server_uuids(all_params.param_uuids, &all_params.param_order, all_params.param_reverse, all_params.param_hidebroken, all_params.param_offset, limit)?.drain(..).map(|x| x.into()).collect(), format, pagination.fields())?),
+        Handler::StationsByUuids => Ok(Station::get_response_fields(connection_new.get_stations_by_uuid(all_params.param_uuids)?.drain(..).map(|x| x.into()).collect(), format, pagination.fields())?),
+        Handler::StationsSearch => Ok(stations_search(connection_new, all_params, cursor.as_ref(), &pagination, format)?),
+        Handler::StationsByColumn(column, exact) => Ok(stations_by_column(connection_new, &all_params, cursor.as_ref(), &pagination, column, search, exact, format)?),
+        Handler::StationsByColumnMultiple(column, exact) => Ok(stations_by_column_multiple(connection_new, &all_params, cursor.as_ref(), &pagination, column, Some(search.to_string()), exact, format)?),
+        Handler::Servers => Ok(dns_resolve(format)?),
+        Handler::Stats => Ok(encode_status(get_status(connection_new)?, format, &config.static_files_dir)),
+        Handler::ServerConfig => Ok(ApiConfig::get_response(config.into(),format)?),
+        Handler::Checks => Ok(StationCheck::get_response(connection_new.get_checks(stationuuid.map(String::from), all_params.param_last_checkuuid, all_params.param_seconds, stationuuid.is_some(), limit)?.drain(..).map(|x|x.into()).collect(),format)?),
+        Handler::CheckSteps => Ok(StationCheckStep::get_response(connection_new.select_station_check_steps_by_stations(&all_params.param_uuids, limit)?.drain(..).map(|x|x.into()).collect(), format)?),
+        Handler::CheckSources => Ok(StationCheckSource::get_response(StationCheckSource::from_checks(connection_new.get_checks(stationuuid.map(String::from), None, 0, false, 1000)?, &CheckConsensus::new(config.check_consensus_ok, config.check_consensus_sources, config.check_interval.as_secs())), format)?),
+        Handler::NowPlaying => Ok(now_playing(connection_new, stationuuid.unwrap_or(""), format)?),
+        Handler::CheckStation => Ok(recheck_station(connection_new, &config, stationuuid.unwrap_or(""), format)?),
+        Handler::Clicks => Ok(StationClick::get_response(connection_new.get_clicks(stationuuid.map(String::from), all_params.param_last_clickuuid, all_params.param_seconds, limit.min(10000))?.drain(..).map(|x|x.into()).collect(),format)?),
+        Handler::StationUrl => Ok(encode_station_url(connection_new, get_only_first_item(connection_new.get_station_by_uuid(stationuuid.unwrap_or(""))?), &remote_ip, format, config.click_valid_timeout.as_secs(), registry)?),
+        Handler::Vote => Ok(encode_message(connection_new.vote_for_station(&remote_ip, get_only_first_item(connection_new.get_station_by_uuid(stationuuid.unwrap_or(""))?)), format)?),
+        Handler::AddStation => Ok(add_station(connection_new, all_params, config.moderation, format)?),
+        Handler::EditStation => Ok(edit_station(connection_new, &auth, stationuuid.unwrap_or(""), all_params, format)?),
+        Handler::DeleteStation => Ok(delete_station(connection_new, &auth, stationuuid.unwrap_or(""), format)?),
+        Handler::PendingStations => Ok(pending_stations(connection_new, &auth, all_params.param_offset, limit, format)?),
+        Handler::Moderate(approve) => Ok(moderate_station(connection_new, &auth, stationuuid.unwrap_or(""), approve, format)?),
+        Handler::StreamingServers => Ok(ApiStreamingServer::get_response(connection_new.get_streaming_servers(&all_params.param_order, all_params.param_reverse, all_params.param_offset, limit)?,format)?),
+        Handler::StreamingServersByServerUuid => Ok(ApiStreamingServer::get_response(connection_new.get_streaming_servers_by_uuids(all_params.param_uuids, &all_params.param_order, all_params.param_reverse, all_params.param_offset, limit)?,format)?),
+        Handler::StreamingServersByStationUuid => Ok(ApiStreamingServer::get_response(connection_new.get_streaming_servers_by_station_uuids(all_params.param_uuids, &all_params.param_order, all_params.param_reverse, all_params.param_offset, limit)?,format)?),
+        Handler::GraphQl => {
+            let limits = graphql::Limits { max_depth: config.graphql_max_depth, max_complexity: config.graphql_max_complexity };
+            let query = all_params.param_query.unwrap_or_default();
+            Ok(ApiResponse::Text(serde_json::to_string(&graphql::execute(connection_new, &query, all_params.param_variables.as_deref(), all_params.param_operation_name.as_deref(), &limits))?))
+        }
+        Handler::Webhooks => Ok(list_webhooks(connection_new, &auth, format)?),
+        Handler::AddWebhook => Ok(add_webhook(connection_new, &auth, all_params, config.webhook_private_targets, format)?),
+        Handler::DeleteWebhook => Ok(delete_webhook(connection_new, &auth, found.param("webhookuuid").unwrap_or(""), format)?),
+        Handler::Events => {
+            let position = match all_params.param_last_event_id {
+                Some(ref id) => match events::EventPosition::parse(id) {
+                    Some(position) => position,
+                    None => return Ok(ApiResponse::ParameterError(String::from("Last-Event-ID is not an id of /events"))),
+                },
+                None => events::EventPosition {
+                    change: all_params.param_last_changeuuid,
+                    check: all_params.param_last_checkuuid,
+                    click: all_params.param_last_clickuuid,
                 },
-                _ => Ok((true,ApiResponse::NotFound)),
+            };
+            // one thread is always left for all other requests
+            let max_clients = config.events_max_clients.min(config.threads.saturating_sub(1));
+            match events::stream(connection_new.clone(), position, max_clients) {
+                Some(producer) => Ok(ApiResponse::Events(producer)),
+                None => Ok(ApiResponse::Unavailable(String::from("too many clients are connected to /events"))),
             }
         }
-    } else {
-        Ok((true,ApiResponse::NotFound))
     }
 }
+
+#[cfg(test)]
+mod tests;
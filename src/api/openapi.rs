@@ -0,0 +1,258 @@
+use crate::api::routes::Route;
+use crate::api::routes::RouteResponse;
+use crate::api::routes::ROUTES;
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
+
+/// Query and form parameters with their type and meaning
+const PARAMETERS: &[(&str, &str, &str)] = &[
+    ("order", "string", "Name of the field to sort by"),
+    ("reverse", "boolean", "Sort descending"),
+    ("hidebroken", "boolean", "Do not list stations that failed the last check"),
+    ("offset", "integer", "Number of results to skip"),
+    ("limit", "integer", "Maximum number of results"),
+    ("cursor", "string", "Continue a list after the position of a previous page, from its Link header"),
+    ("envelope", "boolean", "Wrap JSON lists in an object with the items and the link to the next page"),
+    ("includecount", "boolean", "Send the count of all results in the header X-Total-Count"),
+    ("fields", "string", "Comma separated list of station fields to return in json, csv and xml"),
+    ("query", "string", "Free text search over name, tags, country and language, ordered by relevance. The query itself for /graphql"),
+    ("variables", "string", "JSON object with the values of the variables of the GraphQL query"),
+    ("operationName", "string", "Operation to run if the GraphQL query has more than one"),
+    ("name", "string", "Name of the station"),
+    ("nameExact", "boolean", "Name has to match completely"),
+    ("country", "string", "Country of the station"),
+    ("countryExact", "boolean", "Country has to match completely"),
+    ("countrycode", "string", "ISO 3166-1 alpha-2 code of the country"),
+    ("state", "string", "State of the station"),
+    ("stateExact", "boolean", "State has to match completely"),
+    ("language", "string", "Language of the station"),
+    ("languagecodes", "string", "Comma separated ISO 639 codes of the languages"),
+    ("languageExact", "boolean", "Language has to match completely"),
+    ("tag", "string", "Tag of the station"),
+    ("tagExact", "boolean", "Tag has to match completely"),
+    ("tagList", "string", "Comma separated tags that all have to match completely"),
+    ("tags", "string", "Comma separated tags of the station"),
+    ("codec", "string", "Codec of the stream"),
+    ("bitrateMin", "integer", "Minimum bitrate in kbps"),
+    ("bitrateMax", "integer", "Maximum bitrate in kbps"),
+    ("has_geo_info", "boolean", "Only stations with or without coordinates"),
+    ("has_extended_info", "boolean", "Only stations with or without information from the stream server"),
+    ("is_https", "boolean", "Only stations with or without https stream url"),
+    ("geo_lat", "number", "Latitude of the position"),
+    ("geo_long", "number", "Longitude of the position"),
+    ("geo_distance", "number", "Maximum distance to geo_lat and geo_long in meters"),
+    ("geo_lat_min", "number", "Southern border of the bounding box"),
+    ("geo_lat_max", "number", "Northern border of the bounding box"),
+    ("geo_long_min", "number", "Western border of the bounding box"),
+    ("geo_long_max", "number", "Eastern border of the bounding box"),
+    ("url", "string", "Stream url of the station, for webhooks the url the events are posted to"),
+    ("homepage", "string", "Homepage of the station"),
+    ("favicon", "string", "Url of the icon of the station"),
+    ("force", "boolean", "Add the station even if it looks like a duplicate"),
+    ("uuids", "string", "Comma separated uuids"),
+    ("lastchangeuuid", "string", "Only changes after this one"),
+    ("lastcheckuuid", "string", "Only checks after this one"),
+    ("lastclickuuid", "string", "Only clicks after this one"),
+    ("lastEventId", "string", "Id of the last event the client has seen, the same as the header Last-Event-ID"),
+    ("seconds", "integer", "Only items of the last seconds, 0 for all"),
+    ("secret", "string", "Key of the HMAC-SHA256 signature in the header X-RadioBrowser-Signature of every delivery"),
+    ("events", "string", "Comma separated events: station_broken, station_working, station_url_changed, station_deleted. Empty for all"),
+    ("stationuuid", "string", "Uuid of the station"),
+];
+
+/// Fields of the response objects. Types ending with "?" can be null, "[]" is an array of the type before it
+/// and types starting with an upper case letter are other schemas.
+const SCHEMAS: &[(&str, &[(&str, &str)])] = &[
+    ("Station", &[
+        ("changeuuid", "string"), ("stationuuid", "string"), ("serveruuid", "string?"), ("name", "string"), ("url", "string"),
+        ("url_resolved", "string"), ("homepage", "string"), ("favicon", "string"), ("tags", "string"), ("country", "string"),
+        ("countrycode", "string"), ("iso_3166_2", "string?"), ("state", "string"), ("language", "string"), ("languagecodes", "string?"),
+        ("votes", "integer"), ("lastchangetime", "string"), ("lastchangetime_iso8601", "date-time?"), ("codec", "string"),
+        ("bitrate", "integer"), ("hls", "integer"), ("lastcheckok", "integer"), ("lastchecktime", "string"),
+        ("lastchecktime_iso8601", "date-time?"), ("lastcheckoktime", "string"), ("lastcheckoktime_iso8601", "date-time?"),
+        ("lastlocalchecktime", "string"), ("lastlocalchecktime_iso8601", "date-time?"), ("clicktimestamp", "string"),
+        ("clicktimestamp_iso8601", "date-time?"), ("clickcount", "integer"), ("clicktrend", "integer"), ("ssl_error", "integer?"),
+        ("geo_lat", "number?"), ("geo_long", "number?"), ("has_extended_info", "boolean?"), ("now_playing", "string?"),
+        ("score", "number"), ("distance", "number"),
+    ]),
+    ("StationCheck", &[
+        ("stationuuid", "string"), ("checkuuid", "string"), ("source", "string"), ("codec", "string"), ("bitrate", "integer"),
+        ("hls", "integer"), ("ok", "integer"), ("timestamp_iso8601", "date-time?"), ("timestamp", "string"), ("urlcache", "string"),
+        ("metainfo_overrides_database", "integer?"), ("public", "integer?"), ("name", "string?"), ("description", "string?"),
+        ("tags", "string?"), ("countrycode", "string?"), ("homepage", "string?"), ("favicon", "string?"), ("loadbalancer", "string?"),
+        ("do_not_index", "integer?"), ("countrysubdivisioncode", "string?"), ("server_software", "string?"), ("sampling", "integer?"),
+        ("timing_ms", "integer?"), ("languagecodes", "string?"), ("ssl_error", "integer?"), ("geo_lat", "number?"), ("geo_long", "number?"),
+        ("audio_bitrate", "integer?"), ("audio_sampling", "integer?"), ("audio_liveness", "string?"),
+    ]),
+    ("StationCheckStep", &[
+        ("stepuuid", "string"), ("parent_stepuuid", "string?"), ("checkuuid", "string"), ("stationuuid", "string"), ("url", "string"),
+        ("urltype", "string?"), ("error", "string?"), ("creation_iso8601", "date-time"),
+    ]),
+    ("StationCheckSource", &[
+        ("stationuuid", "string"), ("source", "string"), ("checkuuid", "string"), ("ok", "integer"), ("ssl_error", "integer"),
+        ("timestamp_iso8601", "date-time?"), ("timestamp", "string"), ("consensus", "integer"),
+    ]),
+    ("StationNowPlaying", &[("stationuuid", "string"), ("title", "string?"), ("timestamp_iso8601", "date-time?")]),
+    ("StationRecheck", &[("check", "StationCheck"), ("steps", "StationCheckStep[]")]),
+    ("StationClick", &[
+        ("stationuuid", "string"), ("clickuuid", "string"), ("clicktimestamp_iso8601", "date-time?"), ("clicktimestamp", "string"),
+    ]),
+    ("StationHistory", &[
+        ("changeuuid", "string"), ("stationuuid", "string"), ("name", "string"), ("url", "string"), ("homepage", "string"),
+        ("favicon", "string"), ("tags", "string"), ("country", "string"), ("countrycode", "string"), ("state", "string"),
+        ("language", "string"), ("languagecodes", "string?"), ("votes", "integer"), ("lastchangetime", "string"),
+        ("lastchangetime_iso8601", "date-time?"), ("geo_lat", "number?"), ("geo_long", "number?"),
+    ]),
+    ("StationPending", &[
+        ("stationuuid", "string"), ("name", "string"), ("url", "string"), ("homepage", "string"), ("favicon", "string"),
+        ("country", "string"), ("countrycode", "string"), ("state", "string"), ("language", "string"), ("languagecodes", "string"),
+        ("tags", "string"), ("geo_lat", "number?"), ("geo_long", "number?"), ("creation_iso8601", "date-time?"),
+    ]),
+    ("StationCachedInfo", &[
+        ("ok", "boolean"), ("message", "string"), ("stationuuid", "string"), ("name", "string"), ("url", "string"),
+    ]),
+    ("StationAddResult", &[
+        ("ok", "boolean"), ("message", "string"), ("uuid", "string"), ("duplicates", "StationAddDuplicate[]"),
+    ]),
+    ("StationAddDuplicate", &[
+        ("stationuuid", "string"), ("name", "string"), ("url", "string"), ("reason", "string"),
+    ]),
+    ("ResultMessage", &[("ok", "boolean"), ("message", "string")]),
+    ("ApiCountry", &[("name", "string"), ("iso_3166_1", "string"), ("stationcount", "integer")]),
+    ("ApiLanguage", &[("name", "string"), ("iso_639", "string?"), ("stationcount", "integer")]),
+    ("ExtraInfo", &[("name", "string"), ("stationcount", "integer")]),
+    ("State", &[("name", "string"), ("country", "string"), ("stationcount", "integer")]),
+    ("ServerEntry", &[("ip", "string"), ("name", "string")]),
+    ("ApiStreamingServer", &[
+        ("uuid", "string"), ("url", "string"), ("statusurl", "string?"), ("status", "string?"), ("error", "string?"),
+    ]),
+    ("ApiWebhook", &[
+        ("webhookuuid", "string"), ("owner", "string"), ("url", "string"), ("events", "string"), ("stationuuid", "string"),
+        ("tag", "string"), ("countrycode", "string"), ("creation_iso8601", "date-time?"),
+    ]),
+    ("Status", &[
+        ("supported_version", "integer"), ("software_version", "string?"), ("status", "string"), ("stations", "integer"),
+        ("stations_broken", "integer"), ("tags", "integer"), ("clicks_last_hour", "integer"), ("clicks_last_day", "integer"),
+        ("languages", "integer"), ("countries", "integer"),
+    ]),
+    ("ApiConfig", &[
+        ("check_enabled", "boolean"), ("prometheus_exporter_enabled", "boolean"), ("pull_servers", "string[]"),
+        ("tcp_timeout_seconds", "integer"), ("broken_stations_never_working_timeout_seconds", "integer"),
+        ("broken_stations_timeout_seconds", "integer"), ("checks_timeout_seconds", "integer"), ("click_valid_timeout_seconds", "integer"),
+        ("clicks_timeout_seconds", "integer"), ("mirror_pull_interval_seconds", "integer"), ("update_caches_interval_seconds", "integer"),
+        ("server_name", "string"), ("server_location", "string"), ("server_country_code", "string"), ("check_retries", "integer"),
+        ("check_batchsize", "integer"), ("check_pause_seconds", "integer"), ("api_threads", "integer"), ("cache_type", "string"),
+        ("cache_ttl", "integer"), ("language_replace_filepath", "string"), ("language_to_code_filepath", "string"),
+    ]),
+];
+
+fn type_schema(field_type: &str) -> Value {
+    if let Some(field_type) = field_type.strip_suffix('?') {
+        let mut schema = type_schema(field_type);
+        schema["nullable"] = json!(true);
+        return schema;
+    }
+    if let Some(item_type) = field_type.strip_suffix("[]") {
+        return json!({"type": "array", "items": type_schema(item_type)});
+    }
+    match field_type {
+        "date-time" => json!({"type": "string", "format": "date-time"}),
+        name if name.starts_with(char::is_uppercase) => json!({"$ref": format!("#/components/schemas/{}", name)}),
+        field_type => json!({"type": field_type}),
+    }
+}
+
+fn parameter(name: &str) -> Option<&'static (&'static str, &'static str, &'static str)> {
+    PARAMETERS.iter().find(|(parameter, _, _)| *parameter == name)
+}
+
+fn path_parameter(route: &Route, name: &str) -> Value {
+    let schema = match name {
+        "format" => json!({"type": "string", "enum": route.formats}),
+        "limit" => json!({"type": "integer"}),
+        _ => json!({"type": "string"}),
+    };
+    json!({"name": name, "in": "path", "required": true, "schema": schema})
+}
+
+fn operation(route: &Route, method: &str) -> Value {
+    let mut parameters: Vec<Value> = route.path_parameters().map(|name| path_parameter(route, name)).collect();
+    let known = route.parameters.iter().filter_map(|name| parameter(name));
+    let mut operation = if method == "POST" {
+        let properties: Map<String, Value> = known
+            .map(|(name, parameter_type, description)| (name.to_string(), json!({"type": parameter_type, "description": description})))
+            .collect();
+        let body = json!({"schema": {"type": "object", "properties": properties}});
+        json!({"requestBody": {"content": {"application/x-www-form-urlencoded": body, "application/json": body}}})
+    } else {
+        parameters.extend(known.map(|(name, parameter_type, description)| {
+            json!({"name": name, "in": "query", "description": description, "schema": {"type": parameter_type}})
+        }));
+        json!({})
+    };
+    let content = match route.response {
+        RouteResponse::List(schema) => json!({"application/json": {"schema": {"type": "array", "items": type_schema(schema)}}}),
+        RouteResponse::Object(schema) => json!({"application/json": {"schema": type_schema(schema)}}),
+        RouteResponse::Content(content_type) => json!({ content_type: {} }),
+    };
+    operation["summary"] = json!(route.summary);
+    operation["parameters"] = json!(parameters);
+    operation["responses"] = json!({
+        "200": {"description": "Success", "content": content},
+        "404": {"description": "Unknown path or item"},
+        "405": {"description": "Method is not allowed for the path"},
+        "406": {"description": "Format is not supported by the path"},
+    });
+    operation
+}
+
+/// OpenAPI 3 description of all routes, served at /openapi.json
+pub fn spec(server_url: &str) -> Value {
+    let mut paths = Map::new();
+    for route in ROUTES {
+        let operations: Map<String, Value> = route
+            .methods
+            .iter()
+            .map(|method| (method.to_lowercase(), operation(route, method)))
+            .collect();
+        paths.insert(route.path.to_string(), Value::Object(operations));
+    }
+    let schemas: Map<String, Value> = SCHEMAS
+        .iter()
+        .map(|(name, fields)| {
+            let properties: Map<String, Value> = fields.iter().map(|(field, field_type)| (field.to_string(), type_schema(field_type))).collect();
+            (name.to_string(), json!({"type": "object", "properties": properties}))
+        })
+        .collect();
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Radio-Browser API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{"url": server_url}],
+        "paths": paths,
+        "components": {"schemas": schemas},
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parameter;
+    use super::SCHEMAS;
+    use crate::api::routes::RouteResponse;
+    use crate::api::routes::ROUTES;
+
+    #[test]
+    fn routes_are_described() {
+        for route in ROUTES {
+            for name in route.parameters {
+                assert!(parameter(name).is_some(), "parameter {} of {} has no description", name, route.path);
+            }
+            if let RouteResponse::List(schema) | RouteResponse::Object(schema) = route.response {
+                assert!(SCHEMAS.iter().any(|(name, _)| *name == schema), "schema {} of {} is missing", schema, route.path);
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::api::all_params::AllParameters;
+use crate::api::api_response::ApiResponse;
+use crate::api::data::Station;
+use crate::db::models::DbStationItem;
+use crate::db::StationCursor;
+use crate::db::StationFields;
+use serde::Serialize;
+use std::error::Error;
+
+/// JSON response of a list page with envelope=true
+#[derive(Serialize)]
+struct StationPage<T: Serialize> {
+    items: Vec<T>,
+    next_cursor: Option<String>,
+    next: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_count: Option<u64>,
+}
+
+/// Adds the link to the next page to station lists that can be continued with a cursor
+pub struct Pagination {
+    url: String,
+    order: String,
+    reverse: bool,
+    limit: Option<u32>,
+    /// Fuzzy search results are ordered by their score, they can not be continued
+    cursors: bool,
+    envelope: bool,
+    fields: Option<StationFields>,
+}
+
+impl Pagination {
+    pub fn new(all_params: &AllParameters, fields: Option<StationFields>) -> Self {
+        Pagination {
+            url: all_params.url.clone(),
+            order: all_params.param_order.clone(),
+            reverse: all_params.param_reverse,
+            limit: all_params.param_limit,
+            cursors: all_params.param_query.is_none(),
+            envelope: all_params.param_envelope,
+            fields,
+        }
+    }
+
+    /// Fields of the stations the client asked for, None for all
+    pub fn fields(&self) -> Option<&StationFields> {
+        self.fields.as_ref()
+    }
+
+    /// Only a full page can have a next one
+    fn next_cursor(&self, list: &[DbStationItem]) -> Option<String> {
+        let limit = self.limit?;
+        if !self.cursors || limit == 0 || list.len() < limit as usize {
+            return None;
+        }
+        Some(StationCursor::after(list.last()?, &self.order, self.reverse)?.encode())
+    }
+
+    /// Url of this request relative to the server, with the cursor instead of the offset.
+    /// Parameters that were sent in the body of a POST request are not part of it.
+    fn next_url(&self, cursor: &str) -> String {
+        let mut parts = self.url.splitn(2, '?');
+        let path = parts.next().unwrap_or("");
+        let cursor_param = format!("cursor={}", cursor);
+        let mut query: Vec<&str> = parts
+            .next()
+            .unwrap_or("")
+            .split('&')
+            .filter(|param| {
+                let name = param.split('=').next().unwrap_or("");
+                !name.is_empty() && name != "cursor" && name != "offset"
+            })
+            .collect();
+        query.push(&cursor_param);
+        format!("{}?{}", path, query.join("&"))
+    }
+
+    /// Page of the list, total_count is the count of all results without offset and limit
+    pub fn response(&self, list: Vec<DbStationItem>, total_count: Option<u64>, format: &str) -> Result<ApiResponse, Box<dyn Error>> {
+        let next_cursor = self.next_cursor(&list);
+        let next = next_cursor.as_ref().map(|cursor| self.next_url(cursor));
+        let stations: Vec<Station> = list.into_iter().map(|x| x.into()).collect();
+        let response = match self.fields {
+            _ if !self.envelope || format != "json" => Station::get_response_fields(stations, format, self.fields())?,
+            Some(ref fields) => ApiResponse::Text(serde_json::to_string(&StationPage {
+                items: stations.iter().map(|station| station.field_map(fields)).collect::<Result<Vec<_>, _>>()?,
+                next_cursor,
+                next: next.clone(),
+                total_count,
+            })?),
+            None => ApiResponse::Text(serde_json::to_string(&StationPage {
+                items: stations,
+                next_cursor,
+                next: next.clone(),
+                total_count,
+            })?),
+        };
+        Ok(match response {
+            ApiResponse::Text(text) if next.is_some() || total_count.is_some() => ApiResponse::Paged(text, next, total_count),
+            response => response,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pagination;
+
+    #[test]
+    fn next_url() {
+        let pagination = Pagination {
+            url: String::from("/json/stations/bytag/rock?order=votes&offset=20&limit=10&cursor=ab"),
+            order: String::from("votes"),
+            reverse: false,
+            limit: Some(10),
+            cursors: true,
+            envelope: false,
+            fields: None,
+        };
+        assert_eq!(pagination.next_url("cd"), "/json/stations/bytag/rock?order=votes&limit=10&cursor=cd");
+        let pagination = Pagination {
+            url: String::from("/json/stations"),
+            ..pagination
+        };
+        assert_eq!(pagination.next_url("cd"), "/json/stations?cursor=cd");
+    }
+}
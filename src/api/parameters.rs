@@ -153,6 +153,9 @@ impl RequestParameters {
                                         let value = list.join(",");
                                         map.insert(key, value);
                                     }
+                                    else if value.is_object() {
+                                        map.insert(key, value.to_string());
+                                    }
                                     else{
                                         error!("unsupported value type in json");
                                     }
@@ -205,6 +208,18 @@ impl RequestParameters {
         default
     }
 
+    /// None if the parameter is missing or not a number
+    pub fn get_number_opt(&self, name: &str) -> Option<u32> {
+        let v = self.values.get(name)?;
+        match v.parse::<u32>() {
+            Ok(parsed) => Some(parsed),
+            Err(_) => {
+                error!("could not parse '{}'", v);
+                None
+            }
+        }
+    }
+
     pub fn get_double(&self, name: &str, default: Option<f64>) -> Option<f64> {
         let v = self.values.get(name);
         if let Some(v) = v {
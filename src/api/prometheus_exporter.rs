@@ -15,12 +15,15 @@ pub struct RegistryLinks {
     pub clicks: IntCounter,
     pub cache_hits: IntCounter,
     pub cache_misses: IntCounter,
+    pub rate_limited: IntCounterVec,
 
     pub stations_broken: IntGauge,
     pub stations_working: IntGauge,
     pub stations_todo: IntGauge,
     pub stations_deletable_never_worked: IntGauge,
     pub stations_deletable_were_working: IntGauge,
+    pub check_queue_depth: IntGauge,
+    pub check_queue_lag: IntGauge,
     pub country_count: IntGauge,
     pub tags_count: IntGauge,
     pub language_count: IntGauge,
@@ -28,7 +31,7 @@ pub struct RegistryLinks {
 
 pub fn create_registry(prefix: &str) -> Result<RegistryLinks, Box<dyn Error>> {
     // Create a Counter.
-    let timer = register_histogram_vec!("timer", "Timer for the api", &["method"])?;
+    let timer = HistogramVec::new(histogram_opts!("timer", "Timer for the api"), &["method"])?;
     let api_calls = IntCounterVec::new(
         opts!("api_calls", "Calls to the api"),
         &["method", "url", "status_code"],
@@ -36,6 +39,10 @@ pub fn create_registry(prefix: &str) -> Result<RegistryLinks, Box<dyn Error>> {
     let clicks = IntCounter::new("station_clicks", "Clicks on stations")?;
     let cache_hits = IntCounter::new("cache_hits", "Cache hits")?;
     let cache_misses = IntCounter::new("cache_misses", "Cache misses")?;
+    let rate_limited = IntCounterVec::new(
+        opts!("rate_limited", "Requests rejected because of rate limits"),
+        &["endpoint"],
+    )?;
 
     let stations_broken = IntGauge::new("stations_broken", "Count of stations that are broken")?;
     let stations_working = IntGauge::new(
@@ -54,6 +61,14 @@ pub fn create_registry(prefix: &str) -> Result<RegistryLinks, Box<dyn Error>> {
         "stations_deletable_were_working",
         "Count of stations that are in the list for deletion and which worked at some point",
     )?;
+    let check_queue_depth = IntGauge::new(
+        "check_queue_depth",
+        "Count of stations whose next check is due",
+    )?;
+    let check_queue_lag = IntGauge::new(
+        "check_queue_lag_seconds",
+        "Seconds the longest waiting station is overdue for its next check",
+    )?;
     let country_count = IntGauge::new("country_count", "Count of countries")?;
     let tags_count = IntGauge::new("tags_count", "Count of tags")?;
     let language_count = IntGauge::new("language_count", "Count of languages")?;
@@ -64,11 +79,14 @@ pub fn create_registry(prefix: &str) -> Result<RegistryLinks, Box<dyn Error>> {
     registry.register(Box::new(clicks.clone()))?;
     registry.register(Box::new(cache_hits.clone()))?;
     registry.register(Box::new(cache_misses.clone()))?;
+    registry.register(Box::new(rate_limited.clone()))?;
     registry.register(Box::new(stations_broken.clone()))?;
     registry.register(Box::new(stations_working.clone()))?;
     registry.register(Box::new(stations_todo.clone()))?;
     registry.register(Box::new(stations_deletable_never_worked.clone()))?;
     registry.register(Box::new(stations_deletable_were_working.clone()))?;
+    registry.register(Box::new(check_queue_depth.clone()))?;
+    registry.register(Box::new(check_queue_lag.clone()))?;
     registry.register(Box::new(country_count.clone()))?;
     registry.register(Box::new(tags_count.clone()))?;
     registry.register(Box::new(language_count.clone()))?;
@@ -80,11 +98,14 @@ pub fn create_registry(prefix: &str) -> Result<RegistryLinks, Box<dyn Error>> {
         clicks,
         cache_hits,
         cache_misses,
+        rate_limited,
         stations_broken,
         stations_working,
         stations_todo,
         stations_deletable_never_worked,
         stations_deletable_were_working,
+        check_queue_depth,
+        check_queue_lag,
         country_count,
         tags_count,
         language_count,
@@ -95,6 +116,7 @@ pub fn render<A>(
     connection_new: &A,
     broken_stations_never_working_timeout: u64,
     broken_stations_timeout: u64,
+    check_interval_hours: u32,
     registry: RegistryLinks,
 ) -> Result<ApiResponse, Box<dyn std::error::Error>>
 where
@@ -102,11 +124,12 @@ where
 {
     let stations_broken = connection_new.get_station_count_broken()?;
     let stations_working = connection_new.get_station_count_working()?;
-    let stations_todo = connection_new.get_station_count_todo(24)?;
+    let stations_todo = connection_new.get_station_count_todo(check_interval_hours)?;
     let stations_deletable_never_worked =
         connection_new.get_deletable_never_working(broken_stations_never_working_timeout)?;
     let stations_deletable_were_working =
         connection_new.get_deletable_were_working(broken_stations_timeout)?;
+    let (check_queue_depth, check_queue_lag) = connection_new.get_check_queue(check_interval_hours)?;
 
     let country_count = connection_new.get_country_count()?;
     let tags_count = connection_new.get_tag_count()?;
@@ -121,6 +144,8 @@ where
     registry
         .stations_deletable_were_working
         .set(stations_deletable_were_working.try_into()?);
+    registry.check_queue_depth.set(check_queue_depth.try_into()?);
+    registry.check_queue_lag.set(check_queue_lag.try_into()?);
     registry.country_count.set(country_count.try_into()?);
     registry.tags_count.set(tags_count.try_into()?);
     registry.language_count.set(language_count.try_into()?);
@@ -0,0 +1,262 @@
+use crate::config::Config;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Endpoints that have their own limit per client
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RateLimitKind {
+    Vote,
+    Click,
+    Add,
+    Search,
+    Check,
+}
+
+impl RateLimitKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateLimitKind::Vote => "vote",
+            RateLimitKind::Click => "click",
+            RateLimitKind::Add => "add",
+            RateLimitKind::Search => "search",
+            RateLimitKind::Check => "check",
+        }
+    }
+
+    /// Kind of the request by its url, None for requests without limit
+    pub fn from_url(url: &str) -> Option<RateLimitKind> {
+        let path = url.split('?').next().unwrap_or("");
+        let items: Vec<&str> = path.split('/').collect();
+        match items.as_slice() {
+            ["", "v2", _, "url", _] => Some(RateLimitKind::Click),
+            ["", _, "url", _] => Some(RateLimitKind::Click),
+            ["", _, "vote", _] => Some(RateLimitKind::Vote),
+            ["", _, "add"] => Some(RateLimitKind::Add),
+            ["", _, "stations", "search"] => Some(RateLimitKind::Search),
+            ["", _, "check", _] => Some(RateLimitKind::Check),
+            _ => None,
+        }
+    }
+}
+
+/// Ip address or network like "10.0.0.0/8"
+#[derive(Clone, Debug)]
+struct AllowedNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl AllowedNet {
+    fn parse(entry: &str) -> Option<AllowedNet> {
+        let mut parts = entry.splitn(2, '/');
+        let addr: IpAddr = parts.next()?.trim().parse().ok()?;
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match parts.next() {
+            Some(prefix) => prefix.trim().parse().ok().filter(|prefix| *prefix <= max_prefix)?,
+            None => max_prefix,
+        };
+        Some(AllowedNet { addr, prefix })
+    }
+
+    fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => prefix_matches(&net.octets(), &ip.octets(), self.prefix),
+            (IpAddr::V6(net), IpAddr::V6(ip)) => prefix_matches(&net.octets(), &ip.octets(), self.prefix),
+            _ => false,
+        }
+    }
+}
+
+fn prefix_matches(net: &[u8], ip: &[u8], prefix: u8) -> bool {
+    let full_bytes = (prefix / 8) as usize;
+    let rest_bits = prefix % 8;
+    if net[..full_bytes] != ip[..full_bytes] {
+        return false;
+    }
+    if rest_bits == 0 {
+        return true;
+    }
+    let mask = 0xffu8 << (8 - rest_bits);
+    net[full_bytes] & mask == ip[full_bytes] & mask
+}
+
+fn parse_nets(entries: &[String], option: &str) -> Vec<AllowedNet> {
+    let mut nets = vec![];
+    for entry in entries.iter() {
+        match AllowedNet::parse(entry) {
+            Some(net) => nets.push(net),
+            None => error!("Ignoring invalid entry in {}: {}", option, entry),
+        }
+    }
+    nets
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets per endpoint and client. A bucket holds as many requests as are allowed per minute
+/// and refills continuously, so short bursts are possible as long as the average stays below the limit.
+#[derive(Clone)]
+pub struct RateLimiter {
+    limits: HashMap<RateLimitKind, u32>,
+    allowlist: Vec<AllowedNet>,
+    trusted_proxies: Vec<AllowedNet>,
+    buckets: Arc<Mutex<HashMap<(RateLimitKind, String), Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &Config) -> Self {
+        let mut limits = HashMap::new();
+        limits.insert(RateLimitKind::Vote, config.rate_limit_vote);
+        limits.insert(RateLimitKind::Click, config.rate_limit_click);
+        limits.insert(RateLimitKind::Add, config.rate_limit_add);
+        limits.insert(RateLimitKind::Search, config.rate_limit_search);
+        limits.insert(RateLimitKind::Check, config.rate_limit_check);
+        RateLimiter {
+            limits,
+            allowlist: parse_nets(&config.rate_limit_allowlist, "rate-limit-allowlist"),
+            trusted_proxies: parse_nets(&config.trusted_proxies, "trusted-proxies"),
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Client address for limits. X-Forwarded-For is only read if the connection comes from a trusted proxy,
+    /// then the rightmost address that is not a trusted proxy is the client. The entries left of it could be
+    /// written by the client itself.
+    pub fn client_ip(&self, forwarded_for: Option<&str>, remote_addr: &IpAddr) -> String {
+        let mut client = *remote_addr;
+        if let Some(header) = forwarded_for {
+            for hop in header.rsplit(',') {
+                if !self.is_trusted_proxy(&client) {
+                    break;
+                }
+                match hop.trim().parse::<IpAddr>() {
+                    Ok(ip) => client = ip,
+                    Err(_) => break,
+                }
+            }
+        }
+        client.to_string()
+    }
+
+    fn is_trusted_proxy(&self, ip: &IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(ip))
+    }
+
+    fn is_allowed(&self, client: &str) -> bool {
+        match client.parse::<IpAddr>() {
+            Ok(ip) => self.allowlist.iter().any(|net| net.contains(&ip)),
+            Err(_) => false,
+        }
+    }
+
+    /// Takes one request from the bucket of the client,
+    /// Err contains the time until the next request is possible
+    pub fn check(&self, kind: RateLimitKind, client: &str) -> Result<(), Duration> {
+        self.check_at(kind, client, Instant::now())
+    }
+
+    fn check_at(&self, kind: RateLimitKind, client: &str, now: Instant) -> Result<(), Duration> {
+        let limit = self.limits.get(&kind).cloned().unwrap_or(0);
+        if limit == 0 || self.is_allowed(client) {
+            return Ok(());
+        }
+        let capacity = limit as f64;
+        let per_second = capacity / 60.0;
+        let mut buckets = match self.buckets.lock() {
+            Ok(buckets) => buckets,
+            Err(err) => {
+                error!("Rate limiter lock failed: {}", err);
+                return Ok(());
+            }
+        };
+        let bucket = buckets.entry((kind, client.to_string())).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+
+    /// Forgets clients whose buckets are full again
+    pub fn cleanup(&self) {
+        let now = Instant::now();
+        if let Ok(mut buckets) = self.buckets.lock() {
+            buckets.retain(|_, bucket| now.saturating_duration_since(bucket.updated) < Duration::from_secs(60));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimitKind;
+    use super::RateLimiter;
+    use crate::test_utils::test_config;
+    use std::net::IpAddr;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    #[test]
+    fn kinds_by_url() {
+        assert_eq!(RateLimitKind::from_url("/json/vote/abc"), Some(RateLimitKind::Vote));
+        assert_eq!(RateLimitKind::from_url("/json/url/abc"), Some(RateLimitKind::Click));
+        assert_eq!(RateLimitKind::from_url("/v2/json/url/abc"), Some(RateLimitKind::Click));
+        assert_eq!(RateLimitKind::from_url("/xml/add?name=x"), Some(RateLimitKind::Add));
+        assert_eq!(RateLimitKind::from_url("/json/stations/search?name=x"), Some(RateLimitKind::Search));
+        assert_eq!(RateLimitKind::from_url("/json/check/abc"), Some(RateLimitKind::Check));
+        assert_eq!(RateLimitKind::from_url("/json/stations/byurl"), None);
+    }
+
+    #[test]
+    fn buckets_and_allowlist() {
+        let mut config = test_config();
+        config.rate_limit_vote = 2;
+        config.rate_limit_allowlist = vec![String::from("10.0.0.0/8"), String::from("::1")];
+        let limiter = RateLimiter::new(&config);
+        let start = Instant::now();
+
+        assert!(limiter.check_at(RateLimitKind::Vote, "192.168.1.1", start).is_ok());
+        assert!(limiter.check_at(RateLimitKind::Vote, "192.168.1.1", start).is_ok());
+        let wait = limiter.check_at(RateLimitKind::Vote, "192.168.1.1", start).unwrap_err();
+        assert_eq!(wait.as_secs(), 30);
+        assert!(limiter.check_at(RateLimitKind::Vote, "192.168.1.2", start).is_ok());
+        assert!(limiter.check_at(RateLimitKind::Vote, "192.168.1.1", start + Duration::from_secs(30)).is_ok());
+        assert!(limiter.check_at(RateLimitKind::Click, "192.168.1.1", start).is_ok());
+
+        for _ in 0..10 {
+            assert!(limiter.check_at(RateLimitKind::Vote, "10.1.2.3", start).is_ok());
+            assert!(limiter.check_at(RateLimitKind::Vote, "::1", start).is_ok());
+        }
+    }
+
+    #[test]
+    fn client_behind_proxies() {
+        let mut config = test_config();
+        let proxy: IpAddr = "192.168.0.1".parse().unwrap();
+        let direct = RateLimiter::new(&config);
+        assert_eq!(direct.client_ip(Some("10.0.0.1"), &proxy), "192.168.0.1");
+
+        config.trusted_proxies = vec![String::from("192.168.0.0/24")];
+        let limiter = RateLimiter::new(&config);
+        assert_eq!(limiter.client_ip(None, &proxy), "192.168.0.1");
+        assert_eq!(limiter.client_ip(Some("10.0.0.1"), &proxy), "10.0.0.1");
+        assert_eq!(limiter.client_ip(Some("1.2.3.4, 10.0.0.1"), &proxy), "10.0.0.1");
+        assert_eq!(limiter.client_ip(Some("10.0.0.1, 192.168.0.7"), &proxy), "10.0.0.1");
+        assert_eq!(limiter.client_ip(Some("192.168.0.8, 192.168.0.7"), &proxy), "192.168.0.8");
+        assert_eq!(limiter.client_ip(Some("unknown, 192.168.0.7"), &proxy), "192.168.0.7");
+        assert_eq!(limiter.client_ip(Some("10.0.0.1"), &"10.0.0.9".parse().unwrap()), "10.0.0.9");
+    }
+}
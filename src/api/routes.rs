@@ -0,0 +1,332 @@
+use percent_encoding::percent_decode_str;
+
+/// Parameters of lists that are not stations
+const LIST: &[&str] = &["order", "reverse", "hidebroken", "offset", "limit"];
+/// Parameters of station lists that can be paged with a cursor
+const STATION_LIST: &[&str] = &["order", "reverse", "hidebroken", "offset", "limit", "cursor", "envelope", "includecount", "fields"];
+const TOP_LIST: &[&str] = &["hidebroken", "offset", "limit", "fields"];
+const SEARCH: &[&str] = &[
+    "query", "name", "nameExact", "country", "countryExact", "countrycode", "state", "stateExact", "language", "languageExact",
+    "tag", "tagExact", "tagList", "codec", "bitrateMin", "bitrateMax", "has_geo_info", "has_extended_info", "is_https",
+    "geo_lat", "geo_long", "geo_distance", "geo_lat_min", "geo_lat_max", "geo_long_min", "geo_long_max",
+    "order", "reverse", "hidebroken", "offset", "limit", "cursor", "envelope", "includecount", "fields",
+];
+const STATION_FIELDS: &[&str] = &[
+    "name", "url", "homepage", "favicon", "country", "countrycode", "state", "language", "languagecodes", "tags", "geo_lat", "geo_long",
+];
+const ADD_STATION: &[&str] = &[
+    "name", "url", "homepage", "favicon", "country", "countrycode", "state", "language", "languagecodes", "tags", "geo_lat", "geo_long", "force",
+];
+const STREAMING_SERVERS: &[&str] = &["order", "reverse", "offset", "limit"];
+
+/// Methods of the API calls, parameters can be in the query or in the body
+const API: &[&str] = &["GET", "POST"];
+const FILE: &[&str] = &["GET"];
+/// Changes of stored data, a link or a prefetching browser must not trigger them
+const CHANGE: &[&str] = &["POST"];
+
+/// Formats of the {format} placeholder
+const STATION_FORMATS: &[&str] = &["json", "xml", "csv", "m3u", "pls", "xspf", "ttl", "geojson", "kml"];
+const LIST_FORMATS: &[&str] = &["json", "xml", "csv"];
+const OBJECT_FORMATS: &[&str] = &["json", "xml"];
+const STATUS_FORMATS: &[&str] = &["json", "xml", "html"];
+const URL_FORMATS: &[&str] = &["json", "xml", "m3u", "pls"];
+
+/// What a route answers with
+pub enum RouteResponse {
+    /// List of items of the schema, encoded in the format of the path
+    List(&'static str),
+    /// Single item of the schema, encoded in the format of the path
+    Object(&'static str),
+    /// Content with a fixed type, like static files
+    Content(&'static str),
+}
+
+/// Code that answers a route, with the values that differ between routes of the same kind
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Handler {
+    Docs,
+    OpenApi,
+    Metrics,
+    /// File name in the static directory and its content type
+    StaticFile(&'static str, &'static str),
+    Languages,
+    Countries,
+    CountryCodes,
+    States,
+    Codecs,
+    Tags,
+    Stations,
+    StationsTopVote,
+    StationsTopClick,
+    StationsLastClick,
+    StationsLastChange,
+    StationsBroken,
+    StationChanges,
+    StationsByUrl,
+    StationsByServerUuid,
+    StationsByUuids,
+    StationsSearch,
+    /// Column and if the search term has to match exactly
+    StationsByColumn(&'static str, bool),
+    /// Column with comma separated values and if one of them has to match exactly
+    StationsByColumnMultiple(&'static str, bool),
+    Servers,
+    Stats,
+    ServerConfig,
+    Checks,
+    CheckSteps,
+    /// Latest check of every source of a station
+    CheckSources,
+    NowPlaying,
+    /// Checks the station right now
+    CheckStation,
+    Clicks,
+    StationUrl,
+    Vote,
+    AddStation,
+    EditStation,
+    DeleteStation,
+    PendingStations,
+    /// Approve with true, reject with false
+    Moderate(bool),
+    StreamingServers,
+    StreamingServersByServerUuid,
+    StreamingServersByStationUuid,
+    GraphQl,
+    Events,
+    Webhooks,
+    AddWebhook,
+    DeleteWebhook,
+}
+
+pub struct Route {
+    /// Path with placeholders in braces, e.g. "/{format}/stations/byname/{searchterm}"
+    pub path: &'static str,
+    pub methods: &'static [&'static str],
+    /// Allowed values of the {format} placeholder
+    pub formats: &'static [&'static str],
+    /// Answers may be cached and shared between clients
+    pub cacheable: bool,
+    pub handler: Handler,
+    pub summary: &'static str,
+    /// Names of the query or form parameters that are read
+    pub parameters: &'static [&'static str],
+    pub response: RouteResponse,
+}
+
+use self::Handler::*;
+use self::RouteResponse::{Content, List, Object};
+
+const fn file(path: &'static str, handler: Handler, summary: &'static str, content_type: &'static str) -> Route {
+    Route {
+        path,
+        methods: FILE,
+        formats: &[],
+        cacheable: true,
+        handler,
+        summary,
+        parameters: &[],
+        response: Content(content_type),
+    }
+}
+
+const fn api(path: &'static str, handler: Handler, formats: &'static [&'static str], summary: &'static str, parameters: &'static [&'static str], response: RouteResponse) -> Route {
+    Route {
+        path,
+        methods: API,
+        formats,
+        cacheable: true,
+        handler,
+        summary,
+        parameters,
+        response,
+    }
+}
+
+impl Route {
+    /// Calls that change something or count clicks have to reach the server every time
+    const fn uncached(self) -> Route {
+        Route { cacheable: false, ..self }
+    }
+
+    const fn post_only(self) -> Route {
+        Route { methods: CHANGE, cacheable: false, ..self }
+    }
+}
+
+/// Every path the server answers, the first matching route wins.
+/// The OpenAPI description in /openapi.json is generated from it.
+pub const ROUTES: &[Route] = &[
+    file("/", Docs, "Documentation of the API", "text/html"),
+    file("/openapi.json", OpenApi, "OpenAPI description of the API", "application/json"),
+    file("/metrics", Metrics, "Prometheus metrics, if enabled", "text/plain").uncached(),
+    file("/favicon.ico", StaticFile("favicon.ico", "image/png"), "Icon of the server", "image/png"),
+    file("/robots.txt", StaticFile("robots.txt", "text/plain"), "Rules for crawlers", "text/plain"),
+    file("/main.css", StaticFile("main.css", "text/css"), "Style sheet of the documentation", "text/css"),
+    api("/v2/{format}/url/{stationuuid}", StationUrl, URL_FORMATS, "Deprecated, use /{format}/url/{stationuuid}", &[], Object("StationCachedInfo")).uncached(),
+    api("/{format}/languages", Languages, LIST_FORMATS, "List of languages", LIST, List("ApiLanguage")),
+    api("/{format}/languages/{filter}", Languages, LIST_FORMATS, "List of languages that contain the filter", LIST, List("ApiLanguage")),
+    api("/{format}/countries", Countries, LIST_FORMATS, "List of countries", LIST, List("ApiCountry")),
+    api("/{format}/countries/{filter}", Countries, LIST_FORMATS, "List of countries that contain the filter", LIST, List("ApiCountry")),
+    api("/{format}/countrycodes", CountryCodes, LIST_FORMATS, "List of country codes", LIST, List("ExtraInfo")),
+    api("/{format}/countrycodes/{filter}", CountryCodes, LIST_FORMATS, "List of country codes that contain the filter", LIST, List("ExtraInfo")),
+    api("/{format}/states", States, LIST_FORMATS, "List of states", LIST, List("State")),
+    api("/{format}/states/{filter}", States, LIST_FORMATS, "List of states that contain the filter", LIST, List("State")),
+    api("/{format}/states/{country}/{filter}", States, LIST_FORMATS, "List of states of the country that contain the filter", LIST, List("State")),
+    api("/{format}/codecs", Codecs, LIST_FORMATS, "List of codecs", LIST, List("ExtraInfo")),
+    api("/{format}/codecs/{filter}", Codecs, LIST_FORMATS, "List of codecs that contain the filter", LIST, List("ExtraInfo")),
+    api("/{format}/tags", Tags, LIST_FORMATS, "List of tags", LIST, List("ExtraInfo")),
+    api("/{format}/tags/{filter}", Tags, LIST_FORMATS, "List of tags that contain the filter", LIST, List("ExtraInfo")),
+    api("/{format}/stations", Stations, STATION_FORMATS, "List of all stations", STATION_LIST, List("Station")),
+    api("/{format}/stations/topvote", StationsTopVote, STATION_FORMATS, "Stations with the most votes", TOP_LIST, List("Station")),
+    api("/{format}/stations/topvote/{limit}", StationsTopVote, STATION_FORMATS, "Stations with the most votes", TOP_LIST, List("Station")),
+    api("/{format}/stations/topclick", StationsTopClick, STATION_FORMATS, "Stations with the most clicks", TOP_LIST, List("Station")),
+    api("/{format}/stations/topclick/{limit}", StationsTopClick, STATION_FORMATS, "Stations with the most clicks", TOP_LIST, List("Station")),
+    api("/{format}/stations/lastclick", StationsLastClick, STATION_FORMATS, "Stations that were clicked last", TOP_LIST, List("Station")),
+    api("/{format}/stations/lastclick/{limit}", StationsLastClick, STATION_FORMATS, "Stations that were clicked last", TOP_LIST, List("Station")),
+    api("/{format}/stations/lastchange", StationsLastChange, STATION_FORMATS, "Stations that were changed last", TOP_LIST, List("Station")),
+    api("/{format}/stations/lastchange/{limit}", StationsLastChange, STATION_FORMATS, "Stations that were changed last", TOP_LIST, List("Station")),
+    api("/{format}/stations/broken", StationsBroken, STATION_FORMATS, "Stations that did not pass the last check", &["offset", "limit", "fields"], List("Station")),
+    api("/{format}/stations/broken/{limit}", StationsBroken, STATION_FORMATS, "Stations that did not pass the last check", &["offset", "fields"], List("Station")),
+    api("/{format}/stations/changed", StationChanges, LIST_FORMATS, "Changes of all stations", &["lastchangeuuid", "limit"], List("StationHistory")),
+    api("/{format}/stations/changed/{stationuuid}", StationChanges, LIST_FORMATS, "Changes of a station", &["lastchangeuuid", "limit"], List("StationHistory")),
+    api("/{format}/stations/byurl", StationsByUrl, STATION_FORMATS, "Stations with the stream url", &["url", "order", "reverse", "hidebroken", "offset", "limit", "cursor", "envelope", "includecount", "fields"], List("Station")),
+    api("/{format}/stations/byserveruuid", StationsByServerUuid, STATION_FORMATS, "Stations of the streaming servers", &["uuids", "order", "reverse", "hidebroken", "offset", "limit", "fields"], List("Station")),
+    api("/{format}/stations/byuuid", StationsByUuids, STATION_FORMATS, "Stations with the uuids", &["uuids", "fields"], List("Station")),
+    api("/{format}/stations/byuuid/{searchterm}", StationsByColumn("StationUuid", true), STATION_FORMATS, "Station with the uuid", STATION_LIST, List("Station")),
+    api("/{format}/stations/search", StationsSearch, STATION_FORMATS, "Search for stations", SEARCH, List("Station")),
+    api("/{format}/stations/byname/{searchterm}", StationsByColumn("Name", false), STATION_FORMATS, "Stations whose name contains the search term", STATION_LIST, List("Station")),
+    api("/{format}/stations/bynameexact/{searchterm}", StationsByColumn("Name", true), STATION_FORMATS, "Stations with the name", STATION_LIST, List("Station")),
+    api("/{format}/stations/bycodec/{searchterm}", StationsByColumn("Codec", false), STATION_FORMATS, "Stations whose codec contains the search term", STATION_LIST, List("Station")),
+    api("/{format}/stations/bycodecexact/{searchterm}", StationsByColumn("Codec", true), STATION_FORMATS, "Stations with the codec", STATION_LIST, List("Station")),
+    api("/{format}/stations/bycountry/{searchterm}", StationsByColumn("Country", false), STATION_FORMATS, "Stations whose country contains the search term", STATION_LIST, List("Station")),
+    api("/{format}/stations/bycountryexact/{searchterm}", StationsByColumn("Country", true), STATION_FORMATS, "Stations with the country", STATION_LIST, List("Station")),
+    api("/{format}/stations/bycountrycodeexact/{searchterm}", StationsByColumn("CountryCode", true), STATION_FORMATS, "Stations with the country code", STATION_LIST, List("Station")),
+    api("/{format}/stations/bystate/{searchterm}", StationsByColumn("Subcountry", false), STATION_FORMATS, "Stations whose state contains the search term", STATION_LIST, List("Station")),
+    api("/{format}/stations/bystateexact/{searchterm}", StationsByColumn("Subcountry", true), STATION_FORMATS, "Stations with the state", STATION_LIST, List("Station")),
+    api("/{format}/stations/bytag/{searchterm}", StationsByColumnMultiple("Tags", false), STATION_FORMATS, "Stations with a tag that contains the search term", STATION_LIST, List("Station")),
+    api("/{format}/stations/bytagexact/{searchterm}", StationsByColumnMultiple("Tags", true), STATION_FORMATS, "Stations with the tag", STATION_LIST, List("Station")),
+    api("/{format}/stations/bylanguage/{searchterm}", StationsByColumnMultiple("Language", false), STATION_FORMATS, "Stations with a language that contains the search term", STATION_LIST, List("Station")),
+    api("/{format}/stations/bylanguageexact/{searchterm}", StationsByColumnMultiple("Language", true), STATION_FORMATS, "Stations with the language", STATION_LIST, List("Station")),
+    api("/{format}/servers", Servers, &["json"], "List of all API servers", &[], List("ServerEntry")),
+    api("/{format}/stats", Stats, STATUS_FORMATS, "Statistics of the server", &[], Object("Status")),
+    api("/{format}/config", ServerConfig, OBJECT_FORMATS, "Configuration of the server", &[], Object("ApiConfig")),
+    api("/{format}/checks", Checks, LIST_FORMATS, "Latest checks of all stations", &["lastcheckuuid", "seconds", "limit"], List("StationCheck")),
+    api("/{format}/checks/{stationuuid}", Checks, LIST_FORMATS, "Checks of a station", &["lastcheckuuid", "seconds", "limit"], List("StationCheck")),
+    api("/{format}/checksteps", CheckSteps, LIST_FORMATS, "Steps of the checks of the stations", &["uuids"], List("StationCheckStep")),
+    api("/{format}/checksources/{stationuuid}", CheckSources, LIST_FORMATS, "Latest check of a station from every source, which shows where it can be reached", &[], List("StationCheckSource")),
+    api("/{format}/nowplaying/{stationuuid}", NowPlaying, OBJECT_FORMATS, "Title the station played when its stream metadata was last read", &[], Object("StationNowPlaying")),
+    api("/{format}/check/{stationuuid}", CheckStation, OBJECT_FORMATS, "Check a station right now and store the result, limited by rate-limit-check", &[], Object("StationRecheck")).post_only(),
+    api("/{format}/clicks", Clicks, LIST_FORMATS, "Latest clicks of all stations", &["lastclickuuid", "seconds"], List("StationClick")),
+    api("/{format}/clicks/{stationuuid}", Clicks, LIST_FORMATS, "Clicks of a station", &["lastclickuuid", "seconds"], List("StationClick")),
+    api("/{format}/url/{stationuuid}", StationUrl, URL_FORMATS, "Stream url of a station, counts as a click", &[], Object("StationCachedInfo")).uncached(),
+    api("/{format}/vote/{stationuuid}", Vote, OBJECT_FORMATS, "Vote for a station", &[], Object("ResultMessage")).uncached(),
+    api("/{format}/add", AddStation, OBJECT_FORMATS, "Add a station", ADD_STATION, Object("StationAddResult")).uncached(),
+    api("/{format}/edit/{stationuuid}", EditStation, OBJECT_FORMATS, "Change a station, needs an api key", STATION_FIELDS, Object("ResultMessage")).post_only(),
+    api("/{format}/delete/{stationuuid}", DeleteStation, OBJECT_FORMATS, "Delete a station, needs an api key", &[], Object("ResultMessage")).post_only(),
+    api("/{format}/pending", PendingStations, OBJECT_FORMATS, "Stations that wait for moderation, needs an api key", &["offset", "limit"], List("StationPending")).uncached(),
+    api("/{format}/approve/{stationuuid}", Moderate(true), OBJECT_FORMATS, "Publish a pending station, needs an api key", &[], Object("ResultMessage")).post_only(),
+    api("/{format}/reject/{stationuuid}", Moderate(false), OBJECT_FORMATS, "Reject a pending station, needs an api key", &[], Object("ResultMessage")).post_only(),
+    api("/{format}/streamingservers", StreamingServers, OBJECT_FORMATS, "List of streaming servers", STREAMING_SERVERS, List("ApiStreamingServer")),
+    api("/{format}/streamingservers/byserveruuid", StreamingServersByServerUuid, OBJECT_FORMATS, "Streaming servers with the uuids", &["uuids", "order", "reverse", "offset", "limit"], List("ApiStreamingServer")),
+    api("/{format}/streamingservers/bystationuuid", StreamingServersByStationUuid, OBJECT_FORMATS, "Streaming servers of the stations", &["uuids", "order", "reverse", "offset", "limit"], List("ApiStreamingServer")),
+    api("/{format}/webhooks", Webhooks, OBJECT_FORMATS, "Webhooks of the api key, all of them with the admin token", &[], List("ApiWebhook")).uncached(),
+    api("/{format}/webhooks/add", AddWebhook, OBJECT_FORMATS, "Register a webhook for station events, needs an api key", &["url", "secret", "events", "stationuuid", "tag", "countrycode"], Object("ApiWebhook")).uncached(),
+    api("/{format}/webhooks/delete/{webhookuuid}", DeleteWebhook, OBJECT_FORMATS, "Remove a webhook, needs an api key", &[], Object("ResultMessage")).uncached(),
+    api("/events", Events, &[], "Server-Sent Events with the station changes, checks and clicks that are inserted from now on", &["lastchangeuuid", "lastcheckuuid", "lastclickuuid", "lastEventId"], Content("text/event-stream")).uncached(),
+    api("/graphql", GraphQl, &[], "GraphQL query over stations, tags, countries, checks and clicks", &["query", "variables", "operationName"], Content("application/json")),
+];
+
+impl Route {
+    /// Path parameters are any segment, the rest has to be equal
+    fn matches(&self, segments: &[String]) -> bool {
+        let pattern: Vec<&str> = self.path.split('/').collect();
+        pattern.len() == segments.len()
+            && pattern
+                .iter()
+                .zip(segments)
+                .all(|(pattern, segment)| pattern.starts_with('{') || *pattern == segment.as_str())
+    }
+
+    /// Names of the placeholders in the path
+    pub fn path_parameters(&self) -> impl Iterator<Item = &'static str> {
+        self.path
+            .split('/')
+            .filter(|segment| segment.starts_with('{'))
+            .map(|segment| segment.trim_start_matches('{').trim_end_matches('}'))
+    }
+
+    pub fn allows(&self, method: &str) -> bool {
+        self.methods.contains(&method)
+    }
+}
+
+/// Route that was found for a path, with the values of its placeholders
+pub struct RouteMatch {
+    pub route: &'static Route,
+    values: Vec<(&'static str, String)>,
+}
+
+impl RouteMatch {
+    /// Value of the placeholder, None if the route does not have it
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.values.iter().find(|(key, _)| *key == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn format(&self) -> &str {
+        self.param("format").unwrap_or("")
+    }
+
+    pub fn format_allowed(&self) -> bool {
+        self.route.formats.is_empty() || self.route.formats.contains(&self.format())
+    }
+}
+
+/// Route of the path without query, None if the server does not know it
+pub fn find(path: &str) -> Option<RouteMatch> {
+    let segments: Vec<String> = path.split('/').map(|segment| percent_decode_str(segment).decode_utf8_lossy().into_owned()).collect();
+    let route = ROUTES.iter().find(|route| route.matches(&segments))?;
+    let values = route
+        .path
+        .split('/')
+        .zip(segments)
+        .filter(|(pattern, _)| pattern.starts_with('{'))
+        .map(|(pattern, value)| (pattern.trim_start_matches('{').trim_end_matches('}'), value))
+        .collect();
+    Some(RouteMatch { route, values })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find;
+    use super::Handler;
+
+    #[test]
+    fn find_routes() {
+        assert_eq!(find("/").map(|found| found.route.path), Some("/"));
+        assert_eq!(find("/json/stations").map(|found| found.route.handler), Some(Handler::Stations));
+        let found = find("/xml/stations/bytag/rock%20pop").unwrap();
+        assert_eq!(found.route.handler, Handler::StationsByColumnMultiple("Tags", false));
+        assert_eq!(found.param("searchterm"), Some("rock pop"));
+        assert_eq!(found.format(), "xml");
+        assert!(found.format_allowed());
+        let found = find("/v2/m3u/url/abc").unwrap();
+        assert_eq!(found.route.path, "/v2/{format}/url/{stationuuid}");
+        assert_eq!(found.param("stationuuid"), Some("abc"));
+        assert!(found.format_allowed());
+        assert!(!find("/m3u/stats").unwrap().format_allowed());
+        assert!(find("/json/stations/unknown").is_none());
+        assert!(find("/json/stations/bytag/rock/more").is_none());
+    }
+
+    #[test]
+    fn allowed_methods() {
+        let route = find("/json/stations").unwrap().route;
+        assert!(route.allows("GET") && route.allows("POST"));
+        assert!(!route.allows("DELETE"));
+        assert!(!find("/robots.txt").unwrap().route.allows("POST"));
+        let route = find("/json/delete/abc").unwrap().route;
+        assert!(route.allows("POST") && !route.allows("GET"));
+    }
+}
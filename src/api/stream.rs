@@ -0,0 +1,164 @@
+use crate::api::compression::ContentEncoding;
+use crate::api::compression::Encoder;
+use crate::api::conditional::EtagHasher;
+use std::error::Error;
+use std::io::Read;
+use std::io::Write;
+use std::sync::mpsc::sync_channel;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::SyncSender;
+use std::thread;
+
+/// Size of the chunks that are handed from the writing thread to the response
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Chunks that may wait for the client, the writer blocks if the client is slower
+const CHANNEL_CHUNKS: usize = 4;
+
+/// A chunk of the body, or the error that stopped the writer
+type Chunk = Result<Vec<u8>, String>;
+
+struct ChannelWriter {
+    sender: SyncSender<Chunk>,
+    buffer: Vec<u8>,
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_SIZE));
+        self.sender
+            .send(Ok(chunk))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "client closed the connection"))
+    }
+}
+
+struct ChannelReader {
+    receiver: Receiver<Chunk>,
+    chunk: Vec<u8>,
+    position: usize,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.position >= self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(Ok(chunk)) => {
+                    self.chunk = chunk;
+                    self.position = 0;
+                }
+                // an error makes the server drop the connection, so the client does not take a cut off body as complete
+                Ok(Err(err)) => return Err(std::io::Error::new(std::io::ErrorKind::Other, err)),
+                Err(_) => return Ok(0),
+            }
+        }
+        let count = buf.len().min(self.chunk.len() - self.position);
+        buf[..count].copy_from_slice(&self.chunk[self.position..self.position + count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+/// Passes everything on to the inner writer and keeps a copy with its entity tag, to store a streamed body in the cache
+pub struct TeeWriter<'a> {
+    inner: &'a mut dyn Write,
+    copy: Vec<u8>,
+    etag: EtagHasher,
+}
+
+impl<'a> TeeWriter<'a> {
+    pub fn new(inner: &'a mut dyn Write) -> Self {
+        TeeWriter {
+            inner,
+            copy: vec![],
+            etag: EtagHasher::new(),
+        }
+    }
+
+    /// The written body and its entity tag
+    pub fn into_copy(self) -> (Vec<u8>, String) {
+        (self.copy, self.etag.finish())
+    }
+}
+
+impl Write for TeeWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let count = self.inner.write(buf)?;
+        self.copy.extend_from_slice(&buf[..count]);
+        self.etag.update(&buf[..count]);
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Body that is written by the producer in its own thread while the client reads it,
+/// optionally compressed. Only a few chunks are kept in memory at any time.
+pub fn stream_body<F>(encoding: Option<ContentEncoding>, producer: F) -> rouille::ResponseBody
+where
+    F: FnOnce(&mut dyn Write) -> Result<(), Box<dyn Error>> + Send + 'static,
+{
+    let (sender, receiver) = sync_channel::<Chunk>(CHANNEL_CHUNKS);
+    let error_sender = sender.clone();
+    thread::spawn(move || {
+        let writer: Box<dyn Write + Send> = Box::new(ChannelWriter {
+            sender,
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+        });
+        let mut encoder = Encoder::new(encoding, writer);
+        let result = producer(&mut encoder).and_then(|_| {
+            let mut writer = encoder.finish()?;
+            writer.flush()?;
+            Ok(())
+        });
+        if let Err(err) = result {
+            error!("Streamed response stopped: {}", err);
+            let _ = error_sender.send(Err(err.to_string()));
+        }
+    });
+    rouille::ResponseBody::from_reader(ChannelReader {
+        receiver,
+        chunk: vec![],
+        position: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stream_body;
+    use std::io::Read;
+
+    #[test]
+    fn streamed_body() {
+        let body = stream_body(None, |writer| {
+            for i in 0..100_000 {
+                write!(writer, "{},", i)?;
+            }
+            Ok(())
+        });
+        let (mut reader, size) = body.into_reader_and_size();
+        assert_eq!(size, None);
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert!(text.starts_with("0,1,2,"));
+        assert!(text.ends_with(",99999,"));
+
+        let body = stream_body(None, |writer| {
+            writer.write_all(b"start")?;
+            Err("database gone".into())
+        });
+        let (mut reader, _) = body.into_reader_and_size();
+        assert!(reader.read_to_string(&mut String::new()).is_err());
+    }
+}
@@ -0,0 +1,809 @@
+use super::cache;
+use super::conditional;
+use super::handle_connection;
+use super::prometheus_exporter;
+use super::rate_limit::RateLimiter;
+use super::routes;
+use crate::config::ApiKey;
+use crate::config::CacheType;
+use crate::config::Config;
+use crate::db::models::NOW_PLAYING_MAX_AGE;
+use crate::db::models::StationCheckItemNew;
+use crate::db::DbConnection;
+use crate::test_utils::free_port;
+use crate::test_utils::sample_db;
+use crate::test_utils::sample_sqlite_db;
+use crate::test_utils::station_change;
+use crate::test_utils::test_config;
+use percent_encoding::utf8_percent_encode;
+use percent_encoding::NON_ALPHANUMERIC;
+use rouille::Request;
+use rouille::Response;
+use serde_json::Value;
+use std::convert::TryInto;
+use std::io::Read;
+
+/// Database backends the tests run against
+trait TestDb: DbConnection + Clone + Send + 'static {}
+
+impl<C: DbConnection + Clone + Send + 'static> TestDb for C {}
+
+fn request<C: TestDb>(conn: &C, method: &str, url: &str, ip: &str) -> (u16, String) {
+    request_with_config(conn, test_config(), method, url, vec![("X-Forwarded-For".to_string(), ip.to_string())])
+}
+
+fn request_with_config<C: TestDb>(conn: &C, config: Config, method: &str, url: &str, headers: Vec<(String, String)>) -> (u16, String) {
+    let rate_limiter = RateLimiter::new(&config);
+    request_with_limiter(conn, config, &rate_limiter, method, url, headers)
+}
+
+fn request_with_limiter<C: TestDb>(conn: &C, config: Config, rate_limiter: &RateLimiter, method: &str, url: &str, headers: Vec<(String, String)>) -> (u16, String) {
+    let response = send(conn, config, rate_limiter, method, url, headers);
+    let (mut reader, _) = response.data.into_reader_and_size();
+    // binary files like the favicon are not text
+    let mut body = vec![];
+    reader.read_to_end(&mut body).unwrap();
+    (response.status_code, String::from_utf8_lossy(&body).into_owned())
+}
+
+fn send<C: TestDb>(conn: &C, config: Config, rate_limiter: &RateLimiter, method: &str, url: &str, headers: Vec<(String, String)>) -> Response {
+    let registry = prometheus_exporter::create_registry(&config.prometheus_exporter_prefix).unwrap();
+    let cache = cache::GenericCache::new(
+        config.cache_type.clone().into(),
+        config.cache_url.clone(),
+        config.cache_ttl.as_secs().try_into().unwrap(),
+    );
+    let request = Request::fake_http(method, url, headers, vec![]);
+    handle_connection(conn, &request, config, registry, cache, rate_limiter)
+}
+
+fn header<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+    response.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_ref())
+}
+
+fn get_json<C: TestDb>(conn: &C, url: &str) -> Vec<Value> {
+    let (status, body) = request(conn, "GET", url, "10.0.0.1");
+    assert_eq!(status, 200, "{}: {}", url, body);
+    serde_json::from_str(&body).unwrap()
+}
+
+/// Rows of a csv response with the header as first row, fails if they have different lengths
+fn get_csv<C: TestDb>(conn: &C, url: &str) -> Vec<csv::StringRecord> {
+    let (status, body) = request(conn, "GET", url, "10.0.0.1");
+    assert_eq!(status, 200, "{}: {}", url, body);
+    let mut reader = csv::ReaderBuilder::new().has_headers(false).from_reader(body.as_bytes());
+    reader.records().collect::<Result<_, _>>().unwrap()
+}
+
+fn names(list: &[Value]) -> Vec<&str> {
+    list.iter().map(|item| item["name"].as_str().unwrap()).collect()
+}
+
+fn station_lists<C: TestDb>(conn: C) {
+    assert_eq!(names(&get_json(&conn, "/json/stations")), vec!["Jazz Radio", "News Radio", "Rock Radio"]);
+    assert_eq!(names(&get_json(&conn, "/json/stations?order=name&reverse=true&limit=1")), vec!["Rock Radio"]);
+    assert_eq!(names(&get_json(&conn, "/json/stations/bytagexact/rock")), vec!["Jazz Radio", "Rock Radio"]);
+    assert_eq!(names(&get_json(&conn, "/json/stations/search?name=news")), vec!["News Radio"]);
+    let found = get_json(&conn, "/json/stations/search?query=rock%20radoi&order=relevance");
+    assert_eq!(names(&found), vec!["Rock Radio", "Jazz Radio"]);
+    assert!(found[0]["score"].as_f64().unwrap() > found[1]["score"].as_f64().unwrap());
+    assert!(get_json(&conn, "/json/stations")[0].get("score").is_none());
+    let rows = get_csv(&conn, "/csv/stations/search?query=rock%20radoi&order=relevance");
+    assert_eq!(rows.len(), 3);
+    let score = rows[0].iter().position(|column| column == "score").unwrap();
+    assert!(rows[1][score].parse::<f64>().unwrap() > 0.0);
+    assert_eq!(get_json(&conn, "/json/stations/changed/s-jazz").len(), 1);
+    assert_eq!(get_json(&conn, "/json/countrycodes")[0]["stationcount"], 3);
+
+    let (status, _) = request(&conn, "GET", "/json/unknown", "10.0.0.1");
+    assert_eq!(status, 404);
+    let (status, _) = request(&conn, "GET", "/yaml/stations", "10.0.0.1");
+    assert_eq!(status, 406);
+    let (status, _) = request(&conn, "GET", "/m3u/stats", "10.0.0.1");
+    assert_eq!(status, 406);
+    let config = test_config();
+    let response = send(&conn, config.clone(), &RateLimiter::new(&config), "DELETE", "/json/stations", vec![]);
+    assert_eq!(response.status_code, 405);
+    assert_eq!(header(&response, "Allow"), Some("GET, POST"));
+    let response = send(&conn, config.clone(), &RateLimiter::new(&config), "POST", "/robots.txt", vec![]);
+    assert_eq!(response.status_code, 405);
+}
+
+fn add_vote_and_click<C: TestDb>(conn: C) {
+    let (status, body) = request(&conn, "POST", "/json/add?name=New%20Radio&url=http://new.example.com/&countrycode=AT", "10.0.0.1");
+    assert_eq!(status, 200);
+    let result: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(result["ok"], true, "{}", body);
+    assert_eq!(get_json(&conn, "/json/stations").len(), 4);
+
+    let (_, body) = request(&conn, "GET", "/json/vote/s-news", "10.0.0.1");
+    assert!(body.contains("\"ok\":true"), "{}", body);
+    let (_, body) = request(&conn, "GET", "/json/vote/s-news", "10.0.0.1");
+    assert!(body.contains("\"ok\":false"), "{}", body);
+    assert_eq!(names(&get_json(&conn, "/json/stations/topvote/1")), vec!["News Radio"]);
+
+    let (status, _) = request(&conn, "GET", "/json/url/s-rock", "10.0.0.1");
+    assert_eq!(status, 200);
+    // a click is never answered with 304, even if the client sends a matching tag
+    let config = test_config();
+    let headers = vec![("If-None-Match".to_string(), String::from("*")), ("X-Forwarded-For".to_string(), String::from("10.0.0.1"))];
+    let response = send(&conn, config.clone(), &RateLimiter::new(&config), "GET", "/json/url/s-rock", headers);
+    assert_eq!(response.status_code, 200);
+    assert_eq!(header(&response, "ETag"), None);
+    assert_eq!(get_json(&conn, "/json/clicks/s-rock").len(), 1);
+    assert_eq!(names(&get_json(&conn, "/json/stations/lastclick/1")), vec!["Rock Radio"]);
+}
+
+fn add_duplicates<C: TestDb>(conn: C) {
+    let add = |query: &str| -> Value {
+        let (status, body) = request(&conn, "POST", &format!("/json/add?{}", query), "10.0.0.1");
+        assert_eq!(status, 200);
+        serde_json::from_str(&body).unwrap()
+    };
+
+    let result = add("name=Other&url=HTTP://JAZZ.example.com/live&countrycode=AT");
+    assert_eq!(result["ok"], false);
+    assert_eq!(result["uuid"], "s-jazz");
+    assert_eq!(result["duplicates"][0]["reason"], "url");
+
+    let result = add("name=Jaz%20Radio&url=http://other.example.com/&countrycode=DE");
+    assert_eq!(result["ok"], false);
+    assert_eq!(result["uuid"], "");
+    assert_eq!(result["duplicates"][0]["stationuuid"], "s-jazz");
+    assert_eq!(result["duplicates"][0]["reason"], "name");
+
+    assert_eq!(add("name=Other&url=http://jazz.example.com/live&countrycode=AT&force=true")["ok"], false);
+    assert_eq!(add("name=Jaz%20Radio&url=http://other.example.com/&countrycode=DE&force=true")["ok"], true);
+    assert_eq!(add("name=Rock%20Radio&url=http://rock.example.at/&countrycode=AT")["ok"], true);
+    assert_eq!(get_json(&conn, "/json/stations").len(), 5);
+}
+
+fn edit_and_delete<C: TestDb>(conn: C) {
+    let mut config = test_config();
+    config.admin_token = Some(String::from("admin-secret"));
+    config.api_keys = vec![ApiKey { name: String::from("alice"), key: String::from("alice-secret") }];
+    let call = |url: &str, token: &str| {
+        let headers = vec![("Authorization".to_string(), format!("Bearer {}", token))];
+        request_with_config(&conn, config.clone(), "POST", url, headers)
+    };
+
+    let (status, _) = call("/json/edit/s-jazz?name=Smooth%20Jazz", "wrong");
+    assert_eq!(status, 401);
+    let (status, body) = call("/json/edit/s-jazz?name=Smooth%20Jazz&tags=jazz,smooth", "alice-secret");
+    assert_eq!(status, 200);
+    assert!(body.contains("\"ok\":true"), "{}", body);
+    let station = &get_json(&conn, "/json/stations/byuuid/s-jazz")[0];
+    assert_eq!(station["name"], "Smooth Jazz");
+    assert_eq!(station["tags"], "jazz,smooth");
+    assert_eq!(get_json(&conn, "/json/stations/changed/s-jazz").len(), 2);
+    assert_eq!(names(&get_json(&conn, "/json/stations/search?query=smoth")), vec!["Smooth Jazz"]);
+    let (_, body) = call("/json/edit/s-unknown?name=Nothing", "alice-secret");
+    assert!(body.contains("\"ok\":false"), "{}", body);
+
+    let headers = vec![("Authorization".to_string(), String::from("Bearer admin-secret"))];
+    let (status, _) = request_with_config(&conn, config.clone(), "GET", "/json/delete/s-jazz", headers);
+    assert_eq!(status, 405);
+    let (status, _) = call("/json/delete/s-jazz", "alice-secret");
+    assert_eq!(status, 403);
+    let (status, body) = call("/json/delete/s-jazz", "admin-secret");
+    assert_eq!(status, 200);
+    assert!(body.contains("\"ok\":true"), "{}", body);
+    assert!(get_json(&conn, "/json/stations/byuuid/s-jazz").is_empty());
+    assert!(get_json(&conn, "/json/stations/search?query=smooth").is_empty());
+}
+
+fn moderation_queue<C: TestDb>(conn: C) {
+    let mut config = test_config();
+    config.admin_token = Some(String::from("admin-secret"));
+    config.moderation = true;
+    let call = |method: &str, url: &str, token: &str| {
+        let headers = vec![("Authorization".to_string(), format!("Bearer {}", token))];
+        request_with_config(&conn, config.clone(), method, url, headers)
+    };
+
+    let (_, body) = call("POST", "/json/add?name=Queued%20Radio&url=http://queued.example.com/&countrycode=AT", "");
+    let result: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(result["ok"], true, "{}", body);
+    let queued = result["uuid"].as_str().unwrap().to_string();
+    let (_, body) = call("POST", "/json/add?name=Spam%20Radio&url=http://spam.example.com/&countrycode=AT", "");
+    let spam: Value = serde_json::from_str(&body).unwrap();
+    let (_, body) = call("POST", "/json/add?name=Queued%20Again&url=http://queued.example.com/&countrycode=AT&force=true", "");
+    let again: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(again["ok"], false, "{}", body);
+    assert_eq!(again["duplicates"][0]["reason"], "pending");
+    assert_eq!(get_json(&conn, "/json/stations").len(), 3);
+    assert!(get_json(&conn, "/json/stations/search?name=Queued").is_empty());
+
+    let (status, _) = call("GET", "/json/pending", "");
+    assert_eq!(status, 401);
+    let (status, body) = call("GET", "/json/pending", "admin-secret");
+    assert_eq!(status, 200);
+    let pending: Vec<Value> = serde_json::from_str(&body).unwrap();
+    assert_eq!(names(&pending), vec!["Queued Radio", "Spam Radio"]);
+
+    let (_, body) = call("POST", &format!("/json/approve/{}", queued), "admin-secret");
+    assert!(body.contains("\"ok\":true"), "{}", body);
+    let (_, body) = call("POST", &format!("/json/reject/{}", spam["uuid"].as_str().unwrap()), "admin-secret");
+    assert!(body.contains("\"ok\":true"), "{}", body);
+    let (_, body) = call("POST", &format!("/json/approve/{}", queued), "admin-secret");
+    assert!(body.contains("\"ok\":false"), "{}", body);
+
+    assert_eq!(names(&get_json(&conn, "/json/stations/search?name=Radio")), vec!["Jazz Radio", "News Radio", "Queued Radio", "Rock Radio"]);
+    assert_eq!(get_json(&conn, &format!("/json/stations/changed/{}", queued)).len(), 1);
+    let (_, body) = call("GET", "/json/pending", "admin-secret");
+    assert_eq!(body, "[]");
+}
+
+fn webhooks<C: TestDb>(conn: C) {
+    let mut config = test_config();
+    config.admin_token = Some(String::from("admin-secret"));
+    config.api_keys = vec![
+        ApiKey { name: String::from("alice"), key: String::from("alice-secret") },
+        ApiKey { name: String::from("bob"), key: String::from("bob-secret") },
+    ];
+    let call = |url: &str, token: &str| {
+        let headers = vec![("Authorization".to_string(), format!("Bearer {}", token))];
+        request_with_config(&conn, config.clone(), "POST", url, headers)
+    };
+
+    let (status, _) = call("/json/webhooks/add?url=http://203.0.113.10/&secret=s", "");
+    assert_eq!(status, 401);
+    let (status, _) = call("/json/webhooks/add?url=ftp://203.0.113.10/&secret=s", "alice-secret");
+    assert_eq!(status, 400);
+    let (status, _) = call("/json/webhooks/add?url=http://169.254.169.254/&secret=s", "alice-secret");
+    assert_eq!(status, 400);
+    let (status, _) = call("/json/webhooks/add?url=http://203.0.113.10/&secret=s&events=station_exploded", "alice-secret");
+    assert_eq!(status, 400);
+    let (status, body) = call("/json/webhooks/add?url=http://203.0.113.10/&secret=s&events=station_broken,station_deleted&tag=Rock", "alice-secret");
+    assert_eq!(status, 200, "{}", body);
+    let added: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(added["owner"], "alice");
+    assert_eq!(added["tag"], "rock");
+    assert!(added.get("secret").is_none());
+    call("/json/webhooks/add?url=http://203.0.113.11/&secret=t", "bob-secret");
+
+    let list = |token: &str| -> Vec<Value> { serde_json::from_str(&call("/json/webhooks", token).1).unwrap() };
+    assert_eq!(list("alice-secret").len(), 1);
+    assert_eq!(list("admin-secret").len(), 2);
+
+    let uuid = added["webhookuuid"].as_str().unwrap();
+    let (_, body) = call(&format!("/json/webhooks/delete/{}", uuid), "bob-secret");
+    assert!(body.contains("\"ok\":false"), "{}", body);
+    let (_, body) = call(&format!("/json/webhooks/delete/{}", uuid), "alice-secret");
+    assert!(body.contains("\"ok\":true"), "{}", body);
+    assert!(list("alice-secret").is_empty());
+}
+
+fn check_on_request<C: TestDb>(conn: C) {
+    let url = format!("http://127.0.0.1:{}/stream", free_port());
+    conn.insert_station_by_change(&[station_change("s-down", "c-down", "Down Radio", &url, "pop")], "test")
+        .unwrap();
+    let mut config = test_config();
+    config.rate_limit_check = 1;
+    let rate_limiter = RateLimiter::new(&config);
+    let call = |url: &str, config: &Config| {
+        let headers = vec![("X-Forwarded-For".to_string(), "10.0.0.1".to_string())];
+        request_with_limiter(&conn, config.clone(), &rate_limiter, "POST", url, headers)
+    };
+
+    assert_eq!(call("/json/check/s-down", &config).0, 423, "only servers that check stations");
+    config.enable_check = true;
+    assert_eq!(call("/json/check/s-unknown", &config).0, 429);
+
+    let config = Config { rate_limit_check: 0, ..config };
+    assert_eq!(request_with_config(&conn, config.clone(), "GET", "/json/check/s-down", vec![]).0, 405);
+    assert_eq!(request_with_config(&conn, config.clone(), "POST", "/json/check/s-unknown", vec![]).0, 404);
+    let (status, body) = request_with_config(&conn, config.clone(), "POST", "/json/check/s-down", vec![]);
+    assert_eq!(status, 200, "{}", body);
+    let result: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(result["check"]["ok"], 0);
+    assert_eq!(result["check"]["source"], "test");
+    let checkuuid = result["check"]["checkuuid"].as_str().unwrap();
+    let steps = result["steps"].as_array().unwrap();
+    assert!(!steps.is_empty());
+    assert!(steps.iter().all(|step| step["checkuuid"] == checkuuid));
+
+    let station = conn.get_station_by_uuid("s-down").unwrap().pop().unwrap();
+    assert!(station.lastlocalchecktime_iso8601.is_some());
+    assert_eq!(conn.get_checks(Some("s-down".to_string()), None, 0, true, 10).unwrap().len(), 1);
+
+    // a station that was just checked gets the same check again
+    let (status, body) = request_with_config(&conn, config.clone(), "POST", "/json/check/s-down", vec![]);
+    assert_eq!(status, 200, "{}", body);
+    let result: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(result["check"]["checkuuid"], checkuuid);
+    assert_eq!(result["steps"].as_array().unwrap().len(), steps.len());
+    assert_eq!(conn.get_checks(Some("s-down".to_string()), None, 0, true, 10).unwrap().len(), 1);
+}
+
+fn check_sources<C: TestDb>(conn: C) {
+    let mut ok = StationCheckItemNew::broken("s-jazz".to_string(), "k1".to_string(), "eu".to_string(), 1);
+    ok.check_ok = true;
+    let checks = vec![ok, StationCheckItemNew::broken("s-jazz".to_string(), "k2".to_string(), "us".to_string(), 1)];
+    conn.insert_checks(checks).unwrap();
+    let mut config = test_config();
+    config.check_consensus_sources = 1;
+
+    let (status, body) = request_with_config(&conn, config, "GET", "/json/checksources/s-jazz", vec![]);
+    assert_eq!(status, 200, "{}", body);
+    let sources: Value = serde_json::from_str(&body).unwrap();
+    let sources = sources.as_array().unwrap();
+    assert_eq!(sources.len(), 2);
+    assert_eq!((&sources[0]["source"], &sources[0]["ok"], &sources[0]["consensus"]), (&Value::from("us"), &Value::from(0), &Value::from(1)));
+    assert_eq!((&sources[1]["source"], &sources[1]["ok"], &sources[1]["consensus"]), (&Value::from("eu"), &Value::from(1), &Value::from(0)));
+}
+
+fn now_playing<C: TestDb>(conn: C) {
+    conn.set_now_playing(&[("s-jazz".to_string(), "Miles Davis - So What".to_string())]).unwrap();
+
+    let (status, body) = request(&conn, "GET", "/json/nowplaying/s-jazz", "10.0.0.1");
+    assert_eq!(status, 200, "{}", body);
+    let now_playing: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(now_playing["title"], "Miles Davis - So What");
+    assert!(now_playing["timestamp_iso8601"].is_string());
+    let (_, body) = request(&conn, "GET", "/json/nowplaying/s-rock", "10.0.0.1");
+    assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["title"], Value::Null);
+    assert_eq!(request(&conn, "GET", "/json/nowplaying/unknown", "10.0.0.1").0, 404);
+
+    let stations = get_json(&conn, "/json/stations?fields=name,now_playing");
+    assert_eq!(stations[0]["now_playing"], "Miles Davis - So What");
+    assert_eq!(stations[1]["now_playing"], Value::Null);
+    let stations = get_json(&conn, "/json/stations");
+    assert_eq!(stations[0]["now_playing"], "Miles Davis - So What");
+    assert!(stations[1].get("now_playing").is_none(), "{}", stations[1]);
+    let (status, body) = request(&conn, "GET", "/csv/stations", "10.0.0.1");
+    assert_eq!(status, 200, "{}", body);
+}
+
+#[test]
+fn now_playing_expires() {
+    let conn = sample_db();
+    conn.set_now_playing(&[("s-jazz".to_string(), "Miles Davis - So What".to_string())]).unwrap();
+    conn.advance_clock(NOW_PLAYING_MAX_AGE.as_secs() as i64 + 1);
+
+    let (_, body) = request(&conn, "GET", "/json/nowplaying/s-jazz", "10.0.0.1");
+    assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["title"], Value::Null);
+    assert!(get_json(&conn, "/json/stations")[0].get("now_playing").is_none());
+}
+
+fn rate_limits<C: TestDb>(conn: C) {
+    let mut config = test_config();
+    config.rate_limit_vote = 2;
+    config.rate_limit_allowlist = vec![String::from("10.1.0.0/16")];
+    config.trusted_proxies = vec![String::from("127.0.0.1"), String::from("10.9.0.0/16")];
+    let rate_limiter = RateLimiter::new(&config);
+    let call = |url: &str, ip: &str| {
+        let headers = vec![("X-Forwarded-For".to_string(), ip.to_string())];
+        request_with_limiter(&conn, config.clone(), &rate_limiter, "GET", url, headers)
+    };
+
+    assert_eq!(call("/json/vote/s-jazz", "10.0.0.1").0, 200);
+    assert_eq!(call("/json/vote/s-rock", "10.0.0.1").0, 200);
+    let (status, body) = call("/json/vote/s-news", "10.0.0.1");
+    assert_eq!(status, 429);
+    assert_eq!(body, "too many requests");
+    assert_eq!(call("/json/vote/s-news", "10.0.0.2, 10.0.0.1").0, 429);
+    assert_eq!(call("/json/vote/s-news", "10.0.0.1, 10.9.0.1").0, 429);
+    assert_eq!(call("/json/vote/s-news", "10.0.0.1, 10.0.0.2").0, 200);
+    assert_eq!(call("/json/stations", "10.0.0.1").0, 200);
+    for _ in 0..5 {
+        assert_eq!(call("/json/vote/s-news", "10.1.2.3").0, 200);
+    }
+}
+
+fn conditional_requests<C: TestDb>(conn: C) {
+    let config = test_config();
+    let rate_limiter = RateLimiter::new(&config);
+    let get = |headers: Vec<(&str, String)>| {
+        let headers = headers.into_iter().map(|(key, value)| (key.to_string(), value)).collect();
+        send(&conn, config.clone(), &rate_limiter, "GET", "/json/stations/search", headers)
+    };
+
+    let response = get(vec![]);
+    assert_eq!(response.status_code, 200);
+    let etag = header(&response, "ETag").unwrap().to_string();
+    let last_modified = header(&response, "Last-Modified").unwrap().to_string();
+    assert_eq!(header(&response, "Cache-Control"), Some("public, max-age=60"));
+
+    let response = get(vec![("If-None-Match", etag.clone())]);
+    assert_eq!(response.status_code, 304);
+    assert_eq!(header(&response, "ETag"), Some(etag.as_str()));
+    assert_eq!(get(vec![("If-Modified-Since", last_modified)]).status_code, 304);
+    assert_eq!(get(vec![("If-None-Match", String::from("\"old\""))]).status_code, 200);
+
+    let (_, body) = request(&conn, "GET", "/json/vote/s-news", "10.0.0.1");
+    assert!(body.contains("\"ok\":true"), "{}", body);
+    assert_eq!(get(vec![("If-None-Match", etag)]).status_code, 200);
+}
+
+fn compressed_responses<C: TestDb>(conn: C) {
+    let config = test_config();
+    let rate_limiter = RateLimiter::new(&config);
+    let get = |encoding: &str| {
+        let headers = vec![("Accept-Encoding".to_string(), encoding.to_string())];
+        send(&conn, config.clone(), &rate_limiter, "GET", "/json/stations/search", headers)
+    };
+    let read = |response: Response| {
+        let (mut reader, _) = response.data.into_reader_and_size();
+        let mut body = vec![];
+        reader.read_to_end(&mut body).unwrap();
+        body
+    };
+
+    let plain = get("identity");
+    assert_eq!(header(&plain, "Content-Encoding"), None);
+    let plain_etag = header(&plain, "ETag").unwrap().to_string();
+    let plain_body = read(plain);
+    assert!(plain_body.len() > 1024);
+
+    let gzip = get("gzip, deflate");
+    assert_eq!(header(&gzip, "Content-Encoding"), Some("gzip"));
+    assert_eq!(header(&gzip, "Vary"), Some("Accept-Encoding"));
+    let gzip_etag = header(&gzip, "ETag").unwrap().to_string();
+    assert_ne!(gzip_etag, plain_etag);
+    let mut decoded = vec![];
+    flate2::read::GzDecoder::new(&read(gzip)[..]).read_to_end(&mut decoded).unwrap();
+    assert_eq!(decoded, plain_body);
+
+    let br = get("gzip, br");
+    assert_eq!(header(&br, "Content-Encoding"), Some("br"));
+    let mut decoded = vec![];
+    brotli::Decompressor::new(&read(br)[..], 4096).read_to_end(&mut decoded).unwrap();
+    assert_eq!(decoded, plain_body);
+
+    let headers = vec![("Accept-Encoding".to_string(), "gzip".to_string()), ("If-None-Match".to_string(), gzip_etag)];
+    assert_eq!(send(&conn, config.clone(), &rate_limiter, "GET", "/json/stations/search", headers).status_code, 304);
+}
+
+fn streamed_station_lists<C: TestDb>(conn: C) {
+    let list = get_json(&conn, "/json/stations?order=name");
+    assert_eq!(names(&list), vec!["Jazz Radio", "News Radio", "Rock Radio"]);
+    let searched = get_json(&conn, "/json/stations/search?order=name");
+    assert_eq!(list, searched);
+
+    let (status, body) = request(&conn, "GET", "/xml/stations?limit=2", "10.0.0.1");
+    assert_eq!(status, 200);
+    assert!(body.contains("<result>"), "{}", body);
+    assert_eq!(body.matches("<station ").count(), 2);
+    let (_, body) = request(&conn, "GET", "/csv/stations", "10.0.0.1");
+    assert!(body.starts_with("changeuuid,stationuuid,"), "{}", body);
+    assert_eq!(body.lines().count(), 4);
+    let (_, body) = request(&conn, "GET", "/m3u/stations", "10.0.0.1");
+    assert!(body.starts_with("#EXTM3U\r\n#RADIOBROWSERUUID:"), "{}", body);
+
+    let config = test_config();
+    let rate_limiter = RateLimiter::new(&config);
+    let headers = vec![("Accept-Encoding".to_string(), "gzip".to_string())];
+    let response = send(&conn, config.clone(), &rate_limiter, "GET", "/json/stations", headers);
+    assert_eq!(header(&response, "Content-Encoding"), Some("gzip"));
+    assert_eq!(header(&response, "Content-Type"), Some("application/json"));
+    let (reader, size) = response.data.into_reader_and_size();
+    assert_eq!(size, None);
+    let mut decoded = String::new();
+    flate2::read::GzDecoder::new(reader).read_to_string(&mut decoded).unwrap();
+    let decoded: Vec<Value> = serde_json::from_str(&decoded).unwrap();
+    assert_eq!(decoded.len(), 3);
+
+    // the entity tag is only known at the end of the stream, it comes with the cached copy
+    let mut config = test_config();
+    config.cache_type = CacheType::BuiltIn;
+    let registry = prometheus_exporter::create_registry(&config.prometheus_exporter_prefix).unwrap();
+    let cache = cache::GenericCache::new(config.cache_type.clone().into(), config.cache_url.clone(), 60);
+    let get = |headers: Vec<(String, String)>| {
+        let request = Request::fake_http("GET", "/json/stations", headers, vec![]);
+        handle_connection(&conn, &request, config.clone(), registry.clone(), cache.clone(), &rate_limiter)
+    };
+    let streamed = get(vec![]);
+    assert_eq!(header(&streamed, "ETag"), None);
+    let mut body = vec![];
+    streamed.data.into_reader_and_size().0.read_to_end(&mut body).unwrap();
+    let cached = get(vec![]);
+    let etag = header(&cached, "ETag").unwrap().to_string();
+    assert_eq!(etag, conditional::etag(&body));
+    assert_eq!(get(vec![("If-None-Match".to_string(), etag)]).status_code, 304);
+}
+
+fn cursor_pagination<C: TestDb>(conn: C) {
+    let conn = geo_db(conn);
+    let config = test_config();
+    let rate_limiter = RateLimiter::new(&config);
+    let mut url = String::from("/json/stations/search?order=name&limit=2");
+    let mut paged: Vec<String> = vec![];
+    for _ in 0..10 {
+        let response = send(&conn, config.clone(), &rate_limiter, "GET", &url, vec![]);
+        assert_eq!(response.status_code, 200);
+        let next = header(&response, "Link").map(|link| link.trim_start_matches('<').split('>').next().unwrap().to_string());
+        let mut body = String::new();
+        response.data.into_reader_and_size().0.read_to_string(&mut body).unwrap();
+        let list: Vec<Value> = serde_json::from_str(&body).unwrap();
+        paged.extend(names(&list).into_iter().map(String::from));
+        match next {
+            Some(next) => url = next,
+            None => break,
+        }
+    }
+    let all = get_json(&conn, "/json/stations/search?order=name");
+    assert_eq!(paged, names(&all));
+
+    let (status, body) = request(&conn, "GET", "/json/stations?order=votes&reverse=true&limit=4&envelope=true", "10.0.0.1");
+    assert_eq!(status, 200);
+    let page: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(page["items"].as_array().unwrap().len(), 4);
+    let cursor = page["next_cursor"].as_str().unwrap().to_string();
+    let next = page["next"].as_str().unwrap();
+    assert!(next.starts_with("/json/stations?order=votes&reverse=true&limit=4&envelope=true&cursor="), "{}", next);
+    let (_, body) = request(&conn, "GET", next, "10.0.0.1");
+    let page: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(page["items"].as_array().unwrap().len(), 2);
+    assert!(page["next"].is_null());
+
+    let (status, _) = request(&conn, "GET", "/json/stations?cursor=nothex", "10.0.0.1");
+    assert_eq!(status, 400);
+    for params in ["order=relevance", "order=distance&geo_lat=50&geo_long=8", "query=radio"] {
+        let (status, body) = request(&conn, "GET", &format!("/json/stations/search?{}&cursor={}", params, cursor), "10.0.0.1");
+        assert_eq!(status, 400, "{}: {}", params, body);
+    }
+    let (_, body) = request(&conn, "GET", "/json/stations/search?query=radio&limit=1&envelope=true", "10.0.0.1");
+    let page: Value = serde_json::from_str(&body).unwrap();
+    assert!(page["next"].is_null(), "{}", body);
+}
+
+fn include_count<C: TestDb>(conn: C) {
+    let conn = geo_db(conn);
+    let config = test_config();
+    let rate_limiter = RateLimiter::new(&config);
+    let all = get_json(&conn, "/json/stations/bycountrycodeexact/DE");
+    let url = "/json/stations/bycountrycodeexact/DE?limit=1&includecount=true";
+    let response = send(&conn, config.clone(), &rate_limiter, "GET", url, vec![]);
+    assert_eq!(response.status_code, 200);
+    assert_eq!(header(&response, "X-Total-Count"), Some(all.len().to_string().as_str()));
+    let response = send(&conn, config.clone(), &rate_limiter, "GET", "/json/stations/bycountrycodeexact/DE?limit=1", vec![]);
+    assert_eq!(header(&response, "X-Total-Count"), None);
+
+    let url = "/json/stations/search?geo_lat=52.52&geo_long=13.405&geo_distance=50000&limit=1&includecount=true&envelope=true";
+    let (status, body) = request(&conn, "GET", url, "10.0.0.1");
+    assert_eq!(status, 200);
+    let page: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(page["items"].as_array().unwrap().len(), 1);
+    assert_eq!(page["total_count"].as_u64(), Some(2));
+}
+
+fn selected_fields<C: TestDb>(conn: C) {
+    let keys = |station: &Value| station.as_object().unwrap().keys().cloned().collect::<Vec<String>>();
+    for url in ["/json/stations?fields=name,stationuuid", "/json/stations/search?order=votes&fields=name,stationuuid", "/json/stations/topvote?fields=stationuuid,name"] {
+        let list = get_json(&conn, url);
+        assert!(!list.is_empty(), "{}", url);
+        for station in list.iter() {
+            assert_eq!(keys(station), vec!["name", "stationuuid"], "{}", url);
+        }
+    }
+    let (_, body) = request(&conn, "GET", "/json/stations?limit=2&envelope=true&fields=url", "10.0.0.1");
+    let page: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(keys(&page["items"][0]), vec!["url"]);
+
+    let (status, body) = request(&conn, "GET", "/csv/stations?order=name&fields=votes,name", "10.0.0.1");
+    assert_eq!(status, 200);
+    assert_eq!(body.lines().next(), Some("name,votes"));
+    let (_, body) = request(&conn, "GET", "/xml/stations?limit=1&fields=name", "10.0.0.1");
+    assert!(body.contains("<station name=") && !body.contains("stationuuid="), "{}", body);
+
+    let (status, _) = request(&conn, "GET", "/json/stations?fields=name,password", "10.0.0.1");
+    assert_eq!(status, 400);
+}
+
+fn openapi_spec<C: TestDb>(conn: C) {
+    let (status, body) = request(&conn, "GET", "/openapi.json", "10.0.0.1");
+    assert_eq!(status, 200);
+    let spec: Value = serde_json::from_str(&body).unwrap();
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+
+    let station = &get_json(&conn, "/json/stations?limit=1")[0];
+    let properties = &spec["components"]["schemas"]["Station"]["properties"];
+    for key in station.as_object().unwrap().keys() {
+        assert!(properties.get(key).is_some(), "field {} of stations is not described", key);
+    }
+
+    // every described route has to be answered, paths that are not described are not
+    let uuid = station["stationuuid"].as_str().unwrap();
+    for route in routes::ROUTES {
+        assert!(spec["paths"].get(route.path).is_some(), "{}", route.path);
+        for (method, operation) in spec["paths"][route.path].as_object().unwrap() {
+            for name in route.parameters {
+                let description = match operation["requestBody"]["content"]["application/json"]["schema"]["properties"].get(name) {
+                    Some(property) => &property["description"],
+                    None => &operation["parameters"].as_array().unwrap().iter().find(|parameter| parameter["name"] == *name && parameter["in"] == "query").unwrap_or(&Value::Null)["description"],
+                };
+                assert!(description.as_str().is_some_and(|text| !text.is_empty()), "parameter {} of {} {} is not described", name, method, route.path);
+            }
+        }
+        // asks the DNS of the public servers, or never ends
+        if route.path == "/{format}/servers" || route.path == "/events" {
+            continue;
+        }
+        let url = route
+            .path
+            .replace("{format}", "json")
+            .replace("{limit}", "1")
+            .replace("{stationuuid}", uuid)
+            .replace("{searchterm}", "a")
+            .replace("{filter}", "a")
+            .replace("{country}", "a");
+        let (status, body) = request(&conn, "GET", &url, "10.0.0.1");
+        assert_ne!(status, 404, "{}: {}", url, body);
+    }
+    let (status, _) = request(&conn, "GET", "/json/stations/unknown", "10.0.0.1");
+    assert_eq!(status, 404);
+}
+
+fn graphql_queries<C: TestDb>(conn: C) {
+    let graphql = |query: &str| {
+        let url = format!("/graphql?query={}", utf8_percent_encode(query, NON_ALPHANUMERIC));
+        let (status, body) = request(&conn, "GET", &url, "10.0.0.1");
+        assert_eq!(status, 200, "{}", body);
+        serde_json::from_str::<Value>(&body).unwrap()
+    };
+    let answer = graphql(r#"{ station(uuid: "s-jazz") { name tags history(limit: 1) { name } } stations(tag: "rock", order: "name") { name } }"#);
+    assert!(answer.get("errors").is_none(), "{}", answer);
+    assert_eq!(answer["data"]["station"]["name"], "Jazz Radio");
+    assert_eq!(answer["data"]["station"]["history"][0]["name"], "Jazz Radio");
+    assert_eq!(names(answer["data"]["stations"].as_array().unwrap()), vec!["Jazz Radio", "Rock Radio"]);
+
+    let answer = graphql("{ stations { password } }");
+    assert!(answer.get("data").is_none());
+    assert!(answer["errors"][0]["message"].as_str().unwrap().contains("password"), "{}", answer);
+    let answer = graphql("{ stations { checksteps { children { children { children { children { children { children { children { children { children { url } } } } } } } } } } } }");
+    assert!(answer["errors"][0]["message"].as_str().unwrap().contains("depth"), "{}", answer);
+}
+
+#[test]
+fn search_typos_by_backend() {
+    // the full-text index of sqlite and mysql looks up the start of each word, only the memory database finds typos in front
+    let sqlite = sample_sqlite_db();
+    assert_eq!(names(&get_json(&sqlite, "/json/stations/search?query=jazx")), vec!["Jazz Radio"]);
+    assert!(get_json(&sqlite, "/json/stations/search?query=jzaz").is_empty());
+    let memory = sample_db();
+    assert_eq!(names(&get_json(&memory, "/json/stations/search?query=jazx")), vec!["Jazz Radio"]);
+    assert_eq!(names(&get_json(&memory, "/json/stations/search?query=jzaz")), vec!["Jazz Radio"]);
+}
+
+/// Reads the body of an event stream until it contains the text
+fn read_events(response: Response, until: &str) -> String {
+    let (mut reader, _) = response.data.into_reader_and_size();
+    let mut text = String::new();
+    let mut buffer = [0; 4096];
+    while !text.contains(until) {
+        let count = reader.read(&mut buffer).unwrap();
+        assert!(count > 0, "{}", text);
+        text.push_str(&String::from_utf8_lossy(&buffer[..count]));
+    }
+    text
+}
+
+#[test]
+fn event_stream() {
+    let conn = sample_db();
+    conn.advance_clock(61);
+    let config = test_config();
+    let rate_limiter = RateLimiter::new(&config);
+    let last_event = vec![("Last-Event-ID".to_string(), "c-jazz,,".to_string())];
+    let response = send(&conn, config.clone(), &rate_limiter, "GET", "/events", last_event.clone());
+    assert_eq!(response.status_code, 200);
+    assert_eq!(header(&response, "Content-Type"), Some("text/event-stream"));
+
+    // every client needs a thread
+    let (status, _) = request(&conn, "GET", "/events", "10.0.0.1");
+    assert_eq!(status, 503);
+
+    // the comment is written when there is nothing more to send
+    let text = read_events(response, "\n:\n\n");
+    assert_eq!(text.matches("event: change\n").count(), 2, "{}", text);
+    assert!(text.contains("id: c-rock,,\nevent: change\ndata: {"), "{}", text);
+    assert!(text.contains("id: c-news,,\n"), "{}", text);
+    assert!(!text.contains("c-jazz"), "{}", text);
+
+    let (status, _) = request(&conn, "GET", "/events?lastEventId=c-jazz", "10.0.0.1");
+    assert_eq!(status, 400);
+}
+
+/// Sample db with three more stations that have a location
+fn geo_db<C: TestDb>(conn: C) -> C {
+    for (name, lat, long) in [("Berlin", 52.52, 13.405), ("Potsdam", 52.396, 13.058), ("Munich", 48.137, 11.575)] {
+        let url = format!("/json/add?name={}&url=http://{}.example.com/&countrycode=DE&geo_lat={}&geo_long={}", name, name, lat, long);
+        let (status, body) = request(&conn, "POST", &url, "10.0.0.1");
+        assert_eq!(status, 200, "{}", body);
+    }
+    conn
+}
+
+fn geo_search<C: TestDb>(conn: C) {
+    let conn = geo_db(conn);
+
+    let near = get_json(&conn, "/json/stations/search?geo_lat=52.52&geo_long=13.405&geo_distance=50000&order=distance&reverse=true");
+    assert_eq!(names(&near), vec!["Potsdam", "Berlin"]);
+    assert_eq!(near[1]["distance"].as_f64(), Some(0.0));
+    assert!(near[0]["distance"].as_f64().unwrap() > 20000.0);
+
+    let all = get_json(&conn, "/json/stations/search?geo_lat=48.137&geo_long=11.575&order=distance&limit=2");
+    assert_eq!(names(&all), vec!["Munich", "Potsdam"]);
+    let all = get_json(&conn, "/json/stations/search?geo_lat=48.137&geo_long=11.575&order=distance&offset=3");
+    assert_eq!(all.len(), 3);
+    assert!(all[0].get("distance").is_none());
+    let rows = get_csv(&conn, "/csv/stations/search?geo_lat=48.137&geo_long=11.575&order=distance");
+    let distance = rows[0].iter().position(|column| column == "distance").unwrap();
+    assert_eq!(&rows[1][distance], "0.0");
+    assert_eq!(&rows[rows.len() - 1][distance], "");
+
+    let viewport = "/json/stations/search?geo_lat_min=52&geo_lat_max=53&geo_long_min=12&geo_long_max=14";
+    assert_eq!(names(&get_json(&conn, viewport)), vec!["Berlin", "Potsdam"]);
+    let around_the_world = "/json/stations/search?geo_lat_min=52&geo_lat_max=53&geo_long_min=14&geo_long_max=12";
+    assert!(get_json(&conn, around_the_world).is_empty());
+
+    for invalid in ["geo_lat=91&geo_long=0", "geo_lat=NaN&geo_long=0", "geo_lat=0&geo_long=-181", "geo_lat=0&geo_long=0&geo_distance=-1", "geo_lat_min=-100",
+        "geo_lat_min=53&geo_lat_max=52&geo_long_min=12&geo_long_max=14"] {
+        let (status, _) = request(&conn, "GET", &format!("/json/stations/search?{}", invalid), "10.0.0.1");
+        assert_eq!(status, 400, "{}", invalid);
+    }
+}
+
+fn geo_formats<C: TestDb>(conn: C) {
+    let conn = geo_db(conn);
+    let (status, body) = request(&conn, "GET", "/geojson/stations", "10.0.0.1");
+    assert_eq!(status, 200);
+    let collection: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(collection["type"], "FeatureCollection");
+    let features = collection["features"].as_array().unwrap();
+    assert_eq!(features.len(), 3);
+    assert_eq!(features[0]["geometry"]["coordinates"][0].as_f64(), Some(13.405));
+    assert_eq!(features[0]["properties"]["name"], "Berlin");
+
+    let (status, body) = request(&conn, "GET", "/kml/stations/search?name=Munich", "10.0.0.1");
+    assert_eq!(status, 200);
+    assert!(body.contains(">11.575,48.137<"), "{}", body);
+    assert!(body.contains("<Data name=\"countrycode\">"), "{}", body);
+    assert_eq!(body.matches("<Placemark").count(), 1);
+
+    let (status, _) = request(&conn, "GET", "/kml/tags", "10.0.0.1");
+    assert_eq!(status, 406);
+}
+
+/// Runs the tests against the memory database and against a temporary sqlite file
+macro_rules! db_tests {
+    ($($name:ident),* $(,)?) => {
+        mod memory {
+            $(#[test]
+            fn $name() {
+                super::$name(crate::test_utils::sample_db());
+            })*
+        }
+
+        mod sqlite {
+            $(#[test]
+            fn $name() {
+                super::$name(crate::test_utils::sample_sqlite_db());
+            })*
+        }
+    };
+}
+
+db_tests!(
+    station_lists,
+    add_vote_and_click,
+    add_duplicates,
+    edit_and_delete,
+    moderation_queue,
+    webhooks,
+    check_on_request,
+    check_sources,
+    now_playing,
+    rate_limits,
+    conditional_requests,
+    compressed_responses,
+    streamed_station_lists,
+    cursor_pagination,
+    include_count,
+    selected_fields,
+    openapi_spec,
+    graphql_queries,
+    geo_search,
+    geo_formats
+);
@@ -1,18 +1,27 @@
 use crate::check::diff_calc::DiffCalc;
 use crate::check::favicon::get_best_icon;
+use crate::check::icy::IcyReader;
+use crate::check::liveness::probe_stream;
+use crate::check::liveness::Liveness;
+use crate::check::scheduler::schedule_next_checks;
+use crate::check::scheduler::CheckScheduler;
 use crate::config::get_cache_tags_replace;
 use crate::config::get_cache_language_replace;
 use crate::config::get_cache_language_to_code;
+use crate::db::models::CheckConsensus;
 use crate::db::models::DbStationItem;
 use crate::db::models::DbStreamingServerNew;
 use crate::db::models::StationCheckItemNew;
 use crate::db::models::StationCheckStepItemNew;
 use crate::db::DbConnection;
+use crate::webhooks::StationEvent;
+use crate::webhooks::Webhooks;
 use av_stream_info_rust;
 use av_stream_info_rust::StreamCheckResult;
 use av_stream_info_rust::UrlType;
 use rayon::prelude::*;
 use reqwest::blocking::Client;
+use reqwest::blocking::Response;
 use std;
 use std::collections::HashMap;
 use std::time::Duration;
@@ -26,6 +35,8 @@ pub struct StationOldNew {
     pub station: DiffCalc<DbStationItem>,
     pub check: StationCheckItemNew,
     pub steps: Vec<StationCheckStepItemNew>,
+    /// Title from the ICY metadata of the stream
+    pub now_playing: Option<String>,
 }
 
 /// returns list of
@@ -134,12 +145,63 @@ fn flatten_check_result(
     (list, found_working)
 }
 
+/// Reads the frames of a working stream, the check is broken if it has no frames of its codec
+/// or they repeat like silence or a loop
+fn check_audio(
+    stream: Result<&mut IcyReader<Response>, String>,
+    check: &mut StationCheckItemNew,
+    steps: &mut Vec<StationCheckStepItemNew>,
+    duration: Duration,
+) {
+    let probe = stream.map_err(|err| err.into()).and_then(|stream| probe_stream(stream, &check.codec, duration));
+    let error = match probe {
+        Ok(Some(probe)) => {
+            trace!("audio of {}: {:?}", check.station_uuid, probe);
+            check.audio_liveness = Some(probe.liveness.as_str().to_string());
+            match probe.liveness {
+                Liveness::NoFrames => Some(format!("no {} frames found", check.codec)),
+                liveness => {
+                    check.audio_bitrate = Some(probe.bitrate);
+                    check.audio_sampling = Some(probe.sampling);
+                    match liveness {
+                        Liveness::Varying => None,
+                        Liveness::RepeatedFrame => Some(String::from("every frame is the same")),
+                        _ => Some(String::from("a sequence of frames repeats")),
+                    }
+                }
+            }
+        }
+        Ok(None) => return,
+        Err(err) => Some(err.to_string()),
+    };
+    if error.is_some() {
+        check.check_ok = false;
+    }
+    let parent_stepuuid = steps
+        .iter()
+        .rev()
+        .find(|step| step.urltype.as_deref() == Some("STREAM") && step.url == check.url)
+        .map(|step| step.stepuuid.clone());
+    steps.push(StationCheckStepItemNew {
+        stepuuid: Uuid::new_v4().as_hyphenated().to_string(),
+        parent_stepuuid,
+        checkuuid: check.checkuuid.clone().unwrap_or_default(),
+        stationuuid: check.station_uuid.clone(),
+        url: check.url.clone(),
+        urltype: Some("AUDIO".to_string()),
+        error,
+    });
+}
+
 fn dbcheck_internal(
     diff: DiffCalc<DbStationItem>,
     source: &str,
     timeout: u64,
     max_depth: u8,
     retries: u8,
+    client: &Client,
+    audio_duration: Duration,
+    now_playing: bool,
 ) -> StationOldNew {
     let checkuuid = Uuid::new_v4().as_hyphenated().to_string();
     let now = Instant::now();
@@ -147,7 +209,7 @@ fn dbcheck_internal(
     let checks: StreamCheckResult =
         av_stream_info_rust::check_tree(&diff.new.url, timeout as u32, max_depth, retries, true);
     let timing_ms = now.elapsed().as_millis();
-    let (steps, check) = flatten_check_result(
+    let (mut steps, check) = flatten_check_result(
         diff.new.stationuuid.clone(),
         checkuuid.clone(),
         checks,
@@ -158,11 +220,31 @@ fn dbcheck_internal(
     trace!("Check finished: {} - {}", diff.new.stationuuid, diff.new.name);
 
     match check {
-        Some(check) => StationOldNew {
-            station: diff,
-            check,
-            steps,
-        },
+        Some(mut check) => {
+            let read_audio = !audio_duration.is_zero();
+            let now_playing = if check.check_ok && !check.hls && (read_audio || now_playing) {
+                // the check does not hand out its connection, the frames and the title share the next one
+                let mut stream = IcyReader::open(client, &check.url).map_err(|err| err.to_string());
+                if read_audio {
+                    check_audio(stream.as_mut().map_err(|err| err.clone()), &mut check, &mut steps, audio_duration);
+                }
+                match stream {
+                    Ok(mut stream) if now_playing => stream.read_title().unwrap_or_else(|err| {
+                        debug!("unable to read title of {}: {}", check.station_uuid, err);
+                        None
+                    }),
+                    _ => None,
+                }
+            } else {
+                None
+            };
+            StationOldNew {
+                station: diff,
+                check,
+                steps,
+                now_playing,
+            }
+        }
         None => {
             let check = StationCheckItemNew::broken(
                 diff.new.stationuuid.clone(),
@@ -174,11 +256,27 @@ fn dbcheck_internal(
                 station: diff,
                 check,
                 steps,
+                now_playing: None,
             }
         }
     }
 }
 
+/// Changes of the station that webhooks are told about, from the stored station before and after the check.
+/// With consensus a single failed check does not have to change lastcheckok.
+fn station_events(before: &DbStationItem, after: &DbStationItem) -> Vec<(StationEvent, DbStationItem, Option<String>)> {
+    let mut events = vec![];
+    if before.lastcheckok && !after.lastcheckok {
+        events.push((StationEvent::Broken, after.clone(), None));
+    } else if !before.lastcheckok && before.lastchecktime_iso8601.is_some() && after.lastcheckok {
+        events.push((StationEvent::Working, after.clone(), None));
+    }
+    if before.url != after.url {
+        events.push((StationEvent::UrlChanged, after.clone(), Some(before.url.clone())));
+    }
+    events
+}
+
 pub fn dbcheck<C>(
     mut conn: C,
     source: &str,
@@ -193,12 +291,61 @@ pub fn dbcheck<C>(
     favicon_size_min: usize,
     favicon_size_max: usize,
     favicon_size_optimum: usize,
+    audio_duration: Duration,
+    now_playing: bool,
+    scheduler: &dyn CheckScheduler,
+    consensus: &CheckConsensus,
 ) -> Result<usize, Box<dyn std::error::Error>>
 where
     C: DbConnection,
 {
     let stations = conn.get_stations_to_check(24, stations_count)?;
     let checked_count = stations.len();
+    check_stations(
+        conn,
+        stations,
+        source,
+        concurrency,
+        timeout,
+        max_depth,
+        retries,
+        add_streaming_servers,
+        recheck_existing_favicon,
+        enable_extract_favicon,
+        favicon_size_min,
+        favicon_size_max,
+        favicon_size_optimum,
+        audio_duration,
+        now_playing,
+        scheduler,
+        consensus,
+    )?;
+    Ok(checked_count)
+}
+
+/// Checks the given stations and stores the results, returns the inserted checks
+pub fn check_stations<C>(
+    mut conn: C,
+    stations: Vec<DbStationItem>,
+    source: &str,
+    concurrency: usize,
+    timeout: u64,
+    max_depth: u8,
+    retries: u8,
+    add_streaming_servers: bool,
+    recheck_existing_favicon: bool,
+    enable_extract_favicon: bool,
+    favicon_size_min: usize,
+    favicon_size_max: usize,
+    favicon_size_optimum: usize,
+    audio_duration: Duration,
+    now_playing: bool,
+    scheduler: &dyn CheckScheduler,
+    consensus: &CheckConsensus,
+) -> Result<Vec<StationCheckItemNew>, Box<dyn std::error::Error>>
+where
+    C: DbConnection,
+{
     let agent = format!("{}/{}", crate_name!(), crate_version!());
 
     let client = Client::builder()
@@ -255,7 +402,7 @@ where
         stations
             .into_par_iter()
             .map(|station| DiffCalc::new(station))
-            .map(|diff| dbcheck_internal(diff, source, timeout, max_depth, retries))
+            .map(|diff| dbcheck_internal(diff, source, timeout, max_depth, retries, &client, audio_duration, now_playing))
             .map(|mut diff| {
                 if diff.check.metainfo_overrides_database {
                     debug!("override station: uuid='{}'", diff.station.new.stationuuid);
@@ -427,10 +574,29 @@ where
             .collect()
     });
 
+    // stations are only read again if someone listens to their changes
+    let webhooks = Webhooks::load(&conn).unwrap_or_else(|err| {
+        error!("Unable to load webhooks: {}", err);
+        Webhooks::default()
+    });
+    let uuids: Vec<String> = results.iter().map(|result| result.station.old.stationuuid.clone()).collect();
+    let before: HashMap<String, DbStationItem> = if webhooks.is_empty() {
+        HashMap::new()
+    } else {
+        conn.get_stations_by_uuid(uuids.clone())?
+            .into_iter()
+            .map(|station| (station.stationuuid.clone(), station))
+            .collect()
+    };
+
     // do real insert
     let mut checks = vec![];
     let mut steps = vec![];
+    let mut titles = vec![];
     for result in results {
+        if let Some(title) = result.now_playing {
+            titles.push((result.check.station_uuid.clone(), title));
+        }
         checks.push(result.check);
         steps.extend(result.steps);
 
@@ -442,7 +608,27 @@ where
 
     let (_x, _y, inserted) = conn.insert_checks(checks)?;
     conn.insert_station_check_steps(&steps)?;
-    conn.update_station_with_check_data(&inserted, true)?;
+    conn.update_station_with_check_data(&inserted, true, consensus)?;
+    if !titles.is_empty() {
+        conn.set_now_playing(&titles)?;
+    }
+    schedule_next_checks(&conn, scheduler, inserted.iter().map(|check| check.station_uuid.clone()).collect())?;
+
+    if !before.is_empty() {
+        let result = conn.get_stations_by_uuid(uuids).and_then(|after| {
+            for station in after.iter() {
+                if let Some(old) = before.get(&station.stationuuid) {
+                    for (event, station, previous_url) in station_events(old, station) {
+                        webhooks.notify(&conn, event, &station, previous_url.as_deref())?;
+                    }
+                }
+            }
+            Ok(())
+        });
+        if let Err(err) = result {
+            error!("Unable to queue webhook deliveries: {}", err);
+        }
+    }
 
     if add_streaming_servers {
         let mut urls_full: Vec<_> = inserted
@@ -467,5 +653,5 @@ where
         )?;
     }
 
-    Ok(checked_count)
+    Ok(inserted)
 }
@@ -0,0 +1,189 @@
+use crate::db::DbConnection;
+use rayon::prelude::*;
+use reqwest::blocking::Client;
+use reqwest::blocking::Response;
+use std::error::Error;
+use std::io;
+use std::io::Read;
+
+/// Largest interval of metadata that is accepted, servers usually send 8000 or 16000 bytes of audio in between
+const MAX_METAINT: usize = 256 * 1024;
+/// Metadata blocks that are read before giving up, many servers only send a title in the first one
+const MAX_BLOCKS: usize = 3;
+
+/// Stream that was requested with ICY metadata. Reading it gives the audio without the metadata blocks,
+/// the titles in the blocks are kept on the way.
+pub struct IcyReader<R> {
+    inner: R,
+    /// Bytes of audio between two metadata blocks, None if the server does not send metadata
+    metaint: Option<usize>,
+    /// Bytes of audio until the next metadata block
+    left: usize,
+    blocks: usize,
+    title: Option<String>,
+}
+
+impl IcyReader<Response> {
+    /// Opens the stream and asks the server to send metadata
+    pub fn open(client: &Client, url: &str) -> Result<IcyReader<Response>, Box<dyn Error>> {
+        let response = client.get(url).header("Icy-MetaData", "1").send()?.error_for_status()?;
+        let metaint = response
+            .headers()
+            .get("icy-metaint")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<usize>().ok());
+        Ok(IcyReader::new(response, metaint))
+    }
+}
+
+impl<R: Read> IcyReader<R> {
+    fn new(inner: R, metaint: Option<usize>) -> Self {
+        let metaint = metaint.filter(|metaint| *metaint > 0 && *metaint <= MAX_METAINT);
+        IcyReader {
+            inner,
+            metaint,
+            left: metaint.unwrap_or(0),
+            blocks: 0,
+            title: None,
+        }
+    }
+
+    /// Reads the metadata block at the current position, false if the stream ended
+    fn read_block(&mut self) -> io::Result<bool> {
+        let mut length = [0u8; 1];
+        if self.inner.read(&mut length)? == 0 {
+            return Ok(false);
+        }
+        let mut block = vec![0u8; length[0] as usize * 16];
+        self.inner.read_exact(&mut block)?;
+        let end = block.iter().rposition(|byte| *byte != 0).map(|pos| pos + 1).unwrap_or(0);
+        if let Some(title) = parse_stream_title(&block[..end]) {
+            self.title = Some(title);
+        }
+        self.blocks += 1;
+        Ok(true)
+    }
+
+    /// Latest title of the stream. If none was read yet, reads on until one of the next metadata blocks has one.
+    /// None if the server does not send metadata or no title.
+    pub fn read_title(&mut self) -> io::Result<Option<String>> {
+        let last_block = self.blocks + MAX_BLOCKS;
+        let mut buffer = [0u8; 16 * 1024];
+        while self.title.is_none() && self.metaint.is_some() && self.blocks < last_block {
+            if self.read(&mut buffer)? == 0 {
+                break;
+            }
+        }
+        Ok(self.title.clone())
+    }
+}
+
+impl<R: Read> Read for IcyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let metaint = match self.metaint {
+            Some(metaint) => metaint,
+            None => return self.inner.read(buf),
+        };
+        if self.left == 0 {
+            if !self.read_block()? {
+                return Ok(0);
+            }
+            self.left = metaint;
+        }
+        let max = buf.len().min(self.left);
+        let read = self.inner.read(&mut buf[..max])?;
+        self.left -= read;
+        Ok(read)
+    }
+}
+
+/// Title from metadata like "StreamTitle='Artist - Song';StreamUrl='';", None if it is empty
+fn parse_stream_title(metadata: &[u8]) -> Option<String> {
+    // not all servers send utf8, latin1 is the usual alternative
+    let metadata = match std::str::from_utf8(metadata) {
+        Ok(metadata) => metadata.to_string(),
+        Err(_) => metadata.iter().map(|byte| *byte as char).collect(),
+    };
+    let start = metadata.find("StreamTitle='")? + "StreamTitle='".len();
+    let rest = &metadata[start..];
+    // titles may contain quotes themselves, only a quote followed by a semicolon ends them
+    let end = rest.find("';").unwrap_or_else(|| rest.trim_end_matches('\'').len());
+    let title = rest[..end].trim();
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.to_string())
+    }
+}
+
+/// Reads the current titles of the most clicked working stations, returns how many were found
+pub fn update_now_playing<C>(conn: &C, client: &Client, stations_count: u32, concurrency: usize) -> Result<usize, Box<dyn Error>>
+where
+    C: DbConnection,
+{
+    let stations = conn.get_stations_topclick(true, 0, stations_count)?;
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(concurrency).build()?;
+    let titles: Vec<(String, String)> = pool.install(|| {
+        stations
+            .into_par_iter()
+            .filter(|station| !station.hls)
+            .filter_map(|station| {
+                let url = if station.url_resolved.is_empty() { &station.url } else { &station.url_resolved };
+                match IcyReader::open(client, url).and_then(|mut stream| Ok(stream.read_title()?)) {
+                    Ok(title) => title.map(|title| (station.stationuuid, title)),
+                    Err(err) => {
+                        debug!("unable to read title of {}: {}", station.stationuuid, err);
+                        None
+                    }
+                }
+            })
+            .collect()
+    });
+    if !titles.is_empty() {
+        conn.set_now_playing(&titles)?;
+    }
+    Ok(titles.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_stream_title;
+    use super::IcyReader;
+    use std::io::Cursor;
+    use std::io::Read;
+
+    fn metadata_block(text: &str) -> Vec<u8> {
+        let mut block = text.as_bytes().to_vec();
+        block.resize(text.len().div_ceil(16) * 16, 0);
+        let mut data = vec![(block.len() / 16) as u8];
+        data.extend(block);
+        data
+    }
+
+    #[test]
+    fn metadata_and_titles() {
+        let mut stream = vec![1u8; 100];
+        stream.extend(metadata_block(""));
+        stream.extend(vec![2u8; 100]);
+        stream.extend(metadata_block("StreamTitle='Artist - It's a Song';StreamUrl='';"));
+        stream.extend(vec![3u8; 50]);
+
+        let mut reader = IcyReader::new(Cursor::new(stream.clone()), Some(100));
+        let mut audio = vec![];
+        reader.read_to_end(&mut audio).unwrap();
+        assert_eq!(audio, [vec![1u8; 100], vec![2u8; 100], vec![3u8; 50]].concat());
+        assert_eq!(reader.read_title().unwrap().as_deref(), Some("Artist - It's a Song"));
+
+        let mut reader = IcyReader::new(Cursor::new(stream.clone()), Some(100));
+        assert_eq!(reader.read_title().unwrap().as_deref(), Some("Artist - It's a Song"));
+        let mut reader = IcyReader::new(Cursor::new(stream.clone()), None);
+        assert_eq!(reader.read_title().unwrap(), None);
+        let mut audio = vec![];
+        reader.read_to_end(&mut audio).unwrap();
+        assert_eq!(audio, stream);
+
+        assert_eq!(parse_stream_title(b"StreamTitle='';"), None);
+        assert_eq!(parse_stream_title(b"StreamUrl='http://example.com';"), None);
+        assert_eq!(parse_stream_title(b"StreamTitle='Caf\xe9 del Mar'").as_deref(), Some("Café del Mar"));
+    }
+}
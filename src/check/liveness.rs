@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::io::Read;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Highest bitrate in bytes per second that is expected, reading stops after twice the duration at this rate
+/// in case the server sends faster than real time
+const MAX_BYTES_PER_SECOND: f64 = 320.0 * 1000.0 / 8.0;
+/// Frames in a row that are needed to call a stream decodable
+const MIN_FRAMES: usize = 20;
+/// Shortest sequence of frames that has to repeat to find a loop. A loop is found once this many
+/// of its frames were read the second time, so it can be almost as long as the read duration.
+const LOOP_MIN_FRAMES: usize = 20;
+
+/// How the frames of a few seconds of a stream differ from each other. The frames are only found by
+/// their headers and compared byte by byte, the audio is not decoded. Silence shows up as the same
+/// frame again and again, because that is what most encoders write for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Liveness {
+    Varying,
+    /// The same frame over and over, usually silence
+    RepeatedFrame,
+    /// A sequence of frames that repeats, like an "offline" jingle
+    RepeatedSequence,
+    /// No frame headers of the claimed codec, e.g. an html error page
+    NoFrames,
+}
+
+impl Liveness {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Liveness::Varying => "varying",
+            Liveness::RepeatedFrame => "repeated_frame",
+            Liveness::RepeatedSequence => "repeated_sequence",
+            Liveness::NoFrames => "no_frames",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioProbe {
+    pub frames: usize,
+    /// Bitrate in kbit/s measured from the size and duration of the frames
+    pub bitrate: u32,
+    /// Sample rate of the frames in Hz
+    pub sampling: u32,
+    pub liveness: Liveness,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FrameFormat {
+    /// MPEG audio layer III
+    Mp3,
+    /// AAC in ADTS frames
+    Adts,
+}
+
+impl FrameFormat {
+    fn from_codec(codec: &str) -> Option<FrameFormat> {
+        match codec.split(',').next().unwrap_or("").trim().to_uppercase().as_str() {
+            "MP3" => Some(FrameFormat::Mp3),
+            "AAC" | "AAC+" => Some(FrameFormat::Adts),
+            _ => None,
+        }
+    }
+}
+
+struct FrameHeader {
+    header_len: usize,
+    frame_len: usize,
+    samples: u32,
+    sampling: u32,
+}
+
+const MP3_BITRATES_V1: [u32; 15] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+const MP3_BITRATES_V2: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+const ADTS_SAMPLING: [u32; 13] = [96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350];
+
+fn parse_mp3_header(data: &[u8]) -> Option<FrameHeader> {
+    if data.len() < 4 || data[0] != 0xFF || data[1] & 0xE0 != 0xE0 {
+        return None;
+    }
+    let version = (data[1] >> 3) & 0x03;
+    let layer = (data[1] >> 1) & 0x03;
+    let bitrate_index = (data[2] >> 4) as usize;
+    let sampling_index = ((data[2] >> 2) & 0x03) as usize;
+    let padding = ((data[2] >> 1) & 0x01) as usize;
+    // only layer III, free format bitrates are not supported
+    if version == 1 || layer != 1 || bitrate_index == 0 || bitrate_index == 15 || sampling_index == 3 {
+        return None;
+    }
+    let (bitrate, sampling, samples) = match version {
+        3 => (MP3_BITRATES_V1[bitrate_index], [44100, 48000, 32000][sampling_index], 1152),
+        2 => (MP3_BITRATES_V2[bitrate_index], [22050, 24000, 16000][sampling_index], 576),
+        _ => (MP3_BITRATES_V2[bitrate_index], [11025, 12000, 8000][sampling_index], 576),
+    };
+    let frame_len = (samples / 8 * bitrate * 1000 / sampling) as usize + padding;
+    Some(FrameHeader {
+        header_len: 4,
+        frame_len,
+        samples,
+        sampling,
+    })
+}
+
+fn parse_adts_header(data: &[u8]) -> Option<FrameHeader> {
+    if data.len() < 7 || data[0] != 0xFF || data[1] & 0xF6 != 0xF0 {
+        return None;
+    }
+    let header_len = if data[1] & 0x01 == 1 { 7 } else { 9 };
+    let sampling = *ADTS_SAMPLING.get(((data[2] >> 2) & 0x0F) as usize)?;
+    let frame_len = (((data[3] & 0x03) as usize) << 11) | ((data[4] as usize) << 3) | ((data[5] >> 5) as usize);
+    let blocks = (data[6] & 0x03) as u32 + 1;
+    if frame_len <= header_len {
+        return None;
+    }
+    Some(FrameHeader {
+        header_len,
+        frame_len,
+        samples: 1024 * blocks,
+        sampling,
+    })
+}
+
+fn parse_header(data: &[u8], format: FrameFormat) -> Option<FrameHeader> {
+    match format {
+        FrameFormat::Mp3 => parse_mp3_header(data),
+        FrameFormat::Adts => parse_adts_header(data),
+    }
+}
+
+/// Finds the frames in the data. A frame only counts if the next one starts right after it,
+/// bytes between frames are skipped and the last incomplete frame is ignored.
+fn analyze(data: &[u8], format: FrameFormat) -> AudioProbe {
+    let mut hashes: Vec<u64> = vec![];
+    let mut frame_bytes = 0u64;
+    let mut samples = 0u64;
+    let mut sampling = 0u32;
+    let mut skipped = 0usize;
+    let mut pos = 0;
+    let mut synced = false;
+    while pos < data.len() {
+        let header = match parse_header(&data[pos..], format) {
+            Some(header) if pos + header.frame_len <= data.len() => header,
+            // the last frame was cut off
+            Some(_) if synced => break,
+            _ => {
+                synced = false;
+                skipped += 1;
+                pos += 1;
+                continue;
+            }
+        };
+        let end = pos + header.frame_len;
+        // a frame after a gap needs the next frame to confirm it is not random data
+        if !synced && end < data.len() && parse_header(&data[end..], format).is_none() {
+            skipped += 1;
+            pos += 1;
+            continue;
+        }
+        synced = true;
+        let mut hasher = DefaultHasher::new();
+        data[pos + header.header_len..end].hash(&mut hasher);
+        hashes.push(hasher.finish());
+        frame_bytes += header.frame_len as u64;
+        samples += header.samples as u64;
+        sampling = header.sampling;
+        pos = end;
+    }
+
+    if hashes.len() < MIN_FRAMES || skipped * 10 > data.len() {
+        return AudioProbe {
+            frames: hashes.len(),
+            bitrate: 0,
+            sampling: 0,
+            liveness: Liveness::NoFrames,
+        };
+    }
+    let seconds = samples as f64 / sampling as f64;
+    let bitrate = (frame_bytes as f64 * 8.0 / seconds / 1000.0).round() as u32;
+    AudioProbe {
+        frames: hashes.len(),
+        bitrate,
+        sampling,
+        liveness: liveness(&hashes),
+    }
+}
+
+/// Silence encodes to the same frame again and again, a loop repeats a longer sequence of frames
+fn liveness(hashes: &[u64]) -> Liveness {
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    for hash in hashes {
+        *counts.entry(*hash).or_insert(0) += 1;
+    }
+    if counts.values().max().map(|count| count * 2 >= hashes.len()).unwrap_or(false) {
+        return Liveness::RepeatedFrame;
+    }
+
+    let mut last_seen: HashMap<u64, usize> = HashMap::new();
+    let mut distance = 0;
+    let mut run = 0;
+    for (i, hash) in hashes.iter().enumerate() {
+        match last_seen.insert(*hash, i) {
+            Some(previous) if i - previous >= LOOP_MIN_FRAMES => {
+                if i - previous == distance {
+                    run += 1;
+                } else {
+                    distance = i - previous;
+                    run = 1;
+                }
+                if run >= LOOP_MIN_FRAMES {
+                    return Liveness::RepeatedSequence;
+                }
+            }
+            _ => {
+                run = 0;
+            }
+        }
+    }
+    Liveness::Varying
+}
+
+/// Reads the stream for the given time and looks for frames of the claimed codec in it.
+/// None if frames of the codec can not be found by their headers.
+pub fn probe_stream<R: Read>(stream: &mut R, codec: &str, duration: Duration) -> Result<Option<AudioProbe>, Box<dyn Error>> {
+    let format = match FrameFormat::from_codec(codec) {
+        Some(format) => format,
+        None => return Ok(None),
+    };
+    let max_bytes = (duration.as_secs_f64() * MAX_BYTES_PER_SECOND * 2.0) as usize;
+    let start = Instant::now();
+    let mut data = vec![];
+    let mut buffer = [0u8; 16 * 1024];
+    while start.elapsed() < duration && data.len() < max_bytes {
+        match stream.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => data.extend_from_slice(&buffer[..read]),
+            // analyze what was read until the timeout of the client
+            Err(err) if !data.is_empty() => {
+                debug!("stopped reading: {}", err);
+                break;
+            }
+            Err(err) => return Err(Box::new(err)),
+        }
+    }
+    Ok(Some(analyze(&data, format)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::analyze;
+    use super::FrameFormat;
+    use super::Liveness;
+
+    /// MPEG-1 layer III frame with 128kbit/s at 44.1kHz, 417 bytes long
+    fn mp3_frame(seed: u32) -> Vec<u8> {
+        let mut frame = vec![0xFF, 0xFB, 0x90, 0x64];
+        frame.extend((0..413).map(|i| ((seed.wrapping_mul(2654435761).wrapping_add(i * 31)) >> 3) as u8));
+        frame
+    }
+
+    fn stream(seeds: impl Iterator<Item = u32>) -> Vec<u8> {
+        seeds.flat_map(mp3_frame).collect()
+    }
+
+    #[test]
+    fn frames_and_liveness() {
+        let probe = analyze(&stream(0..100), FrameFormat::Mp3);
+        assert_eq!(probe.liveness, Liveness::Varying);
+        assert_eq!(probe.frames, 100);
+        assert_eq!(probe.sampling, 44100);
+        assert_eq!(probe.bitrate, 128);
+
+        let mut garbage = b"ICY 200 OK\r\n\r\n".to_vec();
+        garbage.extend(stream(0..100));
+        assert_eq!(analyze(&garbage, FrameFormat::Mp3).frames, 100);
+
+        let html = b"<html><body>Station offline</body></html>".repeat(1000);
+        assert_eq!(analyze(&html, FrameFormat::Mp3).liveness, Liveness::NoFrames);
+        assert_eq!(analyze(&stream(0..100), FrameFormat::Adts).liveness, Liveness::NoFrames);
+
+        assert_eq!(analyze(&stream((0..100).map(|i| if i < 40 { i } else { 0 })), FrameFormat::Mp3).liveness, Liveness::RepeatedFrame);
+        assert_eq!(analyze(&stream((0..100).map(|i| i % 30)), FrameFormat::Mp3).liveness, Liveness::RepeatedSequence);
+        assert_eq!(analyze(&stream((0..100).map(|i| i % 5)), FrameFormat::Mp3).liveness, Liveness::Varying, "too short for a loop");
+        assert_eq!(analyze(&stream((0..200).map(|i| i % 170)), FrameFormat::Mp3).liveness, Liveness::RepeatedSequence, "longer than half of it");
+        assert_eq!(analyze(&stream((0..200).map(|i| i % 190)), FrameFormat::Mp3).liveness, Liveness::Varying, "not repeated long enough");
+    }
+}
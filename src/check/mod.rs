@@ -1,5 +1,14 @@
 mod check;
 mod favicon;
+mod icy;
+mod liveness;
 mod diff_calc;
+mod scheduler;
 
+pub use check::check_stations;
 pub use check::dbcheck;
+pub use icy::update_now_playing;
+pub use scheduler::create_scheduler;
+
+#[cfg(test)]
+mod tests;
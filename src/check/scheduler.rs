@@ -0,0 +1,219 @@
+use crate::config::CheckSchedulerType;
+use crate::config::Config;
+use crate::db::models::DbStationItem;
+use crate::db::models::StationCheckItem;
+use crate::db::DbConnection;
+use chrono::DateTime;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::error::Error;
+use std::time::Duration;
+
+/// Checks in this time are the history the adaptive scheduler looks at
+const HISTORY_SECONDS: u32 = 7 * 24 * 60 * 60;
+/// Most checks of a station that are read as its history
+const HISTORY_LIMIT: usize = 1000;
+/// Checks in a row with the same result that make a station stable
+const STABLE_CHECKS: usize = 7;
+
+/// Decides when a station is checked again, after it was checked just now
+pub trait CheckScheduler: Send + Sync {
+    /// Seconds of check history interval() needs, 0 if it does not look at it
+    fn history_seconds(&self) -> u32;
+
+    /// Time until the next check. The station has the result of the current check,
+    /// the history its checks of the last history_seconds(), oldest first.
+    fn interval(&self, station: &DbStationItem, history: &[StationCheckItem], now: DateTime<Utc>) -> Duration;
+}
+
+/// Every station is checked again after the same time
+pub struct FixedScheduler {
+    pub interval: Duration,
+}
+
+impl CheckScheduler for FixedScheduler {
+    fn history_seconds(&self) -> u32 {
+        0
+    }
+
+    fn interval(&self, _station: &DbStationItem, _history: &[StationCheckItem], _now: DateTime<Utc>) -> Duration {
+        self.interval
+    }
+}
+
+/// Starts from the base interval and divides it by the count of state changes of flapping stations,
+/// halves it for stations that were changed within the base interval and doubles it for stations
+/// with many equal results in a row. Stations with clicks get a shorter interval on top,
+/// the result always stays between min and max.
+pub struct AdaptiveScheduler {
+    pub base: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl CheckScheduler for AdaptiveScheduler {
+    fn history_seconds(&self) -> u32 {
+        HISTORY_SECONDS
+    }
+
+    fn interval(&self, station: &DbStationItem, history: &[StationCheckItem], now: DateTime<Utc>) -> Duration {
+        let base = self.base.as_secs_f64();
+        let flips = history.windows(2).filter(|pair| pair[0].check_ok != pair[1].check_ok).count();
+        let changed_recently = station
+            .lastchangetime_iso8601
+            .map(|time| (now - time).num_seconds() as f64 <= base)
+            .unwrap_or(false);
+        let stable = history.len() >= STABLE_CHECKS
+            && history[history.len() - STABLE_CHECKS..].iter().all(|check| check.check_ok == station.lastcheckok);
+
+        let mut seconds = if flips > 0 {
+            base / (flips as f64 + 1.0)
+        } else if changed_recently {
+            base / 2.0
+        } else if stable {
+            base * 2.0
+        } else {
+            base
+        };
+        seconds /= 1.0 + (1.0 + station.clickcount as f64).log10() / 2.0;
+        Duration::from_secs_f64(seconds.clamp(self.min.as_secs_f64(), self.max.as_secs_f64().max(self.min.as_secs_f64())))
+    }
+}
+
+pub fn create_scheduler(config: &Config) -> Box<dyn CheckScheduler> {
+    match config.check_scheduler {
+        CheckSchedulerType::Fixed => Box::new(FixedScheduler {
+            interval: config.check_interval,
+        }),
+        CheckSchedulerType::Adaptive => Box::new(AdaptiveScheduler {
+            base: config.check_interval,
+            min: config.check_interval_min,
+            max: config.check_interval_max,
+        }),
+    }
+}
+
+/// Stores the next check time the scheduler calculates for each of the just checked stations
+pub fn schedule_next_checks<C: DbConnection>(
+    conn: &C,
+    scheduler: &dyn CheckScheduler,
+    stationuuids: Vec<String>,
+) -> Result<(), Box<dyn Error>> {
+    let now = Utc::now();
+    let history_seconds = scheduler.history_seconds();
+    let mut histories: HashMap<String, Vec<StationCheckItem>> = HashMap::new();
+    if history_seconds > 0 {
+        let limit = (HISTORY_LIMIT * stationuuids.len()) as u32;
+        for check in conn.get_check_history(&stationuuids, history_seconds, limit)? {
+            histories.entry(check.station_uuid.to_lowercase()).or_default().push(check);
+        }
+    }
+    let mut next_checks = vec![];
+    for station in conn.get_stations_by_uuid(stationuuids)? {
+        let history = histories.remove(&station.stationuuid.to_lowercase()).unwrap_or_default();
+        let history = &history[history.len().saturating_sub(HISTORY_LIMIT)..];
+        let interval = scheduler.interval(&station, history, now);
+        trace!("next check of {} in {}s", station.stationuuid, interval.as_secs());
+        next_checks.push((station.stationuuid, interval.as_secs()));
+    }
+    conn.set_next_check_times(&next_checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::schedule_next_checks;
+    use super::AdaptiveScheduler;
+    use super::CheckScheduler;
+    use crate::db::models::DbStationItem;
+    use crate::db::models::StationCheckItem;
+    use crate::db::models::StationCheckItemNew;
+    use crate::db::DbConnection;
+    use crate::test_utils::sample_db;
+    use chrono::DateTime;
+    use chrono::Duration as ChronoDuration;
+    use chrono::Utc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    const HOUR: u64 = 60 * 60;
+
+    fn history(results: &[bool]) -> Vec<StationCheckItem> {
+        let conn = sample_db();
+        let checks: Vec<_> = results
+            .iter()
+            .enumerate()
+            .map(|(i, ok)| {
+                let mut check = StationCheckItemNew::broken("s-rock".to_string(), format!("k{}", i), "test".to_string(), 1);
+                check.check_ok = *ok;
+                check
+            })
+            .collect();
+        conn.insert_checks(checks).unwrap();
+        conn.get_checks(Some("s-rock".to_string()), None, 0, true, 100).unwrap()
+    }
+
+    #[test]
+    fn adaptive_intervals() {
+        let scheduler = AdaptiveScheduler {
+            base: Duration::from_secs(24 * HOUR),
+            min: Duration::from_secs(HOUR),
+            max: Duration::from_secs(72 * HOUR),
+        };
+        let now = Utc::now();
+        let mut station = sample_db().get_station_by_uuid("s-rock").unwrap().pop().unwrap();
+        station.lastchangetime_iso8601 = Some(now - ChronoDuration::days(60));
+        station.lastcheckok = true;
+        station.clickcount = 0;
+
+        assert_eq!(scheduler.interval(&station, &history(&[true]), now).as_secs(), 24 * HOUR);
+        assert_eq!(scheduler.interval(&station, &history(&[true; 7]), now).as_secs(), 48 * HOUR);
+        assert_eq!(scheduler.interval(&station, &history(&[true, false, true]), now).as_secs(), 8 * HOUR);
+        assert_eq!(scheduler.interval(&station, &history(&[true, false, true, false, true, false]), now).as_secs(), 4 * HOUR);
+        assert_eq!(scheduler.interval(&station, &history(&[false, true].repeat(20)), now).as_secs(), HOUR);
+
+        station.lastchangetime_iso8601 = Some(now - ChronoDuration::hours(2));
+        assert_eq!(scheduler.interval(&station, &history(&[true; 7]), now).as_secs(), 12 * HOUR);
+
+        // 99 clicks halve the interval
+        station.lastchangetime_iso8601 = None;
+        station.clickcount = 99;
+        assert_eq!(scheduler.interval(&station, &history(&[true]), now).as_secs(), 12 * HOUR);
+    }
+
+    /// Remembers the history it got for each station
+    struct RecordingScheduler {
+        histories: Mutex<Vec<(String, Vec<String>)>>,
+    }
+
+    impl CheckScheduler for RecordingScheduler {
+        fn history_seconds(&self) -> u32 {
+            HOUR as u32
+        }
+
+        fn interval(&self, station: &DbStationItem, history: &[StationCheckItem], _now: DateTime<Utc>) -> Duration {
+            let checks = history.iter().map(|check| check.check_uuid.clone()).collect();
+            self.histories.lock().unwrap().push((station.stationuuid.clone(), checks));
+            Duration::from_secs(HOUR)
+        }
+    }
+
+    #[test]
+    fn histories_oldest_first() {
+        let conn = sample_db();
+        let check = |stationuuid: &str, checkuuid: &str| StationCheckItemNew::broken(stationuuid.to_string(), checkuuid.to_string(), "test".to_string(), 1);
+        conn.insert_checks(vec![check("s-rock", "r1"), check("s-jazz", "j1"), check("s-rock", "r2"), check("s-rock", "r3")]).unwrap();
+        conn.advance_clock(2 * HOUR as i64);
+        conn.insert_checks(vec![check("s-jazz", "j2"), check("s-rock", "r4"), check("s-jazz", "j3")]).unwrap();
+
+        let scheduler = RecordingScheduler { histories: Mutex::new(vec![]) };
+        schedule_next_checks(&conn, &scheduler, vec!["s-rock".to_string(), "s-jazz".to_string(), "s-news".to_string()]).unwrap();
+        let mut histories = scheduler.histories.into_inner().unwrap();
+        histories.sort();
+        let expected = [("s-jazz", vec!["j2", "j3"]), ("s-news", vec![]), ("s-rock", vec!["r4"])];
+        assert_eq!(histories.len(), expected.len());
+        for ((stationuuid, checks), (expected_uuid, expected_checks)) in histories.iter().zip(expected.iter()) {
+            assert_eq!(stationuuid, expected_uuid);
+            assert_eq!(checks, expected_checks);
+        }
+    }
+}
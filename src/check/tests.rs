@@ -0,0 +1,36 @@
+use super::create_scheduler;
+use super::dbcheck;
+use crate::db::models::CheckConsensus;
+use crate::db::DbConnection;
+use crate::db::MemoryConnection;
+use crate::test_utils::free_port;
+use crate::test_utils::station_change;
+use crate::test_utils::test_config;
+use std::time::Duration;
+
+#[test]
+fn check_unreachable_station() {
+    let conn = MemoryConnection::new();
+    let scheduler = create_scheduler(&test_config());
+    let url = format!("http://127.0.0.1:{}/stream", free_port());
+    conn.insert_station_by_change(&[station_change("s-down", "c-down", "Down Radio", &url, "pop")], "test")
+        .unwrap();
+
+    let checked = dbcheck(conn.clone(), "test", 1, 10, 2, 2, 0, false, false, false, 32, 256, 128, Duration::from_secs(0), false, scheduler.as_ref(), &CheckConsensus::default()).unwrap();
+    assert_eq!(checked, 1);
+
+    let station = conn.get_station_by_uuid("s-down").unwrap().pop().unwrap();
+    assert!(!station.lastcheckok);
+    assert!(station.lastlocalchecktime_iso8601.is_some());
+    assert!(station.lastcheckoktime_iso8601.is_none());
+    let checks = conn.get_checks(Some("s-down".to_string()), None, 0, true, 100).unwrap();
+    assert_eq!(checks.len(), 1);
+    assert!(!checks[0].check_ok);
+    assert_eq!(checks[0].source, "test");
+    assert!(!conn.select_station_check_steps_by_stations(&["s-down".to_string()], 100).unwrap().is_empty());
+
+    // checked stations are not in the queue again for the next 24 hours
+    assert_eq!(dbcheck(conn.clone(), "test", 1, 10, 2, 2, 0, false, false, false, 32, 256, 128, Duration::from_secs(0), false, scheduler.as_ref(), &CheckConsensus::default()).unwrap(), 0);
+    conn.advance_clock(24 * 60 * 60 + 1);
+    assert_eq!(conn.get_station_count_todo(24).unwrap(), 1);
+}
@@ -1,3 +1,5 @@
+use crate::webhooks::StationEvent;
+use crate::webhooks::Webhooks;
 use crate::DbConnection;
 use std::error::Error;
 
@@ -21,6 +23,7 @@ pub fn do_cleanup<C>(
         conn_new_style.get_deletable_were_working(broken_stations_timeout)?;
     if delete {
         conn_new_style.delete_never_working(broken_stations_never_working_timeout)?;
+        notify_deleted(&conn_new_style, broken_stations_timeout);
         conn_new_style.delete_were_working(broken_stations_timeout)?;
         conn_new_style.delete_old_checks(checks_timeout)?;
         conn_new_style.delete_old_clicks(clicks_timeout)?;
@@ -35,3 +38,25 @@ pub fn do_cleanup<C>(
     info!("STATS: {} Checks/Hour, {} Checks/Day, {} Working stations, {} Broken stations, {} to do, deletable {} + {}", checks_hour, checks_day, stations_working, stations_broken, stations_todo, stations_deletable_never_worked, stations_deletable_were_working);
     Ok(())
 }
+
+/// Tells webhooks about the stations delete_were_working() is about to remove
+fn notify_deleted<C: DbConnection>(conn: &C, broken_stations_timeout: u64) {
+    let result = conn
+        .get_stations_deletable_were_working(broken_stations_timeout)
+        .and_then(|stations| {
+            if stations.is_empty() {
+                return Ok(());
+            }
+            let webhooks = Webhooks::load(conn)?;
+            for station in stations.iter() {
+                webhooks.notify(conn, StationEvent::Deleted, station, None)?;
+            }
+            Ok(())
+        });
+    if let Err(err) = result {
+        error!("Unable to queue webhook deliveries: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,35 @@
+use super::do_cleanup;
+use crate::db::models::CheckConsensus;
+use crate::db::models::StationCheckItemNew;
+use crate::db::DbConnection;
+use crate::test_utils::sample_db;
+
+const DAY: u64 = 24 * 60 * 60;
+
+#[test]
+fn cleanup_deletes_never_working_stations() {
+    let conn = sample_db();
+    let mut check = StationCheckItemNew::broken("s-rock".to_string(), "k1".to_string(), "test".to_string(), 1);
+    check.check_ok = true;
+    let (_, _, inserted) = conn.insert_checks(vec![check]).unwrap();
+    conn.update_station_with_check_data(&inserted, true, &CheckConsensus::default()).unwrap();
+    let station = conn.get_station_by_uuid("s-news").unwrap().pop().unwrap();
+    assert!(conn.increase_clicks("10.0.0.1", &station, 60).unwrap());
+
+    do_cleanup(true, conn.clone(), DAY, 3 * DAY, 30 * DAY, 30 * DAY, 3 * DAY).unwrap();
+    assert_eq!(conn.get_stations_by_all("name", false, false, None, None, 0, 100).unwrap().len(), 3);
+    assert_eq!(conn.get_station_by_uuid("s-news").unwrap()[0].clickcount, 1);
+
+    conn.advance_clock((3 * DAY + 1) as i64);
+    do_cleanup(false, conn.clone(), DAY, 3 * DAY, 30 * DAY, 30 * DAY, 3 * DAY).unwrap();
+    assert_eq!(conn.get_deletable_never_working(3 * DAY).unwrap(), 2);
+    assert_eq!(conn.get_stations_by_all("name", false, false, None, None, 0, 100).unwrap().len(), 3);
+
+    do_cleanup(true, conn.clone(), DAY, 3 * DAY, 30 * DAY, 30 * DAY, 3 * DAY).unwrap();
+    let stations = conn.get_stations_by_all("name", false, false, None, None, 0, 100).unwrap();
+    assert_eq!(stations.len(), 1);
+    assert_eq!(stations[0].stationuuid, "s-rock");
+    // history and clicks of deleted stations are gone as well
+    assert_eq!(conn.get_changes(None, None, 100).unwrap().len(), 1);
+    assert_eq!(conn.get_clicks(None, None, 0, 100).unwrap().len(), 0);
+}
@@ -1,6 +1,5 @@
 use crate::db::models::StationHistoryItem;
 use crate::db::DbConnection;
-use crate::db::MysqlConnection;
 use std::error::Error;
 
 fn change_duplicated(item1: &StationHistoryItem, item2: &StationHistoryItem) -> bool {
@@ -21,8 +20,8 @@ fn change_duplicated(item1: &StationHistoryItem, item2: &StationHistoryItem) ->
     false
 }
 
-pub fn delete_duplicate_changes(
-    conn: &mut MysqlConnection,
+pub fn delete_duplicate_changes<C: DbConnection>(
+    conn: &mut C,
     min_change_count: u32,
 ) -> Result<(), Box<dyn Error>> {
     debug!("delete_duplicate_changes({})", min_change_count);
@@ -74,7 +73,7 @@ pub fn delete_duplicate_changes(
     Ok(())
 }
 
-pub fn resethistory(conn: &mut MysqlConnection) -> Result<(), Box<dyn Error>> {
+pub fn resethistory<C: DbConnection>(conn: &mut C) -> Result<(), Box<dyn Error>> {
     debug!("resethistory()");
     conn.resethistory()?;
     println!("");
@@ -19,6 +19,21 @@ impl From<CacheType> for String {
     }
 }
 
+#[derive(Debug, Clone)]
+pub enum CheckSchedulerType {
+    Fixed,
+    Adaptive,
+}
+
+impl From<CheckSchedulerType> for String {
+    fn from(c: CheckSchedulerType) -> Self {
+        match c {
+            CheckSchedulerType::Fixed => String::from("fixed"),
+            CheckSchedulerType::Adaptive => String::from("adaptive"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub allow_database_downgrade: bool,
@@ -73,9 +88,47 @@ pub struct Config {
     pub favicon_size_optimum: usize,
     pub refresh_config_interval: Duration,
     pub cleanup_interval: Duration,
+    pub admin_token: Option<String>,
+    pub api_keys: Vec<ApiKey>,
+    pub moderation: bool,
+    pub rate_limit_vote: u32,
+    pub rate_limit_click: u32,
+    pub rate_limit_add: u32,
+    pub rate_limit_search: u32,
+    pub rate_limit_check: u32,
+    pub rate_limit_allowlist: Vec<String>,
+    pub trusted_proxies: Vec<String>,
+    pub graphql_max_depth: u32,
+    pub graphql_max_complexity: u64,
+    pub events_max_clients: usize,
+    pub webhook_retries: u32,
+    pub webhook_private_targets: bool,
+    pub check_scheduler: CheckSchedulerType,
+    pub check_interval: Duration,
+    pub check_interval_min: Duration,
+    pub check_interval_max: Duration,
+    pub check_consensus_ok: u32,
+    pub check_consensus_sources: u32,
+    pub check_audio_duration: Duration,
+    pub check_now_playing: bool,
+    pub now_playing_interval: Duration,
+    pub now_playing_stations: u32,
     pub sub_command: ConfigSubCommand,
 }
 
+/// Named key that allows editing stations through the api
+#[derive(Clone)]
+pub struct ApiKey {
+    pub name: String,
+    pub key: String,
+}
+
+impl std::fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKey").field("name", &self.name).field("key", &"***").finish()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ConfigSubCommand {
     None,
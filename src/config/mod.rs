@@ -4,7 +4,9 @@ mod data_mapping_item;
 
 use clap::ArgAction;
 use clap::{Arg, Command};
+pub use config::ApiKey;
 pub use config::CacheType;
+pub use config::CheckSchedulerType;
 pub use config::Config;
 pub use config::ConfigSubCommand;
 pub use config_error::ConfigError;
@@ -245,6 +247,33 @@ fn get_hosts_from_config(config: &toml::Value) -> Result<Vec<String>, Box<dyn Er
     Ok(list)
 }
 
+fn get_api_keys_from_config(config: &toml::Value) -> Result<Vec<ApiKey>, Box<dyn Error>> {
+    let mut list = vec![];
+    let setting = config.get("apikeys");
+    if let Some(setting) = setting {
+        let setting_decoded = setting.as_table().ok_or(Box::new(ConfigError::TypeError(
+            "apikeys".into(),
+            setting.to_string(),
+        )))?;
+        for (name, value) in setting_decoded {
+            let key = value.get("key");
+            if let Some(key) = key {
+                let key_str = key.as_str().ok_or(Box::new(ConfigError::TypeError(
+                    "key".into(),
+                    name.to_string(),
+                )))?;
+                if !key_str.is_empty() {
+                    list.push(ApiKey {
+                        name: name.to_string(),
+                        key: key_str.to_string(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(list)
+}
+
 fn load_config() -> Result<Config, Box<dyn Error>> {
     let hostname_str: String = hostname::get()
         .map(|os_string| os_string.to_string_lossy().into_owned())
@@ -481,10 +510,122 @@ fn load_config() -> Result<Config, Box<dyn Error>> {
             Arg::new("cache-ttl")
                 .long("cache-ttl")
                 .value_name("DURATION")
-                .help("time to life for cache items")
+                .help("time to life for cache items, also used as max-age for clients")
                 .env("CACHETTL")
                 .num_args(1),
         )
+        .arg(
+            Arg::new("admin-token")
+                .long("admin-token")
+                .value_name("TOKEN")
+                .help("token that allows editing and deleting stations through the api")
+                .env("ADMIN_TOKEN")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("moderation")
+                .long("moderation")
+                .value_name("MODERATION")
+                .help("new stations wait in a review queue until an admin approves them")
+                .env("MODERATION")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("rate-limit-vote")
+                .long("rate-limit-vote")
+                .value_name("REQUESTS_PER_MINUTE")
+                .help("maximum votes per minute from one ip, 0 is unlimited")
+                .env("RATE_LIMIT_VOTE")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("rate-limit-click")
+                .long("rate-limit-click")
+                .value_name("REQUESTS_PER_MINUTE")
+                .help("maximum clicks per minute from one ip, 0 is unlimited")
+                .env("RATE_LIMIT_CLICK")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("rate-limit-add")
+                .long("rate-limit-add")
+                .value_name("REQUESTS_PER_MINUTE")
+                .help("maximum added stations per minute from one ip, 0 is unlimited")
+                .env("RATE_LIMIT_ADD")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("rate-limit-search")
+                .long("rate-limit-search")
+                .value_name("REQUESTS_PER_MINUTE")
+                .help("maximum station searches per minute from one ip, 0 is unlimited")
+                .env("RATE_LIMIT_SEARCH")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("rate-limit-check")
+                .long("rate-limit-check")
+                .value_name("REQUESTS_PER_MINUTE")
+                .help("maximum checks of stations on request per minute from one ip, 0 is unlimited")
+                .env("RATE_LIMIT_CHECK")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("rate-limit-allowlist")
+                .long("rate-limit-allowlist")
+                .value_name("IPS")
+                .help("comma separated ips or networks (e.g. 10.0.0.0/8) without rate limits")
+                .env("RATE_LIMIT_ALLOWLIST")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("trusted-proxies")
+                .long("trusted-proxies")
+                .value_name("IPS")
+                .help("comma separated ips or networks of reverse proxies, only their X-Forwarded-For header is used for rate limits")
+                .env("TRUSTED_PROXIES")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("graphql-max-depth")
+                .long("graphql-max-depth")
+                .value_name("FIELDS")
+                .help("maximum nesting of fields in queries to /graphql")
+                .env("GRAPHQL_MAX_DEPTH")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("graphql-max-complexity")
+                .long("graphql-max-complexity")
+                .value_name("FIELDS")
+                .help("maximum number of fields a query to /graphql may return, lists count with their limit")
+                .env("GRAPHQL_MAX_COMPLEXITY")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("events-max-clients")
+                .long("events-max-clients")
+                .value_name("CLIENTS")
+                .help("maximum number of clients connected to /events at the same time, each of them uses one of the threads")
+                .env("EVENTS_MAX_CLIENTS")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("webhook-retries")
+                .long("webhook-retries")
+                .value_name("RETRIES")
+                .help("retries of a failed webhook delivery before it is dropped")
+                .env("WEBHOOK_RETRIES")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("webhook-private-targets")
+                .long("webhook-private-targets")
+                .value_name("WEBHOOK_PRIVATE_TARGETS")
+                .help("allow webhooks to loopback, private and link-local addresses")
+                .env("WEBHOOK_PRIVATE_TARGETS")
+                .num_args(1),
+        )
         .arg(
             Arg::new("retries")
                 .short('r')
@@ -558,6 +699,86 @@ fn load_config() -> Result<Config, Box<dyn Error>> {
                 .env("PAUSE")
                 .num_args(1),
         )
+        .arg(
+            Arg::new("check-scheduler")
+                .long("check-scheduler")
+                .value_name("SCHEDULER")
+                .help("when stations are checked again, one of fixed (after check-interval),adaptive (more often for flapping, changed and popular stations)")
+                .env("CHECK_SCHEDULER")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("check-interval")
+                .long("check-interval")
+                .value_name("CHECK_INTERVAL")
+                .help("time until a station is checked again")
+                .env("CHECK_INTERVAL")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("check-interval-min")
+                .long("check-interval-min")
+                .value_name("CHECK_INTERVAL_MIN")
+                .help("shortest time until a station is checked again by the adaptive scheduler")
+                .env("CHECK_INTERVAL_MIN")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("check-interval-max")
+                .long("check-interval-max")
+                .value_name("CHECK_INTERVAL_MAX")
+                .help("longest time until a station is checked again by the adaptive scheduler")
+                .env("CHECK_INTERVAL_MAX")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("check-consensus-ok")
+                .long("check-consensus-ok")
+                .value_name("CHECK_CONSENSUS_OK")
+                .help("successful checks of different sources that make a station working, 0 for the majority of them")
+                .env("CHECK_CONSENSUS_OK")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("check-consensus-sources")
+                .long("check-consensus-sources")
+                .value_name("CHECK_CONSENSUS_SOURCES")
+                .help("sources with the most recent checks of a station that are part of the consensus, 0 for all of them checking within check-interval")
+                .env("CHECK_CONSENSUS_SOURCES")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("check-audio-duration")
+                .long("check-audio-duration")
+                .value_name("CHECK_AUDIO_DURATION")
+                .help("time to read from every working stream to find its frames and detect repeated frames or loops, 0 to only check the headers")
+                .env("CHECK_AUDIO_DURATION")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("check-now-playing")
+                .long("check-now-playing")
+                .value_name("CHECK_NOW_PLAYING")
+                .help("read the current title from the ICY metadata of every working stream")
+                .env("CHECK_NOW_PLAYING")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("now-playing-interval")
+                .long("now-playing-interval")
+                .value_name("NOW_PLAYING_INTERVAL")
+                .help("interval to read the current titles of the most clicked stations, 0 to only read them in checks, needs check-now-playing")
+                .env("NOW_PLAYING_INTERVAL")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("now-playing-stations")
+                .long("now-playing-stations")
+                .value_name("NOW_PLAYING_STATIONS")
+                .help("count of the most clicked stations whose titles are read every now-playing-interval")
+                .env("NOW_PLAYING_STATIONS")
+                .num_args(1),
+        )
         .arg(
             Arg::new("stations")
                 .short('n')
@@ -840,6 +1061,53 @@ fn load_config() -> Result<Config, Box<dyn Error>> {
         )),
     }?;
 
+    let admin_token: Option<String> =
+        Some(get_option_string(&matches, &config, "admin-token", String::from(""))?)
+            .filter(|token| !token.is_empty());
+    let api_keys = get_api_keys_from_config(&config)?;
+    let moderation: bool = get_option_bool(&matches, &config, "moderation", false)?;
+    let rate_limit_vote: u32 = get_option_number(&matches, &config, "rate-limit-vote", 0)? as u32;
+    let rate_limit_click: u32 = get_option_number(&matches, &config, "rate-limit-click", 0)? as u32;
+    let rate_limit_add: u32 = get_option_number(&matches, &config, "rate-limit-add", 0)? as u32;
+    let rate_limit_search: u32 = get_option_number(&matches, &config, "rate-limit-search", 0)? as u32;
+    let rate_limit_check: u32 = get_option_number(&matches, &config, "rate-limit-check", 2)? as u32;
+    let rate_limit_allowlist: Vec<String> = get_option_string(&matches, &config, "rate-limit-allowlist", String::from(""))?
+        .split(",")
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect();
+    let trusted_proxies: Vec<String> = get_option_string(&matches, &config, "trusted-proxies", String::from(""))?
+        .split(",")
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect();
+
+    let graphql_max_depth: u32 = get_option_number(&matches, &config, "graphql-max-depth", 10)? as u32;
+    let graphql_max_complexity: u64 = get_option_number(&matches, &config, "graphql-max-complexity", 10000)? as u64;
+    let events_max_clients: usize = get_option_number(&matches, &config, "events-max-clients", 2)? as usize;
+    let webhook_retries: u32 = get_option_number(&matches, &config, "webhook-retries", 5)? as u32;
+    let webhook_private_targets: bool = get_option_bool(&matches, &config, "webhook-private-targets", false)?;
+
+    let check_scheduler_str: String =
+        get_option_string(&matches, &config, "check-scheduler", String::from("fixed"))?;
+    let check_scheduler: CheckSchedulerType = match check_scheduler_str.as_str() {
+        "fixed" => Ok(CheckSchedulerType::Fixed),
+        "adaptive" => Ok(CheckSchedulerType::Adaptive),
+        _ => Err(ConfigError::TypeError(
+            "check-scheduler".into(),
+            "possible values are fixed,adaptive".into(),
+        )),
+    }?;
+    let check_interval = get_option_duration(&matches, &config, "check-interval", String::from("24hours"))?;
+    let check_interval_min = get_option_duration(&matches, &config, "check-interval-min", String::from("1hour"))?;
+    let check_interval_max = get_option_duration(&matches, &config, "check-interval-max", String::from("7days"))?;
+    let check_consensus_ok: u32 = get_option_number(&matches, &config, "check-consensus-ok", 0)? as u32;
+    let check_consensus_sources: u32 = get_option_number(&matches, &config, "check-consensus-sources", 0)? as u32;
+    let check_audio_duration = get_option_duration(&matches, &config, "check-audio-duration", String::from("0secs"))?;
+    let check_now_playing: bool = get_option_bool(&matches, &config, "check-now-playing", false)?;
+    let now_playing_interval = get_option_duration(&matches, &config, "now-playing-interval", String::from("0secs"))?;
+    let now_playing_stations: u32 = get_option_number(&matches, &config, "now-playing-stations", 100)? as u32;
+
     let max_duplicates = get_option_number(&matches, &config, "max-duplicates", 0)? as usize;
     let mut servers_pull = vec![];
     let mirrors = matches.get_many("mirror");
@@ -914,6 +1182,31 @@ fn load_config() -> Result<Config, Box<dyn Error>> {
         favicon_size_optimum,
         refresh_config_interval,
         cleanup_interval,
+        admin_token,
+        api_keys,
+        moderation,
+        rate_limit_vote,
+        rate_limit_click,
+        rate_limit_add,
+        rate_limit_search,
+        rate_limit_check,
+        rate_limit_allowlist,
+        trusted_proxies,
+        graphql_max_depth,
+        graphql_max_complexity,
+        events_max_clients,
+        webhook_retries,
+        webhook_private_targets,
+        check_scheduler,
+        check_interval,
+        check_interval_min,
+        check_interval_max,
+        check_consensus_ok,
+        check_consensus_sources,
+        check_audio_duration,
+        check_now_playing,
+        now_playing_interval,
+        now_playing_stations,
         no_migrations,
         sub_command,
     })
@@ -1,3 +1,4 @@
+use crate::db::models::CheckConsensus;
 use crate::db::models::DBCountry;
 use crate::db::models::DbStreamingServerNew;
 use crate::db::models::DbStreamingServer;
@@ -8,15 +9,31 @@ use crate::db::models::StationClickItemNew;
 use crate::db::models::State;
 use crate::db::models::ExtraInfo;
 use crate::db::models::DbStationItem;
+use crate::db::models::StationAddItem;
+use crate::db::models::StationDuplicate;
+use crate::db::models::DuplicateReason;
 use crate::db::models::StationCheckItem;
 use crate::db::models::StationCheckItemNew;
 use crate::db::models::StationChangeItemNew;
 use crate::db::models::StationHistoryItem;
 use crate::db::models::StationClickItem;
+use crate::db::models::NowPlayingItem;
+use crate::db::models::WebhookDeliveryItem;
+use crate::db::models::WebhookItem;
+use crate::db::db_search::similar_names;
+use crate::db::db_utils::fix_url;
+use crate::db::db_geo::GeoBoundingBox;
+use crate::db::db_cursor::StationCursor;
+use crate::db::db_fields::StationFields;
 use std::error::Error;
 use std::collections::HashMap;
 
+/// Best matches of the name search that find_duplicate_stations() compares with the new name
+const DUPLICATE_NAME_CANDIDATES: u32 = 100;
+
 pub trait DbConnection {
+    fn migrations_needed(&self) -> Result<bool, Box<dyn Error>>;
+    fn do_migrations(&self, ignore_migration_errors: bool, allow_database_downgrade: bool) -> Result<(), Box<dyn Error>>;
     fn get_station_count_broken(&self) -> Result<u64, Box<dyn Error>>;
     fn get_station_count_working(&self) -> Result<u64, Box<dyn Error>>;
     fn get_station_count_todo(&self, hours: u32) -> Result<u64, Box<dyn Error>>;
@@ -27,30 +44,136 @@ pub trait DbConnection {
     fn get_language_count(&self) -> Result<u64, Box<dyn Error>>;
     fn get_click_count_last_hour(&self) -> Result<u64, Box<dyn Error>>;
     fn get_click_count_last_day(&self) -> Result<u64, Box<dyn Error>>;
+    /// Stations whose next check time has passed, or without one that were not checked locally in the last hours.
+    /// The longest waiting ones come first, among equally waiting ones those with more clicks.
     fn get_stations_to_check(&mut self, hours: u32, itemcount: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
+    /// Sets the next check time of the stations to now plus the seconds
+    fn set_next_check_times(&self, list: &[(String, u64)]) -> Result<(), Box<dyn Error>>;
+    /// Count of stations get_stations_to_check() would return without a limit,
+    /// and seconds the longest waiting of them that has a check time is overdue
+    fn get_check_queue(&self, hours: u32) -> Result<(u64, u64), Box<dyn Error>>;
     fn get_station_by_uuid(&self, id_str: &str) -> Result<Vec<DbStationItem>,Box<dyn Error>>;
     fn get_stations_by_uuid(&self, uuids: Vec<String>) -> Result<Vec<DbStationItem>,Box<dyn Error>>;
-    fn get_stations_by_column_multiple(&self,column_name: &str,search: Option<String>,exact: bool,order: &str,reverse: bool,hidebroken: bool,offset: u32,limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
-    fn get_stations_by_all(&self,order: &str,reverse: bool,hidebroken: bool,offset: u32,limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
+    fn get_stations_by_column_multiple(&self,column_name: &str,search: Option<String>,exact: bool,order: &str,reverse: bool,hidebroken: bool,fields: Option<&StationFields>,cursor: Option<&StationCursor>,offset: u32,limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
+    /// Number of stations get_stations_by_column_multiple() finds without offset and limit
+    fn count_stations_by_column_multiple(&self,column_name: &str,search: Option<String>,exact: bool,hidebroken: bool) -> Result<u64, Box<dyn Error>>;
+    /// With a cursor the list starts after it, order and reverse have to be the ones of the cursor
+    fn get_stations_by_all(&self,order: &str,reverse: bool,hidebroken: bool,fields: Option<&StationFields>,cursor: Option<&StationCursor>,offset: u32,limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
+    /// Same stations as get_stations_by_all() without cursor, handed to the callback one by one while they are read from the database.
+    /// Backends that share a single connection or lock collect the list first, so a slow reader can not block everybody else.
+    fn for_each_station_by_all(&self,order: &str,reverse: bool,hidebroken: bool,fields: Option<&StationFields>,offset: u32,limit: u32,callback: &mut dyn FnMut(DbStationItem) -> Result<(), Box<dyn Error>>) -> Result<(), Box<dyn Error>> {
+        for station in self.get_stations_by_all(order, reverse, hidebroken, fields, None, offset, limit)? {
+            callback(station)?;
+        }
+        Ok(())
+    }
     fn get_stations_uuid_order_by_changes(&mut self, min_change_count: u32) -> Result<Vec<String>, Box<dyn Error>>;
+    /// If geo_lat and geo_long are given, every station gets its distance to this point in meters.
+    /// geo_distance limits the result to this radius, order "distance" sorts by it.
     fn get_stations_advanced(
         &self,name: Option<String>,name_exact: bool,country: Option<String>,country_exact: bool,countrycode: Option<String>,
         state: Option<String>,state_exact: bool,language: Option<String>,
         language_exact: bool,tag: Option<String>,tag_exact: bool,tag_list: Vec<String>,
         codec: Option<String>,
-        bitrate_min: u32,bitrate_max: u32,has_geo_info: Option<bool>,has_extended_info: Option<bool>, is_https: Option<bool>, order: &str,reverse: bool,hidebroken: bool,offset: u32,limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
+        bitrate_min: u32,bitrate_max: u32,has_geo_info: Option<bool>,has_extended_info: Option<bool>, is_https: Option<bool>,
+        geo_lat: Option<f64>,geo_long: Option<f64>,geo_distance: Option<f64>,geo_bbox: Option<GeoBoundingBox>, order: &str,reverse: bool,hidebroken: bool,fields: Option<&StationFields>,cursor: Option<&StationCursor>,offset: u32,limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
+    /// Number of stations get_stations_advanced() finds without offset and limit
+    fn count_stations_advanced(
+        &self,name: Option<String>,name_exact: bool,country: Option<String>,country_exact: bool,countrycode: Option<String>,
+        state: Option<String>,state_exact: bool,language: Option<String>,
+        language_exact: bool,tag: Option<String>,tag_exact: bool,tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,bitrate_max: u32,has_geo_info: Option<bool>,has_extended_info: Option<bool>, is_https: Option<bool>,
+        geo_lat: Option<f64>,geo_long: Option<f64>,geo_distance: Option<f64>,geo_bbox: Option<GeoBoundingBox>,hidebroken: bool) -> Result<u64, Box<dyn Error>>;
+    /// Search for all words of the query in name, tags, state and language on top of the filters of get_stations_advanced().
+    /// The databases use their full-text index and look up the words as prefixes, so only typos at the end of a word are tolerated.
+    /// Every returned station has a score, order "relevance" sorts by it. The API does not accept cursors for these searches.
+    fn get_stations_search(
+        &self,query: &str,name: Option<String>,name_exact: bool,country: Option<String>,country_exact: bool,countrycode: Option<String>,
+        state: Option<String>,state_exact: bool,language: Option<String>,
+        language_exact: bool,tag: Option<String>,tag_exact: bool,tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,bitrate_max: u32,has_geo_info: Option<bool>,has_extended_info: Option<bool>, is_https: Option<bool>,
+        geo_lat: Option<f64>,geo_long: Option<f64>,geo_distance: Option<f64>,geo_bbox: Option<GeoBoundingBox>, order: &str,reverse: bool,hidebroken: bool,cursor: Option<&StationCursor>,offset: u32,limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
+    /// Number of stations get_stations_search() finds without offset and limit
+    fn count_stations_search(
+        &self,query: &str,name: Option<String>,name_exact: bool,country: Option<String>,country_exact: bool,countrycode: Option<String>,
+        state: Option<String>,state_exact: bool,language: Option<String>,
+        language_exact: bool,tag: Option<String>,tag_exact: bool,tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,bitrate_max: u32,has_geo_info: Option<bool>,has_extended_info: Option<bool>, is_https: Option<bool>,
+        geo_lat: Option<f64>,geo_long: Option<f64>,geo_distance: Option<f64>,geo_bbox: Option<GeoBoundingBox>,hidebroken: bool) -> Result<u64, Box<dyn Error>>;
     fn get_changes(&self, stationuuid: Option<String>, changeuuid: Option<String>, limit: u32) -> Result<Vec<StationHistoryItem>, Box<dyn Error>>;
     fn get_changes_for_stations(&self, station_uuids: Vec<String>) -> Result<Vec<StationHistoryItem>, Box<dyn Error>>;
 
     fn add_station_opt(&self, name: Option<String>, url: Option<String>, homepage: Option<String>, favicon: Option<String>,
         countrycode: Option<String>, state: Option<String>, language: Option<String>, languagecodes: Option<String>, tags: Option<String>, geo_lat: Option<f64>, geo_long: Option<f64>) -> Result<String, Box<dyn Error>>;
+    /// Existing stations that are probably the same as a new one: same url or resolved url,
+    /// or a similar name in the same country. Urls are compared before and after fix_url(),
+    /// submissions in the review queue only by url. The full-text index finds the candidates
+    /// for similar names, only DUPLICATE_NAME_CANDIDATES of them are compared.
+    fn find_duplicate_stations(&self, name: &str, url: &str, countrycode: &str) -> Result<Vec<StationDuplicate>, Box<dyn Error>> {
+        let mut duplicates: Vec<StationDuplicate> = vec![];
+        let mut add = |stationuuid: String, name: String, url: String, reason: DuplicateReason| {
+            if !duplicates.iter().any(|d| d.stationuuid == stationuuid) {
+                duplicates.push(StationDuplicate { stationuuid, name, url, reason });
+            }
+        };
+        let url = url.trim();
+        if !url.is_empty() {
+            let mut urls = vec![url.to_string()];
+            if let Ok(fixed) = fix_url(url, false) {
+                if fixed != url {
+                    urls.push(fixed);
+                }
+            }
+            for (column, reason) in [("Url", DuplicateReason::Url), ("UrlCache", DuplicateReason::UrlResolved)] {
+                for url in urls.iter() {
+                    for station in self.get_stations_by_column(column, url.clone(), true, "name", false, false, None, None, 0, 100)? {
+                        add(station.stationuuid, station.name, station.url, reason);
+                    }
+                }
+            }
+            for station in self.get_pending_stations_by_url(&urls)? {
+                add(station.stationuuid, station.name, station.url, DuplicateReason::Pending);
+            }
+        }
+        let countrycode = countrycode.trim();
+        if countrycode.len() == 2 && !name.trim().is_empty() {
+            let candidates = self.get_stations_search(name, None, false, None, false, Some(countrycode.to_string()), None, false, None, false, None, false, vec![], None,
+                0, u32::MAX, None, None, None, None, None, None, None, "relevance", false, false, None, 0, DUPLICATE_NAME_CANDIDATES)?;
+            for station in candidates {
+                if similar_names(name, &station.name) {
+                    add(station.stationuuid, station.name, station.url, DuplicateReason::Name);
+                }
+            }
+        }
+        Ok(duplicates)
+    }
+    /// Changes the given fields of an existing station and writes a history entry with the source,
+    /// so mirrors get the change. Returns the new changeuuid.
+    fn edit_station_opt(&self, stationuuid: &str, name: Option<String>, url: Option<String>, homepage: Option<String>, favicon: Option<String>,
+        countrycode: Option<String>, state: Option<String>, language: Option<String>, languagecodes: Option<String>, tags: Option<String>, geo_lat: Option<f64>, geo_long: Option<f64>, source: &str) -> Result<String, Box<dyn Error>>;
+    /// Checks a new station like add_station_opt() but puts it into the review queue instead of the station list.
+    /// Returns the stationuuid it will get after approval.
+    fn add_pending_station_opt(&self, name: Option<String>, url: Option<String>, homepage: Option<String>, favicon: Option<String>,
+        countrycode: Option<String>, state: Option<String>, language: Option<String>, languagecodes: Option<String>, tags: Option<String>, geo_lat: Option<f64>, geo_long: Option<f64>) -> Result<String, Box<dyn Error>>;
+    /// Review queue, oldest first
+    fn get_pending_stations(&self, offset: u32, limit: u32) -> Result<Vec<StationAddItem>, Box<dyn Error>>;
+    /// Stations in the review queue that have one of the urls
+    fn get_pending_stations_by_url(&self, urls: &[String]) -> Result<Vec<StationAddItem>, Box<dyn Error>>;
+    /// Moves a station from the review queue to the station list
+    fn approve_pending_station(&self, stationuuid: &str) -> Result<(), Box<dyn Error>>;
+    fn reject_pending_station(&self, stationuuid: &str) -> Result<(), Box<dyn Error>>;
 
     fn get_stations_broken(&self, offset: u32, limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
     fn get_stations_topvote(&self, hidebroken: bool, offset: u32, limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
     fn get_stations_topclick(&self, hidebroken: bool, offset: u32, limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
     fn get_stations_lastclick(&self, hidebroken: bool, offset: u32, limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
     fn get_stations_lastchange(&self, hidebroken: bool, offset: u32, limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
-    fn get_stations_by_column(&self,column_name: &str,search: String,exact: bool,order: &str,reverse: bool,hidebroken: bool,offset: u32,limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
+    fn get_stations_by_column(&self,column_name: &str,search: String,exact: bool,order: &str,reverse: bool,hidebroken: bool,fields: Option<&StationFields>,cursor: Option<&StationCursor>,offset: u32,limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
+    /// Number of stations get_stations_by_column() finds without offset and limit
+    fn count_stations_by_column(&self,column_name: &str,search: String,exact: bool,hidebroken: bool) -> Result<u64, Box<dyn Error>>;
     fn get_stations_by_server_uuids(&self,uuids: Vec<String>, order: &str,reverse: bool,hidebroken: bool,offset: u32,limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
 
     fn get_pull_server_lastid(&self, server: &str) -> Result<Option<String>, Box<dyn Error>>;
@@ -67,10 +190,17 @@ pub trait DbConnection {
     fn get_countries(&self, search: Option<String>, order: String, reverse: bool, hidebroken: bool, offset: u32, limit: u32) -> Result<Vec<DBCountry>, Box<dyn Error>>;
     fn get_states(&self, country: Option<String>, search: Option<String>, order: String, reverse: bool, hidebroken: bool, offset: u32, limit: u32) -> Result<Vec<State>, Box<dyn Error>>;
     fn get_checks(&self, stationuuid: Option<String>, checkuuid: Option<String>, seconds: u32, include_history: bool, limit: u32) -> Result<Vec<StationCheckItem>, Box<dyn Error>>;
-    fn get_clicks(&self, stationuuid: Option<String>, clickuuid: Option<String>, seconds: u32) -> Result<Vec<StationClickItem>, Box<dyn Error>>;
+    /// The newest checks of the stations in the last seconds, at most limit of them, oldest first
+    fn get_check_history(&self, stationuuids: &[String], seconds: u32, limit: u32) -> Result<Vec<StationCheckItem>, Box<dyn Error>>;
+    fn get_clicks(&self, stationuuid: Option<String>, clickuuid: Option<String>, seconds: u32, limit: u32) -> Result<Vec<StationClickItem>, Box<dyn Error>>;
+    /// Uuid of the newest change, check or click, the start for reading the ones that are inserted later
+    fn get_last_changeuuid(&self) -> Result<Option<String>, Box<dyn Error>>;
+    fn get_last_checkuuid(&self) -> Result<Option<String>, Box<dyn Error>>;
+    fn get_last_clickuuid(&self) -> Result<Option<String>, Box<dyn Error>>;
 
     fn insert_checks(&self, list: Vec<StationCheckItemNew>) -> Result<(Vec<StationCheckItemNew>,Vec<StationCheckItemNew>,Vec<StationCheckItemNew>), Box<dyn std::error::Error>>;
-    fn update_station_with_check_data(&self, list: &Vec<StationCheckItemNew>, local: bool) -> Result<(), Box<dyn Error>>;
+    /// Stores the result of the checks in their stations, whether a station works is decided by the consensus of all sources
+    fn update_station_with_check_data(&self, list: &Vec<StationCheckItemNew>, local: bool, consensus: &CheckConsensus) -> Result<(), Box<dyn Error>>;
     //fn update_station(&self, station: &DbStationItem, reason: &str) -> Result<(), Box<dyn Error>>;
     fn delete_stationhistory_more_than(&self, itemcount: u32) -> Result<(), Box<dyn Error>>;
     fn delete_stationhistory_byid_more_than(&self, stationuuid: String, itemcount: usize) -> Result<(), Box<dyn Error>>;
@@ -79,6 +209,8 @@ pub trait DbConnection {
 
     fn delete_never_working(&mut self, seconds: u64) -> Result<(), Box<dyn Error>>;
     fn delete_were_working(&mut self, seconds: u64) -> Result<(), Box<dyn Error>>;
+    /// Stations that delete_were_working would delete now
+    fn get_stations_deletable_were_working(&self, seconds: u64) -> Result<Vec<DbStationItem>, Box<dyn Error>>;
     fn get_duplicated_stations(&self, column_key: &str, max_duplicates: usize) -> Result<Vec<String>, Box<dyn Error>>;
     fn delete_stations(&self, stationuuids: &[String]) -> Result<(), Box<dyn Error>>;
     fn delete_old_checks(&mut self, seconds: u64) -> Result<(), Box<dyn Error>>;
@@ -108,7 +240,8 @@ pub trait DbConnection {
 
     fn insert_station_check_steps(&mut self, station_check_steps: &[StationCheckStepItemNew]) -> Result<(),Box<dyn std::error::Error>>;
     fn select_station_check_steps(&self) -> Result<Vec<StationCheckStepItem>,Box<dyn std::error::Error>>;
-    fn select_station_check_steps_by_stations(&self, stationuuids: &[String]) -> Result<Vec<StationCheckStepItem>,Box<dyn std::error::Error>>;
+    /// Steps in the order they were added, so the parents of the steps are always part of the list
+    fn select_station_check_steps_by_stations(&self, stationuuids: &[String], limit: u32) -> Result<Vec<StationCheckStepItem>,Box<dyn std::error::Error>>;
     fn delete_old_station_check_steps(&mut self, seconds: u32) -> Result<(),Box<dyn std::error::Error>>;
 
     fn get_servers_to_check(&mut self, hours: u32, chunksize: u32) -> Result<Vec<DbStreamingServer>, Box<dyn Error>>;
@@ -118,4 +251,21 @@ pub trait DbConnection {
     fn get_streaming_servers_by_station_uuids(&self, uuids: Vec<String>, order: &str,reverse: bool,offset: u32,limit: u32) -> Result<Vec<DbStreamingServer>, Box<dyn Error>>;
     fn insert_streaming_servers(&mut self, items: Vec<DbStreamingServerNew>) -> Result<(), Box<dyn Error>>;
     fn update_streaming_servers(&mut self, items: Vec<DbStreamingServer>) -> Result<(), Box<dyn Error>>;
+
+    fn add_webhook(&self, webhook: &WebhookItem) -> Result<(), Box<dyn Error>>;
+    /// Webhooks of the owner, or all of them
+    fn get_webhooks(&self, owner: Option<&str>) -> Result<Vec<WebhookItem>, Box<dyn Error>>;
+    /// Removes the webhook with its waiting deliveries, if an owner is given only one of the owner
+    fn delete_webhook(&self, webhookuuid: &str, owner: Option<&str>) -> Result<(), Box<dyn Error>>;
+    /// Queues the event for the webhook, it is due immediately
+    fn insert_webhook_delivery(&self, webhookuuid: &str, event: &str, payload: &str) -> Result<(), Box<dyn Error>>;
+    /// Deliveries that are due, oldest first
+    fn get_due_webhook_deliveries(&self, limit: u32) -> Result<Vec<WebhookDeliveryItem>, Box<dyn Error>>;
+    fn delete_webhook_delivery(&self, deliveryuuid: &str) -> Result<(), Box<dyn Error>>;
+    /// Counts a failed attempt, the next one is due after the seconds
+    fn retry_webhook_delivery(&self, deliveryuuid: &str, seconds: u64, error: &str) -> Result<(), Box<dyn Error>>;
+
+    /// Replaces the titles of the stations, pairs of stationuuid and title
+    fn set_now_playing(&self, titles: &[(String, String)]) -> Result<(), Box<dyn Error>>;
+    fn get_now_playing(&self, stationuuid: &str) -> Result<Option<NowPlayingItem>, Box<dyn Error>>;
 }
@@ -0,0 +1,208 @@
+use crate::db::models::DbStationItem;
+use std::cmp::Ordering;
+
+/// Position after the last station of a page, for pagination that does not get slower on deep pages
+/// and does not skip or repeat stations if the list changes between two requests.
+/// Stations are ordered by the order column and then by StationID, the cursor holds both values of the last station.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StationCursor {
+    pub order: String,
+    pub reverse: bool,
+    pub id: i32,
+    /// Value of the order column, None for NULL
+    pub value: Option<String>,
+}
+
+/// Parameter of the SQL condition of a cursor
+#[derive(Clone, Debug, PartialEq)]
+pub enum CursorParam {
+    Text(String),
+    Int(i64),
+}
+
+impl StationCursor {
+    /// Random order and orders that are computed after the query can not continue at a position
+    pub fn supports_order(order: &str) -> bool {
+        !matches!(order, "random" | "relevance" | "distance")
+    }
+
+    fn is_numeric(order: &str) -> bool {
+        matches!(order, "votes" | "bitrate" | "lastcheckok" | "clickcount" | "clicktrend")
+    }
+
+    /// Cursor that continues after the station, None if the order does not support cursors
+    pub fn after(station: &DbStationItem, order: &str, reverse: bool) -> Option<StationCursor> {
+        if !StationCursor::supports_order(order) {
+            return None;
+        }
+        Some(StationCursor {
+            order: order.to_string(),
+            reverse,
+            id: station.id,
+            value: StationCursor::value_of(station, order),
+        })
+    }
+
+    fn value_of(station: &DbStationItem, order: &str) -> Option<String> {
+        let time = |time: &Option<chrono::DateTime<chrono::Utc>>| time.map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string());
+        match order {
+            "url" => Some(station.url.clone()),
+            "homepage" => Some(station.homepage.clone()),
+            "favicon" => Some(station.favicon.clone()),
+            "tags" => Some(station.tags.clone()),
+            "country" => Some(station.country.clone()),
+            "state" => Some(station.state.clone()),
+            "language" => Some(station.language.clone()),
+            "votes" => Some(station.votes.to_string()),
+            "codec" => Some(station.codec.clone()),
+            "bitrate" => Some(station.bitrate.to_string()),
+            "lastcheckok" => Some(if station.lastcheckok { "1" } else { "0" }.to_string()),
+            "lastchecktime" => time(&station.lastchecktime_iso8601),
+            "clicktimestamp" => time(&station.clicktimestamp_iso8601),
+            "clickcount" => Some(station.clickcount.to_string()),
+            "clicktrend" => Some(station.clicktrend.to_string()),
+            "changetimestamp" => time(&station.lastchangetime_iso8601),
+            _ => Some(station.name.clone()),
+        }
+    }
+
+    /// Opaque token for urls, hex encoded "order,reverse,id[,value]"
+    pub fn encode(&self) -> String {
+        let mut plain = format!("{},{},{}", self.order, if self.reverse { 1 } else { 0 }, self.id);
+        if let Some(ref value) = self.value {
+            plain.push(',');
+            plain.push_str(value);
+        }
+        plain.bytes().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    pub fn decode(token: &str) -> Option<StationCursor> {
+        if token.len() % 2 != 0 || !token.is_ascii() {
+            return None;
+        }
+        let bytes: Vec<u8> = (0..token.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&token[i..i + 2], 16))
+            .collect::<Result<_, _>>()
+            .ok()?;
+        let plain = String::from_utf8(bytes).ok()?;
+        let mut parts = plain.splitn(4, ',');
+        let order = parts.next()?.to_string();
+        let reverse = match parts.next()? {
+            "0" => false,
+            "1" => true,
+            _ => return None,
+        };
+        let id = parts.next()?.parse().ok()?;
+        let value = parts.next().map(|value| value.to_string());
+        if !StationCursor::supports_order(&order) {
+            return None;
+        }
+        if StationCursor::is_numeric(&order) && value.as_ref().map(|value| value.parse::<i64>().is_err()).unwrap_or(true) {
+            return None;
+        }
+        Some(StationCursor { order, reverse, id, value })
+    }
+
+    /// Condition that starts after the cursor for the given order column, works for mysql and sqlite.
+    /// NULL values come first in ascending order, like the databases sort them.
+    /// Named parameters are ":cursor_value" and ":cursor_id", positional ones are returned by positional_params() in the same order.
+    pub fn sql_condition(&self, column: &str, named: bool) -> String {
+        let (value, id) = if named { (":cursor_value", ":cursor_id") } else { ("?", "?") };
+        match (&self.value, self.reverse) {
+            (Some(_), false) => format!(" AND ({c} > {v} OR ({c} = {v} AND StationID > {i}))", c = column, v = value, i = id),
+            (Some(_), true) => format!(" AND ({c} < {v} OR ({c} = {v} AND StationID < {i}) OR {c} IS NULL)", c = column, v = value, i = id),
+            (None, false) => format!(" AND ({c} IS NOT NULL OR StationID > {i})", c = column, i = id),
+            (None, true) => format!(" AND {c} IS NULL AND StationID < {i}", c = column, i = id),
+        }
+    }
+
+    fn value_param(&self) -> Option<CursorParam> {
+        let value = self.value.as_ref()?;
+        if StationCursor::is_numeric(&self.order) {
+            Some(CursorParam::Int(value.parse().unwrap_or(0)))
+        } else {
+            Some(CursorParam::Text(value.clone()))
+        }
+    }
+
+    /// Parameters for the named placeholders of sql_condition()
+    pub fn named_params(&self) -> Vec<(&'static str, CursorParam)> {
+        let mut params = vec![("cursor_id", CursorParam::Int(self.id.into()))];
+        if let Some(value) = self.value_param() {
+            params.push(("cursor_value", value));
+        }
+        params
+    }
+
+    /// Parameters for the positional placeholders of sql_condition()
+    pub fn positional_params(&self) -> Vec<CursorParam> {
+        match self.value_param() {
+            Some(value) => vec![value.clone(), value, CursorParam::Int(self.id.into())],
+            None => vec![CursorParam::Int(self.id.into())],
+        }
+    }
+
+    /// Same as the SQL condition, for backends that filter in memory
+    pub fn is_before(&self, station: &DbStationItem) -> bool {
+        let ordering = match (StationCursor::value_of(station, &self.order), &self.value) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(a), Some(b)) => {
+                if StationCursor::is_numeric(&self.order) {
+                    a.parse::<i64>().unwrap_or(0).cmp(&b.parse::<i64>().unwrap_or(0))
+                } else {
+                    a.to_lowercase().cmp(&b.to_lowercase())
+                }
+            }
+        }
+        .then(station.id.cmp(&self.id));
+        if self.reverse {
+            ordering == Ordering::Less
+        } else {
+            ordering == Ordering::Greater
+        }
+    }
+}
+
+/// ORDER BY with StationID as tie breaker, so pages are stable for stations with the same value
+pub fn sql_order(column: &str, reverse: bool) -> String {
+    let direction = if reverse { "DESC" } else { "ASC" };
+    format!("{} {}, StationID {}", column, direction, direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StationCursor;
+
+    #[test]
+    fn cursor_tokens() {
+        let cursor = StationCursor {
+            order: String::from("name"),
+            reverse: true,
+            id: 42,
+            value: Some(String::from("Radio, 1 ä")),
+        };
+        assert_eq!(StationCursor::decode(&cursor.encode()), Some(cursor.clone()));
+        let null_cursor = StationCursor { value: None, ..cursor };
+        assert_eq!(StationCursor::decode(&null_cursor.encode()), Some(null_cursor));
+
+        assert_eq!(StationCursor::decode("xyz"), None);
+        assert_eq!(StationCursor::decode(""), None);
+        let random = StationCursor { order: String::from("random"), reverse: false, id: 1, value: None };
+        assert_eq!(StationCursor::decode(&random.encode()), None);
+        let votes = StationCursor { order: String::from("votes"), reverse: false, id: 1, value: Some(String::from("abc")) };
+        assert_eq!(StationCursor::decode(&votes.encode()), None);
+    }
+
+    #[test]
+    fn cursor_conditions() {
+        let cursor = StationCursor { order: String::from("votes"), reverse: false, id: 7, value: Some(String::from("3")) };
+        assert_eq!(cursor.sql_condition("Votes", true), " AND (Votes > :cursor_value OR (Votes = :cursor_value AND StationID > :cursor_id))");
+        assert_eq!(cursor.positional_params().len(), 3);
+        let cursor = StationCursor { order: String::from("lastchecktime"), reverse: true, id: 7, value: None };
+        assert_eq!(cursor.sql_condition("LastCheckTime", false), " AND LastCheckTime IS NULL AND StationID < ?");
+        assert_eq!(cursor.positional_params().len(), 1);
+    }
+}
@@ -5,19 +5,27 @@ use std::fmt::Result;
 
 #[derive(Debug, Clone)]
 pub enum DbError {
-    //ConnectionError(String),
+    ConnectionError(String),
     VoteError(String),
     AddStationError(String),
+    EditStationError(String),
+    ModerationError(String),
+    WebhookError(String),
     IllegalOrderError(String),
+    UnknownColumnError(String),
 }
 
 impl Display for DbError {
     fn fmt(&self, f: &mut Formatter) -> Result {
         match *self {
-            //DbError::ConnectionError(ref v) => write!(f, "ConnectionError '{}'", v),
+            DbError::ConnectionError(ref v) => write!(f, "ConnectionError '{}'", v),
             DbError::VoteError(ref v) => write!(f, "VoteError '{}'", v),
             DbError::AddStationError(ref v) => write!(f, "AddStationError '{}'", v),
+            DbError::EditStationError(ref v) => write!(f, "EditStationError '{}'", v),
+            DbError::ModerationError(ref v) => write!(f, "ModerationError '{}'", v),
+            DbError::WebhookError(ref v) => write!(f, "WebhookError '{}'", v),
             DbError::IllegalOrderError(ref v) => write!(f, "IllegalOrderError '{}'", v),
+            DbError::UnknownColumnError(ref v) => write!(f, "UnknownColumnError '{}'", v),
         }
     }
 }
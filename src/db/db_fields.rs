@@ -0,0 +1,140 @@
+/// Fields of stations in the API with the column of the Station table they are read from.
+/// score is calculated after the query, distance from the coordinates that are always selected.
+/// now_playing is the current title of the NowPlaying table, which station queries join.
+const FIELDS: &[(&str, Option<&str>)] = &[
+    ("changeuuid", Some("ChangeUuid")),
+    ("stationuuid", Some("StationUuid")),
+    ("serveruuid", Some("ServerUuid")),
+    ("name", Some("Name")),
+    ("url", Some("Url")),
+    ("url_resolved", Some("UrlCache")),
+    ("homepage", Some("Homepage")),
+    ("favicon", Some("Favicon")),
+    ("tags", Some("Tags")),
+    ("country", Some("Country")),
+    ("countrycode", Some("CountryCode")),
+    ("iso_3166_2", Some("CountrySubdivisionCode")),
+    ("state", Some("Subcountry")),
+    ("language", Some("Language")),
+    ("languagecodes", Some("LanguageCodes")),
+    ("votes", Some("Votes")),
+    ("lastchangetime", Some("Creation")),
+    ("lastchangetime_iso8601", Some("Creation")),
+    ("codec", Some("Codec")),
+    ("bitrate", Some("Bitrate")),
+    ("hls", Some("Hls")),
+    ("lastcheckok", Some("LastCheckOK")),
+    ("lastchecktime", Some("LastCheckTime")),
+    ("lastchecktime_iso8601", Some("LastCheckTime")),
+    ("lastcheckoktime", Some("LastCheckOkTime")),
+    ("lastcheckoktime_iso8601", Some("LastCheckOkTime")),
+    ("lastlocalchecktime", Some("LastLocalCheckTime")),
+    ("lastlocalchecktime_iso8601", Some("LastLocalCheckTime")),
+    ("clicktimestamp", Some("ClickTimestamp")),
+    ("clicktimestamp_iso8601", Some("ClickTimestamp")),
+    ("clickcount", Some("clickcount")),
+    ("clicktrend", Some("ClickTrend")),
+    ("ssl_error", Some("SslError")),
+    ("geo_lat", Some("GeoLat")),
+    ("geo_long", Some("GeoLong")),
+    ("has_extended_info", Some("ExtendedInfo")),
+    ("now_playing", Some("NowPlaying")),
+    ("score", None),
+    ("distance", None),
+];
+
+/// Columns every projection needs, for the conversion of rows, cursors and distances
+const KEY_COLUMNS: &[&str] = &["StationID", "ChangeUuid", "StationUuid", "GeoLat", "GeoLong"];
+
+/// Columns that are selected twice, as timestamp and as formatted text
+const TIME_COLUMNS: &[&str] = &["Creation", "LastCheckTime", "LastCheckOkTime", "LastLocalCheckTime", "ClickTimestamp"];
+
+/// Fields of stations a client asked for with the parameter "fields", in the order of the station struct
+#[derive(Clone, Debug, PartialEq)]
+pub struct StationFields {
+    names: Vec<&'static str>,
+}
+
+impl StationFields {
+    /// Comma separated list of field names, the error names the first unknown field
+    pub fn parse(list: &str) -> Result<StationFields, String> {
+        let requested: Vec<&str> = list.split(',').map(|name| name.trim()).filter(|name| !name.is_empty()).collect();
+        if let Some(unknown) = requested.iter().find(|name| !FIELDS.iter().any(|(field, _)| field == *name)) {
+            return Err(format!("unknown field '{}'", unknown));
+        }
+        if requested.is_empty() {
+            return Err(String::from("no fields"));
+        }
+        let names = FIELDS
+            .iter()
+            .map(|(field, _)| *field)
+            .filter(|field| requested.contains(field))
+            .collect();
+        Ok(StationFields { names })
+    }
+
+    /// Every field of a station
+    pub fn all() -> StationFields {
+        StationFields {
+            names: FIELDS.iter().map(|(field, _)| *field).collect(),
+        }
+    }
+
+    pub fn names(&self) -> &[&'static str] {
+        &self.names
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(&name)
+    }
+
+    /// SELECT list with the columns of the fields and the order column.
+    /// Time columns get an additional "<column>Formated" text column from time_format.
+    pub fn select(&self, order_column: &str, time_format: fn(&str) -> String) -> String {
+        let mut columns: Vec<&str> = KEY_COLUMNS.to_vec();
+        let field_columns = FIELDS
+            .iter()
+            .filter(|(field, _)| self.contains(field))
+            .filter_map(|(_, column)| *column);
+        let order_column = FIELDS.iter().filter_map(|(_, column)| *column).find(|column| *column == order_column);
+        for column in field_columns.chain(order_column) {
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+        columns
+            .iter()
+            .map(|column| {
+                if TIME_COLUMNS.contains(column) {
+                    format!("{},{}", column, time_format(column))
+                } else {
+                    column.to_string()
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StationFields;
+
+    #[test]
+    fn station_fields() {
+        let fields = StationFields::parse("name, stationuuid,codec").unwrap();
+        assert_eq!(fields.names(), &["stationuuid", "name", "codec"]);
+        assert!(StationFields::parse("name,password").unwrap_err().contains("password"));
+        assert!(StationFields::parse(",").is_err());
+
+        let fields = StationFields::parse("lastchecktime,score").unwrap();
+        assert_eq!(
+            fields.select("Votes", |column| format!("F({})", column)),
+            "StationID,ChangeUuid,StationUuid,GeoLat,GeoLong,LastCheckTime,F(LastCheckTime),Votes"
+        );
+        assert_eq!(
+            fields.select("RAND()", |column| format!("F({})", column)),
+            "StationID,ChangeUuid,StationUuid,GeoLat,GeoLong,LastCheckTime,F(LastCheckTime)"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::db::models::DbStationItem;
+use std::cmp::Ordering;
+use std::error::Error;
+
+/// Mean earth radius in meters
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Radius in meters of the first circle that is searched for the nearest stations
+const NEAREST_FIRST_RADIUS: f64 = 50_000.0;
+
+/// Area between two latitudes and two longitudes, e.g. the viewport of a map.
+/// If west is bigger than east the box crosses the antimeridian.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeoBoundingBox {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+impl GeoBoundingBox {
+    /// Smallest box that contains the circle with the distance in meters around the point
+    pub fn around(lat: f64, long: f64, distance: f64) -> Self {
+        let angle = distance / EARTH_RADIUS;
+        let south = lat - angle.to_degrees();
+        let north = lat + angle.to_degrees();
+        if south <= -90.0 || north >= 90.0 {
+            // circle contains a pole, every longitude is possible
+            return GeoBoundingBox {
+                south: south.max(-90.0),
+                west: -180.0,
+                north: north.min(90.0),
+                east: 180.0,
+            };
+        }
+        let delta_long = (angle.sin() / lat.to_radians().cos()).asin().to_degrees();
+        let mut west = long - delta_long;
+        let mut east = long + delta_long;
+        if west < -180.0 {
+            west += 360.0;
+        }
+        if east > 180.0 {
+            east -= 360.0;
+        }
+        GeoBoundingBox { south, west, north, east }
+    }
+
+    pub fn contains(&self, lat: f64, long: f64) -> bool {
+        if lat < self.south || lat > self.north {
+            return false;
+        }
+        if self.west <= self.east {
+            long >= self.west && long <= self.east
+        } else {
+            long >= self.west || long <= self.east
+        }
+    }
+
+    /// SQL condition for the columns GeoLat and GeoLong that works for mysql and sqlite,
+    /// the named parameters are "{prefix}_south", "{prefix}_west", "{prefix}_north" and "{prefix}_east"
+    pub fn sql_condition(&self, prefix: &str) -> String {
+        let joiner = if self.west <= self.east { "AND" } else { "OR" };
+        format!(
+            " AND GeoLat >= :{p}_south AND GeoLat <= :{p}_north AND (GeoLong >= :{p}_west {joiner} GeoLong <= :{p}_east)",
+            p = prefix,
+            joiner = joiner
+        )
+    }
+
+    pub fn sql_params(&self, prefix: &str) -> Vec<(String, f64)> {
+        vec![
+            (format!("{}_south", prefix), self.south),
+            (format!("{}_west", prefix), self.west),
+            (format!("{}_north", prefix), self.north),
+            (format!("{}_east", prefix), self.east),
+        ]
+    }
+}
+
+/// Great circle distance in meters (haversine formula)
+pub fn distance(lat1: f64, long1: f64, lat2: f64, long2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_long = (long2 - long1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_long / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+}
+
+/// The boxes a station has to be in to match the geo filters of get_stations_advanced(),
+/// used to let the database do the coarse filtering
+pub fn geo_boxes(geo_lat: Option<f64>, geo_long: Option<f64>, geo_distance: Option<f64>, geo_bbox: Option<GeoBoundingBox>) -> Vec<GeoBoundingBox> {
+    let mut boxes: Vec<GeoBoundingBox> = geo_bbox.into_iter().collect();
+    if let (Some(lat), Some(long), Some(distance)) = (geo_lat, geo_long, geo_distance) {
+        boxes.push(GeoBoundingBox::around(lat, long, distance));
+    }
+    boxes
+}
+
+/// The distance can only be filtered and ordered by after the database query
+fn needs_complete_result(geo_lat: Option<f64>, geo_long: Option<f64>, geo_distance: Option<f64>, order: &str) -> bool {
+    geo_lat.is_some() && geo_long.is_some() && (geo_distance.is_some() || order == "distance")
+}
+
+/// Radius around the point that contains at least the wanted number of stations, so ordering by distance
+/// only has to read the stations inside of its bounding box. The circle grows from NEAREST_FIRST_RADIUS
+/// until count_within() finds enough stations in it or it reaches max_distance, then max_distance is returned
+/// because the whole requested area is needed anyway.
+pub fn nearest_radius<F>(wanted: u64, max_distance: Option<f64>, mut count_within: F) -> Result<Option<f64>, Box<dyn Error>>
+where
+    F: FnMut(f64) -> Result<u64, Box<dyn Error>>,
+{
+    // half of the circumference reaches every point of the earth
+    let max = max_distance.unwrap_or(std::f64::consts::PI * EARTH_RADIUS);
+    let mut radius = NEAREST_FIRST_RADIUS;
+    while radius < max {
+        if count_within(radius)? >= wanted {
+            return Ok(Some(radius));
+        }
+        radius *= 4.0;
+    }
+    Ok(max_distance)
+}
+
+/// Offset and limit for the database query of get_stations_advanced()
+pub fn query_range(geo_lat: Option<f64>, geo_long: Option<f64>, geo_distance: Option<f64>, order: &str, offset: u32, limit: u32) -> (u32, u32) {
+    if needs_complete_result(geo_lat, geo_long, geo_distance, order) {
+        (0, u32::MAX)
+    } else {
+        (offset, limit)
+    }
+}
+
+/// Point and radius if the exact distance has to be checked after the database query,
+/// the database can only filter by the bounding box around them
+pub fn distance_filter(geo_lat: Option<f64>, geo_long: Option<f64>, geo_distance: Option<f64>) -> Option<(f64, f64, f64)> {
+    Some((geo_lat?, geo_long?, geo_distance?))
+}
+
+/// Number of locations inside the radius, rounded like the distances of apply_distance()
+pub fn count_within_distance(locations: Vec<(f64, f64)>, lat: f64, long: f64, max: f64) -> u64 {
+    locations
+        .into_iter()
+        .filter(|(station_lat, station_long)| distance(lat, long, *station_lat, *station_long).round() <= max)
+        .count() as u64
+}
+
+/// Post processing of the database result that was queried with query_range()
+pub fn finish_query(list: Vec<DbStationItem>, geo_lat: Option<f64>, geo_long: Option<f64>, geo_distance: Option<f64>, order: &str, reverse: bool, offset: u32, limit: u32) -> Vec<DbStationItem> {
+    let complete = needs_complete_result(geo_lat, geo_long, geo_distance, order);
+    let list = apply_distance(list, geo_lat, geo_long, geo_distance, order, reverse);
+    if complete {
+        list.into_iter().skip(offset as usize).take(limit as usize).collect()
+    } else {
+        list
+    }
+}
+
+/// Sets the distance to the given point for every station that has a location,
+/// removes the stations outside of geo_distance and sorts by distance if ordered by it.
+/// Stations without location are kept as long as there is no geo_distance and come last.
+fn apply_distance(list: Vec<DbStationItem>, geo_lat: Option<f64>, geo_long: Option<f64>, geo_distance: Option<f64>, order: &str, reverse: bool) -> Vec<DbStationItem> {
+    let (lat, long) = match (geo_lat, geo_long) {
+        (Some(lat), Some(long)) => (lat, long),
+        _ => return list,
+    };
+    let mut list: Vec<DbStationItem> = list
+        .into_iter()
+        .filter_map(|mut station| {
+            station.distance = match (station.geo_lat, station.geo_long) {
+                (Some(station_lat), Some(station_long)) => Some(distance(lat, long, station_lat, station_long).round()),
+                _ => None,
+            };
+            match (geo_distance, station.distance) {
+                (Some(max), Some(distance)) if distance > max => None,
+                (Some(_), None) => None,
+                _ => Some(station),
+            }
+        })
+        .collect();
+    if order == "distance" {
+        list.sort_by(|a, b| match (a.distance, b.distance) {
+            (Some(a), Some(b)) => {
+                let ordering = a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+                if reverse {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            }
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+    }
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::distance;
+    use super::nearest_radius;
+    use super::GeoBoundingBox;
+
+    #[test]
+    fn distance_between_cities() {
+        // Berlin - Munich is about 504 km
+        let berlin_munich = distance(52.52, 13.405, 48.137, 11.575);
+        assert!((berlin_munich - 504_000.0).abs() < 2_000.0);
+        assert_eq!(distance(10.0, 20.0, 10.0, 20.0), 0.0);
+    }
+
+    #[test]
+    fn bounding_boxes() {
+        let around = GeoBoundingBox::around(52.52, 13.405, 50_000.0);
+        assert!(around.contains(52.9, 13.405));
+        assert!(!around.contains(53.0, 13.405));
+        assert!(around.contains(52.52, 14.0));
+        assert!(!around.contains(52.52, 14.2));
+
+        let pacific = GeoBoundingBox::around(0.0, 179.9, 50_000.0);
+        assert!(pacific.west > pacific.east);
+        assert!(pacific.contains(0.0, -179.9));
+        assert!(!pacific.contains(0.0, 0.0));
+        assert!(pacific.sql_condition("b").contains("GeoLong >= :b_west OR GeoLong <= :b_east"));
+
+        let pole = GeoBoundingBox::around(89.9, 0.0, 50_000.0);
+        assert!(pole.contains(89.9, 180.0));
+    }
+
+    #[test]
+    fn nearest_radius_grows() {
+        let mut radiuses = vec![];
+        let radius = nearest_radius(10, None, |radius| {
+            radiuses.push(radius);
+            Ok(if radius > 100_000.0 { 10 } else { 3 })
+        })
+        .unwrap();
+        assert_eq!(radius, Some(200_000.0));
+        assert_eq!(radiuses, vec![50_000.0, 200_000.0]);
+
+        assert_eq!(nearest_radius(10, Some(100_000.0), |_| Ok(0)).unwrap(), Some(100_000.0));
+        assert_eq!(nearest_radius(10, None, |_| Ok(0)).unwrap(), None);
+    }
+}
@@ -0,0 +1,2529 @@
+use crate::api::data::Station;
+use crate::db::db_cursor::StationCursor;
+use crate::db::db_fields::StationFields;
+use crate::db::db_error::DbError;
+use crate::db::db_geo::count_within_distance;
+use crate::db::db_geo::distance_filter;
+use crate::db::db_geo::finish_query;
+use crate::db::db_geo::geo_boxes;
+use crate::db::db_geo::query_range;
+use crate::db::db_geo::GeoBoundingBox;
+use crate::db::db_search::rank_stations;
+use crate::db::db_search::station_relevance;
+use crate::db::db_utils::check_new_station;
+use crate::db::db_utils::edit_station_fields;
+use crate::db::db_utils::filter_order_1_n;
+use crate::db::db_utils::fix_multi_field;
+use crate::db::models::CheckConsensus;
+use crate::db::models::DBCountry;
+use crate::db::models::DbStationItem;
+use crate::db::models::NowPlayingItem;
+use crate::db::models::NOW_PLAYING_MAX_AGE;
+use crate::db::models::DbStreamingServer;
+use crate::db::models::DbStreamingServerNew;
+use crate::db::models::ExtraInfo;
+use crate::db::models::State;
+use crate::db::models::StationAddItem;
+use crate::db::models::StationChangeItemNew;
+use crate::db::models::StationCheckItem;
+use crate::db::models::StationCheckItemNew;
+use crate::db::models::StationCheckStepItem;
+use crate::db::models::StationCheckStepItemNew;
+use crate::db::models::StationClickItem;
+use crate::db::models::StationClickItemNew;
+use crate::db::models::StationHistoryItem;
+use crate::db::models::WebhookDeliveryItem;
+use crate::db::models::WebhookItem;
+use crate::db::DbConnection;
+use celes::Country;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::NaiveDateTime;
+use chrono::Timelike;
+use chrono::Utc;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::error::Error;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use url::Url;
+use uuid::Uuid;
+
+/// DbConnection that keeps everything in process memory. Nothing is persisted,
+/// it is meant for tests and for a demo mode ("memory:" connection string).
+/// Filtering, ordering and time windows follow the mysql queries as closely as possible.
+#[derive(Clone, Default)]
+pub struct MemoryConnection {
+    data: Arc<Mutex<MemoryData>>,
+}
+
+#[derive(Default)]
+struct MemoryData {
+    clock_offset: i64,
+
+    stations: Vec<DbStationItem>,
+    pending: Vec<StationAddItem>,
+    history: Vec<HistoryRow>,
+    checks: Vec<CheckRow>,
+    check_steps: Vec<StationCheckStepItem>,
+    clicks: Vec<ClickRow>,
+    votes: Vec<VoteRow>,
+    streaming_servers: Vec<StreamingServerRow>,
+    pull_servers: Vec<PullServerRow>,
+    webhooks: Vec<WebhookItem>,
+    webhook_deliveries: Vec<WebhookDeliveryRow>,
+    next_checks: HashMap<String, DateTime<Utc>>,
+    now_playing: HashMap<String, NowPlayingItem>,
+    caches: HashMap<String, BTreeMap<String, (u32, u32)>>,
+
+    last_station_id: i32,
+    last_history_id: i32,
+    last_check_id: i32,
+    last_check_step_id: u32,
+    last_click_id: i32,
+    last_streaming_server_id: u32,
+    last_webhook_delivery_id: u32,
+}
+
+struct HistoryRow {
+    id: i32,
+    changeuuid: String,
+    stationuuid: String,
+    name: String,
+    url: String,
+    homepage: String,
+    favicon: String,
+    tags: String,
+    countrycode: String,
+    state: String,
+    language: String,
+    languagecodes: String,
+    votes: i32,
+    creation: DateTime<Utc>,
+    geo_lat: Option<f64>,
+    geo_long: Option<f64>,
+}
+
+/// clickcount, clicks last day, clicks the day before, last click
+type ClickCounter = (u32, i32, i32, Option<DateTime<Utc>>);
+
+struct CheckRow {
+    item: StationCheckItem,
+    inserttime: DateTime<Utc>,
+}
+
+struct ClickRow {
+    id: i32,
+    clickuuid: String,
+    stationuuid: String,
+    ip: Option<String>,
+    clicktimestamp: DateTime<Utc>,
+    inserttime: DateTime<Utc>,
+}
+
+struct VoteRow {
+    ip: String,
+    station_id: i32,
+    timestamp: DateTime<Utc>,
+}
+
+struct StreamingServerRow {
+    id: u32,
+    uuid: String,
+    url: String,
+    statusurl: Option<String>,
+    status: Option<String>,
+    error: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+struct WebhookDeliveryRow {
+    id: u32,
+    deliveryuuid: String,
+    webhookuuid: String,
+    event: String,
+    payload: String,
+    attempts: u32,
+    next_attempt: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct PullServerRow {
+    name: String,
+    lastid: Option<String>,
+    lastcheckid: Option<String>,
+    lastclickuuid: Option<String>,
+}
+
+impl MemoryConnection {
+    pub fn new() -> Self {
+        MemoryConnection {
+            data: Arc::new(Mutex::new(MemoryData::default())),
+        }
+    }
+
+    /// Move the clock of this database forward, so time based queries
+    /// (cleanup, click dedup, pull windows) can be tested without waiting
+    #[cfg(test)]
+    pub fn advance_clock(&self, seconds: i64) {
+        if let Ok(mut data) = self.data.lock() {
+            data.clock_offset += seconds;
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, MemoryData>, Box<dyn Error>> {
+        self.data
+            .lock()
+            .map_err(|err| Box::new(DbError::ConnectionError(err.to_string())) as Box<dyn Error>)
+    }
+}
+
+impl MemoryData {
+    /// Stations get_stations_by_column() finds, unsorted
+    fn stations_by_column(&self, column_name: &str, search: &str, exact: bool, hidebroken: bool) -> Result<Vec<&DbStationItem>, Box<dyn Error>> {
+        let mut list = vec![];
+        for station in self.stations.iter() {
+            if hidebroken && !station.lastcheckok {
+                continue;
+            }
+            if let Some(value) = station_column(station, column_name)? {
+                let found = if exact {
+                    eq_ci(value, search)
+                } else {
+                    like_ci(value, search)
+                };
+                if found {
+                    list.push(station);
+                }
+            }
+        }
+        Ok(list)
+    }
+
+    /// Stations get_stations_by_column_multiple() finds, unsorted
+    fn stations_by_column_multiple(&self, column_name: &str, search: &Option<String>, exact: bool, hidebroken: bool) -> Result<Vec<&DbStationItem>, Box<dyn Error>> {
+        let mut list = vec![];
+        // comparing against NULL never matches in sql
+        if let Some(search) = search {
+            for station in self.stations.iter() {
+                if hidebroken && !station.lastcheckok {
+                    continue;
+                }
+                let value = station_column(station, column_name)?.unwrap_or_default();
+                let found = if exact {
+                    multi_field_contains(value, search)
+                } else {
+                    like_ci(value, search)
+                };
+                if found {
+                    list.push(station);
+                }
+            }
+        }
+        Ok(list)
+    }
+
+    /// Stations get_stations_advanced() finds, unsorted and only filtered by the bounding box of geo_distance
+    fn stations_advanced(
+        &self,
+        name: Option<String>,
+        name_exact: bool,
+        country: Option<String>,
+        country_exact: bool,
+        countrycode: Option<String>,
+        state: Option<String>,
+        state_exact: bool,
+        language: Option<String>,
+        language_exact: bool,
+        tag: Option<String>,
+        tag_exact: bool,
+        tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,
+        bitrate_max: u32,
+        has_geo_info: Option<bool>,
+        has_extended_info: Option<bool>,
+        is_https: Option<bool>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+        geo_bbox: Option<GeoBoundingBox>,
+        hidebroken: bool,
+    ) -> Vec<&DbStationItem> {
+        let text_matches = |value: &str, search: &Option<String>, exact: bool| match search {
+            Some(search) if exact => eq_ci(value, search),
+            Some(search) => like_ci(value, search),
+            None => true,
+        };
+        let multi_matches = |value: &str, search: &str, exact: bool| {
+            if exact {
+                multi_field_contains(value, search)
+            } else {
+                like_ci(value, search)
+            }
+        };
+        let geo_boxes = geo_boxes(geo_lat, geo_long, geo_distance, geo_bbox);
+        self.stations
+            .iter()
+            .filter(|s| s.bitrate >= bitrate_min && s.bitrate <= bitrate_max)
+            .filter(|s| match has_geo_info {
+                Some(true) => s.geo_lat.is_some() && s.geo_long.is_some(),
+                Some(false) => s.geo_lat.is_none() || s.geo_long.is_none(),
+                None => true,
+            })
+            .filter(|s| match has_extended_info {
+                Some(wanted) => s.has_extended_info == Some(wanted),
+                None => true,
+            })
+            .filter(|s| match is_https {
+                Some(true) => starts_with_ci(&s.url_resolved, "https://"),
+                Some(false) => starts_with_ci(&s.url_resolved, "http://"),
+                None => true,
+            })
+            .filter(|s| text_matches(&s.name, &name, name_exact))
+            .filter(|s| text_matches(&s.country, &country, country_exact))
+            .filter(|s| match countrycode {
+                Some(ref countrycode) => eq_ci(&s.countrycode, countrycode),
+                None => true,
+            })
+            .filter(|s| text_matches(&s.state, &state, state_exact))
+            .filter(|s| match language {
+                Some(ref language) => multi_matches(&s.language, language, language_exact),
+                None => true,
+            })
+            .filter(|s| match tag {
+                Some(ref tag) => multi_matches(&s.tags, tag, tag_exact),
+                None => true,
+            })
+            .filter(|s| match codec {
+                Some(ref codec) => eq_ci(&s.codec, codec),
+                None => true,
+            })
+            .filter(|s| tag_list.iter().all(|tag| multi_matches(&s.tags, tag, tag_exact)))
+            .filter(|s| match (s.geo_lat, s.geo_long) {
+                (Some(lat), Some(long)) => geo_boxes.iter().all(|geo_box| geo_box.contains(lat, long)),
+                _ => geo_boxes.is_empty(),
+            })
+            .filter(|s| !hidebroken || s.lastcheckok)
+            .collect()
+    }
+
+    /// Current time with the same (second) resolution mysql DATETIME columns have
+    fn now(&self) -> DateTime<Utc> {
+        let now = Utc::now().with_nanosecond(0).unwrap_or_else(Utc::now);
+        now + Duration::seconds(self.clock_offset)
+    }
+
+    fn seconds_ago(&self, seconds: u64) -> DateTime<Utc> {
+        self.now() - Duration::seconds(seconds as i64)
+    }
+
+    /// Title of the station, if it is not older than NOW_PLAYING_MAX_AGE
+    fn now_playing(&self, stationuuid: &str) -> Option<&NowPlayingItem> {
+        let limit = self.seconds_ago(NOW_PLAYING_MAX_AGE.as_secs());
+        self.now_playing
+            .get(stationuuid)
+            .filter(|item| item.updatetime_iso8601.map(|time| time > limit).unwrap_or(false))
+    }
+
+    /// Time the station is due for its next check, None if it was never checked locally
+    fn check_due(&self, station: &DbStationItem, hours: u32) -> Option<DateTime<Utc>> {
+        self.next_checks
+            .get(&station.stationuuid)
+            .copied()
+            .or_else(|| station.lastlocalchecktime_iso8601.map(|time| time + Duration::hours(i64::from(hours))))
+    }
+
+    fn station_exists(&self, stationuuid: &str) -> bool {
+        self.stations
+            .iter()
+            .any(|station| eq_ci(&station.stationuuid, stationuuid))
+    }
+
+    fn find_station_mut(&mut self, stationuuid: &str) -> Option<&mut DbStationItem> {
+        self.stations
+            .iter_mut()
+            .find(|station| eq_ci(&station.stationuuid, stationuuid))
+    }
+
+    /// Removes stations and everything that references them,
+    /// like the "ON DELETE CASCADE" foreign keys do in mysql
+    fn remove_stations<F>(&mut self, filter: F)
+    where
+        F: Fn(&DbStationItem) -> bool,
+    {
+        let removed: HashSet<String> = self
+            .stations
+            .iter()
+            .filter(|station| filter(station))
+            .map(|station| station.stationuuid.to_lowercase())
+            .collect();
+        if removed.is_empty() {
+            return;
+        }
+        self.stations
+            .retain(|station| !removed.contains(&station.stationuuid.to_lowercase()));
+        self.clicks
+            .retain(|click| !removed.contains(&click.stationuuid.to_lowercase()));
+        self.remove_checks(|check| removed.contains(&check.item.station_uuid.to_lowercase()));
+    }
+
+    fn remove_checks<F>(&mut self, filter: F)
+    where
+        F: Fn(&CheckRow) -> bool,
+    {
+        let removed: HashSet<String> = self
+            .checks
+            .iter()
+            .filter(|check| filter(check))
+            .map(|check| check.item.check_uuid.clone())
+            .collect();
+        self.checks
+            .retain(|check| !removed.contains(&check.item.check_uuid));
+        self.check_steps
+            .retain(|step| !removed.contains(&step.checkuuid));
+    }
+
+    fn backup_stations_by_uuid(&mut self, stationuuids: &[String], _source: &str) {
+        let now = self.now();
+        let mut rows = vec![];
+        for station in self.stations.iter() {
+            if !stationuuids
+                .iter()
+                .any(|stationuuid| eq_ci(stationuuid, &station.stationuuid))
+            {
+                continue;
+            }
+            rows.push(HistoryRow {
+                id: 0,
+                changeuuid: station.changeuuid.clone(),
+                stationuuid: station.stationuuid.clone(),
+                name: station.name.clone(),
+                url: station.url.clone(),
+                homepage: station.homepage.clone(),
+                favicon: station.favicon.clone(),
+                tags: station.tags.clone(),
+                countrycode: station.countrycode.clone(),
+                state: station.state.clone(),
+                language: station.language.clone(),
+                languagecodes: station.languagecodes.clone(),
+                votes: station.votes,
+                creation: station.lastchangetime_iso8601.unwrap_or(now),
+                geo_lat: station.geo_lat,
+                geo_long: station.geo_long,
+            });
+        }
+        for mut row in rows {
+            self.last_history_id += 1;
+            row.id = self.last_history_id;
+            self.history.push(row);
+        }
+    }
+
+    fn new_station(&mut self, stationuuid: String, changeuuid: String) -> DbStationItem {
+        self.last_station_id += 1;
+        DbStationItem {
+            id: self.last_station_id,
+            changeuuid,
+            stationuuid,
+            serveruuid: None,
+            name: String::new(),
+            url: String::new(),
+            url_resolved: String::new(),
+            homepage: String::new(),
+            favicon: String::new(),
+            tags: String::new(),
+            country: String::new(),
+            countrycode: String::new(),
+            iso_3166_2: None,
+            state: String::new(),
+            language: String::new(),
+            languagecodes: String::new(),
+            votes: 0,
+            lastchangetime: String::new(),
+            lastchangetime_iso8601: Some(self.now()),
+            codec: String::new(),
+            bitrate: 0,
+            hls: false,
+            lastcheckok: true,
+            lastchecktime: String::new(),
+            lastchecktime_iso8601: None,
+            lastcheckoktime: String::new(),
+            lastcheckoktime_iso8601: None,
+            lastlocalchecktime: String::new(),
+            lastlocalchecktime_iso8601: None,
+            clicktimestamp: String::new(),
+            clicktimestamp_iso8601: None,
+            clickcount: 0,
+            clicktrend: 0,
+            ssl_error: false,
+            geo_lat: None,
+            geo_long: None,
+            has_extended_info: Some(false),
+            score: None,
+            distance: None,
+            now_playing: None,
+        }
+    }
+
+    fn insert_new_station(&mut self, item: StationAddItem) {
+        let changeuuid = Uuid::new_v4().as_hyphenated().to_string();
+        let mut station = self.new_station(item.stationuuid, changeuuid);
+        station.name = item.name;
+        station.url = item.url;
+        station.homepage = item.homepage;
+        station.favicon = item.favicon;
+        station.country = item.country;
+        station.countrycode = item.countrycode;
+        station.state = item.state;
+        station.language = item.language;
+        station.languagecodes = item.languagecodes;
+        station.tags = item.tags;
+        station.geo_lat = item.geo_lat;
+        station.geo_long = item.geo_long;
+        let stationuuid = station.stationuuid.clone();
+        self.stations.push(station);
+        self.backup_stations_by_uuid(std::slice::from_ref(&stationuuid), "INITIAL");
+    }
+
+    /// Rows of the "StationCheck" view: the latest check of every station from every source
+    fn current_checks(&self) -> Vec<&CheckRow> {
+        let mut latest: HashMap<(String, String), &CheckRow> = HashMap::new();
+        for check in self.checks.iter() {
+            let key = (
+                check.item.station_uuid.to_lowercase(),
+                check.item.source.clone(),
+            );
+            match latest.get(&key) {
+                Some(existing) if existing.item.check_id > check.item.check_id => {}
+                _ => {
+                    latest.insert(key, check);
+                }
+            }
+        }
+        let mut list: Vec<&CheckRow> = latest.into_values().collect();
+        list.sort_by_key(|check| check.item.check_id);
+        list
+    }
+
+    fn cache_table(&mut self, table_name: &str) -> &mut BTreeMap<String, (u32, u32)> {
+        self.caches.entry(table_name.to_string()).or_default()
+    }
+
+    fn get_pull_server(&self, server: &str) -> Option<&PullServerRow> {
+        self.pull_servers.iter().find(|item| item.name == server)
+    }
+
+    fn get_pull_server_mut(&mut self, server: &str) -> &mut PullServerRow {
+        let pos = self.pull_servers.iter().position(|item| item.name == server);
+        match pos {
+            Some(pos) => &mut self.pull_servers[pos],
+            None => {
+                self.pull_servers.push(PullServerRow {
+                    name: server.to_string(),
+                    ..Default::default()
+                });
+                let last = self.pull_servers.len() - 1;
+                &mut self.pull_servers[last]
+            }
+        }
+    }
+
+    fn streaming_servers_output<'a, I>(
+        list: I,
+        order: &str,
+        reverse: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Vec<DbStreamingServer>
+    where
+        I: Iterator<Item = &'a StreamingServerRow>,
+    {
+        let mut list: Vec<&StreamingServerRow> = list.collect();
+        match order {
+            "url" => list.sort_by(|a, b| cmp_ci(&a.url, &b.url)),
+            "error" => list.sort_by(|a, b| a.error.cmp(&b.error)),
+            "createdat" => list.sort_by_key(|item| item.created_at),
+            "changedat" => list.sort_by_key(|item| item.updated_at),
+            "random" => list.sort_by_cached_key(|_| Uuid::new_v4()),
+            _ => list.sort_by_key(|item| item.id),
+        }
+        if reverse {
+            list.reverse();
+        }
+        page(list, offset, limit)
+            .into_iter()
+            .map(|item| {
+                DbStreamingServer::new(
+                    item.id,
+                    item.uuid.clone(),
+                    item.url.clone(),
+                    item.statusurl.clone(),
+                    item.status.clone(),
+                    item.error.clone(),
+                )
+            })
+            .collect()
+    }
+}
+
+impl DbConnection for MemoryConnection {
+    fn migrations_needed(&self) -> Result<bool, Box<dyn Error>> {
+        Ok(false)
+    }
+
+    fn do_migrations(
+        &self,
+        _ignore_migration_errors: bool,
+        _allow_database_downgrade: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn get_station_count_broken(&self) -> Result<u64, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.stations.iter().filter(|s| !s.lastcheckok).count() as u64)
+    }
+
+    fn get_station_count_working(&self) -> Result<u64, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.stations.iter().filter(|s| s.lastcheckok).count() as u64)
+    }
+
+    fn get_station_count_todo(&self, hours: u32) -> Result<u64, Box<dyn Error>> {
+        let data = self.lock()?;
+        let limit = data.seconds_ago(u64::from(hours) * 3600);
+        Ok(data
+            .stations
+            .iter()
+            .filter(|s| is_null_or_older(&s.lastlocalchecktime_iso8601, limit))
+            .count() as u64)
+    }
+
+    fn get_deletable_never_working(&self, seconds: u64) -> Result<u64, Box<dyn Error>> {
+        let data = self.lock()?;
+        let limit = data.seconds_ago(seconds);
+        Ok(data
+            .stations
+            .iter()
+            .filter(|s| never_working(s, limit))
+            .count() as u64)
+    }
+
+    fn get_deletable_were_working(&self, seconds: u64) -> Result<u64, Box<dyn Error>> {
+        let data = self.lock()?;
+        let limit = data.seconds_ago(seconds);
+        Ok(data
+            .stations
+            .iter()
+            .filter(|s| were_working(s, limit))
+            .count() as u64)
+    }
+
+    fn get_tag_count(&self) -> Result<u64, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.caches.get("TagCache").map(|c| c.len()).unwrap_or(0) as u64)
+    }
+
+    fn get_country_count(&self) -> Result<u64, Box<dyn Error>> {
+        let data = self.lock()?;
+        let countries: HashSet<String> = data
+            .stations
+            .iter()
+            .map(|s| s.country.to_lowercase())
+            .collect();
+        Ok(countries.len() as u64)
+    }
+
+    fn get_language_count(&self) -> Result<u64, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.caches.get("LanguageCache").map(|c| c.len()).unwrap_or(0) as u64)
+    }
+
+    fn get_click_count_last_hour(&self) -> Result<u64, Box<dyn Error>> {
+        let data = self.lock()?;
+        let limit = data.seconds_ago(60 * 60);
+        Ok(data.clicks.iter().filter(|c| c.clicktimestamp >= limit).count() as u64)
+    }
+
+    fn get_click_count_last_day(&self) -> Result<u64, Box<dyn Error>> {
+        let data = self.lock()?;
+        let limit = data.seconds_ago(24 * 60 * 60);
+        Ok(data.clicks.iter().filter(|c| c.clicktimestamp >= limit).count() as u64)
+    }
+
+    fn get_stations_to_check(
+        &mut self,
+        hours: u32,
+        itemcount: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let now = data.now();
+        let mut list: Vec<(Option<DateTime<Utc>>, &DbStationItem)> = data
+            .stations
+            .iter()
+            .map(|s| (data.check_due(s, hours), s))
+            .filter(|(due, _)| due.map(|due| due <= now).unwrap_or(true))
+            .collect();
+        list.sort_by(|(due_a, a), (due_b, b)| due_a.cmp(due_b).then(b.clickcount.cmp(&a.clickcount)));
+        let list: Vec<&DbStationItem> = list.into_iter().map(|(_, s)| s).collect();
+        Ok(output_stations(&data, page(list, 0, itemcount)))
+    }
+
+    fn set_next_check_times(&self, list: &[(String, u64)]) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let now = data.now();
+        for (stationuuid, seconds) in list {
+            data.next_checks.insert(stationuuid.clone(), now + Duration::seconds(*seconds as i64));
+        }
+        Ok(())
+    }
+
+    fn get_check_queue(&self, hours: u32) -> Result<(u64, u64), Box<dyn Error>> {
+        let data = self.lock()?;
+        let now = data.now();
+        let mut depth = 0;
+        let mut lag = 0;
+        for station in data.stations.iter() {
+            match data.check_due(station, hours) {
+                Some(due) if due <= now => {
+                    depth += 1;
+                    lag = lag.max((now - due).num_seconds() as u64);
+                }
+                Some(_) => {}
+                None => depth += 1,
+            }
+        }
+        Ok((depth, lag))
+    }
+
+    fn get_station_by_uuid(&self, id_str: &str) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut list: Vec<&DbStationItem> = data
+            .stations
+            .iter()
+            .filter(|s| eq_ci(&s.stationuuid, id_str))
+            .collect();
+        sort_stations(&mut list, "name", false);
+        Ok(output_stations(&data, list))
+    }
+
+    fn get_stations_by_uuid(&self, uuids: Vec<String>) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let list: Vec<&DbStationItem> = data
+            .stations
+            .iter()
+            .filter(|s| uuids.iter().any(|uuid| eq_ci(uuid, &s.stationuuid)))
+            .collect();
+        Ok(output_stations(&data, list))
+    }
+
+    fn get_stations_by_column_multiple(
+        &self,
+        column_name: &str,
+        search: Option<String>,
+        exact: bool,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        _fields: Option<&StationFields>,
+        cursor: Option<&StationCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut list = data.stations_by_column_multiple(column_name, &search, exact, hidebroken)?;
+        sort_stations(&mut list, order, reverse);
+        Ok(output_stations(&data, page(after_cursor(list, cursor), offset, limit)))
+    }
+
+    fn count_stations_by_column_multiple(&self, column_name: &str, search: Option<String>, exact: bool, hidebroken: bool) -> Result<u64, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.stations_by_column_multiple(column_name, &search, exact, hidebroken)?.len() as u64)
+    }
+
+    fn get_stations_by_all(
+        &self,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        _fields: Option<&StationFields>,
+        cursor: Option<&StationCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut list: Vec<&DbStationItem> = data
+            .stations
+            .iter()
+            .filter(|s| !hidebroken || s.lastcheckok)
+            .collect();
+        sort_stations(&mut list, order, reverse);
+        Ok(output_stations(&data, page(after_cursor(list, cursor), offset, limit)))
+    }
+
+    fn get_stations_uuid_order_by_changes(&mut self, min_change_count: u32) -> Result<Vec<String>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut counts: Vec<(String, u32)> = vec![];
+        for row in data.history.iter() {
+            match counts.iter_mut().find(|(uuid, _)| eq_ci(uuid, &row.stationuuid)) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((row.stationuuid.clone(), 1)),
+            }
+        }
+        counts.retain(|(_, count)| *count > min_change_count);
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        Ok(counts.into_iter().map(|(uuid, _)| uuid).collect())
+    }
+
+    fn get_stations_advanced(
+        &self,
+        name: Option<String>,
+        name_exact: bool,
+        country: Option<String>,
+        country_exact: bool,
+        countrycode: Option<String>,
+        state: Option<String>,
+        state_exact: bool,
+        language: Option<String>,
+        language_exact: bool,
+        tag: Option<String>,
+        tag_exact: bool,
+        tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,
+        bitrate_max: u32,
+        has_geo_info: Option<bool>,
+        has_extended_info: Option<bool>,
+        is_https: Option<bool>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+        geo_bbox: Option<GeoBoundingBox>,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        _fields: Option<&StationFields>,
+        cursor: Option<&StationCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut list = data.stations_advanced(name, name_exact, country, country_exact, countrycode, state, state_exact,
+            language, language_exact, tag, tag_exact, tag_list, codec, bitrate_min, bitrate_max, has_geo_info, has_extended_info, is_https,
+            geo_lat, geo_long, geo_distance, geo_bbox, hidebroken);
+        sort_stations(&mut list, order, reverse);
+        let (query_offset, query_limit) = query_range(geo_lat, geo_long, geo_distance, order, offset, limit);
+        let list = output_stations(&data, page(after_cursor(list, cursor), query_offset, query_limit));
+        Ok(finish_query(list, geo_lat, geo_long, geo_distance, order, reverse, offset, limit))
+    }
+
+    fn count_stations_advanced(
+        &self,
+        name: Option<String>,
+        name_exact: bool,
+        country: Option<String>,
+        country_exact: bool,
+        countrycode: Option<String>,
+        state: Option<String>,
+        state_exact: bool,
+        language: Option<String>,
+        language_exact: bool,
+        tag: Option<String>,
+        tag_exact: bool,
+        tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,
+        bitrate_max: u32,
+        has_geo_info: Option<bool>,
+        has_extended_info: Option<bool>,
+        is_https: Option<bool>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+        geo_bbox: Option<GeoBoundingBox>,
+        hidebroken: bool,
+    ) -> Result<u64, Box<dyn Error>> {
+        let data = self.lock()?;
+        let list = data.stations_advanced(name, name_exact, country, country_exact, countrycode, state, state_exact,
+            language, language_exact, tag, tag_exact, tag_list, codec, bitrate_min, bitrate_max, has_geo_info, has_extended_info, is_https,
+            geo_lat, geo_long, geo_distance, geo_bbox, hidebroken);
+        Ok(count_stations(list, geo_lat, geo_long, geo_distance))
+    }
+
+    fn get_stations_search(
+        &self,
+        query: &str,
+        name: Option<String>,
+        name_exact: bool,
+        country: Option<String>,
+        country_exact: bool,
+        countrycode: Option<String>,
+        state: Option<String>,
+        state_exact: bool,
+        language: Option<String>,
+        language_exact: bool,
+        tag: Option<String>,
+        tag_exact: bool,
+        tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,
+        bitrate_max: u32,
+        has_geo_info: Option<bool>,
+        has_extended_info: Option<bool>,
+        is_https: Option<bool>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+        geo_bbox: Option<GeoBoundingBox>,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        cursor: Option<&StationCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        // without an index every station has to be scored, paging has to be done afterwards
+        let list = self.get_stations_advanced(name, name_exact, country, country_exact, countrycode, state, state_exact, language, language_exact,
+            tag, tag_exact, tag_list, codec, bitrate_min, bitrate_max, has_geo_info, has_extended_info, is_https,
+            geo_lat, geo_long, geo_distance, geo_bbox, order, reverse, hidebroken, None, cursor, 0, u32::MAX)?;
+        Ok(rank_stations(list, query, order, reverse)
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    fn count_stations_search(
+        &self,
+        query: &str,
+        name: Option<String>,
+        name_exact: bool,
+        country: Option<String>,
+        country_exact: bool,
+        countrycode: Option<String>,
+        state: Option<String>,
+        state_exact: bool,
+        language: Option<String>,
+        language_exact: bool,
+        tag: Option<String>,
+        tag_exact: bool,
+        tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,
+        bitrate_max: u32,
+        has_geo_info: Option<bool>,
+        has_extended_info: Option<bool>,
+        is_https: Option<bool>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+        geo_bbox: Option<GeoBoundingBox>,
+        hidebroken: bool,
+    ) -> Result<u64, Box<dyn Error>> {
+        // only matching is needed for the count, the stations are neither scored into copies nor sorted
+        let data = self.lock()?;
+        let list = data.stations_advanced(name, name_exact, country, country_exact, countrycode, state, state_exact,
+            language, language_exact, tag, tag_exact, tag_list, codec, bitrate_min, bitrate_max, has_geo_info, has_extended_info, is_https,
+            geo_lat, geo_long, geo_distance, geo_bbox, hidebroken);
+        let list = list.into_iter().filter(|station| station_relevance(station, query) > 0.0).collect();
+        Ok(count_stations(list, geo_lat, geo_long, geo_distance))
+    }
+
+    fn get_stations_broken(&self, offset: u32, limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut list: Vec<&DbStationItem> = data.stations.iter().filter(|s| !s.lastcheckok).collect();
+        sort_stations(&mut list, "random", false);
+        Ok(output_stations(&data, page(list, offset, limit)))
+    }
+
+    fn get_stations_topvote(&self, hidebroken: bool, offset: u32, limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        self.get_stations_by_all("votes", true, hidebroken, None, None, offset, limit)
+    }
+
+    fn get_stations_topclick(&self, hidebroken: bool, offset: u32, limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        self.get_stations_by_all("clickcount", true, hidebroken, None, None, offset, limit)
+    }
+
+    fn get_stations_lastclick(&self, hidebroken: bool, offset: u32, limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        self.get_stations_by_all("clicktimestamp", true, hidebroken, None, None, offset, limit)
+    }
+
+    fn get_stations_lastchange(&self, hidebroken: bool, offset: u32, limit: u32) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        self.get_stations_by_all("changetimestamp", true, hidebroken, None, None, offset, limit)
+    }
+
+    fn get_stations_by_column(
+        &self,
+        column_name: &str,
+        search: String,
+        exact: bool,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        _fields: Option<&StationFields>,
+        cursor: Option<&StationCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut list = data.stations_by_column(column_name, &search, exact, hidebroken)?;
+        sort_stations(&mut list, order, reverse);
+        Ok(output_stations(&data, page(after_cursor(list, cursor), offset, limit)))
+    }
+
+    fn count_stations_by_column(&self, column_name: &str, search: String, exact: bool, hidebroken: bool) -> Result<u64, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.stations_by_column(column_name, &search, exact, hidebroken)?.len() as u64)
+    }
+
+    fn get_stations_by_server_uuids(
+        &self,
+        uuids: Vec<String>,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut list: Vec<&DbStationItem> = data
+            .stations
+            .iter()
+            .filter(|s| match s.serveruuid {
+                Some(ref serveruuid) => uuids.iter().any(|uuid| eq_ci(uuid, serveruuid)),
+                None => false,
+            })
+            .filter(|s| !hidebroken || s.lastcheckok)
+            .collect();
+        sort_stations(&mut list, order, reverse);
+        Ok(output_stations(&data, page(list, offset, limit)))
+    }
+
+    fn get_stations_with_empty_icon(&mut self) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data
+            .stations
+            .iter()
+            .filter(|s| s.favicon.is_empty())
+            .map(|s| (s.stationuuid.clone(), s.homepage.clone()))
+            .collect())
+    }
+
+    fn get_stations_with_non_empty_icon(&mut self) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data
+            .stations
+            .iter()
+            .filter(|s| !s.favicon.is_empty())
+            .map(|s| (s.stationuuid.clone(), s.homepage.clone()))
+            .collect())
+    }
+
+    fn get_changes(
+        &self,
+        stationuuid: Option<String>,
+        changeuuid: Option<String>,
+        limit: u32,
+    ) -> Result<Vec<StationHistoryItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut list: Vec<&HistoryRow> = data.history.iter().collect();
+        if let Some(changeuuid) = changeuuid {
+            // only deliver changes that are at least a minute old, so that
+            // pulling mirrors do not miss changes of running transactions
+            let start_id = data
+                .history
+                .iter()
+                .find(|row| eq_ci(&row.changeuuid, &changeuuid))
+                .map(|row| row.id)
+                .unwrap_or(0);
+            let stable_time = data.seconds_ago(60);
+            let end_id = data
+                .history
+                .iter()
+                .filter(|row| row.creation <= stable_time)
+                .map(|row| row.id)
+                .max();
+            list.retain(|row| {
+                row.id >= start_id
+                    && end_id.map(|end_id| row.id <= end_id).unwrap_or(false)
+                    && !eq_ci(&row.changeuuid, &changeuuid)
+            });
+        }
+        if let Some(stationuuid) = stationuuid {
+            list.retain(|row| eq_ci(&row.stationuuid, &stationuuid));
+        }
+        list.sort_by_key(|row| row.id);
+        Ok(page(list, 0, limit).into_iter().map(output_history).collect())
+    }
+
+    fn get_changes_for_stations(&self, station_uuids: Vec<String>) -> Result<Vec<StationHistoryItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut list: Vec<&HistoryRow> = data
+            .history
+            .iter()
+            .filter(|row| station_uuids.iter().any(|uuid| eq_ci(uuid, &row.stationuuid)))
+            .collect();
+        list.sort_by_key(|row| row.creation);
+        Ok(list.into_iter().map(output_history).collect())
+    }
+
+    fn get_checks(
+        &self,
+        stationuuid: Option<String>,
+        checkuuid: Option<String>,
+        seconds: u32,
+        include_history: bool,
+        limit: u32,
+    ) -> Result<Vec<StationCheckItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut list: Vec<&CheckRow> = if include_history {
+            data.checks.iter().collect()
+        } else {
+            data.current_checks()
+        };
+        if let Some(stationuuid) = stationuuid {
+            list.retain(|check| eq_ci(&check.item.station_uuid, &stationuuid));
+        }
+        if let Some(checkuuid) = checkuuid {
+            let start_id = data
+                .checks
+                .iter()
+                .find(|check| eq_ci(&check.item.check_uuid, &checkuuid))
+                .map(|check| check.item.check_id)
+                .unwrap_or(0);
+            let stable_time = data.seconds_ago(60);
+            let end_id = data
+                .checks
+                .iter()
+                .filter(|check| check.inserttime <= stable_time)
+                .map(|check| check.item.check_id)
+                .max();
+            list.retain(|check| {
+                check.item.check_id >= start_id
+                    && end_id.map(|end_id| check.item.check_id <= end_id).unwrap_or(false)
+                    && !eq_ci(&check.item.check_uuid, &checkuuid)
+            });
+        }
+        if seconds > 0 {
+            let time = data.seconds_ago(seconds.into());
+            list.retain(|check| check.item.check_time_iso8601.map(|t| t > time).unwrap_or(false));
+        }
+        list.sort_by_key(|check| check.item.check_id);
+        Ok(page(list, 0, limit)
+            .into_iter()
+            .map(|check| check.item.clone())
+            .collect())
+    }
+
+    fn get_check_history(&self, stationuuids: &[String], seconds: u32, limit: u32) -> Result<Vec<StationCheckItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let time = data.seconds_ago(seconds.into());
+        let mut list: Vec<&CheckRow> = data
+            .checks
+            .iter()
+            .filter(|check| stationuuids.iter().any(|uuid| eq_ci(&check.item.station_uuid, uuid)))
+            .filter(|check| check.item.check_time_iso8601.map(|t| t > time).unwrap_or(false))
+            .collect();
+        list.sort_by_key(|check| std::cmp::Reverse(check.item.check_id));
+        list.truncate(limit as usize);
+        Ok(list.into_iter().rev().map(|check| check.item.clone()).collect())
+    }
+
+    fn get_clicks(
+        &self,
+        stationuuid: Option<String>,
+        clickuuid: Option<String>,
+        seconds: u32,
+        limit: u32,
+    ) -> Result<Vec<StationClickItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut list: Vec<&ClickRow> = data.clicks.iter().collect();
+        if let Some(stationuuid) = stationuuid {
+            list.retain(|click| eq_ci(&click.stationuuid, &stationuuid));
+        }
+        if let Some(clickuuid) = clickuuid {
+            let start_id = data
+                .clicks
+                .iter()
+                .find(|click| eq_ci(&click.clickuuid, &clickuuid))
+                .map(|click| click.id)
+                .unwrap_or(0);
+            let stable_time = data.seconds_ago(60);
+            let end_id = data
+                .clicks
+                .iter()
+                .filter(|click| click.inserttime <= stable_time)
+                .map(|click| click.id)
+                .max();
+            list.retain(|click| {
+                click.id >= start_id
+                    && end_id.map(|end_id| click.id <= end_id).unwrap_or(false)
+                    && !eq_ci(&click.clickuuid, &clickuuid)
+            });
+        }
+        if seconds > 0 {
+            let time = data.seconds_ago(seconds.into());
+            list.retain(|click| click.clicktimestamp > time);
+        }
+        list.sort_by_key(|click| click.id);
+        Ok(page(list, 0, limit)
+            .into_iter()
+            .map(|click| StationClickItem {
+                id: click.id,
+                stationuuid: click.stationuuid.clone(),
+                ip: click.ip.clone().unwrap_or_default(),
+                clickuuid: click.clickuuid.clone(),
+                clicktimestamp_iso8601: Some(click.clicktimestamp),
+                clicktimestamp: format_time(&Some(click.clicktimestamp)),
+            })
+            .collect())
+    }
+
+    fn get_last_changeuuid(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.history.iter().max_by_key(|row| row.id).map(|row| row.changeuuid.clone()))
+    }
+
+    fn get_last_checkuuid(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.checks.iter().max_by_key(|check| check.item.check_id).map(|check| check.item.check_uuid.clone()))
+    }
+
+    fn get_last_clickuuid(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.clicks.iter().max_by_key(|click| click.id).map(|click| click.clickuuid.clone()))
+    }
+
+    fn add_station_opt(
+        &self,
+        name: Option<String>,
+        url: Option<String>,
+        homepage: Option<String>,
+        favicon: Option<String>,
+        countrycode: Option<String>,
+        state: Option<String>,
+        language: Option<String>,
+        languagecodes: Option<String>,
+        tags: Option<String>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+    ) -> Result<String, Box<dyn Error>> {
+        let station = check_new_station(name, url, homepage, favicon, countrycode, state, language, languagecodes, tags, geo_lat, geo_long)?;
+        let stationuuid = station.stationuuid.clone();
+        self.lock()?.insert_new_station(station);
+        Ok(stationuuid)
+    }
+
+    fn add_pending_station_opt(
+        &self,
+        name: Option<String>,
+        url: Option<String>,
+        homepage: Option<String>,
+        favicon: Option<String>,
+        countrycode: Option<String>,
+        state: Option<String>,
+        language: Option<String>,
+        languagecodes: Option<String>,
+        tags: Option<String>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+    ) -> Result<String, Box<dyn Error>> {
+        let mut station = check_new_station(name, url, homepage, favicon, countrycode, state, language, languagecodes, tags, geo_lat, geo_long)?;
+        let stationuuid = station.stationuuid.clone();
+        let mut data = self.lock()?;
+        station.creation_iso8601 = Some(data.now());
+        data.pending.push(station);
+        Ok(stationuuid)
+    }
+
+    fn get_pending_stations(&self, offset: u32, limit: u32) -> Result<Vec<StationAddItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.pending.iter().skip(offset as usize).take(limit as usize).cloned().collect())
+    }
+
+    fn get_pending_stations_by_url(&self, urls: &[String]) -> Result<Vec<StationAddItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.pending.iter().filter(|station| urls.contains(&station.url)).cloned().collect())
+    }
+
+    fn approve_pending_station(&self, stationuuid: &str) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let index = data
+            .pending
+            .iter()
+            .position(|station| station.stationuuid == stationuuid)
+            .ok_or(DbError::ModerationError(String::from("station is not in the review queue")))?;
+        let station = data.pending.remove(index);
+        data.insert_new_station(station);
+        Ok(())
+    }
+
+    fn reject_pending_station(&self, stationuuid: &str) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let count = data.pending.len();
+        data.pending.retain(|station| station.stationuuid != stationuuid);
+        if data.pending.len() == count {
+            return Err(Box::new(DbError::ModerationError(String::from("station is not in the review queue"))));
+        }
+        Ok(())
+    }
+
+    fn edit_station_opt(
+        &self,
+        stationuuid: &str,
+        name: Option<String>,
+        url: Option<String>,
+        homepage: Option<String>,
+        favicon: Option<String>,
+        countrycode: Option<String>,
+        state: Option<String>,
+        language: Option<String>,
+        languagecodes: Option<String>,
+        tags: Option<String>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        source: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let now = data.now();
+        let changeuuid = Uuid::new_v4().as_hyphenated().to_string();
+        let station = data
+            .find_station_mut(stationuuid)
+            .ok_or(DbError::EditStationError(String::from("station not found")))?;
+        let mut edited = station.clone();
+        edit_station_fields(&mut edited, name, url, homepage, favicon, countrycode, state, language, languagecodes, tags, geo_lat, geo_long)?;
+        edited.lastchangetime_iso8601 = Some(now);
+        edited.changeuuid = changeuuid.clone();
+        *station = edited;
+        let stationuuid = station.stationuuid.clone();
+        data.backup_stations_by_uuid(std::slice::from_ref(&stationuuid), source);
+
+        Ok(changeuuid)
+    }
+
+    fn get_pull_server_lastid(&self, server: &str) -> Result<Option<String>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.get_pull_server(server).and_then(|s| s.lastid.clone()))
+    }
+
+    fn set_pull_server_lastid(&self, server: &str, lastid: &str) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        data.get_pull_server_mut(server).lastid = Some(lastid.to_string());
+        Ok(())
+    }
+
+    fn get_pull_server_lastcheckid(&self, server: &str) -> Result<Option<String>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.get_pull_server(server).and_then(|s| s.lastcheckid.clone()))
+    }
+
+    fn set_pull_server_lastcheckid(&self, server: &str, lastcheckid: &str) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        data.get_pull_server_mut(server).lastcheckid = Some(lastcheckid.to_string());
+        Ok(())
+    }
+
+    fn get_pull_server_lastclickid(&self, server: &str) -> Result<Option<String>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.get_pull_server(server).and_then(|s| s.lastclickuuid.clone()))
+    }
+
+    fn set_pull_server_lastclickid(&self, server: &str, lastclickuuid: &str) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        data.get_pull_server_mut(server).lastclickuuid = Some(lastclickuuid.to_string());
+        Ok(())
+    }
+
+    fn insert_station_by_change(
+        &self,
+        list_station_changes: &[StationChangeItemNew],
+        source: &str,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let mut list_ids: Vec<String> = vec![];
+        for change in list_station_changes {
+            // ignore changes that are already known
+            if data
+                .history
+                .iter()
+                .any(|row| eq_ci(&row.changeuuid, &change.changeuuid))
+            {
+                continue;
+            }
+            let now = data.now();
+            let station = match data.find_station_mut(&change.stationuuid) {
+                Some(station) => station,
+                None => {
+                    let station =
+                        data.new_station(change.stationuuid.clone(), change.changeuuid.clone());
+                    data.stations.push(station);
+                    let last = data.stations.len() - 1;
+                    &mut data.stations[last]
+                }
+            };
+            station.name = change.name.clone();
+            station.url = change.url.clone();
+            station.homepage = change.homepage.clone();
+            station.favicon = change.favicon.clone();
+            station.country = change.country.clone();
+            station.countrycode = change.countrycode.clone();
+            station.state = change.state.clone();
+            station.language = fix_multi_field(&change.language);
+            station.tags = fix_multi_field(&change.tags);
+            station.changeuuid = change.changeuuid.clone();
+            station.geo_lat = change.geo_lat;
+            station.geo_long = change.geo_long;
+            station.url_resolved = String::new();
+            station.lastchangetime_iso8601 = Some(now);
+            if !list_ids.contains(&change.stationuuid) {
+                list_ids.push(change.stationuuid.clone());
+            }
+        }
+        data.backup_stations_by_uuid(&list_ids, source);
+        Ok(list_ids)
+    }
+
+    fn insert_checks(
+        &self,
+        list: Vec<StationCheckItemNew>,
+    ) -> Result<
+        (
+            Vec<StationCheckItemNew>,
+            Vec<StationCheckItemNew>,
+            Vec<StationCheckItemNew>,
+        ),
+        Box<dyn Error>,
+    > {
+        let mut data = self.lock()?;
+        let mut inserted = vec![];
+        let mut ignored_checks_no_station = vec![];
+        let mut existing_checks = vec![];
+        for item in list {
+            // ignore checks, where there is no station in the database
+            if !data.station_exists(&item.station_uuid) {
+                ignored_checks_no_station.push(item);
+                continue;
+            }
+            // ignore checks that are already in the database
+            if let Some(ref checkuuid) = item.checkuuid {
+                if data
+                    .checks
+                    .iter()
+                    .any(|check| eq_ci(&check.item.check_uuid, checkuuid))
+                {
+                    existing_checks.push(item);
+                    continue;
+                }
+            }
+            let now = data.now();
+            let checktime = item
+                .timestamp
+                .as_ref()
+                .and_then(|timestamp| parse_time(timestamp))
+                .unwrap_or(now);
+            data.last_check_id += 1;
+            let check = StationCheckItem {
+                check_id: data.last_check_id,
+                check_time_iso8601: Some(checktime),
+                check_time: format_time(&Some(checktime)),
+                check_uuid: item
+                    .checkuuid
+                    .clone()
+                    .unwrap_or_else(|| Uuid::new_v4().as_hyphenated().to_string()),
+                station_uuid: item.station_uuid.clone(),
+                source: item.source.clone(),
+                codec: item.codec.clone(),
+                bitrate: item.bitrate,
+                hls: item.hls,
+                check_ok: item.check_ok,
+                url: item.url.clone(),
+                metainfo_overrides_database: item.metainfo_overrides_database,
+                public: item.public,
+                name: item.name.clone(),
+                description: item.description.clone(),
+                tags: item.tags.clone(),
+                countrycode: item.countrycode.clone(),
+                homepage: item.homepage.clone(),
+                favicon: item.favicon.clone(),
+                loadbalancer: item.loadbalancer.clone(),
+                do_not_index: item.do_not_index,
+                countrysubdivisioncode: item.countrysubdivisioncode.clone(),
+                server_software: item.server_software.clone(),
+                sampling: item.sampling,
+                timing_ms: item.timing_ms,
+                languagecodes: item.languagecodes.clone(),
+                // not stored by the mysql implementation either
+                ssl_error: false,
+                geo_lat: item.geo_lat,
+                geo_long: item.geo_long,
+                audio_bitrate: item.audio_bitrate,
+                audio_sampling: item.audio_sampling,
+                audio_liveness: item.audio_liveness.clone(),
+            };
+            data.checks.push(CheckRow {
+                item: check,
+                inserttime: now,
+            });
+            inserted.push(item);
+        }
+        Ok((existing_checks, ignored_checks_no_station, inserted))
+    }
+
+    fn update_station_with_check_data(
+        &self,
+        list: &Vec<StationCheckItemNew>,
+        local: bool,
+        consensus: &CheckConsensus,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+
+        // consensus over the current check of every source, most recent first
+        let mut majority_vote: HashMap<String, bool> = HashMap::new();
+        {
+            let now = data.now();
+            let mut results: HashMap<String, Vec<bool>> = HashMap::new();
+            for check in data.current_checks().iter().rev() {
+                let age = check.item.check_time_iso8601.map(|time| (now - time).num_seconds()).unwrap_or(i64::MAX);
+                let results = results.entry(check.item.station_uuid.to_lowercase()).or_default();
+                if consensus.is_recent(results.len(), age) {
+                    results.push(check.item.check_ok);
+                }
+            }
+            for (stationuuid, results) in results {
+                majority_vote.insert(stationuuid, consensus.is_working(&results));
+            }
+        }
+
+        let now = data.now();
+        for item in list {
+            let vote = *majority_vote
+                .get(&item.station_uuid.to_lowercase())
+                .unwrap_or(&true);
+
+            let do_not_index = item.metainfo_overrides_database && item.do_not_index.unwrap_or(false);
+            if do_not_index {
+                data.remove_stations(|station| eq_ci(&station.stationuuid, &item.station_uuid));
+                continue;
+            }
+            if let Some(station) = data.find_station_mut(&item.station_uuid) {
+                if local {
+                    station.lastlocalchecktime_iso8601 = Some(now);
+                }
+                station.lastchecktime_iso8601 = Some(now);
+                station.lastcheckok = vote;
+                if item.check_ok {
+                    station.has_extended_info = Some(item.metainfo_overrides_database);
+                    station.lastcheckoktime_iso8601 = Some(now);
+                    station.codec = item.codec.clone();
+                    station.bitrate = item.bitrate;
+                    station.hls = item.hls;
+                    station.url_resolved = item.url.clone();
+                    station.ssl_error = item.ssl_error;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn update_station_auto(&mut self, station: &DbStationItem, reason: &str) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let now = data.now();
+        if let Some(existing) = data.find_station_mut(&station.stationuuid) {
+            existing.name = station.name.clone();
+            existing.homepage = station.homepage.clone();
+            existing.url = station.url.clone();
+            existing.favicon = station.favicon.clone();
+            existing.language = station.language.clone();
+            existing.tags = station.tags.clone();
+            existing.languagecodes = station.languagecodes.clone();
+            existing.countrycode = station.countrycode.clone();
+            existing.iso_3166_2 = station.iso_3166_2.clone();
+            existing.geo_lat = station.geo_lat;
+            existing.geo_long = station.geo_long;
+            existing.lastchangetime_iso8601 = Some(now);
+            existing.changeuuid = Uuid::new_v4().as_hyphenated().to_string();
+        }
+        data.backup_stations_by_uuid(std::slice::from_ref(&station.stationuuid), reason);
+        Ok(())
+    }
+
+    fn insert_clicks(&self, list: &Vec<StationClickItemNew>) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let mut ignored_clicks = 0;
+        for item in list {
+            if !data.station_exists(&item.stationuuid) {
+                ignored_clicks += 1;
+                continue;
+            }
+            if data
+                .clicks
+                .iter()
+                .any(|click| eq_ci(&click.clickuuid, &item.clickuuid))
+            {
+                continue;
+            }
+            let now = data.now();
+            data.last_click_id += 1;
+            let id = data.last_click_id;
+            data.clicks.push(ClickRow {
+                id,
+                clickuuid: item.clickuuid.clone(),
+                stationuuid: item.stationuuid.clone(),
+                ip: None,
+                clicktimestamp: parse_time(&item.clicktimestamp).unwrap_or(now),
+                inserttime: now,
+            });
+        }
+        trace!("Ignored clicks(no stations) for insert: {}", ignored_clicks);
+        Ok(())
+    }
+
+    fn delete_stations(&self, stationuuids: &[String]) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        data.remove_stations(|station| {
+            stationuuids
+                .iter()
+                .any(|uuid| eq_ci(uuid, &station.stationuuid))
+        });
+        Ok(())
+    }
+
+    fn delete_old_checks(&mut self, seconds: u64) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let limit = data.seconds_ago(seconds);
+        data.remove_checks(|check| is_older(&check.item.check_time_iso8601, limit));
+        Ok(())
+    }
+
+    fn delete_old_clicks(&mut self, seconds: u64) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let limit = data.seconds_ago(seconds);
+        data.clicks.retain(|click| click.clicktimestamp >= limit);
+        Ok(())
+    }
+
+    fn delete_never_working(&mut self, seconds: u64) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let limit = data.seconds_ago(seconds);
+        data.remove_stations(|station| never_working(station, limit));
+        Ok(())
+    }
+
+    fn delete_were_working(&mut self, seconds: u64) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let limit = data.seconds_ago(seconds);
+        data.remove_stations(|station| were_working(station, limit));
+        Ok(())
+    }
+
+    fn get_stations_deletable_were_working(&self, seconds: u64) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let limit = data.seconds_ago(seconds);
+        Ok(data.stations.iter().filter(|station| were_working(station, limit)).cloned().collect())
+    }
+
+    fn delete_removed_from_history(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let existing: HashSet<String> = data
+            .stations
+            .iter()
+            .map(|station| station.stationuuid.to_lowercase())
+            .collect();
+        data.history
+            .retain(|row| existing.contains(&row.stationuuid.to_lowercase()));
+        Ok(())
+    }
+
+    fn delete_unused_streaming_servers(&mut self, seconds: u64) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let limit = data.seconds_ago(seconds);
+        let used: HashSet<String> = data
+            .stations
+            .iter()
+            .filter_map(|station| station.serveruuid.clone())
+            .collect();
+        data.streaming_servers
+            .retain(|server| used.contains(&server.uuid) || server.created_at >= limit);
+        Ok(())
+    }
+
+    fn delete_change_by_uuid(&mut self, changeuuids: &[String]) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        data.history
+            .retain(|row| !changeuuids.iter().any(|uuid| eq_ci(uuid, &row.changeuuid)));
+        Ok(())
+    }
+
+    fn delete_stationhistory_byid_more_than(&self, stationuuid: String, itemcount: usize) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let mut items: Vec<(DateTime<Utc>, i32)> = data
+            .history
+            .iter()
+            .filter(|row| eq_ci(&row.stationuuid, &stationuuid))
+            .map(|row| (row.creation, row.id))
+            .collect();
+        items.sort_by_key(|(creation, _)| std::cmp::Reverse(*creation));
+        if items.len() > itemcount {
+            let items_delete: HashSet<i32> = items[itemcount..].iter().map(|(_, id)| *id).collect();
+            data.history.retain(|row| !items_delete.contains(&row.id));
+        }
+        Ok(())
+    }
+
+    fn delete_stationhistory_more_than(&self, _itemcount: u32) -> Result<(), Box<dyn Error>> {
+        // the mysql implementation does only select the rows, it does not delete anything yet
+        Ok(())
+    }
+
+    fn delete_old_station_check_steps(&mut self, seconds: u32) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let limit = data.seconds_ago(seconds.into());
+        data.check_steps.retain(|step| step.inserttime >= limit);
+        Ok(())
+    }
+
+    fn resethistory(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        data.history.clear();
+        let all: Vec<String> = data
+            .stations
+            .iter()
+            .map(|station| station.stationuuid.clone())
+            .collect();
+        data.backup_stations_by_uuid(&all, "INITIAL");
+        Ok(())
+    }
+
+    fn remove_unused_ip_infos_from_stationclicks(&mut self, seconds: u64) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let limit = data.seconds_ago(seconds);
+        for click in data.clicks.iter_mut() {
+            if click.inserttime < limit {
+                click.ip = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn update_stations_clickcount(&self) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let day_1 = data.seconds_ago(24 * 60 * 60);
+        let day_2 = data.seconds_ago(2 * 24 * 60 * 60);
+        let now = data.now();
+        let mut counts: HashMap<String, ClickCounter> = HashMap::new();
+        for click in data.clicks.iter() {
+            let counter = counts
+                .entry(click.stationuuid.to_lowercase())
+                .or_insert((0, 0, 0, None));
+            counter.0 += 1;
+            if click.clicktimestamp > day_1 && click.clicktimestamp <= now {
+                counter.1 += 1;
+            } else if click.clicktimestamp > day_2 && click.clicktimestamp <= day_1 {
+                counter.2 += 1;
+            }
+            if counter.3.map(|t| t < click.clicktimestamp).unwrap_or(true) {
+                counter.3 = Some(click.clicktimestamp);
+            }
+        }
+        for station in data.stations.iter_mut() {
+            let (count, last_day, day_before, last_click) = counts
+                .get(&station.stationuuid.to_lowercase())
+                .cloned()
+                .unwrap_or((0, 0, 0, None));
+            station.clickcount = count;
+            station.clicktrend = last_day - day_before;
+            station.clicktimestamp_iso8601 = last_click;
+        }
+        Ok(())
+    }
+
+    fn calc_country_field(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        for row in data.history.iter_mut() {
+            row.countrycode = row.countrycode.to_uppercase();
+        }
+        for station in data.stations.iter_mut() {
+            station.countrycode = station.countrycode.to_uppercase();
+            if !station.countrycode.is_empty() {
+                station.country = Country::from_alpha2(&station.countrycode)
+                    .map(|d| d.long_name)
+                    .unwrap_or("")
+                    .to_string();
+            }
+        }
+        Ok(())
+    }
+
+    fn get_duplicated_stations(&self, column_key: &str, max_duplicates: usize) -> Result<Vec<String>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut list = vec![];
+        if max_duplicates > 0 {
+            let mut groups: Vec<(String, Vec<&DbStationItem>)> = vec![];
+            for station in data.stations.iter() {
+                if let Some(value) = station_column(station, column_key)? {
+                    match groups.iter_mut().find(|(key, _)| eq_ci(key, value)) {
+                        Some((_, stations)) => stations.push(station),
+                        None => groups.push((value.to_string(), vec![station])),
+                    }
+                }
+            }
+            for (_, mut stations) in groups {
+                if stations.len() > max_duplicates {
+                    stations.sort_by(|a, b| {
+                        b.clickcount
+                            .cmp(&a.clickcount)
+                            .then(b.votes.cmp(&a.votes))
+                            .then(a.stationuuid.cmp(&b.stationuuid))
+                    });
+                    list.extend(
+                        stations
+                            .iter()
+                            .skip(max_duplicates)
+                            .take(1000)
+                            .map(|station| station.stationuuid.clone()),
+                    );
+                }
+            }
+        }
+        Ok(list)
+    }
+
+    fn get_extra(
+        &self,
+        table_name: &str,
+        _column_name: &str,
+        search: Option<String>,
+        order: String,
+        reverse: bool,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<ExtraInfo>, Box<dyn Error>> {
+        let order = filter_order_1_n(&order)?;
+        let data = self.lock()?;
+        let mut list: Vec<(String, u32)> = vec![];
+        if let Some(cache) = data.caches.get(table_name) {
+            for (name, (count, count_working)) in cache.iter() {
+                let count = if hidebroken { *count_working } else { *count };
+                if name.is_empty() || count == 0 {
+                    continue;
+                }
+                if let Some(ref search) = search {
+                    if !like_ci(name, search) {
+                        continue;
+                    }
+                }
+                list.push((name.clone(), count));
+            }
+        }
+        sort_1_n(&mut list, order, reverse);
+        Ok(page(list, offset, limit)
+            .into_iter()
+            .map(|(name, count)| ExtraInfo::new(name, count))
+            .collect())
+    }
+
+    fn get_1_n(
+        &self,
+        column: &str,
+        search: Option<String>,
+        order: String,
+        reverse: bool,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<ExtraInfo>, Box<dyn Error>> {
+        let order = filter_order_1_n(&order)?;
+        let data = self.lock()?;
+        let mut values = vec![];
+        for station in data.stations.iter() {
+            if hidebroken && !station.lastcheckok {
+                continue;
+            }
+            if let Some(value) = station_column(station, column)? {
+                if value.is_empty() {
+                    continue;
+                }
+                if let Some(ref search) = search {
+                    if !like_ci(value, search) {
+                        continue;
+                    }
+                }
+                values.push(value);
+            }
+        }
+        let mut list = group_count(values);
+        sort_1_n(&mut list, order, reverse);
+        Ok(page(list, offset, limit)
+            .into_iter()
+            .map(|(name, count)| ExtraInfo::new(name, count))
+            .collect())
+    }
+
+    fn get_countries(
+        &self,
+        search: Option<String>,
+        order: String,
+        reverse: bool,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DBCountry>, Box<dyn Error>> {
+        Ok(self
+            .get_1_n("CountryCode", search, order, reverse, hidebroken, offset, limit)?
+            .into_iter()
+            .map(|item| DBCountry::new(item.name, item.stationcount))
+            .collect())
+    }
+
+    fn get_states(
+        &self,
+        country: Option<String>,
+        search: Option<String>,
+        order: String,
+        reverse: bool,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<State>, Box<dyn Error>> {
+        let order = filter_order_1_n(&order)?;
+        let data = self.lock()?;
+        let mut list: Vec<(String, String, u32)> = vec![];
+        for station in data.stations.iter() {
+            if station.state.is_empty() || (hidebroken && !station.lastcheckok) {
+                continue;
+            }
+            if let Some(ref country) = country {
+                if !eq_ci(&station.country, country) {
+                    continue;
+                }
+            }
+            if let Some(ref search) = search {
+                if !like_ci(&station.state, search) {
+                    continue;
+                }
+            }
+            match list
+                .iter_mut()
+                .find(|(name, country, _)| eq_ci(name, &station.state) && eq_ci(country, &station.country))
+            {
+                Some((_, _, count)) => *count += 1,
+                None => list.push((station.state.clone(), station.country.clone(), 1)),
+            }
+        }
+        match order {
+            "stationcount" => list.sort_by_key(|(_, _, count)| *count),
+            _ => list.sort_by(|a, b| cmp_ci(&a.0, &b.0)),
+        }
+        if reverse {
+            list.reverse();
+        }
+        Ok(page(list, offset, limit)
+            .into_iter()
+            .map(|(name, country, count)| State::new(name, country, count))
+            .collect())
+    }
+
+    fn get_stations_multi_items(&self, column_name: &str) -> Result<HashMap<String, (u32, u32)>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let mut items = HashMap::new();
+        for station in data.stations.iter() {
+            let value = station_column(station, column_name)?.unwrap_or_default();
+            for single in value.split(',') {
+                let single_trimmed = single.trim().to_lowercase();
+                if !single_trimmed.is_empty() {
+                    let counter = items.entry(single_trimmed).or_insert((0, 0));
+                    counter.0 += 1;
+                    if station.lastcheckok {
+                        counter.1 += 1;
+                    }
+                }
+            }
+        }
+        Ok(items)
+    }
+
+    fn get_cached_items(&self, table_name: &str, _column_name: &str) -> Result<HashMap<String, (u32, u32)>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data
+            .caches
+            .get(table_name)
+            .map(|cache| {
+                cache
+                    .iter()
+                    .map(|(key, value)| (key.to_lowercase(), *value))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn update_cache_item(&self, tag: &String, count: u32, count_working: u32, table_name: &str, _column_name: &str) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        for (key, value) in data.cache_table(table_name).iter_mut() {
+            if eq_ci(key, tag) {
+                *value = (count, count_working);
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_to_cache(&self, tags: HashMap<&String, (u32, u32)>, table_name: &str, _column_name: &str) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let cache = data.cache_table(table_name);
+        for (tag, value) in tags {
+            cache.insert(tag.clone(), value);
+        }
+        Ok(())
+    }
+
+    fn remove_from_cache(&self, tags: Vec<&String>, table_name: &str, _column_name: &str) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        data.cache_table(table_name)
+            .retain(|key, _| !key.is_empty() && !tags.iter().any(|tag| eq_ci(tag, key)));
+        Ok(())
+    }
+
+    fn vote_for_station(&self, ip: &str, station: Option<DbStationItem>) -> Result<String, Box<dyn Error>> {
+        let station = station.ok_or(DbError::VoteError(
+            "could not find station with matching id".to_string(),
+        ))?;
+        let mut data = self.lock()?;
+
+        // delete ipcheck entries after 1 day
+        let limit = data.seconds_ago(24 * 60 * 60);
+        data.votes.retain(|vote| vote.timestamp >= limit);
+
+        // was there a vote from the ip in the last 1 day?
+        if data
+            .votes
+            .iter()
+            .any(|vote| vote.station_id == station.id && vote.ip == ip)
+        {
+            return Err(Box::new(DbError::VoteError(
+                "you are voting for the same station too often".to_string(),
+            )));
+        }
+
+        let now = data.now();
+        data.votes.push(VoteRow {
+            ip: ip.to_string(),
+            station_id: station.id,
+            timestamp: now,
+        });
+
+        match data.stations.iter_mut().find(|s| s.id == station.id) {
+            Some(station) => {
+                station.votes += 1;
+                Ok("voted for station successfully".to_string())
+            }
+            None => Err(Box::new(DbError::VoteError(
+                "could not find station with matching id".to_string(),
+            ))),
+        }
+    }
+
+    fn increase_clicks(&self, ip: &str, station: &DbStationItem, seconds: u64) -> Result<bool, Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let limit = data.seconds_ago(seconds);
+        let clicked = data.clicks.iter().any(|click| {
+            eq_ci(&click.stationuuid, &station.stationuuid)
+                && click.ip.as_deref() == Some(ip)
+                && click.clicktimestamp > limit
+        });
+        if clicked {
+            return Ok(false);
+        }
+
+        let now = data.now();
+        data.last_click_id += 1;
+        let id = data.last_click_id;
+        data.clicks.push(ClickRow {
+            id,
+            clickuuid: Uuid::new_v4().as_hyphenated().to_string(),
+            stationuuid: station.stationuuid.clone(),
+            ip: Some(ip.to_string()),
+            clicktimestamp: now,
+            inserttime: now,
+        });
+        match data.find_station_mut(&station.stationuuid) {
+            Some(station) => {
+                station.clicktimestamp_iso8601 = Some(now);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn sync_votes(&self, list: Vec<Station>) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        for item in list {
+            if let Some(station) = data.find_station_mut(&item.stationuuid) {
+                if station.votes != item.votes {
+                    station.votes = std::cmp::max(station.votes, item.votes);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get_servers_to_check(&mut self, hours: u32, chunksize: u32) -> Result<Vec<DbStreamingServer>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let limit = data.seconds_ago(u64::from(hours) * 3600);
+        let list = data
+            .streaming_servers
+            .iter()
+            .filter(|server| is_null_or_older(&server.updated_at, limit));
+        Ok(MemoryData::streaming_servers_output(list, "id", false, 0, chunksize))
+    }
+
+    fn get_streaming_servers(&self, order: &str, reverse: bool, offset: u32, limit: u32) -> Result<Vec<DbStreamingServer>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(MemoryData::streaming_servers_output(
+            data.streaming_servers.iter(),
+            order,
+            reverse,
+            offset,
+            limit,
+        ))
+    }
+
+    fn get_streaming_servers_by_uuids(&self, uuids: Vec<String>, order: &str, reverse: bool, offset: u32, limit: u32) -> Result<Vec<DbStreamingServer>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let list = data
+            .streaming_servers
+            .iter()
+            .filter(|server| uuids.iter().any(|uuid| eq_ci(uuid, &server.uuid)));
+        Ok(MemoryData::streaming_servers_output(list, order, reverse, offset, limit))
+    }
+
+    fn get_streaming_servers_by_url(&mut self, items: Vec<String>) -> Result<Vec<DbStreamingServer>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let list = data
+            .streaming_servers
+            .iter()
+            .filter(|server| items.iter().any(|url| eq_ci(url, &server.url)));
+        Ok(MemoryData::streaming_servers_output(list, "id", false, 0, u32::MAX))
+    }
+
+    fn get_streaming_servers_by_station_uuids(&self, uuids: Vec<String>, order: &str, reverse: bool, offset: u32, limit: u32) -> Result<Vec<DbStreamingServer>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let urls: Vec<String> = data
+            .stations
+            .iter()
+            .filter(|station| uuids.iter().any(|uuid| eq_ci(uuid, &station.stationuuid)))
+            .filter_map(|station| Url::parse(&station.url_resolved).ok())
+            .map(|mut url| {
+                url.set_path("/");
+                url.set_query(None);
+                url.set_fragment(None);
+                url.to_string()
+            })
+            .collect();
+        let list = data
+            .streaming_servers
+            .iter()
+            .filter(|server| urls.iter().any(|url| eq_ci(url, &server.url)));
+        Ok(MemoryData::streaming_servers_output(list, order, reverse, offset, limit))
+    }
+
+    fn insert_streaming_servers(&mut self, items: Vec<DbStreamingServerNew>) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        for item in items {
+            if data
+                .streaming_servers
+                .iter()
+                .any(|server| eq_ci(&server.url, &item.url))
+            {
+                continue;
+            }
+            let now = data.now();
+            data.last_streaming_server_id += 1;
+            let id = data.last_streaming_server_id;
+            data.streaming_servers.push(StreamingServerRow {
+                id,
+                uuid: Uuid::new_v4().as_hyphenated().to_string(),
+                url: item.url,
+                statusurl: item.statusurl,
+                status: item.status,
+                error: item.error,
+                created_at: now,
+                updated_at: None,
+            });
+        }
+        Ok(())
+    }
+
+    fn update_streaming_servers(&mut self, items: Vec<DbStreamingServer>) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let now = data.now();
+        for item in items.iter() {
+            if let Some(server) = data.streaming_servers.iter_mut().find(|s| s.id == item.id) {
+                server.url = item.url.clone();
+                server.statusurl = item.statusurl.clone();
+                server.status = item.status.clone();
+                server.error = item.error.clone();
+                server.updated_at = Some(now);
+            }
+            for station in data.stations.iter_mut() {
+                if starts_with_ci(&station.url_resolved, &item.url) {
+                    station.serveruuid = Some(item.uuid.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_station_check_steps(&mut self, station_check_steps: &[StationCheckStepItemNew]) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let now = data.now();
+        for step in station_check_steps {
+            data.last_check_step_id += 1;
+            let id = data.last_check_step_id;
+            data.check_steps.push(StationCheckStepItem {
+                id,
+                stepuuid: step.stepuuid.clone(),
+                parent_stepuuid: step.parent_stepuuid.clone(),
+                checkuuid: step.checkuuid.clone(),
+                stationuuid: step.stationuuid.clone(),
+                url: step.url.clone(),
+                urltype: step.urltype.clone(),
+                error: step.error.clone(),
+                inserttime: now,
+            });
+        }
+        Ok(())
+    }
+
+    fn select_station_check_steps(&self) -> Result<Vec<StationCheckStepItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.check_steps.clone())
+    }
+
+    fn select_station_check_steps_by_stations(&self, stationuuids: &[String], limit: u32) -> Result<Vec<StationCheckStepItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data
+            .check_steps
+            .iter()
+            .filter(|step| stationuuids.iter().any(|uuid| eq_ci(uuid, &step.stationuuid)))
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    fn add_webhook(&self, webhook: &WebhookItem) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let now = data.now();
+        data.webhooks.push(WebhookItem {
+            creation_iso8601: Some(now),
+            ..webhook.clone()
+        });
+        Ok(())
+    }
+
+    fn get_webhooks(&self, owner: Option<&str>) -> Result<Vec<WebhookItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data
+            .webhooks
+            .iter()
+            .filter(|webhook| owner.map(|owner| webhook.owner == owner).unwrap_or(true))
+            .cloned()
+            .collect())
+    }
+
+    fn delete_webhook(&self, webhookuuid: &str, owner: Option<&str>) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let count = data.webhooks.len();
+        data.webhooks.retain(|webhook| {
+            webhook.webhookuuid != webhookuuid || !owner.map(|owner| webhook.owner == owner).unwrap_or(true)
+        });
+        if data.webhooks.len() == count {
+            return Err(Box::new(DbError::WebhookError(String::from("webhook not found"))));
+        }
+        data.webhook_deliveries.retain(|delivery| delivery.webhookuuid != webhookuuid);
+        Ok(())
+    }
+
+    fn insert_webhook_delivery(&self, webhookuuid: &str, event: &str, payload: &str) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let now = data.now();
+        data.last_webhook_delivery_id += 1;
+        let id = data.last_webhook_delivery_id;
+        data.webhook_deliveries.push(WebhookDeliveryRow {
+            id,
+            deliveryuuid: Uuid::new_v4().as_hyphenated().to_string(),
+            webhookuuid: webhookuuid.to_string(),
+            event: event.to_string(),
+            payload: payload.to_string(),
+            attempts: 0,
+            next_attempt: now,
+        });
+        Ok(())
+    }
+
+    fn get_due_webhook_deliveries(&self, limit: u32) -> Result<Vec<WebhookDeliveryItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        let now = data.now();
+        let mut due: Vec<&WebhookDeliveryRow> = data.webhook_deliveries.iter().filter(|delivery| delivery.next_attempt <= now).collect();
+        due.sort_by_key(|delivery| delivery.id);
+        Ok(due
+            .into_iter()
+            .filter_map(|delivery| {
+                data.webhooks
+                    .iter()
+                    .find(|webhook| webhook.webhookuuid == delivery.webhookuuid)
+                    .map(|webhook| WebhookDeliveryItem {
+                        deliveryuuid: delivery.deliveryuuid.clone(),
+                        webhookuuid: delivery.webhookuuid.clone(),
+                        url: webhook.url.clone(),
+                        secret: webhook.secret.clone(),
+                        event: delivery.event.clone(),
+                        payload: delivery.payload.clone(),
+                        attempts: delivery.attempts,
+                    })
+            })
+            .take(limit as usize)
+            .collect())
+    }
+
+    fn delete_webhook_delivery(&self, deliveryuuid: &str) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        data.webhook_deliveries.retain(|delivery| delivery.deliveryuuid != deliveryuuid);
+        Ok(())
+    }
+
+    fn retry_webhook_delivery(&self, deliveryuuid: &str, seconds: u64, _error: &str) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let next_attempt = data.now() + Duration::seconds(seconds as i64);
+        if let Some(delivery) = data.webhook_deliveries.iter_mut().find(|delivery| delivery.deliveryuuid == deliveryuuid) {
+            delivery.attempts += 1;
+            delivery.next_attempt = next_attempt;
+        }
+        Ok(())
+    }
+
+    fn set_now_playing(&self, titles: &[(String, String)]) -> Result<(), Box<dyn Error>> {
+        let mut data = self.lock()?;
+        let now = data.now();
+        let limit = data.seconds_ago(NOW_PLAYING_MAX_AGE.as_secs());
+        data.now_playing.retain(|_, item| item.updatetime_iso8601.map(|time| time > limit).unwrap_or(false));
+        for (stationuuid, title) in titles {
+            data.now_playing.insert(
+                stationuuid.clone(),
+                NowPlayingItem {
+                    stationuuid: stationuuid.clone(),
+                    title: title.clone(),
+                    updatetime_iso8601: Some(now),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn get_now_playing(&self, stationuuid: &str) -> Result<Option<NowPlayingItem>, Box<dyn Error>> {
+        let data = self.lock()?;
+        Ok(data.now_playing(stationuuid).cloned())
+    }
+}
+
+/// Access to the station columns that are used by name in queries
+fn station_column<'a>(station: &'a DbStationItem, column_name: &str) -> Result<Option<&'a str>, Box<dyn Error>> {
+    Ok(match column_name {
+        "Name" => Some(&station.name),
+        "Url" => Some(&station.url),
+        "UrlCache" => Some(&station.url_resolved),
+        "Homepage" => Some(&station.homepage),
+        "Favicon" => Some(&station.favicon),
+        "Tags" => Some(&station.tags),
+        "Country" => Some(&station.country),
+        "CountryCode" => Some(&station.countrycode),
+        "Subcountry" => Some(&station.state),
+        "Language" => Some(&station.language),
+        "LanguageCodes" => Some(&station.languagecodes),
+        "Codec" => Some(&station.codec),
+        "StationUuid" => Some(&station.stationuuid),
+        "ChangeUuid" => Some(&station.changeuuid),
+        "ServerUuid" => station.serveruuid.as_deref(),
+        "CountrySubdivisionCode" => station.iso_3166_2.as_deref(),
+        _ => return Err(Box::new(DbError::UnknownColumnError(column_name.to_string()))),
+    })
+}
+
+/// Same columns and fallbacks as filter_order() of the mysql implementation
+fn sort_stations(list: &mut [&DbStationItem], order: &str, reverse: bool) {
+    let compare = |a: &&DbStationItem, b: &&DbStationItem| -> Ordering {
+        match order {
+            "url" => cmp_ci(&a.url, &b.url),
+            "homepage" => cmp_ci(&a.homepage, &b.homepage),
+            "favicon" => cmp_ci(&a.favicon, &b.favicon),
+            "tags" => cmp_ci(&a.tags, &b.tags),
+            "country" => cmp_ci(&a.country, &b.country),
+            "state" => cmp_ci(&a.state, &b.state),
+            "language" => cmp_ci(&a.language, &b.language),
+            "votes" => a.votes.cmp(&b.votes),
+            "codec" => cmp_ci(&a.codec, &b.codec),
+            "bitrate" => a.bitrate.cmp(&b.bitrate),
+            "lastcheckok" => a.lastcheckok.cmp(&b.lastcheckok),
+            "lastchecktime" => a.lastchecktime_iso8601.cmp(&b.lastchecktime_iso8601),
+            "clicktimestamp" => a.clicktimestamp_iso8601.cmp(&b.clicktimestamp_iso8601),
+            "clickcount" => a.clickcount.cmp(&b.clickcount),
+            "clicktrend" => a.clicktrend.cmp(&b.clicktrend),
+            "changetimestamp" => a.lastchangetime_iso8601.cmp(&b.lastchangetime_iso8601),
+            _ => cmp_ci(&a.name, &b.name),
+        }
+        .then(a.id.cmp(&b.id))
+    };
+    if order == "random" {
+        list.sort_by_cached_key(|_| Uuid::new_v4());
+    } else if reverse {
+        list.sort_by(|a, b| compare(b, a));
+    } else {
+        list.sort_by(compare);
+    }
+}
+
+fn sort_1_n(list: &mut [(String, u32)], order: &str, reverse: bool) {
+    match order {
+        "stationcount" => list.sort_by_key(|(_, count)| *count),
+        _ => list.sort_by(|a, b| cmp_ci(&a.0, &b.0)),
+    }
+    if reverse {
+        list.reverse();
+    }
+}
+
+/// GROUP BY with a case insensitive collation, the first spelling wins
+fn group_count(values: Vec<&str>) -> Vec<(String, u32)> {
+    let mut list: Vec<(String, u32)> = vec![];
+    for value in values {
+        match list.iter_mut().find(|(name, _)| eq_ci(name, value)) {
+            Some((_, count)) => *count += 1,
+            None => list.push((value.to_string(), 1)),
+        }
+    }
+    list
+}
+
+fn after_cursor<'a>(list: Vec<&'a DbStationItem>, cursor: Option<&StationCursor>) -> Vec<&'a DbStationItem> {
+    match cursor {
+        Some(cursor) => list.into_iter().filter(|station| cursor.is_before(station)).collect(),
+        None => list,
+    }
+}
+
+/// Stations in the list, only those within geo_distance if it is given
+fn count_stations(list: Vec<&DbStationItem>, geo_lat: Option<f64>, geo_long: Option<f64>, geo_distance: Option<f64>) -> u64 {
+    match distance_filter(geo_lat, geo_long, geo_distance) {
+        Some((lat, long, max)) => {
+            let locations = list.iter().filter_map(|s| Some((s.geo_lat?, s.geo_long?))).collect();
+            count_within_distance(locations, lat, long, max)
+        }
+        None => list.len() as u64,
+    }
+}
+
+fn page<T>(list: Vec<T>, offset: u32, limit: u32) -> Vec<T> {
+    list.into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect()
+}
+
+fn output_stations(data: &MemoryData, list: Vec<&DbStationItem>) -> Vec<DbStationItem> {
+    list.into_iter()
+        .map(|station| {
+            let mut station = station.clone();
+            station.now_playing = data.now_playing(&station.stationuuid).map(|item| item.title.clone());
+            station.lastchangetime = format_time(&station.lastchangetime_iso8601);
+            station.lastchecktime = format_time(&station.lastchecktime_iso8601);
+            station.lastcheckoktime = format_time(&station.lastcheckoktime_iso8601);
+            station.lastlocalchecktime = format_time(&station.lastlocalchecktime_iso8601);
+            station.clicktimestamp = format_time(&station.clicktimestamp_iso8601);
+            station
+        })
+        .collect()
+}
+
+fn output_history(row: &HistoryRow) -> StationHistoryItem {
+    StationHistoryItem {
+        id: row.id,
+        changeuuid: row.changeuuid.clone(),
+        stationuuid: row.stationuuid.clone(),
+        name: row.name.clone(),
+        url: row.url.clone(),
+        homepage: row.homepage.clone(),
+        favicon: row.favicon.clone(),
+        tags: row.tags.clone(),
+        countrycode: row.countrycode.clone(),
+        state: row.state.clone(),
+        language: row.language.clone(),
+        languagecodes: row.languagecodes.clone(),
+        votes: row.votes,
+        lastchangetime: format_time(&Some(row.creation)),
+        lastchangetime_iso8601: Some(row.creation),
+        geo_lat: row.geo_lat,
+        geo_long: row.geo_long,
+    }
+}
+
+fn format_time(time: &Option<DateTime<Utc>>) -> String {
+    time.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+fn parse_time(time: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time)
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S")
+                .map(|t| DateTime::<Utc>::from_naive_utc_and_offset(t, Utc))
+                .ok()
+        })
+}
+
+/// "time < limit" with sql semantics, NULL is never older
+fn is_older(time: &Option<DateTime<Utc>>, limit: DateTime<Utc>) -> bool {
+    time.map(|t| t < limit).unwrap_or(false)
+}
+
+fn is_null_or_older(time: &Option<DateTime<Utc>>, limit: DateTime<Utc>) -> bool {
+    time.map(|t| t < limit).unwrap_or(true)
+}
+
+fn never_working(station: &DbStationItem, limit: DateTime<Utc>) -> bool {
+    station.lastcheckoktime_iso8601.is_none() && is_older(&station.lastchangetime_iso8601, limit)
+}
+
+fn were_working(station: &DbStationItem, limit: DateTime<Utc>) -> bool {
+    !station.lastcheckok && is_older(&station.lastcheckoktime_iso8601, limit)
+}
+
+fn eq_ci(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+fn cmp_ci(a: &str, b: &str) -> Ordering {
+    a.to_lowercase().cmp(&b.to_lowercase())
+}
+
+/// LIKE '%search%'
+fn like_ci(value: &str, search: &str) -> bool {
+    value.to_lowercase().contains(&search.to_lowercase())
+}
+
+fn starts_with_ci(value: &str, prefix: &str) -> bool {
+    value.to_lowercase().starts_with(&prefix.to_lowercase())
+}
+
+/// Exact search in comma separated fields like tags and languages
+fn multi_field_contains(value: &str, search: &str) -> bool {
+    value.split(',').any(|item| eq_ci(item, search))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,133 @@
+use super::MemoryConnection;
+use crate::db::models::CheckConsensus;
+use crate::db::models::StationCheckItemNew;
+use crate::db::DbConnection;
+use crate::test_utils::sample_db;
+use crate::test_utils::station_change;
+
+fn names(conn: &MemoryConnection, order: &str, reverse: bool, hidebroken: bool) -> Vec<String> {
+    conn.get_stations_by_all(order, reverse, hidebroken, None, None, 0, 100)
+        .unwrap()
+        .into_iter()
+        .map(|station| station.name)
+        .collect()
+}
+
+fn broken_check(stationuuid: &str, checkuuid: &str, source: &str) -> StationCheckItemNew {
+    StationCheckItemNew::broken(stationuuid.to_string(), checkuuid.to_string(), source.to_string(), 1)
+}
+
+#[test]
+fn filter_and_order() {
+    let conn = sample_db();
+    assert_eq!(names(&conn, "name", false, false), vec!["Jazz Radio", "News Radio", "Rock Radio"]);
+    assert_eq!(names(&conn, "name", true, false), vec!["Rock Radio", "News Radio", "Jazz Radio"]);
+    assert_eq!(names(&conn, "unknown", false, false), names(&conn, "name", false, false));
+
+    let by_tag = conn
+        .get_stations_by_column_multiple("Tags", Some("ROCK".to_string()), true, "name", false, false, None, None, 0, 100)
+        .unwrap();
+    assert_eq!(by_tag.len(), 2);
+    let by_tag = conn
+        .get_stations_by_column_multiple("Tags", Some("roc".to_string()), true, "name", false, false, None, None, 0, 100)
+        .unwrap();
+    assert_eq!(by_tag.len(), 0);
+    let by_name = conn
+        .get_stations_by_column("Name", "radio".to_string(), false, "name", false, false, None, None, 1, 1)
+        .unwrap();
+    assert_eq!(by_name[0].name, "News Radio");
+    assert!(conn
+        .get_stations_by_column("Nope", "x".to_string(), false, "name", false, false, None, None, 0, 10)
+        .is_err());
+}
+
+#[test]
+fn hidebroken_uses_majority_of_sources() {
+    let conn = sample_db();
+    let checks = vec![broken_check("s-jazz", "k1", "a"), broken_check("s-jazz", "k2", "b")];
+    let (_, _, inserted) = conn.insert_checks(checks).unwrap();
+    conn.update_station_with_check_data(&inserted, false, &CheckConsensus::default()).unwrap();
+    assert_eq!(names(&conn, "name", false, true), vec!["News Radio", "Rock Radio"]);
+    assert_eq!(conn.get_station_count_broken().unwrap(), 1);
+
+    let mut ok = broken_check("s-jazz", "k3", "c");
+    ok.check_ok = true;
+    let mut ok2 = broken_check("s-jazz", "k4", "a");
+    ok2.check_ok = true;
+    let (_, _, inserted) = conn.insert_checks(vec![ok, ok2]).unwrap();
+    conn.update_station_with_check_data(&inserted, false, &CheckConsensus::default()).unwrap();
+    assert_eq!(names(&conn, "name", false, true).len(), 3);
+    // only the latest check of each source counts
+    assert_eq!(conn.get_checks(Some("s-jazz".to_string()), None, 0, false, 100).unwrap().len(), 3);
+    assert_eq!(conn.get_checks(Some("s-jazz".to_string()), None, 0, true, 100).unwrap().len(), 4);
+}
+
+#[test]
+fn consensus_of_recent_sources() {
+    let conn = sample_db();
+    let consensus = CheckConsensus::new(1, 2, 0);
+    let mut ok = broken_check("s-jazz", "k1", "a");
+    ok.check_ok = true;
+    let checks = vec![ok, broken_check("s-jazz", "k2", "b"), broken_check("s-jazz", "k3", "c")];
+    let (_, _, inserted) = conn.insert_checks(checks).unwrap();
+    conn.update_station_with_check_data(&inserted, false, &consensus).unwrap();
+    assert!(!conn.get_station_by_uuid("s-jazz").unwrap()[0].lastcheckok, "source a is not one of the 2 most recent");
+
+    let mut ok = broken_check("s-jazz", "k4", "a");
+    ok.check_ok = true;
+    let (_, _, inserted) = conn.insert_checks(vec![ok]).unwrap();
+    conn.update_station_with_check_data(&inserted, false, &consensus).unwrap();
+    assert!(conn.get_station_by_uuid("s-jazz").unwrap()[0].lastcheckok);
+}
+
+#[test]
+fn consensus_ignores_old_sources() {
+    let conn = sample_db();
+    let mut ok = broken_check("s-jazz", "k1", "a");
+    ok.check_ok = true;
+    conn.insert_checks(vec![ok]).unwrap();
+    conn.advance_clock(2 * 60 * 60);
+    let (_, _, inserted) = conn.insert_checks(vec![broken_check("s-jazz", "k2", "b")]).unwrap();
+    conn.update_station_with_check_data(&inserted, false, &CheckConsensus::new(0, 0, 0)).unwrap();
+    assert!(conn.get_station_by_uuid("s-jazz").unwrap()[0].lastcheckok, "a and b are a tie");
+    conn.update_station_with_check_data(&inserted, false, &CheckConsensus::new(0, 0, 60 * 60)).unwrap();
+    assert!(!conn.get_station_by_uuid("s-jazz").unwrap()[0].lastcheckok, "a stopped checking an hour ago");
+}
+
+#[test]
+fn history_is_delayed_for_mirrors() {
+    let conn = sample_db();
+    conn.insert_station_by_change(
+        &[station_change("s-jazz", "c-jazz-2", "Jazz Radio 2", "http://jazz.example.com/live", "jazz")],
+        "test",
+    )
+    .unwrap();
+    assert_eq!(conn.get_changes(Some("s-jazz".to_string()), None, 100).unwrap().len(), 2);
+    assert_eq!(conn.get_changes(None, Some("c-jazz".to_string()), 100).unwrap().len(), 0);
+    conn.advance_clock(61);
+    let changes = conn.get_changes(None, Some("c-jazz".to_string()), 100).unwrap();
+    let uuids: Vec<String> = changes.into_iter().map(|change| change.changeuuid).collect();
+    assert_eq!(uuids, vec!["c-rock", "c-news", "c-jazz-2"]);
+}
+
+#[test]
+fn votes_and_click_dedup() {
+    let conn = sample_db();
+    let station = conn.get_station_by_uuid("s-rock").unwrap().pop();
+    assert!(conn.vote_for_station("10.0.0.1", station.clone()).is_ok());
+    assert!(conn.vote_for_station("10.0.0.1", station.clone()).is_err());
+    assert!(conn.vote_for_station("10.0.0.2", station.clone()).is_ok());
+    conn.advance_clock(24 * 60 * 60 + 1);
+    assert!(conn.vote_for_station("10.0.0.1", station.clone()).is_ok());
+    assert_eq!(conn.get_stations_topvote(false, 0, 1).unwrap()[0].votes, 3);
+
+    let station = station.unwrap();
+    assert!(conn.increase_clicks("10.0.0.1", &station, 60).unwrap());
+    assert!(!conn.increase_clicks("10.0.0.1", &station, 60).unwrap());
+    assert!(conn.increase_clicks("10.0.0.2", &station, 60).unwrap());
+    conn.advance_clock(61);
+    assert!(conn.increase_clicks("10.0.0.1", &station, 60).unwrap());
+    conn.update_stations_clickcount().unwrap();
+    let top = conn.get_stations_topclick(false, 0, 1).unwrap();
+    assert_eq!((top[0].stationuuid.as_str(), top[0].clickcount), ("s-rock", 3));
+}
@@ -1,7 +1,11 @@
 use crate::db::models::DbStationItem;
+use crate::db::models::NowPlayingItem;
 use crate::db::models::StationCheckItem;
 use crate::db::models::StationHistoryItem;
 use crate::db::models::StationClickItem;
+use crate::db::models::StationAddItem;
+use crate::db::models::WebhookDeliveryItem;
+use crate::db::models::WebhookItem;
 use mysql;
 use mysql::Row;
 
@@ -39,6 +43,9 @@ impl From<Row> for StationCheckItem {
             ssl_error:                     row.take_opt("SslError").unwrap_or(Ok(0)).unwrap_or(0) == 1,
             geo_lat:                       row.take_opt("GeoLat").transpose().unwrap_or(None),
             geo_long:                      row.take_opt("GeoLong").transpose().unwrap_or(None),
+            audio_bitrate:                 row.take_opt("AudioBitrate").transpose().unwrap_or(None),
+            audio_sampling:                row.take_opt("AudioSampling").transpose().unwrap_or(None),
+            audio_liveness:                row.take_opt("AudioLiveness").transpose().unwrap_or(None),
         }
     }
 }
@@ -83,6 +90,9 @@ impl From<Row> for DbStationItem {
             geo_lat:                     row.take_opt("GeoLat").transpose().unwrap_or(None),
             geo_long:                    row.take_opt("GeoLong").transpose().unwrap_or(None),
             has_extended_info:           row.take_opt("ExtendedInfo").transpose().unwrap_or(None),
+            score:                       row.take_opt("Score").transpose().unwrap_or(None),
+            distance:                    None,
+            now_playing:                 row.take_opt("NowPlaying").transpose().unwrap_or(None),
         }
     }
 }
@@ -122,4 +132,65 @@ impl From<Row> for StationClickItem {
             clicktimestamp:            row.take_opt("ClickTimestampFormated").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
         }
     }
-}
\ No newline at end of file
+}
+
+impl From<Row> for StationAddItem {
+    fn from(mut row: Row) -> Self {
+        StationAddItem {
+            stationuuid:               row.take("StationUuid").unwrap(),
+            name:                      row.take_opt("Name").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            url:                       row.take_opt("Url").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            homepage:                  row.take_opt("Homepage").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            favicon:                   row.take_opt("Favicon").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            country:                   row.take_opt("Country").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            countrycode:               row.take_opt("CountryCode").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            state:                     row.take_opt("Subcountry").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            language:                  row.take_opt("Language").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            languagecodes:             row.take_opt("LanguageCodes").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            tags:                      row.take_opt("Tags").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            geo_lat:                   row.take_opt("GeoLat").transpose().unwrap_or(None),
+            geo_long:                  row.take_opt("GeoLong").transpose().unwrap_or(None),
+            creation_iso8601:          row.take_opt("Creation").transpose().ok().flatten().map(|x|chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(x, chrono::Utc)),
+        }
+    }
+}
+
+impl From<Row> for WebhookItem {
+    fn from(mut row: Row) -> Self {
+        WebhookItem {
+            webhookuuid:               row.take("WebhookUuid").unwrap(),
+            owner:                     row.take_opt("Owner").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            url:                       row.take_opt("Url").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            secret:                    row.take_opt("Secret").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            events:                    row.take_opt("Events").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            stationuuid:               row.take_opt("StationUuid").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            tag:                       row.take_opt("Tag").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            countrycode:               row.take_opt("CountryCode").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            creation_iso8601:          row.take_opt("Creation").transpose().ok().flatten().map(|x|chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(x, chrono::Utc)),
+        }
+    }
+}
+
+impl From<Row> for WebhookDeliveryItem {
+    fn from(mut row: Row) -> Self {
+        WebhookDeliveryItem {
+            deliveryuuid:              row.take("DeliveryUuid").unwrap(),
+            webhookuuid:               row.take("WebhookUuid").unwrap(),
+            url:                       row.take_opt("Url").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            secret:                    row.take_opt("Secret").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            event:                     row.take_opt("Event").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            payload:                   row.take_opt("Payload").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            attempts:                  row.take_opt("Attempts").unwrap_or(Ok(0)).unwrap_or(0),
+        }
+    }
+}
+
+impl From<Row> for NowPlayingItem {
+    fn from(mut row: Row) -> Self {
+        NowPlayingItem {
+            stationuuid:               row.take("StationUuid").unwrap(),
+            title:                     row.take_opt("Title").unwrap_or(Ok("".to_string())).unwrap_or("".to_string()),
+            updatetime_iso8601:        row.take_opt("UpdateTime").transpose().ok().flatten().map(|x|chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(x, chrono::Utc)),
+        }
+    }
+}
@@ -548,5 +548,83 @@ r#"ALTER TABLE StationHistory DROP COLUMN CountrySubdivisionCode;"#);
 r#"ALTER TABLE StationHistory MODIFY COLUMN Language VARCHAR(100) NULL;"#,
 r#"ALTER TABLE StationHistory MODIFY COLUMN Language VARCHAR(50) NULL;"#);
 
+    migrations.add_migration("20261018_120000_CreateStationPending",
+r#"CREATE TABLE `StationPending` (
+`PendingID` int(11) NOT NULL AUTO_INCREMENT,
+`StationUuid` char(36) NOT NULL,
+`Name` text NOT NULL,
+`Url` text NOT NULL,
+`Homepage` text NOT NULL,
+`Favicon` text NOT NULL,
+`Country` varchar(250) NOT NULL,
+`CountryCode` varchar(2) NOT NULL,
+`Subcountry` varchar(50) NOT NULL,
+`Language` varchar(100) NOT NULL,
+`LanguageCodes` text NOT NULL,
+`Tags` text NOT NULL,
+`GeoLat` double DEFAULT NULL,
+`GeoLong` double DEFAULT NULL,
+`Creation` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+PRIMARY KEY (`PendingID`),
+UNIQUE KEY `StationUuid` (`StationUuid`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;"#,"DROP TABLE StationPending");
+
+    migrations.add_migration("20261018_130000_CreateWebhook",
+r#"CREATE TABLE `Webhook` (
+`WebhookID` int(11) NOT NULL AUTO_INCREMENT,
+`WebhookUuid` char(36) NOT NULL,
+`Owner` varchar(100) NOT NULL,
+`Url` text NOT NULL,
+`Secret` varchar(250) NOT NULL,
+`Events` varchar(250) NOT NULL,
+`StationUuid` varchar(36) NOT NULL,
+`Tag` varchar(100) NOT NULL,
+`CountryCode` varchar(2) NOT NULL,
+`Creation` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+PRIMARY KEY (`WebhookID`),
+UNIQUE KEY `WebhookUuid` (`WebhookUuid`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;"#,"DROP TABLE Webhook");
+
+    migrations.add_migration("20261018_130100_CreateWebhookDelivery",
+r#"CREATE TABLE `WebhookDelivery` (
+`DeliveryID` int(11) NOT NULL AUTO_INCREMENT,
+`DeliveryUuid` char(36) NOT NULL,
+`WebhookUuid` char(36) NOT NULL,
+`Event` varchar(50) NOT NULL,
+`Payload` mediumtext NOT NULL,
+`Attempts` int(11) NOT NULL DEFAULT 0,
+`NextAttempt` datetime NOT NULL,
+`LastError` text DEFAULT NULL,
+`Creation` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+PRIMARY KEY (`DeliveryID`),
+UNIQUE KEY `DeliveryUuid` (`DeliveryUuid`),
+KEY `WebhookUuid` (`WebhookUuid`),
+KEY `NextAttempt` (`NextAttempt`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;"#,"DROP TABLE WebhookDelivery");
+
+    migrations.add_migration("20261018_140000_Add_Station_NextCheckTime",
+r#"ALTER TABLE Station ADD COLUMN NextCheckTime DATETIME NULL, ADD INDEX NextCheckTime (NextCheckTime);"#,
+r#"ALTER TABLE Station DROP INDEX NextCheckTime, DROP COLUMN NextCheckTime;"#);
+
+    migrations.add_migration("20261018_150000_Add_StationCheckHistory_Audio",
+r#"ALTER TABLE StationCheckHistory ADD COLUMN AudioBitrate INT NULL, ADD COLUMN AudioSampling INT NULL, ADD COLUMN AudioLiveness VARCHAR(20) NULL;"#,
+r#"ALTER TABLE StationCheckHistory DROP COLUMN AudioBitrate, DROP COLUMN AudioSampling, DROP COLUMN AudioLiveness;"#);
+
+    migrations.add_migration("20261018_150010_Recreate_View_StationCheck",
+r#"DROP VIEW StationCheck; CREATE VIEW StationCheck AS SELECT * FROM StationCheckHistory WHERE CheckID IN (select max(CheckID) FROM StationCheckHistory Group By StationUuid,Source);"#,
+r#"DROP VIEW StationCheck; CREATE VIEW StationCheck AS SELECT CheckID,CheckUuid,StationUuid,Source,Codec,Bitrate,Hls,CheckOK,CheckTime,UrlCache,MetainfoOverridesDatabase,Public,Name,Description,Tags,CountryCode,Homepage,Favicon,Loadbalancer,InsertTime,DoNotIndex,CountrySubdivisionCode,ServerSoftware,Sampling,LanguageCodes,TimingMs,SslError,GeoLat,GeoLong FROM StationCheckHistory WHERE CheckID IN (select max(CheckID) FROM StationCheckHistory Group By StationUuid,Source);"#);
+
+    migrations.add_migration("20261018_160000_CreateNowPlaying",
+r#"CREATE TABLE `NowPlaying` (
+`StationUuid` char(36) NOT NULL,
+`Title` text NOT NULL,
+`UpdateTime` datetime NOT NULL,
+PRIMARY KEY (`StationUuid`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;"#,"DROP TABLE NowPlaying");
+
+    migrations.add_migration("20261018_170000_Add_Station_Fulltext",
+r#"ALTER TABLE Station MODIFY COLUMN Tags TEXT CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci, MODIFY COLUMN Language VARCHAR(100) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci NULL; ALTER TABLE Station ADD FULLTEXT INDEX StationSearch (Name, Tags, Subcountry, Language); ALTER TABLE Station ADD FULLTEXT INDEX StationSearchName (Name);"#,
+r#"ALTER TABLE Station DROP INDEX StationSearchName, DROP INDEX StationSearch; ALTER TABLE Station MODIFY COLUMN Tags TEXT CHARACTER SET utf8mb4 COLLATE utf8mb4_bin, MODIFY COLUMN Language VARCHAR(100) CHARACTER SET utf8mb4 COLLATE utf8mb4_bin NULL;"#);
+
     Ok(migrations)
 }
@@ -2,8 +2,26 @@ mod conversions;
 mod migrations;
 mod simple_migrate;
 
+use crate::db::models::CheckConsensus;
 use crate::db::models::DBCountry;
+use crate::db::db_cursor::sql_order;
+use crate::db::db_cursor::CursorParam;
+use crate::db::db_cursor::StationCursor;
+use crate::db::db_fields::StationFields;
 use crate::db::db_error::DbError;
+use crate::db::db_geo::count_within_distance;
+use crate::db::db_geo::distance_filter;
+use crate::db::db_geo::finish_query;
+use crate::db::db_geo::geo_boxes;
+use crate::db::db_geo::query_range;
+use crate::db::db_geo::GeoBoundingBox;
+use crate::db::db_search::fold_text;
+use crate::db::db_search::fulltext_prefixes;
+use crate::db::db_search::sql_score;
+use crate::db::db_utils::check_new_station;
+use crate::db::db_utils::edit_station_fields;
+use crate::db::db_utils::filter_order_1_n;
+use crate::db::db_utils::fix_multi_field;
 use crate::db::models::DbStreamingServer;
 use crate::db::models::DbStreamingServerNew;
 use mysql::Opts;
@@ -26,6 +44,11 @@ use crate::db::models::StationClickItem;
 use crate::db::models::StationClickItemNew;
 use crate::db::models::StationHistoryItem;
 use crate::db::models::DbStationItem;
+use crate::db::models::NowPlayingItem;
+use crate::db::models::NOW_PLAYING_MAX_AGE;
+use crate::db::models::StationAddItem;
+use crate::db::models::WebhookDeliveryItem;
+use crate::db::models::WebhookItem;
 use crate::db::DbConnection;
 use celes::Country;
 use mysql;
@@ -34,6 +57,7 @@ use mysql::QueryResult;
 use mysql::Row;
 use mysql::TxOpts;
 use mysql::Value;
+use std::borrow::Cow;
 use std::error::Error;
 use uuid::Uuid;
 
@@ -59,7 +83,7 @@ impl MysqlConnection {
     Date_Format(ClickTimestamp,'%Y-%m-%d %H:%i:%s') AS ClickTimestampFormated,
     clickcount,ClickTrend,
     LanguageCodes,SslError,GeoLat,GeoLong,ExtendedInfo,CountrySubdivisionCode,
-    ServerUuid";
+    ServerUuid,NowPlaying";
 
     const COLUMNS_CHECK: &'static str =
         "CheckID, StationUuid, CheckUuid, Source, Codec, Bitrate, Hls, CheckOK,
@@ -70,33 +94,21 @@ impl MysqlConnection {
     Description,Tags,CountryCode,
     Homepage,Favicon,Loadbalancer,
     CountrySubdivisionCode,ServerSoftware,Sampling,LanguageCodes,TimingMs,SslError,
-    GeoLat,GeoLong";
+    GeoLat,GeoLong,AudioBitrate,AudioSampling,AudioLiveness";
 
     const COLUMNS_CLICK: &'static str = "ClickID, StationUuid, ClickUuid, IP,
     ClickTimestamp,
     Date_Format(ClickTimestamp,'%Y-%m-%d %H:%i:%s') AS ClickTimestampFormated";
 
+    const COLUMNS_PENDING: &'static str = "StationUuid, Name, Url, Homepage, Favicon, Country, CountryCode, Subcountry,
+    Language, LanguageCodes, Tags, GeoLat, GeoLong, Creation";
+
     pub fn new(connection_str: &str) -> Result<Self, Box<dyn Error>> {
         let opts = Opts::from_url(connection_str)?;
         let pool = mysql::Pool::new(opts)?;
         Ok(MysqlConnection { pool })
     }
 
-    pub fn migrations_needed(&self) -> Result<bool, Box<dyn Error>> {
-        let migrations = migrations::load_migrations(&self.pool)?;
-        Ok(migrations.migrations_needed()?)
-    }
-
-    pub fn do_migrations(
-        &self,
-        ignore_migration_errors: bool,
-        allow_database_downgrade: bool,
-    ) -> Result<(), Box<dyn Error>> {
-        let migrations = migrations::load_migrations(&self.pool)?;
-        migrations.do_migrations(ignore_migration_errors, allow_database_downgrade)?;
-        Ok(())
-    }
-
     fn get_list_from_query_result<A>(
         &self,
         results: QueryResult<mysql::Binary>,
@@ -141,6 +153,59 @@ impl MysqlConnection {
         return Ok(0);
     }
 
+    /// Number of stations that match the condition, without the ones outside of geo_distance
+    fn count_stations_where(
+        &self,
+        condition: &str,
+        params: Vec<(String, Value)>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+    ) -> Result<u64, Box<dyn Error>> {
+        match distance_filter(geo_lat, geo_long, geo_distance) {
+            Some((lat, long, max)) => {
+                let query = format!("SELECT GeoLat, GeoLong FROM Station WHERE{condition}", condition = condition);
+                let mut conn = self.pool.get_conn()?;
+                let locations: Vec<(f64, f64)> = conn.exec(query, params)?;
+                Ok(count_within_distance(locations, lat, long, max))
+            }
+            None => {
+                let query = format!("SELECT COUNT(*) FROM Station WHERE{condition}", condition = condition);
+                self.get_single_column_number_params(&query, Params::from(params))
+            }
+        }
+    }
+
+    fn insert_new_station(transaction: &mut mysql::Transaction<'_>, station: &StationAddItem) -> Result<(), Box<dyn Error>> {
+        let query = "INSERT INTO Station(Name,Url,Homepage,Favicon,Country,CountryCode,Subcountry,Language,LanguageCodes,Tags,ChangeUuid,StationUuid,GeoLat,GeoLong,UrlCache,Creation)
+                        VALUES(:name, :url, :homepage, :favicon, :country, :countrycode, :state, :language, :languagecodes, :tags, :changeuuid, :stationuuid, :geo_lat, :geo_long, '', UTC_TIMESTAMP())";
+        let changeuuid = Uuid::new_v4().as_hyphenated().to_string();
+        let params = params! {
+            "name" => &station.name,
+            "url" => &station.url,
+            "homepage" => &station.homepage,
+            "favicon" => &station.favicon,
+            "country" => &station.country,
+            "countrycode" => &station.countrycode,
+            "state" => &station.state,
+            "language" => &station.language,
+            "languagecodes" => &station.languagecodes,
+            "tags" => &station.tags,
+            "changeuuid" => changeuuid,
+            "stationuuid" => &station.stationuuid,
+            "geo_lat" => station.geo_lat,
+            "geo_long" => station.geo_long,
+        };
+
+        transaction.exec_drop(query, params)?;
+        MysqlConnection::backup_stations_by_uuid(
+            transaction,
+            &(vec![station.stationuuid.clone()]),
+            "INITIAL",
+        )?;
+        Ok(())
+    }
+
     fn backup_stations_by_uuid(
         transaction: &mut mysql::Transaction<'_>,
         stationuuids: &Vec<String>,
@@ -312,6 +377,21 @@ impl MysqlConnection {
 }
 
 impl DbConnection for MysqlConnection {
+    fn migrations_needed(&self) -> Result<bool, Box<dyn Error>> {
+        let migrations = migrations::load_migrations(&self.pool)?;
+        Ok(migrations.migrations_needed()?)
+    }
+
+    fn do_migrations(
+        &self,
+        ignore_migration_errors: bool,
+        allow_database_downgrade: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let migrations = migrations::load_migrations(&self.pool)?;
+        migrations.do_migrations(ignore_migration_errors, allow_database_downgrade)?;
+        Ok(())
+    }
+
     fn get_stations_uuid_order_by_changes(&mut self, min_change_count: u32) -> Result<Vec<String>, Box<dyn Error>> {
         let query = format!(r#"SELECT StationUuid, COUNT(*) AS change_count FROM StationHistory GROUP BY StationUuid HAVING change_count > {} ORDER BY change_count DESC"#, min_change_count);
         let mut conn = self.pool.get_conn()?;
@@ -511,6 +591,16 @@ impl DbConnection for MysqlConnection {
         Ok(())
     }
 
+    fn get_stations_deletable_were_working(&self, seconds: u64) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let query = format!(
+            "SELECT {columns} FROM Station{now_playing} WHERE LastCheckOK=0 AND LastCheckOkTime IS NOT NULL AND LastCheckOkTime < UTC_TIMESTAMP() - INTERVAL :seconds SECOND",
+            columns = MysqlConnection::COLUMNS, now_playing = now_playing_join()
+        );
+        let mut conn = self.pool.get_conn()?;
+        let results = conn.exec_iter(query, params!(seconds))?;
+        self.get_list_from_query_result(results)
+    }
+
     fn delete_unused_streaming_servers(&mut self, seconds: u64) -> Result<(), Box<dyn Error>> {
         trace!("delete_unused_streaming_servers()");
         let mut conn = self.pool.get_conn()?;
@@ -646,12 +736,28 @@ impl DbConnection for MysqlConnection {
         hours: u32,
         itemcount: u32,
     ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
-        let query = format!("SELECT {columns} FROM Station WHERE LastLocalCheckTime IS NULL OR LastLocalCheckTime < UTC_TIMESTAMP() - INTERVAL {interval} HOUR ORDER BY RAND() LIMIT {limit}", columns = MysqlConnection::COLUMNS, interval = hours, limit = itemcount);
+        let query = format!("SELECT {columns} FROM Station{now_playing} WHERE {due} IS NULL OR {due} <= UTC_TIMESTAMP() ORDER BY {due} ASC, ClickCount DESC LIMIT :itemcount", columns = MysqlConnection::COLUMNS, now_playing = now_playing_join(), due = CHECK_DUE);
         let mut conn = self.pool.get_conn()?;
-        let results = conn.exec_iter(query, ())?;
+        let results = conn.exec_iter(query, params!(hours, itemcount))?;
         self.get_list_from_query_result(results)
     }
 
+    fn set_next_check_times(&self, list: &[(String, u64)]) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.pool.get_conn()?;
+        conn.exec_batch(
+            "UPDATE Station SET NextCheckTime=UTC_TIMESTAMP() + INTERVAL :seconds SECOND WHERE StationUuid=:stationuuid",
+            list.iter().map(|(stationuuid, seconds)| params!{stationuuid, seconds}),
+        )?;
+        Ok(())
+    }
+
+    fn get_check_queue(&self, hours: u32) -> Result<(u64, u64), Box<dyn Error>> {
+        let query = format!("SELECT COUNT(*), MAX(TIMESTAMPDIFF(SECOND, {due}, UTC_TIMESTAMP())) FROM Station WHERE {due} IS NULL OR {due} <= UTC_TIMESTAMP()", due = CHECK_DUE);
+        let mut conn = self.pool.get_conn()?;
+        let row: Option<(u64, Option<u64>)> = conn.exec_first(query, params!(hours))?;
+        Ok(row.map(|(depth, lag)| (depth, lag.unwrap_or(0))).unwrap_or((0, 0)))
+    }
+
     fn get_servers_to_check(
         &mut self,
         hours: u32,
@@ -832,8 +938,8 @@ impl DbConnection for MysqlConnection {
 
     fn get_station_by_uuid(&self, id_str: &str) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
         let query = format!(
-            "SELECT {columns} from Station WHERE StationUuid=? ORDER BY Name",
-            columns = MysqlConnection::COLUMNS
+            "SELECT {columns} from Station{now_playing} WHERE StationUuid=? ORDER BY Name",
+            columns = MysqlConnection::COLUMNS, now_playing = now_playing_join()
         );
         let mut conn = self.pool.get_conn()?;
         let results = conn.exec_iter(query, (id_str,))?;
@@ -846,9 +952,9 @@ impl DbConnection for MysqlConnection {
 
         if search_query.len() > 0 {
             let query_select_stations_by_uuid = format!(
-                "SELECT {columns} FROM Station WHERE StationUuid IN ({items})",
+                "SELECT {columns} FROM Station{now_playing} WHERE StationUuid IN ({items})",
                 items = search_query.join(","),
-                columns = MysqlConnection::COLUMNS
+                columns = MysqlConnection::COLUMNS, now_playing = now_playing_join()
             );
             let mut conn = self.pool.get_conn()?;
             let result = conn.exec_iter(query_select_stations_by_uuid, search_params)?;
@@ -872,8 +978,8 @@ impl DbConnection for MysqlConnection {
         limit: u32,
     ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
         self.get_stations_query(format!(
-            "SELECT {columns} from Station WHERE LastCheckOK=FALSE ORDER BY rand() LIMIT {offset},{limit}",
-            columns = MysqlConnection::COLUMNS,
+            "SELECT {columns} from Station{now_playing} WHERE LastCheckOK=FALSE ORDER BY rand() LIMIT {offset},{limit}",
+            columns = MysqlConnection::COLUMNS, now_playing = now_playing_join(),
             offset = offset,
             limit = limit
         ))
@@ -892,8 +998,8 @@ impl DbConnection for MysqlConnection {
             ""
         };
         query = format!(
-            "SELECT {columns} from Station {where} ORDER BY Votes DESC LIMIT {offset},{limit}",
-            columns = MysqlConnection::COLUMNS,
+            "SELECT {columns} from Station{now_playing} {where} ORDER BY Votes DESC LIMIT {offset},{limit}",
+            columns = MysqlConnection::COLUMNS, now_playing = now_playing_join(),
             where = hidebroken_string,
             offset = offset,
             limit = limit
@@ -914,8 +1020,8 @@ impl DbConnection for MysqlConnection {
             ""
         };
         query = format!(
-            "SELECT {columns} from Station {where} ORDER BY clickcount DESC LIMIT {offset},{limit}",
-            columns = MysqlConnection::COLUMNS,
+            "SELECT {columns} from Station{now_playing} {where} ORDER BY clickcount DESC LIMIT {offset},{limit}",
+            columns = MysqlConnection::COLUMNS, now_playing = now_playing_join(),
             where = hidebroken_string,
             offset = offset,
             limit = limit
@@ -936,8 +1042,8 @@ impl DbConnection for MysqlConnection {
             ""
         };
         query = format!(
-            "SELECT {columns} from Station {where} ORDER BY ClickTimestamp DESC LIMIT {offset},{limit}",
-            columns = MysqlConnection::COLUMNS,
+            "SELECT {columns} from Station{now_playing} {where} ORDER BY ClickTimestamp DESC LIMIT {offset},{limit}",
+            columns = MysqlConnection::COLUMNS, now_playing = now_playing_join(),
             where = hidebroken_string,
             offset = offset,
             limit = limit
@@ -958,8 +1064,8 @@ impl DbConnection for MysqlConnection {
             ""
         };
         query = format!(
-            "SELECT {columns} from Station {where} ORDER BY Creation DESC LIMIT {offset},{limit}",
-            columns = MysqlConnection::COLUMNS,
+            "SELECT {columns} from Station{now_playing} {where} ORDER BY Creation DESC LIMIT {offset},{limit}",
+            columns = MysqlConnection::COLUMNS, now_playing = now_playing_join(),
             where = hidebroken_string,
             offset = offset,
             limit = limit
@@ -975,26 +1081,26 @@ impl DbConnection for MysqlConnection {
         order: &str,
         reverse: bool,
         hidebroken: bool,
+        fields: Option<&StationFields>,
+        cursor: Option<&StationCursor>,
         offset: u32,
         limit: u32,
     ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
-        let order = filter_order(order);
-        let reverse_string = if reverse { "DESC" } else { "ASC" };
-        let hidebroken_string = if hidebroken {
-            " AND LastCheckOK=TRUE"
-        } else {
-            ""
-        };
-        let query: String = if exact {
-            format!("SELECT {columns} from Station WHERE LOWER({column_name})=? {hidebroken} ORDER BY {order} {reverse} LIMIT {offset},{limit}", columns = MysqlConnection::COLUMNS, order = order, reverse = reverse_string, hidebroken = hidebroken_string, offset = offset, limit = limit, column_name = column_name)
-        } else {
-            format!("SELECT {columns} from Station WHERE LOWER({column_name}) LIKE CONCAT('%',?,'%') {hidebroken} ORDER BY {order} {reverse} LIMIT {offset},{limit}", columns = MysqlConnection::COLUMNS, order = order, reverse = reverse_string, hidebroken = hidebroken_string, offset = offset, limit = limit, column_name = column_name)
-        };
+        let query = format!("SELECT {columns} from Station{now_playing} WHERE {condition}{cursor} ORDER BY {order} LIMIT {offset},{limit}",
+            columns = station_columns(fields, order), now_playing = now_playing_join(), condition = column_condition(column_name, exact, hidebroken),
+            cursor = cursor_condition(cursor, order, false), order = sql_order(filter_order(order), reverse), offset = offset, limit = limit);
+        let mut params = vec![Value::from(search.to_lowercase())];
+        params.extend(cursor_positional_params(cursor));
         let mut conn = self.pool.get_conn()?;
-        let results = conn.exec_iter(query, (search.to_lowercase(),))?;
+        let results = conn.exec_iter(query, params)?;
         self.get_list_from_query_result(results)
     }
 
+    fn count_stations_by_column(&self, column_name: &str, search: String, exact: bool, hidebroken: bool) -> Result<u64, Box<dyn Error>> {
+        let query = format!("SELECT COUNT(*) FROM Station WHERE {condition}", condition = column_condition(column_name, exact, hidebroken));
+        self.get_single_column_number_params(&query, Params::from(vec![Value::from(search.to_lowercase())]))
+    }
+
     fn get_stations_by_column_multiple(
         &self,
         column_name: &str,
@@ -1003,43 +1109,26 @@ impl DbConnection for MysqlConnection {
         order: &str,
         reverse: bool,
         hidebroken: bool,
+        fields: Option<&StationFields>,
+        cursor: Option<&StationCursor>,
         offset: u32,
         limit: u32,
     ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
-        let order = filter_order(order);
-        let reverse_string = if reverse { "DESC" } else { "ASC" };
-        let hidebroken_string = if hidebroken {
-            " AND LastCheckOK=TRUE"
-        } else {
-            ""
-        };
-        let query: String = if exact {
-            format!(
-                r"SELECT {columns} from Station WHERE ({column_name}=?
-             OR {column_name} LIKE CONCAT('%,',?,',%')
-             OR {column_name} LIKE CONCAT(?,',%')
-             OR {column_name} LIKE CONCAT('%,',?))
-             {hidebroken} ORDER BY {order} {reverse} LIMIT {offset},{limit}",
-                columns = MysqlConnection::COLUMNS,
-                order = order,
-                reverse = reverse_string,
-                hidebroken = hidebroken_string,
-                offset = offset,
-                limit = limit,
-                column_name = column_name
-            )
-        } else {
-            format!("SELECT {columns} from Station WHERE {column_name} LIKE CONCAT('%',?,'%') {hidebroken} ORDER BY {order} {reverse} LIMIT {offset},{limit}", columns = MysqlConnection::COLUMNS, order = order, reverse = reverse_string, hidebroken = hidebroken_string, offset = offset, limit = limit, column_name = column_name)
-        };
+        let query = format!("SELECT {columns} from Station{now_playing} WHERE {condition}{cursor} ORDER BY {order} LIMIT {offset},{limit}",
+            columns = station_columns(fields, order), now_playing = now_playing_join(), condition = column_multiple_condition(column_name, exact, hidebroken),
+            cursor = cursor_condition(cursor, order, false), order = sql_order(filter_order(order), reverse), offset = offset, limit = limit);
+        let mut params = column_multiple_params(search, exact);
+        params.extend(cursor_positional_params(cursor));
         let mut conn = self.pool.get_conn()?;
-        let results = if exact {
-            conn.exec_iter(query, (&search, &search, &search, &search))?
-        } else {
-            conn.exec_iter(query, (search,))?
-        };
+        let results = conn.exec_iter(query, params)?;
         self.get_list_from_query_result(results)
     }
 
+    fn count_stations_by_column_multiple(&self, column_name: &str, search: Option<String>, exact: bool, hidebroken: bool) -> Result<u64, Box<dyn Error>> {
+        let query = format!("SELECT COUNT(*) FROM Station WHERE {condition}", condition = column_multiple_condition(column_name, exact, hidebroken));
+        self.get_single_column_number_params(&query, Params::from(column_multiple_params(search, exact)))
+    }
+
     fn get_stations_by_server_uuids(
         &self,
         uuids: Vec<String>,
@@ -1058,7 +1147,7 @@ impl DbConnection for MysqlConnection {
         };
         let uuids_query: Vec<&str> = (0..uuids.len()).map(|_item| "?").collect();
         let uuids_str = uuids_query.join(",");
-        let query = format!("SELECT {columns} from Station WHERE ServerUuid IN ({search}) {hidebroken} ORDER BY {order} {reverse} LIMIT {offset},{limit}", columns = MysqlConnection::COLUMNS, order = order, reverse = reverse_string, hidebroken = hidebroken_string, offset = offset, limit = limit, search = uuids_str);
+        let query = format!("SELECT {columns} from Station{now_playing} WHERE ServerUuid IN ({search}) {hidebroken} ORDER BY {order} {reverse} LIMIT {offset},{limit}", columns = MysqlConnection::COLUMNS, now_playing = now_playing_join(), order = order, reverse = reverse_string, hidebroken = hidebroken_string, offset = offset, limit = limit, search = uuids_str);
         let mut conn = self.pool.get_conn()?;
         let results = conn.exec_iter(query, uuids)?;
         self.get_list_from_query_result(results)
@@ -1069,23 +1158,50 @@ impl DbConnection for MysqlConnection {
         order: &str,
         reverse: bool,
         hidebroken: bool,
+        fields: Option<&StationFields>,
+        cursor: Option<&StationCursor>,
         offset: u32,
         limit: u32,
     ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
-        let order = filter_order(order);
-        let reverse_string = if reverse { "DESC" } else { "ASC" };
+        let hidebroken_string = if hidebroken {
+            " AND LastCheckOK=TRUE"
+        } else {
+            ""
+        };
+
+        let query: String = format!("SELECT {columns} from Station{now_playing} WHERE TRUE{hidebroken}{cursor} ORDER BY {order} LIMIT {offset},{limit}",
+            columns = station_columns(fields, order), now_playing = now_playing_join(), order = sql_order(filter_order(order), reverse),
+            hidebroken = hidebroken_string, cursor = cursor_condition(cursor, order, false), offset = offset, limit = limit);
+        let mut conn = self.pool.get_conn()?;
+        let results = conn.exec_iter(query, cursor_positional_params(cursor))?;
+        self.get_list_from_query_result(results)
+    }
+
+    fn for_each_station_by_all(
+        &self,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        fields: Option<&StationFields>,
+        offset: u32,
+        limit: u32,
+        callback: &mut dyn FnMut(DbStationItem) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
         let hidebroken_string = if hidebroken {
             " WHERE LastCheckOK=TRUE"
         } else {
             ""
         };
 
-        let query: String = format!("SELECT {columns} from Station {hidebroken} ORDER BY {order} {reverse} LIMIT {offset},{limit}",
-            columns = MysqlConnection::COLUMNS, order = order, reverse = reverse_string,
+        let query: String = format!("SELECT {columns} from Station{now_playing}{hidebroken} ORDER BY {order} LIMIT {offset},{limit}",
+            columns = station_columns(fields, order), now_playing = now_playing_join(), order = sql_order(filter_order(order), reverse),
             hidebroken = hidebroken_string, offset = offset, limit = limit);
         let mut conn = self.pool.get_conn()?;
-        let results = conn.exec_iter(query, ())?;
-        self.get_list_from_query_result(results)
+        // rows are read from the socket while iterating, so only one station is in memory at a time
+        for result in conn.exec_iter(query, ())? {
+            callback(result?.into())?;
+        }
+        Ok(())
     }
 
     fn get_stations_advanced(
@@ -1108,142 +1224,167 @@ impl DbConnection for MysqlConnection {
         has_geo_info: Option<bool>,
         has_extended_info: Option<bool>,
         is_https: Option<bool>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+        geo_bbox: Option<GeoBoundingBox>,
         order: &str,
         reverse: bool,
         hidebroken: bool,
+        fields: Option<&StationFields>,
+        cursor: Option<&StationCursor>,
         offset: u32,
         limit: u32,
     ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
-        let order = filter_order(order);
-        let reverse_string = if reverse { "DESC" } else { "ASC" };
-        let hidebroken_string = if hidebroken {
-            " AND LastCheckOK=TRUE"
-        } else {
-            ""
-        };
-        let mut query = format!(
-            "SELECT {columns} from Station WHERE",
-            columns = MysqlConnection::COLUMNS
-        );
-        query.push_str(" Bitrate >= :bitrate_min AND Bitrate <= :bitrate_max");
-        match has_geo_info {
-            Some(has_geo_info) => {
-                if has_geo_info {
-                    query.push_str(" AND GeoLat IS NOT NULL AND GeoLong IS NOT NULL");
-                } else {
-                    query.push_str(" AND (GeoLat IS NULL OR GeoLong IS NULL)");
-                }
-            }
-            None => {}
-        }
-        match has_extended_info {
-            Some(has_extended_info) => {
-                if has_extended_info {
-                    query.push_str(" AND ExtendedInfo=1");
-                } else {
-                    query.push_str(" AND ExtendedInfo=0");
-                }
-            }
-            None => {}
-        }
-        match is_https {
-            Some(is_https) => {
-                if is_https {
-                    query.push_str(" AND UrlCache LIKE 'https://%'");
-                } else {
-                    query.push_str(" AND UrlCache LIKE 'http://%'");
-                }
-            }
-            None => {}
-        }
-        if name.is_some() {
-            if name_exact {
-                query.push_str(" AND Name=:name");
-            } else {
-                query.push_str(" AND Name LIKE CONCAT('%',:name,'%')");
-            }
-        }
-        if country.is_some() {
-            if country_exact {
-                query.push_str(" AND Country=:country");
-            } else {
-                query.push_str(" AND Country LIKE CONCAT('%',:country,'%')");
-            }
-        }
-        if countrycode.is_some() {
-            query.push_str(" AND UPPER(CountryCode)=UPPER(:countrycode)");
-        }
-        if state.is_some() {
-            if state_exact {
-                query.push_str(" AND Subcountry=:state");
-            } else {
-                query.push_str(" AND Subcountry LIKE CONCAT('%',:state,'%')");
+        let (condition, mut params) = advanced_condition(name, name_exact, country, country_exact, countrycode, state, state_exact,
+            language, language_exact, tag, tag_exact, tag_list, codec, bitrate_min, bitrate_max, has_geo_info, has_extended_info, is_https,
+            geo_lat, geo_long, geo_distance, geo_bbox, hidebroken);
+        if let Some(cursor) = cursor {
+            for (key, value) in cursor.named_params() {
+                params.push((key.to_string(), cursor_value(value)));
             }
         }
-        if language.is_some() {
-            if language_exact {
-                query.push_str(" AND ( Language=:language OR Language LIKE CONCAT('%,',:language,',%') OR Language LIKE CONCAT('%,',:language) OR Language LIKE CONCAT(:language,',%'))");
-            } else {
-                query.push_str(" AND Language LIKE CONCAT('%',:language,'%')");
-            }
-        }
-        if tag.is_some() {
-            if tag_exact {
-                query.push_str(" AND ( Tags=:tag OR Tags LIKE CONCAT('%,',:tag,',%') OR Tags LIKE CONCAT('%,',:tag) OR Tags LIKE CONCAT(:tag,',%'))");
-            } else {
-                query.push_str(" AND Tags LIKE CONCAT('%',:tag,'%')");
+        let (query_offset, query_limit) = query_range(geo_lat, geo_long, geo_distance, order, offset, limit);
+        let query = format!(
+            "SELECT {columns} from Station{now_playing} WHERE{condition}{cursor} ORDER BY {order} LIMIT {offset},{limit}",
+            columns = station_columns(fields, order), now_playing = now_playing_join(),
+            condition = condition,
+            cursor = cursor_condition(cursor, order, true),
+            order = sql_order(filter_order(order), reverse),
+            offset = query_offset,
+            limit = query_limit
+        );
+        let mut conn = self.pool.get_conn()?;
+        let results = conn.exec_iter(query, params)?;
+        let list = self.get_list_from_query_result(results)?;
+        Ok(finish_query(list, geo_lat, geo_long, geo_distance, order, reverse, offset, limit))
+    }
+
+    fn count_stations_advanced(
+        &self,
+        name: Option<String>,
+        name_exact: bool,
+        country: Option<String>,
+        country_exact: bool,
+        countrycode: Option<String>,
+        state: Option<String>,
+        state_exact: bool,
+        language: Option<String>,
+        language_exact: bool,
+        tag: Option<String>,
+        tag_exact: bool,
+        tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,
+        bitrate_max: u32,
+        has_geo_info: Option<bool>,
+        has_extended_info: Option<bool>,
+        is_https: Option<bool>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+        geo_bbox: Option<GeoBoundingBox>,
+        hidebroken: bool,
+    ) -> Result<u64, Box<dyn Error>> {
+        let (condition, params) = advanced_condition(name, name_exact, country, country_exact, countrycode, state, state_exact,
+            language, language_exact, tag, tag_exact, tag_list, codec, bitrate_min, bitrate_max, has_geo_info, has_extended_info, is_https,
+            geo_lat, geo_long, geo_distance, geo_bbox, hidebroken);
+        self.count_stations_where(&condition, params, geo_lat, geo_long, geo_distance)
+    }
+
+    fn get_stations_search(
+        &self,
+        query: &str,
+        name: Option<String>,
+        name_exact: bool,
+        country: Option<String>,
+        country_exact: bool,
+        countrycode: Option<String>,
+        state: Option<String>,
+        state_exact: bool,
+        language: Option<String>,
+        language_exact: bool,
+        tag: Option<String>,
+        tag_exact: bool,
+        tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,
+        bitrate_max: u32,
+        has_geo_info: Option<bool>,
+        has_extended_info: Option<bool>,
+        is_https: Option<bool>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+        geo_bbox: Option<GeoBoundingBox>,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        cursor: Option<&StationCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let (condition, mut params) = advanced_condition(name, name_exact, country, country_exact, countrycode, state, state_exact,
+            language, language_exact, tag, tag_exact, tag_list, codec, bitrate_min, bitrate_max, has_geo_info, has_extended_info, is_https,
+            geo_lat, geo_long, geo_distance, geo_bbox, hidebroken);
+        let (search_condition, relevance, search_params) = search_condition(query);
+        params.extend(search_params);
+        if let Some(cursor) = cursor {
+            for (key, value) in cursor.named_params() {
+                params.push((key.to_string(), cursor_value(value)));
             }
         }
-        if codec.is_some() {
-            query.push_str(" AND LOWER(Codec)=LOWER(:codec)");
-        }
-        let mut params: Vec<(String, Value)> = vec![
-            (String::from("name"), Value::from(name.unwrap_or_default())),
-            (
-                String::from("country"),
-                Value::from(country.unwrap_or_default()),
-            ),
-            (
-                String::from("countrycode"),
-                Value::from(countrycode.unwrap_or_default()),
-            ),
-            (
-                String::from("state"),
-                Value::from(state.unwrap_or_default()),
-            ),
-            (
-                String::from("language"),
-                Value::from(language.unwrap_or_default()),
-            ),
-            (String::from("tag"), Value::from(tag.unwrap_or_default())),
-            (
-                String::from("codec"),
-                Value::from(codec.unwrap_or_default()),
-            ),
-            (String::from("bitrate_min"), Value::from(bitrate_min)),
-            (String::from("bitrate_max"), Value::from(bitrate_max)),
-        ];
-        let mut i = 0;
-        for tag in tag_list {
-            if tag_exact {
-                query.push_str(&format!(" AND ( Tags=:tag{i} OR Tags LIKE CONCAT('%,',:tag{i},',%') OR Tags LIKE CONCAT('%,',:tag{i}) OR Tags LIKE CONCAT(:tag{i},',%'))",i=i));
-            } else {
-                query.push_str(&format!(" AND Tags LIKE CONCAT('%',:tag{i},'%')", i = i));
-            }
-            params.push((format!("tag{i}", i = i), Value::from(tag)));
-            i += 1;
-        }
-        query.push_str(&format!(
-            " {hidebroken} ORDER BY {order} {reverse} LIMIT {offset},{limit}",
-            order = order,
-            reverse = reverse_string,
-            hidebroken = hidebroken_string,
-            offset = offset,
-            limit = limit
-        ));
+        let (query_offset, query_limit) = query_range(geo_lat, geo_long, geo_distance, order, offset, limit);
+        let query = format!(
+            "SELECT {columns},{score} AS Score from Station{now_playing} WHERE{condition}{search}{cursor} ORDER BY {order} LIMIT {offset},{limit}",
+            columns = MysqlConnection::COLUMNS, now_playing = now_playing_join(),
+            score = sql_score(&relevance),
+            condition = condition,
+            search = search_condition,
+            cursor = cursor_condition(cursor, order, true),
+            order = search_order(order, reverse),
+            offset = query_offset,
+            limit = query_limit
+        );
         let mut conn = self.pool.get_conn()?;
         let results = conn.exec_iter(query, params)?;
-        self.get_list_from_query_result(results)
+        let list = self.get_list_from_query_result(results)?;
+        Ok(finish_query(list, geo_lat, geo_long, geo_distance, order, reverse, offset, limit))
+    }
+
+    fn count_stations_search(
+        &self,
+        query: &str,
+        name: Option<String>,
+        name_exact: bool,
+        country: Option<String>,
+        country_exact: bool,
+        countrycode: Option<String>,
+        state: Option<String>,
+        state_exact: bool,
+        language: Option<String>,
+        language_exact: bool,
+        tag: Option<String>,
+        tag_exact: bool,
+        tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,
+        bitrate_max: u32,
+        has_geo_info: Option<bool>,
+        has_extended_info: Option<bool>,
+        is_https: Option<bool>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+        geo_bbox: Option<GeoBoundingBox>,
+        hidebroken: bool,
+    ) -> Result<u64, Box<dyn Error>> {
+        let (condition, mut params) = advanced_condition(name, name_exact, country, country_exact, countrycode, state, state_exact,
+            language, language_exact, tag, tag_exact, tag_list, codec, bitrate_min, bitrate_max, has_geo_info, has_extended_info, is_https,
+            geo_lat, geo_long, geo_distance, geo_bbox, hidebroken);
+        let (search_condition, _, search_params) = search_condition(query);
+        params.extend(search_params);
+        self.count_stations_where(&format!("{}{}", condition, search_condition), params, geo_lat, geo_long, geo_distance)
     }
 
     fn get_changes(
@@ -1335,62 +1476,171 @@ impl DbConnection for MysqlConnection {
         geo_lat: Option<f64>,
         geo_long: Option<f64>,
     ) -> Result<String, Box<dyn Error>> {
+        let station = check_new_station(name, url, homepage, favicon, countrycode, state, language, languagecodes, tags, geo_lat, geo_long)?;
+
         let mut transaction = self.pool.start_transaction(TxOpts::default())?;
+        MysqlConnection::insert_new_station(&mut transaction, &station)?;
+        transaction.commit()?;
 
-        let countrycode: String = countrycode.unwrap_or_default().to_uppercase();
-        let country: String = String::from(
-            Country::from_alpha2(&countrycode)
-                .map(|c| c.long_name)
-                .unwrap_or(""),
-        );
+        Ok(station.stationuuid)
+    }
+
+    fn add_pending_station_opt(
+        &self,
+        name: Option<String>,
+        url: Option<String>,
+        homepage: Option<String>,
+        favicon: Option<String>,
+        countrycode: Option<String>,
+        state: Option<String>,
+        language: Option<String>,
+        languagecodes: Option<String>,
+        tags: Option<String>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+    ) -> Result<String, Box<dyn Error>> {
+        let station = check_new_station(name, url, homepage, favicon, countrycode, state, language, languagecodes, tags, geo_lat, geo_long)?;
 
-        let query = format!("INSERT INTO Station(Name,Url,Homepage,Favicon,Country,CountryCode,Subcountry,Language,LanguageCodes,Tags,ChangeUuid,StationUuid,GeoLat,GeoLong,UrlCache,Creation) 
-                        VALUES(:name, :url, :homepage, :favicon, :country, :countrycode, :state, :language, :languagecodes, :tags, :changeuuid, :stationuuid, :geo_lat, :geo_long, '', UTC_TIMESTAMP())");
+        let query = "INSERT INTO StationPending(StationUuid,Name,Url,Homepage,Favicon,Country,CountryCode,Subcountry,Language,LanguageCodes,Tags,GeoLat,GeoLong,Creation)
+                        VALUES(:stationuuid, :name, :url, :homepage, :favicon, :country, :countrycode, :state, :language, :languagecodes, :tags, :geo_lat, :geo_long, UTC_TIMESTAMP())";
+        let mut conn = self.pool.get_conn()?;
+        conn.exec_drop(
+            query,
+            params! {
+                "stationuuid" => &station.stationuuid,
+                "name" => station.name,
+                "url" => station.url,
+                "homepage" => station.homepage,
+                "favicon" => station.favicon,
+                "country" => station.country,
+                "countrycode" => station.countrycode,
+                "state" => station.state,
+                "language" => station.language,
+                "languagecodes" => station.languagecodes,
+                "tags" => station.tags,
+                "geo_lat" => station.geo_lat,
+                "geo_long" => station.geo_long,
+            },
+        )?;
 
-        let name = name.ok_or(DbError::AddStationError(String::from("name is empty")))?;
-        let url = url.map(|x| fix_url(&x, false)).transpose()?;
-        let homepage = homepage.map(|x| fix_url(&x, true)).transpose()?;
+        Ok(station.stationuuid)
+    }
 
-        if countrycode.len() != 2 {
-            return Err(Box::new(DbError::AddStationError(String::from(
-                "countrycode does not have exactly 2 chars",
-            ))));
+    fn get_pending_stations(&self, offset: u32, limit: u32) -> Result<Vec<StationAddItem>, Box<dyn Error>> {
+        let query = format!("SELECT {columns} FROM StationPending ORDER BY PendingID LIMIT {offset},{limit}", columns = MysqlConnection::COLUMNS_PENDING, offset = offset, limit = limit);
+        let mut conn = self.pool.get_conn()?;
+        let results = conn.exec_iter(query, ())?;
+        self.get_list_from_query_result(results)
+    }
+
+    fn get_pending_stations_by_url(&self, urls: &[String]) -> Result<Vec<StationAddItem>, Box<dyn Error>> {
+        if urls.is_empty() {
+            return Ok(vec![]);
         }
+        let items: Vec<&str> = urls.iter().map(|_| "?").collect();
+        let query = format!("SELECT {columns} FROM StationPending WHERE Url IN ({items})", columns = MysqlConnection::COLUMNS_PENDING, items = items.join(","));
+        let params: Vec<Value> = urls.iter().map(|url| url.clone().into()).collect();
+        let mut conn = self.pool.get_conn()?;
+        let results = conn.exec_iter(query, params)?;
+        self.get_list_from_query_result(results)
+    }
+
+    fn approve_pending_station(&self, stationuuid: &str) -> Result<(), Box<dyn Error>> {
+        let mut transaction = self.pool.start_transaction(TxOpts::default())?;
+        let query = format!("SELECT {columns} FROM StationPending WHERE StationUuid=:stationuuid FOR UPDATE", columns = MysqlConnection::COLUMNS_PENDING);
+        let row: Option<Row> = transaction.exec_first(query, params! { "stationuuid" => stationuuid })?;
+        let station: StationAddItem = row
+            .map(|row| row.into())
+            .ok_or(DbError::ModerationError(String::from("station is not in the review queue")))?;
+        MysqlConnection::insert_new_station(&mut transaction, &station)?;
+        transaction.exec_drop("DELETE FROM StationPending WHERE StationUuid=:stationuuid", params! { "stationuuid" => stationuuid })?;
+        transaction.commit()?;
+        Ok(())
+    }
 
-        if name.len() > 400 {
-            return Err(Box::new(DbError::AddStationError(String::from(
-                "name is longer than 400 chars",
-            ))));
+    fn reject_pending_station(&self, stationuuid: &str) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.pool.get_conn()?;
+        let deleted = conn
+            .exec_iter("DELETE FROM StationPending WHERE StationUuid=:stationuuid", params! { "stationuuid" => stationuuid })?
+            .affected_rows();
+        if deleted == 0 {
+            return Err(Box::new(DbError::ModerationError(String::from("station is not in the review queue"))));
         }
+        Ok(())
+    }
 
-        let stationuuid = Uuid::new_v4().as_hyphenated().to_string();
-        let changeuuid = Uuid::new_v4().as_hyphenated().to_string();
-        let params = params! {
-            "name" => name,
-            "url" => url,
-            "homepage" => homepage.unwrap_or_default(),
-            "favicon" => favicon.unwrap_or_default(),
-            "country" => country,
-            "countrycode" => countrycode,
-            "state" => state.unwrap_or_default(),
-            "language" => fix_multi_field(&language.unwrap_or_default()),
-            "languagecodes" => fix_multi_field(&languagecodes.unwrap_or_default()),
-            "tags" => fix_multi_field(&tags.unwrap_or_default()),
-            "changeuuid" => changeuuid,
-            "stationuuid" => stationuuid.clone(),
-            "geo_lat" => geo_lat,
-            "geo_long" => geo_long,
-        };
+    fn edit_station_opt(
+        &self,
+        stationuuid: &str,
+        name: Option<String>,
+        url: Option<String>,
+        homepage: Option<String>,
+        favicon: Option<String>,
+        countrycode: Option<String>,
+        state: Option<String>,
+        language: Option<String>,
+        languagecodes: Option<String>,
+        tags: Option<String>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        source: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        // the row stays locked until the commit, a concurrent edit can not overwrite the fields in between
+        let mut transaction = self.pool.start_transaction(TxOpts::default())?;
+        let query = format!("SELECT {columns} FROM Station{now_playing} WHERE StationUuid=:stationuuid FOR UPDATE", columns = MysqlConnection::COLUMNS, now_playing = now_playing_join());
+        let row: Option<Row> = transaction.exec_first(query, params! { "stationuuid" => stationuuid })?;
+        let mut station: DbStationItem = row
+            .map(|row| row.into())
+            .ok_or(DbError::EditStationError(String::from("station not found")))?;
+        edit_station_fields(&mut station, name, url, homepage, favicon, countrycode, state, language, languagecodes, tags, geo_lat, geo_long)?;
 
-        transaction.exec_drop(query, params)?;
+        let query = r#"UPDATE Station SET
+                Name=:name,
+                Url=:url,
+                UrlCache=:url_resolved,
+                Homepage=:homepage,
+                Favicon=:favicon,
+                Country=:country,
+                CountryCode=:countrycode,
+                Subcountry=:state,
+                Language=:language,
+                LanguageCodes=:languagecodes,
+                Tags=:tags,
+                GeoLat=:geo_lat,
+                GeoLong=:geo_long,
+                Creation=UTC_TIMESTAMP(),
+                ChangeUuid=:changeuuid
+            WHERE
+                StationUuid=:stationuuid"#;
+        let changeuuid = Uuid::new_v4().as_hyphenated().to_string();
+        transaction.exec_drop(
+            query,
+            params! {
+                "name" => &station.name,
+                "url" => &station.url,
+                "url_resolved" => &station.url_resolved,
+                "homepage" => &station.homepage,
+                "favicon" => &station.favicon,
+                "country" => &station.country,
+                "countrycode" => &station.countrycode,
+                "state" => &station.state,
+                "language" => &station.language,
+                "languagecodes" => &station.languagecodes,
+                "tags" => &station.tags,
+                "geo_lat" => &station.geo_lat,
+                "geo_long" => &station.geo_long,
+                "changeuuid" => &changeuuid,
+                "stationuuid" => &station.stationuuid,
+            },
+        )?;
         MysqlConnection::backup_stations_by_uuid(
             &mut transaction,
-            &(vec![stationuuid.clone()]),
-            "INITIAL",
+            &(vec![station.stationuuid.clone()]),
+            source,
         )?;
         transaction.commit()?;
 
-        Ok(stationuuid)
+        Ok(changeuuid)
     }
 
     fn get_pull_server_lastid(&self, server: &str) -> Result<Option<String>, Box<dyn Error>> {
@@ -1654,12 +1904,12 @@ impl DbConnection for MysqlConnection {
                     // reuse checkuuid
                     match &item.timestamp {
                         Some(timestamp) => {
-                            insert_station_check_query.push("(?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,UTC_TIMESTAMP(),?,?,?,?,?,?,?)");
+                            insert_station_check_query.push("(?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,UTC_TIMESTAMP(),?,?,?,?,?,?,?,?,?,?)");
                             insert_station_check_params.push(checkuuid.into());
                             insert_station_check_params.push(timestamp.into());
                         }
                         None => {
-                            insert_station_check_query.push("(?,UTC_TIMESTAMP(),?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,UTC_TIMESTAMP(),?,?,?,?,?,?,?)");
+                            insert_station_check_query.push("(?,UTC_TIMESTAMP(),?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,UTC_TIMESTAMP(),?,?,?,?,?,?,?,?,?,?)");
                             insert_station_check_params.push(checkuuid.into());
                         }
                     }
@@ -1668,11 +1918,11 @@ impl DbConnection for MysqlConnection {
                     // generate new checkuuid
                     match &item.timestamp {
                         Some(timestamp) => {
-                            insert_station_check_query.push("(UUID(),?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,UTC_TIMESTAMP(),?,?,?,?,?,?,?)");
+                            insert_station_check_query.push("(UUID(),?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,UTC_TIMESTAMP(),?,?,?,?,?,?,?,?,?,?)");
                             insert_station_check_params.push(timestamp.into());
                         }
                         None => {
-                            insert_station_check_query.push("(UUID(),UTC_TIMESTAMP(),?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,UTC_TIMESTAMP(),?,?,?,?,?,?,?)");
+                            insert_station_check_query.push("(UUID(),UTC_TIMESTAMP(),?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,UTC_TIMESTAMP(),?,?,?,?,?,?,?,?,?,?)");
                         }
                     }
                 }
@@ -1706,6 +1956,9 @@ impl DbConnection for MysqlConnection {
 
             insert_station_check_params.push(item.geo_lat.clone().into());
             insert_station_check_params.push(item.geo_long.clone().into());
+            insert_station_check_params.push(item.audio_bitrate.clone().into());
+            insert_station_check_params.push(item.audio_sampling.clone().into());
+            insert_station_check_params.push(item.audio_liveness.clone().into());
 
             inserted.push(item);
         }
@@ -1719,7 +1972,7 @@ impl DbConnection for MysqlConnection {
         if insert_station_check_query.len() > 0 {
             let insert_station_check_params_str = insert_station_check_query.join(",");
             let query_insert_station_check_history = format!("INSERT INTO StationCheckHistory(CheckUuid,CheckTime,StationUuid,Source,Codec,Bitrate,Hls,CheckOK,UrlCache,
-                MetainfoOverridesDatabase,Public,Name,Description,Tags,CountryCode,Homepage,Favicon,Loadbalancer,DoNotIndex,InsertTime,ServerSoftware,Sampling,LanguageCodes,TimingMs,CountrySubdivisionCode,GeoLat,GeoLong) VALUES{}", insert_station_check_params_str);
+                MetainfoOverridesDatabase,Public,Name,Description,Tags,CountryCode,Homepage,Favicon,Loadbalancer,DoNotIndex,InsertTime,ServerSoftware,Sampling,LanguageCodes,TimingMs,CountrySubdivisionCode,GeoLat,GeoLong,AudioBitrate,AudioSampling,AudioLiveness) VALUES{}", insert_station_check_params_str);
             transaction.exec_drop(
                 query_insert_station_check_history,
                 insert_station_check_params,
@@ -1738,6 +1991,7 @@ impl DbConnection for MysqlConnection {
         &self,
         list: &Vec<StationCheckItemNew>,
         local: bool,
+        consensus: &CheckConsensus,
     ) -> Result<(), Box<dyn std::error::Error>> {
         trace!("update_station_with_check_data()");
         let mut transaction = self.pool.start_transaction(TxOpts::default())?;
@@ -1753,15 +2007,22 @@ impl DbConnection for MysqlConnection {
 
         let mut majority_vote: HashMap<String, bool> = HashMap::new();
         if list.len() > 0 {
-            // calculate majority vote for checks
+            // consensus over the current check of every source, most recent first
             let result = transaction.exec_iter(
-                format!("SELECT StationUuid,ROUND(AVG(CheckOk)) AS result FROM StationCheck WHERE StationUuid IN ({uuids}) GROUP BY StationUuid", uuids = query_in),
+                format!("SELECT StationUuid,CheckOK,TIMESTAMPDIFF(SECOND,CheckTime,UTC_TIMESTAMP()) FROM StationCheck WHERE StationUuid IN ({uuids}) ORDER BY CheckID DESC", uuids = query_in),
                 list_station_uuid
             )?;
 
+            let mut results: HashMap<String, Vec<bool>> = HashMap::new();
             for row in result {
-                let (stationuuid, result): (String, u8) = mysql::from_row_opt(row?)?;
-                majority_vote.insert(stationuuid, result == 1);
+                let (stationuuid, check_ok, age): (String, u8, Option<i64>) = mysql::from_row_opt(row?)?;
+                let results = results.entry(stationuuid).or_default();
+                if consensus.is_recent(results.len(), age.unwrap_or(i64::MAX)) {
+                    results.push(check_ok == 1);
+                }
+            }
+            for (stationuuid, results) in results {
+                majority_vote.insert(stationuuid, consensus.is_working(&results));
             }
         }
 
@@ -1947,11 +2208,30 @@ impl DbConnection for MysqlConnection {
         self.get_list_from_query_result(results)
     }
 
+    fn get_check_history(&self, stationuuids: &[String], seconds: u32, limit: u32) -> Result<Vec<StationCheckItem>, Box<dyn Error>> {
+        if stationuuids.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut select_params: Vec<Value> = vec![];
+        let mut select_query = vec![];
+        for stationuuid in stationuuids {
+            select_params.push(stationuuid.into());
+            select_query.push("?");
+        }
+        let query = format!("SELECT * FROM (SELECT {columns} FROM StationCheckHistory WHERE StationUuid IN ({uuids}) AND TIMESTAMPDIFF(SECOND,CheckTime,UTC_TIMESTAMP())<{seconds} ORDER BY CheckID DESC LIMIT {limit}) AS History ORDER BY CheckID", columns = MysqlConnection::COLUMNS_CHECK, uuids = select_query.join(","), seconds = seconds, limit = limit);
+        trace!("get_check_history() {}", query);
+        let mut conn = self.pool.get_conn()?;
+        let results = conn.exec_iter(query, select_params)?;
+
+        self.get_list_from_query_result(results)
+    }
+
     fn get_clicks(
         &self,
         stationuuid: Option<String>,
         clickuuid: Option<String>,
         seconds: u32,
+        limit: u32,
     ) -> Result<Vec<StationClickItem>, Box<dyn Error>> {
         let where_seconds = if seconds > 0 {
             format!(
@@ -1975,10 +2255,10 @@ impl DbConnection for MysqlConnection {
         let query = match stationuuid {
             Some(stationuuid) => {
                 query_params.push((String::from("stationuuid"), stationuuid.into()));
-                format!("SELECT {columns} FROM StationClick WHERE StationUuid=:stationuuid {where_clickuuid} {where_seconds} ORDER BY ClickID LIMIT 10000", columns = MysqlConnection::COLUMNS_CLICK, where_seconds = where_seconds, where_clickuuid = where_clickuuid_str)
+                format!("SELECT {columns} FROM StationClick WHERE StationUuid=:stationuuid {where_clickuuid} {where_seconds} ORDER BY ClickID LIMIT {limit}", columns = MysqlConnection::COLUMNS_CLICK, where_seconds = where_seconds, where_clickuuid = where_clickuuid_str, limit = limit)
             }
             None => {
-                format!("SELECT {columns} FROM StationClick WHERE 1=:one {where_clickuuid} {where_seconds} ORDER BY ClickID LIMIT 10000", columns = MysqlConnection::COLUMNS_CLICK, where_seconds = where_seconds, where_clickuuid = where_clickuuid_str)
+                format!("SELECT {columns} FROM StationClick WHERE 1=:one {where_clickuuid} {where_seconds} ORDER BY ClickID LIMIT {limit}", columns = MysqlConnection::COLUMNS_CLICK, where_seconds = where_seconds, where_clickuuid = where_clickuuid_str, limit = limit)
             }
         };
 
@@ -1989,6 +2269,18 @@ impl DbConnection for MysqlConnection {
         self.get_list_from_query_result(results)
     }
 
+    fn get_last_changeuuid(&self) -> Result<Option<String>, Box<dyn Error>> {
+        Ok(self.pool.get_conn()?.query_first("SELECT ChangeUuid FROM StationHistory ORDER BY StationChangeID DESC LIMIT 1")?)
+    }
+
+    fn get_last_checkuuid(&self) -> Result<Option<String>, Box<dyn Error>> {
+        Ok(self.pool.get_conn()?.query_first("SELECT CheckUuid FROM StationCheckHistory ORDER BY CheckID DESC LIMIT 1")?)
+    }
+
+    fn get_last_clickuuid(&self) -> Result<Option<String>, Box<dyn Error>> {
+        Ok(self.pool.get_conn()?.query_first("SELECT ClickUuid FROM StationClick ORDER BY ClickID DESC LIMIT 1")?)
+    }
+
     fn get_extra(
         &self,
         table_name: &str,
@@ -2386,11 +2678,17 @@ impl DbConnection for MysqlConnection {
 
     fn sync_votes(&self, list: Vec<Station>) -> Result<(), Box<dyn Error>> {
         trace!("sync_votes() 1");
+        if list.is_empty() {
+            return Ok(());
+        }
         let mut transaction = self.pool.start_transaction(TxOpts::default())?;
-        // get current list of votes in database
+        // get current votes of the stations in the list
         let mut stations_current: HashMap<String, i32> = HashMap::new();
         {
-            let result = transaction.exec_iter("SELECT StationUuid,Votes FROM Station", ())?;
+            let items: Vec<&str> = list.iter().map(|_| "?").collect();
+            let query = format!("SELECT StationUuid,Votes FROM Station WHERE StationUuid IN ({items})", items = items.join(","));
+            let params: Vec<Value> = list.iter().map(|station| station.stationuuid.clone().into()).collect();
+            let result = transaction.exec_iter(query, params)?;
             for row in result {
                 let (stationuuid, votes): (String, i32) = mysql::from_row_opt(row?)?;
                 stations_current.insert(stationuuid, votes);
@@ -2462,6 +2760,7 @@ impl DbConnection for MysqlConnection {
     fn select_station_check_steps_by_stations(
         &self,
         stationuuids: &[String],
+        limit: u32,
     ) -> Result<Vec<StationCheckStepItem>, Box<dyn std::error::Error>> {
         let mut conn = self.pool.get_conn()?;
         if stationuuids.len() > 0 {
@@ -2472,7 +2771,7 @@ impl DbConnection for MysqlConnection {
                 select_query.push("?");
             }
 
-            let query = format!("SELECT Id,StationUuid,CheckUuid,Url,UrlType,Error,StepUuid,ParentStepUuid,InsertTime FROM StationCheckStep WHERE StationUuid IN ({})", select_query.join(","));
+            let query = format!("SELECT Id,StationUuid,CheckUuid,Url,UrlType,Error,StepUuid,ParentStepUuid,InsertTime FROM StationCheckStep WHERE StationUuid IN ({}) ORDER BY Id LIMIT {}", select_query.join(","), limit);
             let list = conn.exec_map(
                 query,
                 select_params,
@@ -2517,14 +2816,377 @@ impl DbConnection for MysqlConnection {
         conn.exec_drop(delete_never_working_query, params!(seconds))?;
         Ok(())
     }
+
+    fn add_webhook(&self, webhook: &WebhookItem) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.pool.get_conn()?;
+        conn.exec_drop(
+            "INSERT INTO Webhook(WebhookUuid,Owner,Url,Secret,Events,StationUuid,Tag,CountryCode,Creation)
+              VALUES(:webhookuuid,:owner,:url,:secret,:events,:stationuuid,:tag,:countrycode,UTC_TIMESTAMP())",
+            params! {
+                "webhookuuid" => &webhook.webhookuuid,
+                "owner" => &webhook.owner,
+                "url" => &webhook.url,
+                "secret" => &webhook.secret,
+                "events" => &webhook.events,
+                "stationuuid" => &webhook.stationuuid,
+                "tag" => &webhook.tag,
+                "countrycode" => &webhook.countrycode,
+            },
+        )?;
+        Ok(())
+    }
+
+    fn get_webhooks(&self, owner: Option<&str>) -> Result<Vec<WebhookItem>, Box<dyn Error>> {
+        let mut conn = self.pool.get_conn()?;
+        let query = "SELECT WebhookUuid,Owner,Url,Secret,Events,StationUuid,Tag,CountryCode,Creation FROM Webhook WHERE (:owner IS NULL OR Owner=:owner) ORDER BY WebhookID";
+        let results = conn.exec_iter(query, params!(owner))?;
+        self.get_list_from_query_result(results)
+    }
+
+    fn delete_webhook(&self, webhookuuid: &str, owner: Option<&str>) -> Result<(), Box<dyn Error>> {
+        let mut transaction = self.pool.start_transaction(TxOpts::default())?;
+        let deleted = transaction
+            .exec_iter(
+                "DELETE FROM Webhook WHERE WebhookUuid=:webhookuuid AND (:owner IS NULL OR Owner=:owner)",
+                params!(webhookuuid, owner),
+            )?
+            .affected_rows();
+        if deleted == 0 {
+            return Err(Box::new(DbError::WebhookError(String::from("webhook not found"))));
+        }
+        transaction.exec_drop("DELETE FROM WebhookDelivery WHERE WebhookUuid=:webhookuuid", params!(webhookuuid))?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn insert_webhook_delivery(&self, webhookuuid: &str, event: &str, payload: &str) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.pool.get_conn()?;
+        conn.exec_drop(
+            "INSERT INTO WebhookDelivery(DeliveryUuid,WebhookUuid,Event,Payload,NextAttempt,Creation)
+              VALUES(:deliveryuuid,:webhookuuid,:event,:payload,UTC_TIMESTAMP(),UTC_TIMESTAMP())",
+            params! {
+                "deliveryuuid" => Uuid::new_v4().as_hyphenated().to_string(),
+                "webhookuuid" => webhookuuid,
+                "event" => event,
+                "payload" => payload,
+            },
+        )?;
+        Ok(())
+    }
+
+    fn get_due_webhook_deliveries(&self, limit: u32) -> Result<Vec<WebhookDeliveryItem>, Box<dyn Error>> {
+        let mut conn = self.pool.get_conn()?;
+        let query = "SELECT d.DeliveryUuid,d.WebhookUuid,w.Url,w.Secret,d.Event,d.Payload,d.Attempts FROM WebhookDelivery d
+              JOIN Webhook w ON w.WebhookUuid=d.WebhookUuid
+              WHERE d.NextAttempt <= UTC_TIMESTAMP() ORDER BY d.DeliveryID LIMIT :limit";
+        let results = conn.exec_iter(query, params!(limit))?;
+        self.get_list_from_query_result(results)
+    }
+
+    fn delete_webhook_delivery(&self, deliveryuuid: &str) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.pool.get_conn()?;
+        conn.exec_drop("DELETE FROM WebhookDelivery WHERE DeliveryUuid=:deliveryuuid", params!(deliveryuuid))?;
+        Ok(())
+    }
+
+    fn retry_webhook_delivery(&self, deliveryuuid: &str, seconds: u64, error: &str) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.pool.get_conn()?;
+        conn.exec_drop(
+            "UPDATE WebhookDelivery SET Attempts=Attempts+1,NextAttempt=UTC_TIMESTAMP() + INTERVAL :seconds SECOND,LastError=:error WHERE DeliveryUuid=:deliveryuuid",
+            params!(deliveryuuid, seconds, error),
+        )?;
+        Ok(())
+    }
+
+    fn set_now_playing(&self, titles: &[(String, String)]) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.pool.get_conn()?;
+        conn.exec_batch(
+            "INSERT INTO NowPlaying(StationUuid,Title,UpdateTime) VALUES(:stationuuid,:title,UTC_TIMESTAMP())
+              ON DUPLICATE KEY UPDATE Title=VALUES(Title),UpdateTime=VALUES(UpdateTime)",
+            titles.iter().map(|(stationuuid, title)| params!(stationuuid, title)),
+        )?;
+        conn.exec_drop("DELETE FROM NowPlaying WHERE UpdateTime <= UTC_TIMESTAMP() - INTERVAL :seconds SECOND", params! { "seconds" => NOW_PLAYING_MAX_AGE.as_secs() })?;
+        Ok(())
+    }
+
+    fn get_now_playing(&self, stationuuid: &str) -> Result<Option<NowPlayingItem>, Box<dyn Error>> {
+        let mut conn = self.pool.get_conn()?;
+        let query = "SELECT StationUuid,Title,UpdateTime FROM NowPlaying WHERE StationUuid=:stationuuid AND UpdateTime > UTC_TIMESTAMP() - INTERVAL :seconds SECOND";
+        let results = conn.exec_iter(query, params! { stationuuid, "seconds" => NOW_PLAYING_MAX_AGE.as_secs() })?;
+        let list: Vec<NowPlayingItem> = self.get_list_from_query_result(results)?;
+        Ok(list.into_iter().next())
+    }
 }
 
-fn fix_multi_field(value: &str) -> String {
-    let values: Vec<String> = value
-        .split(",")
-        .map(|v| v.trim().to_lowercase().to_string())
-        .collect();
-    values.join(",")
+/// Time a station is due for its next check, NULL if it was never checked locally
+const CHECK_DUE: &str = "COALESCE(NextCheckTime, LastLocalCheckTime + INTERVAL :hours HOUR)";
+
+/// WHERE condition of get_stations_advanced() and count_stations_advanced() with its named parameters,
+/// so the list and its count always match the same stations
+fn advanced_condition(
+    name: Option<String>,
+    name_exact: bool,
+    country: Option<String>,
+    country_exact: bool,
+    countrycode: Option<String>,
+    state: Option<String>,
+    state_exact: bool,
+    language: Option<String>,
+    language_exact: bool,
+    tag: Option<String>,
+    tag_exact: bool,
+    tag_list: Vec<String>,
+    codec: Option<String>,
+    bitrate_min: u32,
+    bitrate_max: u32,
+    has_geo_info: Option<bool>,
+    has_extended_info: Option<bool>,
+    is_https: Option<bool>,
+    geo_lat: Option<f64>,
+    geo_long: Option<f64>,
+    geo_distance: Option<f64>,
+    geo_bbox: Option<GeoBoundingBox>,
+    hidebroken: bool,
+) -> (String, Vec<(String, Value)>) {
+    let mut query = String::new();
+    query.push_str(" Bitrate >= :bitrate_min AND Bitrate <= :bitrate_max");
+    match has_geo_info {
+        Some(has_geo_info) => {
+            if has_geo_info {
+                query.push_str(" AND GeoLat IS NOT NULL AND GeoLong IS NOT NULL");
+            } else {
+                query.push_str(" AND (GeoLat IS NULL OR GeoLong IS NULL)");
+            }
+        }
+        None => {}
+    }
+    match has_extended_info {
+        Some(has_extended_info) => {
+            if has_extended_info {
+                query.push_str(" AND ExtendedInfo=1");
+            } else {
+                query.push_str(" AND ExtendedInfo=0");
+            }
+        }
+        None => {}
+    }
+    match is_https {
+        Some(is_https) => {
+            if is_https {
+                query.push_str(" AND UrlCache LIKE 'https://%'");
+            } else {
+                query.push_str(" AND UrlCache LIKE 'http://%'");
+            }
+        }
+        None => {}
+    }
+    if name.is_some() {
+        if name_exact {
+            query.push_str(" AND Name=:name");
+        } else {
+            query.push_str(" AND Name LIKE CONCAT('%',:name,'%')");
+        }
+    }
+    if country.is_some() {
+        if country_exact {
+            query.push_str(" AND Country=:country");
+        } else {
+            query.push_str(" AND Country LIKE CONCAT('%',:country,'%')");
+        }
+    }
+    if countrycode.is_some() {
+        query.push_str(" AND UPPER(CountryCode)=UPPER(:countrycode)");
+    }
+    if state.is_some() {
+        if state_exact {
+            query.push_str(" AND Subcountry=:state");
+        } else {
+            query.push_str(" AND Subcountry LIKE CONCAT('%',:state,'%')");
+        }
+    }
+    if language.is_some() {
+        if language_exact {
+            query.push_str(" AND ( Language=:language OR Language LIKE CONCAT('%,',:language,',%') OR Language LIKE CONCAT('%,',:language) OR Language LIKE CONCAT(:language,',%'))");
+        } else {
+            query.push_str(" AND Language LIKE CONCAT('%',:language,'%')");
+        }
+    }
+    if tag.is_some() {
+        if tag_exact {
+            query.push_str(" AND ( Tags=:tag OR Tags LIKE CONCAT('%,',:tag,',%') OR Tags LIKE CONCAT('%,',:tag) OR Tags LIKE CONCAT(:tag,',%'))");
+        } else {
+            query.push_str(" AND Tags LIKE CONCAT('%',:tag,'%')");
+        }
+    }
+    if codec.is_some() {
+        query.push_str(" AND LOWER(Codec)=LOWER(:codec)");
+    }
+    let mut params: Vec<(String, Value)> = vec![
+        (String::from("name"), Value::from(name.unwrap_or_default())),
+        (
+            String::from("country"),
+            Value::from(country.unwrap_or_default()),
+        ),
+        (
+            String::from("countrycode"),
+            Value::from(countrycode.unwrap_or_default()),
+        ),
+        (
+            String::from("state"),
+            Value::from(state.unwrap_or_default()),
+        ),
+        (
+            String::from("language"),
+            Value::from(language.unwrap_or_default()),
+        ),
+        (String::from("tag"), Value::from(tag.unwrap_or_default())),
+        (
+            String::from("codec"),
+            Value::from(codec.unwrap_or_default()),
+        ),
+        (String::from("bitrate_min"), Value::from(bitrate_min)),
+        (String::from("bitrate_max"), Value::from(bitrate_max)),
+    ];
+    let mut i = 0;
+    for tag in tag_list {
+        if tag_exact {
+            query.push_str(&format!(" AND ( Tags=:tag{i} OR Tags LIKE CONCAT('%,',:tag{i},',%') OR Tags LIKE CONCAT('%,',:tag{i}) OR Tags LIKE CONCAT(:tag{i},',%'))",i=i));
+        } else {
+            query.push_str(&format!(" AND Tags LIKE CONCAT('%',:tag{i},'%')", i = i));
+        }
+        params.push((format!("tag{i}", i = i), Value::from(tag)));
+        i += 1;
+    }
+    for (i, geo_box) in geo_boxes(geo_lat, geo_long, geo_distance, geo_bbox).iter().enumerate() {
+        let prefix = format!("geo{i}", i = i);
+        query.push_str(&geo_box.sql_condition(&prefix));
+        for (key, value) in geo_box.sql_params(&prefix) {
+            params.push((key, Value::from(value)));
+        }
+    }
+    if hidebroken {
+        query.push_str(" AND LastCheckOK=TRUE");
+    }
+    (query, params)
+}
+
+/// Shortest word the full-text index contains, the default of innodb_ft_min_token_size
+const FULLTEXT_MIN_TOKEN_SIZE: usize = 3;
+
+/// Condition of get_stations_search() and count_stations_search() that every word of the query is found
+/// in name, tags, state or language, with the relevance of a station and the named parameters of both.
+/// Words that are too short for the full-text index are searched with LIKE.
+fn search_condition(query: &str) -> (String, String, Vec<(String, Value)>) {
+    let (prefixes, short_words) = fulltext_prefixes(query, fold_text, FULLTEXT_MIN_TOKEN_SIZE);
+    let mut condition = String::new();
+    let mut params: Vec<(String, Value)> = vec![];
+    let relevance = if prefixes.is_empty() {
+        String::from("1")
+    } else {
+        condition.push_str(" AND MATCH(Name,Tags,Subcountry,Language) AGAINST(:search IN BOOLEAN MODE)");
+        let all: Vec<String> = prefixes.iter().map(|prefix| format!("+{}*", prefix)).collect();
+        let any: Vec<String> = prefixes.iter().map(|prefix| format!("{}*", prefix)).collect();
+        params.push((String::from("search"), Value::from(all.join(" "))));
+        params.push((String::from("search_name"), Value::from(any.join(" "))));
+        // words in the name count twice
+        String::from("MATCH(Name,Tags,Subcountry,Language) AGAINST(:search IN BOOLEAN MODE) + MATCH(Name) AGAINST(:search_name IN BOOLEAN MODE)")
+    };
+    for (i, word) in short_words.into_iter().enumerate() {
+        condition.push_str(&format!(" AND CONCAT_WS(' ',Name,Tags,Subcountry,Language) LIKE CONCAT('%',:search{i},'%')", i = i));
+        params.push((format!("search{i}", i = i), Value::from(word)));
+    }
+    if params.is_empty() {
+        // nothing to search for
+        condition.push_str(" AND FALSE");
+    }
+    (condition, relevance, params)
+}
+
+/// ORDER BY of get_stations_search(), the best score comes first
+fn search_order(order: &str, reverse: bool) -> String {
+    if order == "relevance" {
+        sql_order("Score", !reverse)
+    } else {
+        sql_order(filter_order(order), reverse)
+    }
+}
+
+/// WHERE condition of get_stations_by_column() and count_stations_by_column(), the search is the only parameter
+fn column_condition(column_name: &str, exact: bool, hidebroken: bool) -> String {
+    let hidebroken_string = if hidebroken {
+        " AND LastCheckOK=TRUE"
+    } else {
+        ""
+    };
+    if exact {
+        format!("LOWER({column_name})=?{hidebroken}", column_name = column_name, hidebroken = hidebroken_string)
+    } else {
+        format!("LOWER({column_name}) LIKE CONCAT('%',?,'%'){hidebroken}", column_name = column_name, hidebroken = hidebroken_string)
+    }
+}
+
+/// WHERE condition of get_stations_by_column_multiple() and count_stations_by_column_multiple(),
+/// the parameters are returned by column_multiple_params()
+fn column_multiple_condition(column_name: &str, exact: bool, hidebroken: bool) -> String {
+    let hidebroken_string = if hidebroken {
+        " AND LastCheckOK=TRUE"
+    } else {
+        ""
+    };
+    if exact {
+        format!(
+            r"({column_name}=?
+             OR {column_name} LIKE CONCAT('%,',?,',%')
+             OR {column_name} LIKE CONCAT(?,',%')
+             OR {column_name} LIKE CONCAT('%,',?)){hidebroken}",
+            column_name = column_name,
+            hidebroken = hidebroken_string
+        )
+    } else {
+        format!("{column_name} LIKE CONCAT('%',?,'%'){hidebroken}", column_name = column_name, hidebroken = hidebroken_string)
+    }
+}
+
+fn column_multiple_params(search: Option<String>, exact: bool) -> Vec<Value> {
+    let search_count = if exact { 4 } else { 1 };
+    vec![Value::from(search); search_count]
+}
+
+fn cursor_condition(cursor: Option<&StationCursor>, order: &str, named: bool) -> String {
+    match cursor {
+        Some(cursor) => cursor.sql_condition(filter_order(order), named),
+        None => String::new(),
+    }
+}
+
+fn cursor_value(param: CursorParam) -> Value {
+    match param {
+        CursorParam::Text(text) => Value::from(text),
+        CursorParam::Int(number) => Value::from(number),
+    }
+}
+
+fn cursor_positional_params(cursor: Option<&StationCursor>) -> Vec<Value> {
+    match cursor {
+        Some(cursor) => cursor.positional_params().into_iter().map(cursor_value).collect(),
+        None => vec![],
+    }
+}
+
+/// Joins the current title of the NowPlaying table to station queries, as column NowPlaying
+fn now_playing_join() -> String {
+    format!(
+        " LEFT JOIN (SELECT StationUuid AS NowPlayingUuid,Title AS NowPlaying FROM NowPlaying WHERE UpdateTime > UTC_TIMESTAMP() - INTERVAL {seconds} SECOND) AS CurrentTitle ON NowPlayingUuid=StationUuid",
+        seconds = NOW_PLAYING_MAX_AGE.as_secs()
+    )
+}
+
+/// SELECT list of station queries, reduced to the columns of the requested fields if there are any
+fn station_columns(fields: Option<&StationFields>, order: &str) -> Cow<'static, str> {
+    match fields {
+        Some(fields) => Cow::Owned(fields.select(filter_order(order), |column| format!("Date_Format({c},'%Y-%m-%d %H:%i:%s') AS {c}Formated", c = column))),
+        None => Cow::Borrowed(MysqlConnection::COLUMNS),
+    }
 }
 
 fn filter_order(order: &str) -> &str {
@@ -2561,33 +3223,3 @@ fn filter_order_streaming_server(order: &str) -> &str {
         _ => "Id",
     }
 }
-
-fn filter_order_1_n(order: &str) -> Result<&str, Box<dyn Error>> {
-    match order {
-        "name" => Ok("name"),
-        "stationcount" => Ok("stationcount"),
-        _ => Err(Box::new(DbError::IllegalOrderError(String::from(order)))),
-    }
-}
-
-fn fix_url(u: &str, allow_empty: bool) -> Result<String, Box<dyn std::error::Error>> {
-    let url_str = u.trim();
-    if url_str.is_empty() {
-        if allow_empty {
-            return Ok(url_str.to_string());
-        } else {
-            return Err(Box::new(DbError::AddStationError(String::from(
-                "empty url not allowed",
-            ))));
-        }
-    }
-    let url = Url::parse(url_str)?;
-    let scheme = url.scheme().to_lowercase();
-    if !scheme.eq("http") && !scheme.eq("https") {
-        return Err(Box::new(DbError::AddStationError(String::from(
-            "unknown url scheme",
-        ))));
-    }
-    let url = url.to_string();
-    Ok(url)
-}
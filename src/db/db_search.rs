@@ -0,0 +1,279 @@
+use crate::db::models::DbStationItem;
+use std::cmp::Ordering;
+use unicode_normalization::UnicodeNormalization;
+
+/// Fields that take part in relevance search with their weight
+const FIELD_WEIGHTS: [(SearchField, f64); 4] = [
+    (SearchField::Name, 1.0),
+    (SearchField::Tags, 0.7),
+    (SearchField::State, 0.5),
+    (SearchField::Language, 0.5),
+];
+
+#[derive(Clone, Copy)]
+enum SearchField {
+    Name,
+    Tags,
+    State,
+    Language,
+}
+
+/// Lowercase and remove combining accents only, "Café Straße" -> "cafe straße"
+pub fn strip_accents(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !('\u{0300}'..='\u{036f}').contains(c))
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Lowercase and remove accents, "Café Ö1" -> "cafe o1"
+pub fn fold_text(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.nfd() {
+        match c {
+            '\u{0300}'..='\u{036f}' => {}
+            'ß' => folded.push_str("ss"),
+            'ø' | 'Ø' => folded.push('o'),
+            'ł' | 'Ł' => folded.push('l'),
+            'đ' | 'Đ' => folded.push('d'),
+            'æ' | 'Æ' => folded.push_str("ae"),
+            'œ' | 'Œ' => folded.push_str("oe"),
+            _ => folded.extend(c.to_lowercase()),
+        }
+    }
+    folded
+}
+
+pub fn tokenize(text: &str) -> Vec<String> {
+    fold_text(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Optimal string alignment distance (levenshtein with transpositions)
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for j in 0..=b.len() {
+        rows[0][j] = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut value = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                value = value.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = value;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Number of typos that are tolerated for a word of this length
+fn allowed_typos(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Start of a query word that is looked up in a full-text index as prefix,
+/// leaves room at the end for the typos station_relevance() tolerates ("radoi" -> "rad").
+/// Typos within the prefix are not found this way, only the memory database tolerates them ("jzaz").
+fn fulltext_prefix(word: &str) -> String {
+    let chars: Vec<char> = word.chars().collect();
+    let length = chars.len().saturating_sub(2 * allowed_typos(chars.len())).max(chars.len().min(3));
+    chars[..length].iter().collect()
+}
+
+/// Prefixes of the query words for a full-text index, the query is folded by fold before.
+/// Prefixes shorter than min_length are not in the index, they are returned as the second list.
+pub fn fulltext_prefixes(query: &str, fold: fn(&str) -> String, min_length: usize) -> (Vec<String>, Vec<String>) {
+    fold(query)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(fulltext_prefix)
+        .partition(|prefix| prefix.chars().count() >= min_length)
+}
+
+/// Score between 0 and 1 from the unbounded relevance of a full-text index, as SQL expression.
+/// Not rounded, small indexes give every word a tiny weight.
+pub fn sql_score(relevance: &str) -> String {
+    format!("({r}) / (1 + ({r}))", r = relevance)
+}
+
+/// Similarity between 0 and 1 of a query word and a word from a station field
+fn token_similarity(query: &[char], word: &[char]) -> f64 {
+    if query == word {
+        return 1.0;
+    }
+    if query.len() >= 2 && word.starts_with(query) {
+        return 0.9;
+    }
+    let allowed = allowed_typos(query.len());
+    if allowed == 0 || query.len().abs_diff(word.len()) > allowed {
+        return 0.0;
+    }
+    let distance = edit_distance(query, word);
+    if distance > allowed {
+        return 0.0;
+    }
+    0.8 - 0.2 * (distance - 1) as f64
+}
+
+fn field_value(station: &DbStationItem, field: SearchField) -> &str {
+    match field {
+        SearchField::Name => &station.name,
+        SearchField::Tags => &station.tags,
+        SearchField::State => &station.state,
+        SearchField::Language => &station.language,
+    }
+}
+
+/// Relevance of a station for a search query between 0 and 1,
+/// 0 means that at least one word of the query was not found
+pub fn station_relevance(station: &DbStationItem, query: &str) -> f64 {
+    let query_tokens: Vec<Vec<char>> = tokenize(query)
+        .iter()
+        .map(|token| token.chars().collect())
+        .collect();
+    if query_tokens.is_empty() {
+        return 0.0;
+    }
+    let fields: Vec<(Vec<Vec<char>>, f64)> = FIELD_WEIGHTS
+        .iter()
+        .map(|(field, weight)| {
+            let words = tokenize(field_value(station, *field))
+                .iter()
+                .map(|word| word.chars().collect())
+                .collect();
+            (words, *weight)
+        })
+        .collect();
+
+    let mut sum = 0.0;
+    for query_token in query_tokens.iter() {
+        let mut best: f64 = 0.0;
+        for (words, weight) in fields.iter() {
+            for word in words {
+                best = best.max(token_similarity(query_token, word) * weight);
+            }
+        }
+        if best == 0.0 {
+            return 0.0;
+        }
+        sum += best;
+    }
+    let score = sum / query_tokens.len() as f64;
+
+    // prefer names that contain the query as it was typed
+    let phrase_bonus = if fold_text(&station.name).contains(fold_text(query).trim()) {
+        0.1
+    } else {
+        0.0
+    };
+    ((score * 0.9 + phrase_bonus) * 1000.0).round() / 1000.0
+}
+
+/// True if two station names are the same after folding or differ only by typos,
+/// numbers have to be the same ("Radio 1" and "Radio 2" are different stations)
+pub fn similar_names(a: &str, b: &str) -> bool {
+    let a: Vec<char> = tokenize(a).concat().chars().collect();
+    let b: Vec<char> = tokenize(b).concat().chars().collect();
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    if a == b {
+        return true;
+    }
+    let digits = |name: &[char]| -> String { name.iter().filter(|c| c.is_numeric()).collect() };
+    if digits(&a) != digits(&b) {
+        return false;
+    }
+    let allowed = allowed_typos(a.len().min(b.len()));
+    allowed > 0 && a.len().abs_diff(b.len()) <= allowed && edit_distance(&a, &b) <= allowed
+}
+
+/// Keeps the stations that match the query and sets their score.
+/// The given order of the list stays as it is, except if ordered by relevance.
+pub fn rank_stations(list: Vec<DbStationItem>, query: &str, order: &str, reverse: bool) -> Vec<DbStationItem> {
+    let mut ranked: Vec<DbStationItem> = list
+        .into_iter()
+        .filter_map(|mut station| {
+            let score = station_relevance(&station, query);
+            if score > 0.0 {
+                station.score = Some(score);
+                Some(station)
+            } else {
+                None
+            }
+        })
+        .collect();
+    if order == "relevance" {
+        ranked.sort_by(|a, b| {
+            let ordering = a.score.partial_cmp(&b.score).unwrap_or(Ordering::Equal);
+            if reverse {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+    }
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fold_text;
+    use super::fulltext_prefixes;
+    use super::rank_stations;
+    use super::similar_names;
+    use crate::db::DbConnection;
+    use crate::test_utils::sample_db;
+
+    #[test]
+    fn fold_accents() {
+        assert_eq!(fold_text("Café Ö1 Straße"), "cafe o1 strasse");
+    }
+
+    #[test]
+    fn fulltext_words() {
+        let (prefixes, short) = fulltext_prefixes("Radoi JAZ Ö1 classical", fold_text, 3);
+        assert_eq!(prefixes, vec!["rad", "jaz", "class"]);
+        assert_eq!(short, vec!["o1"]);
+    }
+
+    #[test]
+    fn similar_station_names() {
+        assert!(similar_names("Jazz Radio", "jazz-radio"));
+        assert!(similar_names("Jazz Radio", "Jaz Radio"));
+        assert!(similar_names("Radio Café", "Radio Cafe FM"));
+        assert!(!similar_names("Radio 1", "Radio 2"));
+        assert!(!similar_names("Rock Radio", "Jazz Radio"));
+        assert!(!similar_names("", ""));
+    }
+
+    #[test]
+    fn word_order_and_typos() {
+        let stations = sample_db().get_stations_by_all("name", false, false, None, None, 0, 100).unwrap();
+        let names = |query: &str| -> Vec<String> {
+            rank_stations(stations.clone(), query, "relevance", false)
+                .into_iter()
+                .map(|station| station.name)
+                .collect()
+        };
+        assert_eq!(names("radio jazz"), vec!["Jazz Radio"]);
+        assert_eq!(names("jaz radoi"), vec!["Jazz Radio"]);
+        assert_eq!(names("rock"), vec!["Rock Radio", "Jazz Radio"]);
+        assert_eq!(names("bavaria news"), vec!["News Radio"]);
+        assert!(names("classic").is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+use crate::db::models::DbStationItem;
+use crate::db::models::NowPlayingItem;
+use crate::db::models::StationCheckItem;
+use crate::db::models::StationHistoryItem;
+use crate::db::models::StationClickItem;
+use crate::db::models::StationAddItem;
+use crate::db::models::WebhookDeliveryItem;
+use crate::db::models::WebhookItem;
+use chrono::DateTime;
+use chrono::NaiveDateTime;
+use chrono::Utc;
+use rusqlite::types::FromSql;
+use rusqlite::Row;
+
+fn take_opt<T: FromSql>(row: &Row, name: &str) -> Option<T> {
+    row.get::<_, Option<T>>(name).ok().flatten()
+}
+
+fn take_string(row: &Row, name: &str) -> String {
+    take_opt(row, name).unwrap_or_default()
+}
+
+fn take_time(row: &Row, name: &str) -> Option<DateTime<Utc>> {
+    take_opt::<NaiveDateTime>(row, name).map(|x| DateTime::<Utc>::from_naive_utc_and_offset(x, Utc))
+}
+
+impl From<&Row<'_>> for StationCheckItem {
+    fn from(row: &Row) -> Self {
+        StationCheckItem {
+            check_id:                      take_opt(row, "CheckID").unwrap_or(0),
+            station_uuid:                  take_string(row, "StationUuid"),
+            check_uuid:                    take_string(row, "CheckUuid"),
+            source:                        take_string(row, "Source"),
+            codec:                         take_string(row, "Codec"),
+            bitrate:                       take_opt(row, "Bitrate").unwrap_or(0),
+            hls:                           take_opt(row, "Hls").unwrap_or(false),
+            check_ok:                      take_opt(row, "CheckOK").unwrap_or(false),
+            check_time_iso8601:            take_time(row, "CheckTime"),
+            check_time:                    take_string(row, "CheckTimeFormated"),
+            url:                           take_string(row, "UrlCache"),
+            metainfo_overrides_database:   take_opt(row, "MetainfoOverridesDatabase").unwrap_or(false),
+            public:                        take_opt(row, "Public"),
+            name:                          take_opt(row, "Name"),
+            description:                   take_opt(row, "Description"),
+            tags:                          take_opt(row, "Tags"),
+            countrycode:                   take_opt(row, "CountryCode"),
+            homepage:                      take_opt(row, "Homepage"),
+            favicon:                       take_opt(row, "Favicon"),
+            loadbalancer:                  take_opt(row, "Loadbalancer"),
+            do_not_index:                  take_opt(row, "DoNotIndex"),
+
+            countrysubdivisioncode:        take_opt(row, "CountrySubdivisionCode"),
+            server_software:               take_opt(row, "ServerSoftware"),
+            sampling:                      take_opt(row, "Sampling"),
+            timing_ms:                     take_opt::<u64>(row, "TimingMs").unwrap_or(0).into(),
+            languagecodes:                 take_opt(row, "LanguageCodes"),
+            ssl_error:                     take_opt(row, "SslError").unwrap_or(false),
+            geo_lat:                       take_opt(row, "GeoLat"),
+            geo_long:                      take_opt(row, "GeoLong"),
+            audio_bitrate:                 take_opt(row, "AudioBitrate"),
+            audio_sampling:                take_opt(row, "AudioSampling"),
+            audio_liveness:                take_opt(row, "AudioLiveness"),
+        }
+    }
+}
+
+impl From<&Row<'_>> for DbStationItem {
+    fn from(row: &Row) -> Self {
+        DbStationItem {
+            id:                          take_opt(row, "StationID").unwrap_or(0),
+            changeuuid:                  take_string(row, "ChangeUuid"),
+            stationuuid:                 take_string(row, "StationUuid"),
+            serveruuid:                  take_opt(row, "ServerUuid"),
+            name:                        take_string(row, "Name"),
+            url:                         take_string(row, "Url"),
+            url_resolved:                take_string(row, "UrlCache"),
+            codec:                       take_string(row, "Codec"),
+            bitrate:                     take_opt(row, "Bitrate").unwrap_or(0),
+            hls:                         take_opt(row, "Hls").unwrap_or(false),
+            lastcheckok:                 take_opt(row, "LastCheckOK").unwrap_or(false),
+            favicon:                     take_string(row, "Favicon"),
+            tags:                        take_string(row, "Tags"),
+            country:                     take_string(row, "Country"),
+            countrycode:                 take_string(row, "CountryCode"),
+            iso_3166_2:                  take_opt(row, "CountrySubdivisionCode"),
+            state:                       take_string(row, "Subcountry"),
+            language:                    take_string(row, "Language"),
+            languagecodes:               take_string(row, "LanguageCodes"),
+            votes:                       take_opt(row, "Votes").unwrap_or(0),
+            lastchangetime_iso8601:      take_time(row, "Creation"),
+            lastchangetime:              take_string(row, "CreationFormated"),
+            homepage:                    take_string(row, "Homepage"),
+            lastchecktime_iso8601:       take_time(row, "LastCheckTime"),
+            lastchecktime:               take_string(row, "LastCheckTimeFormated"),
+            lastcheckoktime_iso8601:     take_time(row, "LastCheckOkTime"),
+            lastcheckoktime:             take_string(row, "LastCheckOkTimeFormated"),
+            lastlocalchecktime_iso8601:  take_time(row, "LastLocalCheckTime"),
+            lastlocalchecktime:          take_string(row, "LastLocalCheckTimeFormated"),
+            clicktimestamp_iso8601:      take_time(row, "ClickTimestamp"),
+            clicktimestamp:              take_string(row, "ClickTimestampFormated"),
+            clickcount:                  take_opt(row, "clickcount").unwrap_or(0),
+            clicktrend:                  take_opt(row, "ClickTrend").unwrap_or(0),
+            ssl_error:                   take_opt(row, "SslError").unwrap_or(false),
+            geo_lat:                     take_opt(row, "GeoLat"),
+            geo_long:                    take_opt(row, "GeoLong"),
+            has_extended_info:           take_opt(row, "ExtendedInfo"),
+            score:                       take_opt(row, "Score"),
+            distance:                    None,
+            now_playing:                 take_opt(row, "NowPlaying"),
+        }
+    }
+}
+
+impl From<&Row<'_>> for StationHistoryItem {
+    fn from(row: &Row) -> Self {
+        StationHistoryItem {
+            id:                          take_opt(row, "StationChangeID").unwrap_or(0),
+            changeuuid:                  take_string(row, "ChangeUuid"),
+            stationuuid:                 take_string(row, "StationUuid"),
+            name:                        take_string(row, "Name"),
+            url:                         take_string(row, "Url"),
+            favicon:                     take_string(row, "Favicon"),
+            tags:                        take_string(row, "Tags"),
+            countrycode:                 take_string(row, "CountryCode"),
+            state:                       take_string(row, "Subcountry"),
+            language:                    take_string(row, "Language"),
+            languagecodes:               take_string(row, "LanguageCodes"),
+            votes:                       take_opt(row, "Votes").unwrap_or(0),
+            lastchangetime_iso8601:      take_time(row, "Creation"),
+            lastchangetime:              take_string(row, "CreationFormated"),
+            homepage:                    take_string(row, "Homepage"),
+            geo_lat:                     take_opt(row, "GeoLat"),
+            geo_long:                    take_opt(row, "GeoLong"),
+        }
+    }
+}
+
+impl From<&Row<'_>> for StationClickItem {
+    fn from(row: &Row) -> Self {
+        StationClickItem {
+            id:                        take_opt(row, "ClickID").unwrap_or(0),
+            clickuuid:                 take_string(row, "ClickUuid"),
+            stationuuid:               take_string(row, "StationUuid"),
+            ip:                        take_string(row, "IP"),
+            clicktimestamp_iso8601:    take_time(row, "ClickTimestamp"),
+            clicktimestamp:            take_string(row, "ClickTimestampFormated"),
+        }
+    }
+}
+
+impl From<&Row<'_>> for StationAddItem {
+    fn from(row: &Row) -> Self {
+        StationAddItem {
+            stationuuid:               take_string(row, "StationUuid"),
+            name:                      take_string(row, "Name"),
+            url:                       take_string(row, "Url"),
+            homepage:                  take_string(row, "Homepage"),
+            favicon:                   take_string(row, "Favicon"),
+            country:                   take_string(row, "Country"),
+            countrycode:               take_string(row, "CountryCode"),
+            state:                     take_string(row, "Subcountry"),
+            language:                  take_string(row, "Language"),
+            languagecodes:             take_string(row, "LanguageCodes"),
+            tags:                      take_string(row, "Tags"),
+            geo_lat:                   take_opt(row, "GeoLat"),
+            geo_long:                  take_opt(row, "GeoLong"),
+            creation_iso8601:          take_time(row, "Creation"),
+        }
+    }
+}
+
+impl From<&Row<'_>> for WebhookItem {
+    fn from(row: &Row) -> Self {
+        WebhookItem {
+            webhookuuid:               take_string(row, "WebhookUuid"),
+            owner:                     take_string(row, "Owner"),
+            url:                       take_string(row, "Url"),
+            secret:                    take_string(row, "Secret"),
+            events:                    take_string(row, "Events"),
+            stationuuid:               take_string(row, "StationUuid"),
+            tag:                       take_string(row, "Tag"),
+            countrycode:               take_string(row, "CountryCode"),
+            creation_iso8601:          take_time(row, "Creation"),
+        }
+    }
+}
+
+impl From<&Row<'_>> for WebhookDeliveryItem {
+    fn from(row: &Row) -> Self {
+        WebhookDeliveryItem {
+            deliveryuuid:              take_string(row, "DeliveryUuid"),
+            webhookuuid:               take_string(row, "WebhookUuid"),
+            url:                       take_string(row, "Url"),
+            secret:                    take_string(row, "Secret"),
+            event:                     take_string(row, "Event"),
+            payload:                   take_string(row, "Payload"),
+            attempts:                  take_opt(row, "Attempts").unwrap_or(0),
+        }
+    }
+}
+
+impl From<&Row<'_>> for NowPlayingItem {
+    fn from(row: &Row) -> Self {
+        NowPlayingItem {
+            stationuuid:               take_string(row, "StationUuid"),
+            title:                     take_string(row, "Title"),
+            updatetime_iso8601:        take_time(row, "UpdateTime"),
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use crate::db::db_sqlite::simple_migrate::Migrations;
+use rusqlite::Connection;
+
+/// The sqlite schema starts at the state the mysql migrations have reached,
+/// there is no older sqlite database that would need the intermediate steps.
+pub fn load_migrations(conn: &Connection) -> Result<Migrations<'_>, Box<dyn std::error::Error>> {
+    let mut migrations = Migrations::new(conn);
+    migrations.add_migration("20261018_100000_CreateStreamingServers",
+r#"CREATE TABLE StreamingServers (
+Id INTEGER PRIMARY KEY AUTOINCREMENT,
+Uuid CHAR(36) NOT NULL UNIQUE,
+Url VARCHAR(300) NOT NULL UNIQUE,
+StatusUrl TEXT,
+Status TEXT,
+Error VARCHAR(50),
+CreatedAt DATETIME NOT NULL,
+UpdatedAt DATETIME
+);"#, "DROP TABLE StreamingServers;");
+
+    migrations.add_migration("20261018_100100_CreateStation",
+r#"CREATE TABLE Station (
+StationID INTEGER PRIMARY KEY AUTOINCREMENT,
+Name TEXT COLLATE NOCASE,
+Url TEXT,
+Homepage TEXT,
+Favicon TEXT,
+Creation DATETIME NOT NULL,
+Country VARCHAR(250) DEFAULT NULL COLLATE NOCASE,
+Language VARCHAR(100) DEFAULT NULL COLLATE NOCASE,
+Tags TEXT,
+Votes INT DEFAULT 0,
+Subcountry VARCHAR(50) DEFAULT NULL COLLATE NOCASE,
+clickcount INT NOT NULL DEFAULT 0,
+ClickTrend INT DEFAULT 0,
+ClickTimestamp DATETIME DEFAULT NULL,
+Codec VARCHAR(20) DEFAULT NULL,
+LastCheckOK BOOLEAN NOT NULL DEFAULT TRUE,
+LastCheckTime DATETIME DEFAULT NULL,
+Bitrate INT NOT NULL DEFAULT 0,
+UrlCache TEXT NOT NULL,
+LastCheckOkTime DATETIME DEFAULT NULL,
+Hls BOOLEAN NOT NULL DEFAULT FALSE,
+ChangeUuid CHAR(36) DEFAULT NULL UNIQUE,
+StationUuid CHAR(36) DEFAULT NULL UNIQUE,
+CountryCode VARCHAR(2),
+LastLocalCheckTime DATETIME,
+CountrySubdivisionCode VARCHAR(6) NULL,
+GeoLat DOUBLE NULL,
+GeoLong DOUBLE NULL,
+SslError BOOLEAN NOT NULL DEFAULT FALSE,
+LanguageCodes TEXT NULL,
+ExtendedInfo BOOLEAN NOT NULL DEFAULT FALSE,
+ServerUuid CHAR(36) REFERENCES StreamingServers(Uuid)
+);"#, "DROP TABLE Station;");
+
+    migrations.add_migration("20261018_100200_CreateIPVoteCheck",
+r#"CREATE TABLE IPVoteCheck (
+CheckID INTEGER PRIMARY KEY AUTOINCREMENT,
+IP VARCHAR(50) NOT NULL,
+StationID INT NOT NULL,
+VoteTimestamp DATETIME NOT NULL
+);"#, "DROP TABLE IPVoteCheck;");
+
+    migrations.add_migration("20261018_100300_CreateLanguageCache",
+r#"CREATE TABLE LanguageCache (
+LanguageName VARCHAR(150) NOT NULL PRIMARY KEY,
+StationCount INT DEFAULT 0,
+StationCountWorking INT DEFAULT 0
+);"#, "DROP TABLE LanguageCache;");
+
+    migrations.add_migration("20261018_100400_CreateTagCache",
+r#"CREATE TABLE TagCache (
+TagName VARCHAR(150) NOT NULL PRIMARY KEY,
+StationCount INT DEFAULT 0,
+StationCountWorking INT DEFAULT 0
+);"#, "DROP TABLE TagCache;");
+
+    migrations.add_migration("20261018_100500_CreateStationClick",
+r#"CREATE TABLE StationClick (
+ClickID INTEGER PRIMARY KEY AUTOINCREMENT,
+ClickTimestamp DATETIME NOT NULL,
+IP VARCHAR(50) DEFAULT NULL,
+StationUuid CHAR(36) REFERENCES Station(StationUuid) ON DELETE CASCADE,
+ClickUuid CHAR(36) UNIQUE,
+InsertTime DATETIME NOT NULL
+);
+CREATE INDEX IN_StationClick_StationUuid_ClickTimestamp ON StationClick(StationUuid, ClickTimestamp);"#, "DROP TABLE StationClick;");
+
+    migrations.add_migration("20261018_100600_CreateStationHistory",
+r#"CREATE TABLE StationHistory (
+StationChangeID INTEGER PRIMARY KEY AUTOINCREMENT,
+Name TEXT,
+Url TEXT,
+Homepage TEXT,
+Favicon TEXT,
+Creation DATETIME NOT NULL,
+Subcountry VARCHAR(50) DEFAULT NULL,
+Language VARCHAR(100) DEFAULT NULL,
+Tags TEXT,
+Votes INT DEFAULT 0,
+ChangeUuid CHAR(36) NOT NULL UNIQUE,
+StationUuid CHAR(36) NOT NULL,
+CountryCode VARCHAR(2),
+GeoLat DOUBLE NULL,
+GeoLong DOUBLE NULL,
+LanguageCodes TEXT NULL,
+Source VARCHAR(100) NOT NULL DEFAULT 'INITIAL',
+CountrySubdivisionCode VARCHAR(6) NULL
+);
+CREATE INDEX IN_StationHistory_StationUuid ON StationHistory(StationUuid);"#, "DROP TABLE StationHistory;");
+
+    migrations.add_migration("20261018_100700_CreatePullServers",
+r#"CREATE TABLE PullServers (
+id INTEGER PRIMARY KEY AUTOINCREMENT,
+name TEXT NOT NULL,
+lastid TEXT,
+lastcheckid TEXT,
+lastclickuuid TEXT
+);"#, "DROP TABLE PullServers;");
+
+    migrations.add_migration("20261018_100800_CreateStationCheckHistory",
+r#"CREATE TABLE StationCheckHistory (
+CheckID INTEGER PRIMARY KEY AUTOINCREMENT,
+StationUuid CHAR(36) NOT NULL REFERENCES Station(StationUuid) ON DELETE CASCADE,
+CheckUuid CHAR(36) NOT NULL UNIQUE,
+Source VARCHAR(100) NOT NULL,
+Codec VARCHAR(20) DEFAULT NULL,
+Bitrate INT NOT NULL DEFAULT 0,
+Hls BOOLEAN NOT NULL DEFAULT FALSE,
+CheckOK BOOLEAN NOT NULL DEFAULT TRUE,
+CheckTime DATETIME NOT NULL,
+InsertTime DATETIME NOT NULL,
+UrlCache TEXT,
+MetainfoOverridesDatabase BOOLEAN NOT NULL DEFAULT FALSE,
+Public BOOLEAN,
+Name TEXT,
+Description TEXT,
+Tags TEXT,
+CountryCode TEXT,
+Homepage TEXT,
+Favicon TEXT,
+Loadbalancer TEXT,
+CountrySubdivisionCode VARCHAR(6) NULL,
+DoNotIndex BOOLEAN NULL,
+ServerSoftware TEXT NULL,
+Sampling INT NULL,
+LanguageCodes TEXT NULL,
+TimingMs INT NULL,
+SslError BOOLEAN NOT NULL DEFAULT FALSE,
+GeoLat DOUBLE NULL,
+GeoLong DOUBLE NULL
+);
+CREATE INDEX IN_StationCheckHistory_StationUuid_Source ON StationCheckHistory(StationUuid,Source);
+CREATE INDEX IN_StationCheckHistory_InsertTime ON StationCheckHistory(InsertTime);"#, "DROP TABLE StationCheckHistory;");
+
+    migrations.add_migration("20261018_100900_Create_View_StationCheck",
+r#"CREATE VIEW StationCheck AS SELECT * FROM StationCheckHistory WHERE CheckID IN (SELECT MAX(CheckID) FROM StationCheckHistory GROUP BY StationUuid,Source);"#,
+r#"DROP VIEW StationCheck;"#);
+
+    migrations.add_migration("20261018_101000_CreateStationCheckStep",
+r#"CREATE TABLE StationCheckStep (
+Id INTEGER PRIMARY KEY AUTOINCREMENT,
+StepUuid CHAR(36) NOT NULL UNIQUE,
+ParentStepUuid CHAR(36) DEFAULT NULL,
+CheckUuid CHAR(36) NOT NULL REFERENCES StationCheckHistory(CheckUuid) ON DELETE CASCADE,
+StationUuid CHAR(36) NOT NULL,
+Url TEXT NOT NULL,
+UrlType TEXT DEFAULT NULL,
+Error TEXT DEFAULT NULL,
+InsertTime DATETIME NOT NULL
+);"#, "DROP TABLE StationCheckStep;");
+
+    migrations.add_migration("20261018_120000_CreateStationPending",
+r#"CREATE TABLE StationPending (
+PendingID INTEGER PRIMARY KEY AUTOINCREMENT,
+StationUuid CHAR(36) NOT NULL UNIQUE,
+Name TEXT NOT NULL,
+Url TEXT NOT NULL,
+Homepage TEXT NOT NULL,
+Favicon TEXT NOT NULL,
+Country VARCHAR(250) NOT NULL,
+CountryCode VARCHAR(2) NOT NULL,
+Subcountry VARCHAR(50) NOT NULL,
+Language VARCHAR(100) NOT NULL,
+LanguageCodes TEXT NOT NULL,
+Tags TEXT NOT NULL,
+GeoLat DOUBLE NULL,
+GeoLong DOUBLE NULL,
+Creation DATETIME NOT NULL
+);"#, "DROP TABLE StationPending;");
+
+    migrations.add_migration("20261018_130000_CreateWebhook",
+r#"CREATE TABLE Webhook (
+WebhookID INTEGER PRIMARY KEY AUTOINCREMENT,
+WebhookUuid CHAR(36) NOT NULL UNIQUE,
+Owner VARCHAR(100) NOT NULL,
+Url TEXT NOT NULL,
+Secret VARCHAR(250) NOT NULL,
+Events VARCHAR(250) NOT NULL,
+StationUuid VARCHAR(36) NOT NULL,
+Tag VARCHAR(100) NOT NULL,
+CountryCode VARCHAR(2) NOT NULL,
+Creation DATETIME NOT NULL
+);"#, "DROP TABLE Webhook;");
+
+    migrations.add_migration("20261018_130100_CreateWebhookDelivery",
+r#"CREATE TABLE WebhookDelivery (
+DeliveryID INTEGER PRIMARY KEY AUTOINCREMENT,
+DeliveryUuid CHAR(36) NOT NULL UNIQUE,
+WebhookUuid CHAR(36) NOT NULL REFERENCES Webhook(WebhookUuid) ON DELETE CASCADE,
+Event VARCHAR(50) NOT NULL,
+Payload TEXT NOT NULL,
+Attempts INTEGER NOT NULL DEFAULT 0,
+NextAttempt DATETIME NOT NULL,
+LastError TEXT DEFAULT NULL,
+Creation DATETIME NOT NULL
+);
+CREATE INDEX IN_WebhookDelivery_NextAttempt ON WebhookDelivery(NextAttempt);"#, "DROP TABLE WebhookDelivery;");
+
+    migrations.add_migration("20261018_140000_Add_Station_NextCheckTime",
+r#"ALTER TABLE Station ADD COLUMN NextCheckTime DATETIME NULL;
+CREATE INDEX IN_Station_NextCheckTime ON Station(NextCheckTime);"#,
+r#"DROP INDEX IN_Station_NextCheckTime;
+ALTER TABLE Station DROP COLUMN NextCheckTime;"#);
+
+    migrations.add_migration("20261018_150000_Add_StationCheckHistory_Audio",
+r#"ALTER TABLE StationCheckHistory ADD COLUMN AudioBitrate INT NULL;
+ALTER TABLE StationCheckHistory ADD COLUMN AudioSampling INT NULL;
+ALTER TABLE StationCheckHistory ADD COLUMN AudioLiveness VARCHAR(20) NULL;"#,
+r#"ALTER TABLE StationCheckHistory DROP COLUMN AudioLiveness;
+ALTER TABLE StationCheckHistory DROP COLUMN AudioSampling;
+ALTER TABLE StationCheckHistory DROP COLUMN AudioBitrate;"#);
+
+    migrations.add_migration("20261018_160000_CreateNowPlaying",
+r#"CREATE TABLE NowPlaying (
+StationUuid CHAR(36) NOT NULL PRIMARY KEY,
+Title TEXT NOT NULL,
+UpdateTime DATETIME NOT NULL
+);"#, "DROP TABLE NowPlaying;");
+
+    migrations.add_migration("20261018_170000_CreateStationSearch",
+r#"CREATE VIRTUAL TABLE StationSearch USING fts5(Name, Tags, Subcountry, Language, content='Station', content_rowid='StationID', tokenize='unicode61 remove_diacritics 2');
+CREATE TRIGGER StationSearch_Insert AFTER INSERT ON Station BEGIN
+INSERT INTO StationSearch(rowid, Name, Tags, Subcountry, Language) VALUES (new.StationID, new.Name, new.Tags, new.Subcountry, new.Language);
+END;
+CREATE TRIGGER StationSearch_Delete AFTER DELETE ON Station BEGIN
+INSERT INTO StationSearch(StationSearch, rowid, Name, Tags, Subcountry, Language) VALUES ('delete', old.StationID, old.Name, old.Tags, old.Subcountry, old.Language);
+END;
+CREATE TRIGGER StationSearch_Update AFTER UPDATE OF Name, Tags, Subcountry, Language ON Station BEGIN
+INSERT INTO StationSearch(StationSearch, rowid, Name, Tags, Subcountry, Language) VALUES ('delete', old.StationID, old.Name, old.Tags, old.Subcountry, old.Language);
+INSERT INTO StationSearch(rowid, Name, Tags, Subcountry, Language) VALUES (new.StationID, new.Name, new.Tags, new.Subcountry, new.Language);
+END;
+INSERT INTO StationSearch(StationSearch) VALUES ('rebuild');"#,
+r#"DROP TRIGGER StationSearch_Update;
+DROP TRIGGER StationSearch_Delete;
+DROP TRIGGER StationSearch_Insert;
+DROP TABLE StationSearch;"#);
+
+    Ok(migrations)
+}
@@ -0,0 +1,2891 @@
+mod conversions;
+mod migrations;
+mod simple_migrate;
+
+use crate::api::data::Station;
+use crate::db::db_cursor::sql_order;
+use crate::db::db_cursor::CursorParam;
+use crate::db::db_cursor::StationCursor;
+use crate::db::db_fields::StationFields;
+use crate::db::db_error::DbError;
+use crate::db::db_geo::count_within_distance;
+use crate::db::db_geo::distance_filter;
+use crate::db::db_geo::finish_query;
+use crate::db::db_geo::geo_boxes;
+use crate::db::db_geo::query_range;
+use crate::db::db_geo::GeoBoundingBox;
+use crate::db::db_search::fulltext_prefixes;
+use crate::db::db_search::sql_score;
+use crate::db::db_search::strip_accents;
+use crate::db::db_utils::check_new_station;
+use crate::db::db_utils::edit_station_fields;
+use crate::db::db_utils::filter_order_1_n;
+use crate::db::db_utils::fix_multi_field;
+use crate::db::models::CheckConsensus;
+use crate::db::models::DBCountry;
+use crate::db::models::DbStationItem;
+use crate::db::models::NowPlayingItem;
+use crate::db::models::NOW_PLAYING_MAX_AGE;
+use crate::db::models::StationAddItem;
+use crate::db::models::DbStreamingServer;
+use crate::db::models::DbStreamingServerNew;
+use crate::db::models::ExtraInfo;
+use crate::db::models::State;
+use crate::db::models::StationChangeItemNew;
+use crate::db::models::StationCheckItem;
+use crate::db::models::StationCheckItemNew;
+use crate::db::models::StationCheckStepItem;
+use crate::db::models::StationCheckStepItemNew;
+use crate::db::models::StationClickItem;
+use crate::db::models::StationClickItemNew;
+use crate::db::models::StationHistoryItem;
+use crate::db::models::WebhookDeliveryItem;
+use crate::db::models::WebhookItem;
+use crate::db::DbConnection;
+use celes::Country;
+use rusqlite::named_params;
+use rusqlite::params;
+use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use rusqlite::Row;
+use rusqlite::ToSql;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::borrow::Cow;
+use std::error::Error;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use url::Url;
+use uuid::Uuid;
+
+/// DbConnection backed by a single sqlite database file, for development
+/// and small mirrors that do not want to run a mysql server.
+/// There is only one connection, access from the API threads and the jobs is serialized by its mutex.
+/// Connection strings look like "sqlite:///var/lib/radiobrowser/radio.db"
+/// or "sqlite::memory:".
+#[derive(Clone)]
+pub struct SqliteConnection {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteConnection {
+    const COLUMNS: &'static str =
+        "StationID,ChangeUuid,StationUuid,Name,Url,Homepage,Favicon,UrlCache,
+    Tags,Country,CountryCode,Subcountry,Language,Votes,
+    Creation,
+    strftime('%Y-%m-%d %H:%M:%S',Creation) AS CreationFormated,
+    Codec,Bitrate,Hls,LastCheckOK,
+    LastCheckTime,
+    strftime('%Y-%m-%d %H:%M:%S',LastCheckTime) AS LastCheckTimeFormated,
+    LastCheckOkTime,
+    strftime('%Y-%m-%d %H:%M:%S',LastCheckOkTime) AS LastCheckOkTimeFormated,
+    LastLocalCheckTime,
+    strftime('%Y-%m-%d %H:%M:%S',LastLocalCheckTime) AS LastLocalCheckTimeFormated,
+    ClickTimestamp,
+    strftime('%Y-%m-%d %H:%M:%S',ClickTimestamp) AS ClickTimestampFormated,
+    clickcount,ClickTrend,
+    LanguageCodes,SslError,GeoLat,GeoLong,ExtendedInfo,CountrySubdivisionCode,
+    ServerUuid,NowPlaying";
+
+    const COLUMNS_CHECK: &'static str =
+        "CheckID, StationUuid, CheckUuid, Source, Codec, Bitrate, Hls, CheckOK,
+    CheckTime,
+    strftime('%Y-%m-%d %H:%M:%S',CheckTime) AS CheckTimeFormated,
+    UrlCache,
+    MetainfoOverridesDatabase,Public,Name,
+    Description,Tags,CountryCode,
+    Homepage,Favicon,Loadbalancer,
+    CountrySubdivisionCode,ServerSoftware,Sampling,LanguageCodes,TimingMs,SslError,
+    GeoLat,GeoLong,AudioBitrate,AudioSampling,AudioLiveness";
+
+    const COLUMNS_CLICK: &'static str = "ClickID, StationUuid, ClickUuid, IP,
+    ClickTimestamp,
+    strftime('%Y-%m-%d %H:%M:%S',ClickTimestamp) AS ClickTimestampFormated";
+
+    const COLUMNS_PENDING: &'static str = "StationUuid, Name, Url, Homepage, Favicon, Country, CountryCode, Subcountry,
+    Language, LanguageCodes, Tags, GeoLat, GeoLong, Creation";
+
+    const COLUMNS_HISTORY: &'static str = "StationChangeID,ChangeUuid,
+    StationUuid,Name,
+    Url,Homepage,
+    Favicon,Tags,
+    Subcountry,
+    CountryCode,
+    Language,Votes,
+    LanguageCodes,
+    GeoLat,GeoLong,
+    Creation,
+    strftime('%Y-%m-%d %H:%M:%S',Creation) AS CreationFormated";
+
+    const COLUMNS_STREAMING_SERVER: &'static str = "Id,Uuid,Url,StatusUrl,Status,Error";
+
+    pub fn new(connection_str: &str) -> Result<Self, Box<dyn Error>> {
+        let path = connection_str
+            .strip_prefix("sqlite://")
+            .or_else(|| connection_str.strip_prefix("sqlite:"))
+            .ok_or(DbError::ConnectionError(format!(
+                "not a sqlite connection string: {}",
+                connection_str
+            )))?;
+        let conn = if path.is_empty() || path == ":memory:" {
+            Connection::open_in_memory()?
+        } else {
+            Connection::open(path)?
+        };
+        conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")?;
+        Ok(SqliteConnection {
+            connection: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    fn connection(&self) -> Result<MutexGuard<'_, Connection>, Box<dyn Error>> {
+        self.connection
+            .lock()
+            .map_err(|err| Box::new(DbError::ConnectionError(err.to_string())) as Box<dyn Error>)
+    }
+
+    fn get_list_from_query<A, P>(&self, query: &str, params: P) -> Result<Vec<A>, Box<dyn Error>>
+    where
+        A: for<'a, 'b> From<&'a Row<'b>>,
+        P: rusqlite::Params,
+    {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(query)?;
+        let mut rows = stmt.query(params)?;
+        let mut list: Vec<A> = vec![];
+        while let Some(row) = rows.next()? {
+            list.push(row.into());
+        }
+        Ok(list)
+    }
+
+    fn get_stations_query(&self, query: String) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        self.get_list_from_query(&query, [])
+    }
+
+    fn get_single_column_number(&self, query: &str) -> Result<u64, Box<dyn Error>> {
+        self.get_single_column_number_params(query, [])
+    }
+
+    fn get_single_column_number_params<P: rusqlite::Params>(
+        &self,
+        query: &str,
+        p: P,
+    ) -> Result<u64, Box<dyn Error>> {
+        let conn = self.connection()?;
+        let items: Option<u64> = conn.query_row(query, p, |row| row.get(0))?;
+        Ok(items.unwrap_or(0))
+    }
+
+    /// Number of stations in the source that match the condition, without the ones outside of geo_distance
+    fn count_stations_where(
+        &self,
+        source: &str,
+        condition: &str,
+        params: Vec<(String, Value)>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let params_ref: Vec<(&str, &dyn ToSql)> = params
+            .iter()
+            .map(|(key, value)| (key.as_str(), value as &dyn ToSql))
+            .collect();
+        match distance_filter(geo_lat, geo_long, geo_distance) {
+            Some((lat, long, max)) => {
+                let query = format!("SELECT GeoLat, GeoLong FROM {source} WHERE{condition}", source = source, condition = condition);
+                let conn = self.connection()?;
+                let mut stmt = conn.prepare(&query)?;
+                let locations = stmt
+                    .query_map(params_ref.as_slice(), |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<Result<Vec<(f64, f64)>, _>>()?;
+                Ok(count_within_distance(locations, lat, long, max))
+            }
+            None => {
+                let query = format!("SELECT COUNT(*) FROM {source} WHERE{condition}", source = source, condition = condition);
+                self.get_single_column_number_params(&query, params_ref.as_slice())
+            }
+        }
+    }
+
+    fn get_string_list<P: rusqlite::Params>(
+        conn: &Connection,
+        query: &str,
+        p: P,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut stmt = conn.prepare(query)?;
+        let list = stmt
+            .query_map(p, |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(list)
+    }
+
+    fn get_streaming_servers_query<P: rusqlite::Params>(
+        &self,
+        query: &str,
+        p: P,
+    ) -> Result<Vec<DbStreamingServer>, Box<dyn Error>> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(query)?;
+        let list = stmt
+            .query_map(p, |row| {
+                Ok(DbStreamingServer::new(
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(list)
+    }
+
+    fn get_station_check_steps_query<P: rusqlite::Params>(
+        &self,
+        query: &str,
+        p: P,
+    ) -> Result<Vec<StationCheckStepItem>, Box<dyn Error>> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(query)?;
+        let list = stmt
+            .query_map(p, |row| {
+                let inserttime: chrono::NaiveDateTime = row.get(8)?;
+                Ok(StationCheckStepItem {
+                    id: row.get(0)?,
+                    stationuuid: row.get(1)?,
+                    checkuuid: row.get(2)?,
+                    url: row.get(3)?,
+                    urltype: row.get(4)?,
+                    error: row.get(5)?,
+                    stepuuid: row.get(6)?,
+                    parent_stepuuid: row.get(7)?,
+                    inserttime: chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(
+                        inserttime,
+                        chrono::Utc,
+                    ),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(list)
+    }
+
+    fn backup_stations_by_uuid(
+        transaction: &Connection,
+        stationuuids: &Vec<String>,
+        source: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if !stationuuids.is_empty() {
+            let mut insert_params: Vec<Value> = vec![source.to_string().into()];
+            for stationuuid in stationuuids {
+                insert_params.push(stationuuid.clone().into());
+            }
+            let query = format!(
+                r#"INSERT INTO StationHistory(Name,Url,Homepage,Favicon,CountryCode,SubCountry,Language,LanguageCodes,CountrySubdivisionCode,Tags,Votes,Creation,StationUuid,ChangeUuid,GeoLat,GeoLong,Source)
+                                                       SELECT Name,Url,Homepage,Favicon,CountryCode,SubCountry,Language,LanguageCodes,CountrySubdivisionCode,Tags,Votes,Creation,StationUuid,ChangeUuid,GeoLat,GeoLong,? FROM Station WHERE StationUuid IN ({})"#,
+                placeholders(stationuuids.len())
+            );
+            transaction.execute(&query, params_from_iter(insert_params))?;
+        }
+        Ok(())
+    }
+
+    fn insert_new_station(transaction: &Connection, station: &StationAddItem) -> Result<(), Box<dyn Error>> {
+        let query = "INSERT INTO Station(Name,Url,Homepage,Favicon,Country,CountryCode,Subcountry,Language,LanguageCodes,Tags,ChangeUuid,StationUuid,GeoLat,GeoLong,UrlCache,Creation)
+                        VALUES(:name, :url, :homepage, :favicon, :country, :countrycode, :state, :language, :languagecodes, :tags, :changeuuid, :stationuuid, :geo_lat, :geo_long, '', datetime('now'))";
+        let changeuuid = Uuid::new_v4().as_hyphenated().to_string();
+        transaction.execute(
+            query,
+            named_params! {
+                ":name": station.name,
+                ":url": station.url,
+                ":homepage": station.homepage,
+                ":favicon": station.favicon,
+                ":country": station.country,
+                ":countrycode": station.countrycode,
+                ":state": station.state,
+                ":language": station.language,
+                ":languagecodes": station.languagecodes,
+                ":tags": station.tags,
+                ":changeuuid": changeuuid,
+                ":stationuuid": station.stationuuid,
+                ":geo_lat": station.geo_lat,
+                ":geo_long": station.geo_long,
+            },
+        )?;
+        SqliteConnection::backup_stations_by_uuid(
+            transaction,
+            &(vec![station.stationuuid.clone()]),
+            "INITIAL",
+        )?;
+        Ok(())
+    }
+
+    fn insert_station_by_change_internal(
+        transaction: &Connection,
+        stationchanges: &[StationChangeItemNew],
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        // filter out changes that already exist in the database
+        let stationuuids: Vec<String> = stationchanges
+            .iter()
+            .map(|item| item.stationuuid.clone())
+            .collect();
+        let stationexists = SqliteConnection::get_string_list(
+            transaction,
+            &format!(
+                "SELECT StationUuid FROM Station WHERE StationUuid IN ({})",
+                placeholders(stationuuids.len())
+            ),
+            params_from_iter(stationuuids.iter()),
+        )?;
+
+        let changeuuids: Vec<String> = stationchanges
+            .iter()
+            .map(|item| item.changeuuid.clone())
+            .collect();
+        let changeexists = SqliteConnection::get_string_list(
+            transaction,
+            &format!(
+                "SELECT ChangeUuid FROM StationHistory WHERE ChangeUuid IN ({})",
+                placeholders(changeuuids.len())
+            ),
+            params_from_iter(changeuuids.iter()),
+        )?;
+
+        let mut hash_ids: HashSet<String> = HashSet::new();
+        let mut list_insert: Vec<&StationChangeItemNew> = vec![];
+        let mut list_update: Vec<&StationChangeItemNew> = vec![];
+        for station in stationchanges {
+            if !changeexists.contains(&station.changeuuid) {
+                if stationexists.contains(&station.stationuuid) || hash_ids.contains(&station.stationuuid) {
+                    list_update.push(station);
+                } else {
+                    list_insert.push(station);
+                }
+                hash_ids.insert(station.stationuuid.clone());
+            }
+        }
+
+        trace!("Ignored changes for insert: {}", changeexists.len());
+
+        // insert stations
+        {
+            let mut stmt = transaction.prepare(
+                "INSERT INTO Station(Name,Url,Homepage,Favicon,Country,CountryCode,Subcountry,Language,Tags,ChangeUuid,StationUuid,GeoLat,GeoLong,UrlCache,Creation)
+                VALUES(?,?,?,?,?,?,?,?,?,?,?,?,?,'',datetime('now'))",
+            )?;
+            for change in list_insert {
+                stmt.execute(params![
+                    change.name,
+                    change.url,
+                    change.homepage,
+                    change.favicon,
+                    change.country,
+                    change.countrycode,
+                    change.state,
+                    fix_multi_field(&change.language),
+                    fix_multi_field(&change.tags),
+                    change.changeuuid,
+                    change.stationuuid,
+                    change.geo_lat,
+                    change.geo_long,
+                ])?;
+            }
+        }
+
+        // update stations
+        {
+            let mut stmt = transaction.prepare(
+                r#"UPDATE Station SET
+                Name=:name,
+                Url=:url,
+                Homepage=:homepage,
+                Favicon=:favicon,
+                Country=:country,
+                CountryCode=:countrycode,
+                Subcountry=:subcountry,
+                Language=:language,
+                Tags=:tags,
+                ChangeUuid=:changeuuid,
+                GeoLat=:geolat,
+                GeoLong=:geolong,
+                UrlCache='',
+                Creation=datetime('now')
+            WHERE StationUuid=:stationuuid"#,
+            )?;
+            for change in list_update {
+                stmt.execute(named_params! {
+                    ":name": change.name,
+                    ":url": change.url,
+                    ":homepage": change.homepage,
+                    ":favicon": change.favicon,
+                    ":country": change.country,
+                    ":countrycode": change.countrycode,
+                    ":subcountry": change.state,
+                    ":language": fix_multi_field(&change.language),
+                    ":tags": fix_multi_field(&change.tags),
+                    ":changeuuid": change.changeuuid,
+                    ":geolat": change.geo_lat,
+                    ":geolong": change.geo_long,
+                    ":stationuuid": change.stationuuid,
+                })?;
+            }
+        }
+        Ok(hash_ids.into_iter().collect())
+    }
+}
+
+impl DbConnection for SqliteConnection {
+    fn migrations_needed(&self) -> Result<bool, Box<dyn Error>> {
+        let conn = self.connection()?;
+        let migrations = migrations::load_migrations(&conn)?;
+        migrations.migrations_needed()
+    }
+
+    fn do_migrations(
+        &self,
+        ignore_migration_errors: bool,
+        allow_database_downgrade: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let conn = self.connection()?;
+        let migrations = migrations::load_migrations(&conn)?;
+        migrations.do_migrations(ignore_migration_errors, allow_database_downgrade)?;
+        Ok(())
+    }
+
+    fn get_stations_uuid_order_by_changes(&mut self, min_change_count: u32) -> Result<Vec<String>, Box<dyn Error>> {
+        let query = "SELECT StationUuid FROM StationHistory GROUP BY StationUuid HAVING COUNT(*) > ? ORDER BY COUNT(*) DESC";
+        let conn = self.connection()?;
+        SqliteConnection::get_string_list(&conn, query, [min_change_count])
+    }
+
+    fn get_stations_with_empty_icon(&mut self) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        trace!("get_stations_with_empty_icon()");
+        let query = "SELECT StationUuid, Homepage FROM Station WHERE Favicon='' OR Favicon IS NULL";
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(query)?;
+        let stations = stmt
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get::<_, Option<String>>(1)?.unwrap_or_default()))
+            })?
+            .collect::<Result<Vec<(String, String)>, _>>()?;
+        Ok(stations)
+    }
+
+    fn get_stations_with_non_empty_icon(&mut self) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        trace!("get_stations_with_non_empty_icon()");
+        let query = "SELECT StationUuid, Homepage FROM Station WHERE Favicon<>'' AND Favicon IS NOT NULL";
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(query)?;
+        let stations = stmt
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get::<_, Option<String>>(1)?.unwrap_or_default()))
+            })?
+            .collect::<Result<Vec<(String, String)>, _>>()?;
+        Ok(stations)
+    }
+
+    fn update_station_auto(&mut self, station: &DbStationItem, reason: &str) -> Result<(), Box<dyn Error>> {
+        trace!("update_station_auto({})", station.stationuuid);
+        let query = r#"UPDATE Station SET
+                Name=:name,
+                Homepage=:homepage,
+                Url=:url,
+                Favicon=:favicon,
+                Tags=:tags,
+                Language=:language,
+                LanguageCodes=:languagecodes,
+                CountryCode=:countrycode,
+                CountrySubdivisionCode=:countrysubdivisioncode,
+                GeoLat=:geolat,
+                GeoLong=:geolong,
+                Creation=datetime('now'),
+                ChangeUuid=:changeuuid
+            WHERE
+                StationUuid=:stationuuid"#;
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        transaction.execute(
+            query,
+            named_params! {
+                ":name": station.name,
+                ":homepage": station.homepage,
+                ":url": station.url,
+                ":favicon": station.favicon,
+                ":language": station.language,
+                ":tags": station.tags,
+                ":languagecodes": station.languagecodes,
+                ":countrycode": station.countrycode,
+                ":countrysubdivisioncode": station.iso_3166_2,
+                ":geolat": station.geo_lat,
+                ":geolong": station.geo_long,
+                ":stationuuid": station.stationuuid,
+                ":changeuuid": Uuid::new_v4().as_hyphenated().to_string(),
+            },
+        )?;
+        SqliteConnection::backup_stations_by_uuid(
+            &transaction,
+            &(vec![station.stationuuid.to_string()]),
+            reason,
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn calc_country_field(&mut self) -> Result<(), Box<dyn Error>> {
+        trace!("calc_country_field() 0");
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+
+        transaction.execute_batch("UPDATE Station SET CountryCode=UPPER(CountryCode); UPDATE StationHistory SET CountryCode=UPPER(CountryCode);")?;
+
+        trace!("calc_country_field() 1");
+
+        let result = SqliteConnection::get_string_list(
+            &transaction,
+            "SELECT DISTINCT(CountryCode) FROM Station WHERE CountryCode IS NOT NULL",
+            [],
+        )?;
+        trace!("calc_country_field() 2");
+        {
+            let mut stmt = transaction
+                .prepare("UPDATE Station SET Country=:country WHERE CountryCode=:countrycode")?;
+            for cc in result.iter() {
+                let country = Country::from_alpha2(cc).map(|d| d.long_name).unwrap_or("");
+                stmt.execute(named_params! {":countrycode": cc, ":country": country})?;
+            }
+        }
+        trace!("calc_country_field() 3");
+        transaction.commit()?;
+        trace!("calc_country_field() 4");
+
+        Ok(())
+    }
+
+    fn get_duplicated_stations(
+        &self,
+        column_key: &str,
+        max_duplicates: usize,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        trace!(
+            "get_duplicated_stations({},{}) started..",
+            column_key,
+            max_duplicates
+        );
+        let mut list = vec![];
+        if max_duplicates > 0 {
+            let conn = self.connection()?;
+            let urls = SqliteConnection::get_string_list(
+                &conn,
+                &format!("SELECT {column_key} FROM Station WHERE {column_key} IS NOT NULL GROUP BY {column_key} HAVING COUNT({column_key})>?", column_key = column_key),
+                params![max_duplicates],
+            )?;
+            for url in urls.iter() {
+                let uuids = SqliteConnection::get_string_list(
+                    &conn,
+                    &format!("SELECT StationUuid FROM Station WHERE {column_key}=? ORDER BY clickcount DESC, Votes DESC, StationUuid LIMIT ?,1000", column_key = column_key),
+                    params![url, max_duplicates],
+                )?;
+                list.extend(uuids);
+            }
+        }
+        Ok(list)
+    }
+
+    fn delete_stations(&self, stationuuids: &[String]) -> Result<(), Box<dyn Error>> {
+        trace!("delete_stations()");
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        {
+            let mut stmt = transaction.prepare("DELETE FROM Station WHERE StationUuid=?")?;
+            for uuid in stationuuids {
+                stmt.execute([uuid])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn delete_old_checks(&mut self, seconds: u64) -> Result<(), Box<dyn Error>> {
+        trace!("delete_old_checks()");
+        let delete_old_checks_history_query = "DELETE FROM StationCheckHistory WHERE CheckTime < datetime('now', ?)";
+        let conn = self.connection()?;
+        conn.execute(delete_old_checks_history_query, [seconds_ago(seconds)])?;
+        Ok(())
+    }
+
+    fn delete_old_clicks(&mut self, seconds: u64) -> Result<(), Box<dyn Error>> {
+        trace!("delete_old_clicks()");
+        let delete_old_clicks_query = "DELETE FROM StationClick WHERE ClickTimestamp < datetime('now', ?)";
+        let conn = self.connection()?;
+        conn.execute(delete_old_clicks_query, [seconds_ago(seconds)])?;
+        Ok(())
+    }
+
+    fn delete_removed_from_history(&mut self) -> Result<(), Box<dyn Error>> {
+        trace!("delete_removed_from_history()");
+        let query = "DELETE FROM StationHistory WHERE NOT EXISTS (SELECT 1 FROM Station s WHERE s.StationUuid=StationHistory.StationUuid AND s.Tags IS NOT NULL)";
+        let conn = self.connection()?;
+        conn.execute(query, [])?;
+        Ok(())
+    }
+
+    fn delete_never_working(&mut self, seconds: u64) -> Result<(), Box<dyn Error>> {
+        trace!("delete_never_working()");
+        let delete_never_working_query = "DELETE FROM Station WHERE LastCheckOkTime IS NULL AND Creation < datetime('now', ?)";
+        let conn = self.connection()?;
+        conn.execute(delete_never_working_query, [seconds_ago(seconds)])?;
+        Ok(())
+    }
+
+    fn delete_were_working(&mut self, seconds: u64) -> Result<(), Box<dyn Error>> {
+        trace!("delete_were_working()");
+        let delete_were_working_query = "DELETE FROM Station WHERE LastCheckOK=0 AND LastCheckOkTime IS NOT NULL AND LastCheckOkTime < datetime('now', ?)";
+        let conn = self.connection()?;
+        conn.execute(delete_were_working_query, [seconds_ago(seconds)])?;
+        Ok(())
+    }
+
+    fn get_stations_deletable_were_working(&self, seconds: u64) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let query = format!(
+            "SELECT {columns} FROM Station{now_playing} WHERE LastCheckOK=0 AND LastCheckOkTime IS NOT NULL AND LastCheckOkTime < datetime('now', ?)",
+            columns = SqliteConnection::COLUMNS, now_playing = now_playing_join()
+        );
+        self.get_list_from_query(&query, [seconds_ago(seconds)])
+    }
+
+    fn delete_unused_streaming_servers(&mut self, seconds: u64) -> Result<(), Box<dyn Error>> {
+        trace!("delete_unused_streaming_servers()");
+        let conn = self.connection()?;
+        let query = "DELETE FROM StreamingServers WHERE CreatedAt < datetime('now', ?) AND NOT EXISTS (SELECT 1 FROM Station st WHERE st.ServerUuid=StreamingServers.Uuid)";
+        conn.execute(query, [seconds_ago(seconds)])?;
+        Ok(())
+    }
+
+    fn delete_change_by_uuid(&mut self, changeuuids: &[String]) -> Result<(), Box<dyn Error>> {
+        trace!("delete_change_by_uuid()");
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        {
+            let mut stmt = transaction.prepare("DELETE FROM StationHistory WHERE ChangeUuid=?")?;
+            for uuid in changeuuids {
+                stmt.execute([uuid])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn resethistory(&mut self) -> Result<(), Box<dyn Error>> {
+        trace!("resethistory()");
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        transaction.execute("DELETE FROM StationHistory;", [])?;
+        trace!("resethistory() deletion done");
+        transaction.execute(r#"INSERT INTO StationHistory(Name,Url,Homepage,Favicon,CountryCode,SubCountry,Language,LanguageCodes,CountrySubdivisionCode,Tags,Votes,Creation,StationUuid,ChangeUuid,GeoLat,GeoLong,Source)
+                                                      SELECT Name,Url,Homepage,Favicon,CountryCode,SubCountry,Language,LanguageCodes,CountrySubdivisionCode,Tags,Votes,Creation,StationUuid,ChangeUuid,GeoLat,GeoLong,'INITIAL' FROM Station"#, [])?;
+        trace!("resethistory() reinsert done");
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn remove_unused_ip_infos_from_stationclicks(
+        &mut self,
+        seconds: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        trace!("remove_unused_ip_infos_from_stationclicks()");
+        let query = "UPDATE StationClick SET IP=NULL WHERE InsertTime < datetime('now', ?)";
+        let conn = self.connection()?;
+        conn.execute(query, [seconds_ago(seconds)])?;
+        Ok(())
+    }
+
+    fn update_stations_clickcount(&self) -> Result<(), Box<dyn Error>> {
+        trace!("update_stations_clickcount() 1");
+        let query = "UPDATE Station SET
+        clickcount=IFNULL((SELECT COUNT(*) FROM StationClick sc WHERE Station.StationUuid=sc.StationUuid),0),
+        ClickTrend=
+        (
+            (SELECT COUNT(*) FROM StationClick sc1 WHERE sc1.StationUuid=Station.StationUuid AND ClickTimestamp>datetime('now','-1 day') AND ClickTimestamp<=datetime('now')) -
+            (SELECT COUNT(*) FROM StationClick sc2 WHERE sc2.StationUuid=Station.StationUuid AND ClickTimestamp>datetime('now','-2 day') AND ClickTimestamp<=datetime('now','-1 day'))
+        ),
+        ClickTimestamp=(SELECT MAX(ClickTimestamp) FROM StationClick sc WHERE sc.StationUuid=Station.StationUuid);";
+        self.connection()?.execute(query, [])?;
+        trace!("update_stations_clickcount() 2");
+        Ok(())
+    }
+
+    fn get_station_count_broken(&self) -> Result<u64, Box<dyn Error>> {
+        self.get_single_column_number(
+            "SELECT COUNT(*) AS Items FROM Station WHERE LastCheckOK=0 OR LastCheckOK IS NULL",
+        )
+    }
+
+    fn get_station_count_working(&self) -> Result<u64, Box<dyn Error>> {
+        self.get_single_column_number("SELECT COUNT(*) AS Items FROM Station WHERE LastCheckOK=1")
+    }
+
+    fn get_tag_count(&self) -> Result<u64, Box<dyn Error>> {
+        self.get_single_column_number("SELECT COUNT(*) AS StationCount FROM TagCache")
+    }
+
+    fn get_country_count(&self) -> Result<u64, Box<dyn Error>> {
+        self.get_single_column_number("SELECT COUNT(DISTINCT(Country)) AS StationCount FROM Station")
+    }
+
+    fn get_language_count(&self) -> Result<u64, Box<dyn Error>> {
+        self.get_single_column_number("SELECT COUNT(*) AS StationCount FROM LanguageCache")
+    }
+
+    fn get_click_count_last_hour(&self) -> Result<u64, Box<dyn Error>> {
+        self.get_single_column_number("SELECT COUNT(*) FROM StationClick WHERE ClickTimestamp >= datetime('now','-60 minutes')")
+    }
+
+    fn get_click_count_last_day(&self) -> Result<u64, Box<dyn Error>> {
+        self.get_single_column_number("SELECT COUNT(*) FROM StationClick WHERE ClickTimestamp >= datetime('now','-24 hours')")
+    }
+
+    /**
+     * Get number of stations that do not have any checks in the last x hours
+     */
+    fn get_station_count_todo(&self, hours: u32) -> Result<u64, Box<dyn Error>> {
+        self.get_single_column_number_params(
+            "SELECT COUNT(*) AS Items FROM Station WHERE LastLocalCheckTime IS NULL OR LastLocalCheckTime < datetime('now', ?)",
+            [format!("-{} hours", hours)],
+        )
+    }
+
+    fn delete_stationhistory_byid_more_than(&self, stationuuid: String, itemcount: usize) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.connection()?;
+        let items: Vec<u64> = {
+            let mut stmt = conn.prepare(
+                "SELECT StationChangeID FROM StationHistory WHERE StationUuid=? ORDER BY Creation DESC",
+            )?;
+            let list = stmt
+                .query_map([stationuuid], |row| row.get(0))?
+                .collect::<Result<Vec<u64>, _>>()?;
+            list
+        };
+        if items.len() > itemcount {
+            let (_items_keep, items_delete) = items.split_at(itemcount);
+            let transaction = conn.transaction()?;
+            {
+                let mut stmt = transaction.prepare("DELETE FROM StationHistory WHERE StationChangeID=?")?;
+                for changeid in items_delete {
+                    stmt.execute([changeid])?;
+                }
+            }
+            transaction.commit()?;
+        }
+        Ok(())
+    }
+
+    fn delete_stationhistory_more_than(&self, itemcount: u32) -> Result<(), Box<dyn Error>> {
+        let conn = self.connection()?;
+        let query = r#"SELECT StationChangeID FROM
+            (
+                SELECT StationChangeID,
+                ROW_NUMBER() OVER (PARTITION BY StationUuid ORDER BY Creation) AS row_number
+                FROM StationHistory
+                WHERE StationUuid='0f902505-76c7-489b-8ddc-03b05b5867ae'
+            )
+            AS temp_table WHERE temp_table.row_number > ?;"#;
+        let mut stmt = conn.prepare(query)?;
+        let _items = stmt
+            .query_map([itemcount], |row| row.get(0))?
+            .collect::<Result<Vec<u64>, _>>()?;
+        Ok(())
+        //DELETE FROM StationHistory WHERE StationChangeID IN ();
+    }
+
+    fn get_stations_to_check(
+        &mut self,
+        hours: u32,
+        itemcount: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let query = format!("SELECT {columns} FROM Station{now_playing} WHERE {due} IS NULL OR {due} <= datetime('now') ORDER BY {due} ASC, ClickCount DESC LIMIT {limit}", columns = SqliteConnection::COLUMNS, now_playing = now_playing_join(), due = check_due(hours), limit = itemcount);
+        self.get_stations_query(query)
+    }
+
+    fn set_next_check_times(&self, list: &[(String, u64)]) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        {
+            let mut stmt = transaction.prepare("UPDATE Station SET NextCheckTime=datetime('now', :next) WHERE StationUuid=:stationuuid")?;
+            for (stationuuid, seconds) in list {
+                stmt.execute(named_params! {":next": format!("+{} seconds", seconds), ":stationuuid": stationuuid})?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn get_check_queue(&self, hours: u32) -> Result<(u64, u64), Box<dyn Error>> {
+        let query = format!(
+            "SELECT COUNT(*), MAX(CAST(strftime('%s', 'now') AS INTEGER) - CAST(strftime('%s', {due}) AS INTEGER)) FROM Station WHERE {due} IS NULL OR {due} <= datetime('now')",
+            due = check_due(hours)
+        );
+        let conn = self.connection()?;
+        let (depth, lag): (u64, Option<u64>) = conn.query_row(&query, [], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok((depth, lag.unwrap_or(0)))
+    }
+
+    fn get_servers_to_check(
+        &mut self,
+        hours: u32,
+        chunksize: u32,
+    ) -> Result<Vec<DbStreamingServer>, Box<dyn Error>> {
+        let query = format!(
+            "SELECT {columns} FROM StreamingServers WHERE UpdatedAt IS NULL OR UpdatedAt < datetime('now', '-{hours} hours') LIMIT {chunksize}",
+            columns = SqliteConnection::COLUMNS_STREAMING_SERVER,
+            hours = hours,
+            chunksize = chunksize
+        );
+        self.get_streaming_servers_query(&query, [])
+    }
+
+    fn get_streaming_servers_by_url(
+        &mut self,
+        items: Vec<String>,
+    ) -> Result<Vec<DbStreamingServer>, Box<dyn Error>> {
+        if !items.is_empty() {
+            let query = format!(
+                "SELECT {columns} FROM StreamingServers WHERE Url IN ({search})",
+                columns = SqliteConnection::COLUMNS_STREAMING_SERVER,
+                search = placeholders(items.len())
+            );
+            self.get_streaming_servers_query(&query, params_from_iter(items.iter()))
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    fn get_streaming_servers(
+        &self,
+        order: &str,
+        reverse: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStreamingServer>, Box<dyn Error>> {
+        let order = filter_order_streaming_server(order);
+        let reverse_string = if reverse { "DESC" } else { "ASC" };
+        let query = format!("SELECT {columns} FROM StreamingServers ORDER BY {order} {reverse} LIMIT {offset},{limit}", columns = SqliteConnection::COLUMNS_STREAMING_SERVER, order = order, reverse = reverse_string, offset = offset, limit = limit);
+        self.get_streaming_servers_query(&query, [])
+    }
+
+    fn get_streaming_servers_by_uuids(
+        &self,
+        uuids: Vec<String>,
+        order: &str,
+        reverse: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStreamingServer>, Box<dyn Error>> {
+        if !uuids.is_empty() {
+            let order = filter_order_streaming_server(order);
+            let reverse_string = if reverse { "DESC" } else { "ASC" };
+            let query = format!("SELECT {columns} FROM StreamingServers WHERE Uuid IN ({search}) ORDER BY {order} {reverse} LIMIT {offset},{limit}", columns = SqliteConnection::COLUMNS_STREAMING_SERVER, order = order, reverse = reverse_string, offset = offset, limit = limit, search = placeholders(uuids.len()));
+            self.get_streaming_servers_query(&query, params_from_iter(uuids.iter()))
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    fn get_streaming_servers_by_station_uuids(
+        &self,
+        uuids: Vec<String>,
+        order: &str,
+        reverse: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStreamingServer>, Box<dyn Error>> {
+        if !uuids.is_empty() {
+            let urls: Vec<_> = self
+                .get_stations_by_uuid(uuids)?
+                .drain(..)
+                .filter_map(|station| Url::parse(&station.url_resolved).ok())
+                .map(|mut url| {
+                    url.set_path("/");
+                    url.set_query(None);
+                    url.set_fragment(None);
+                    url.to_string()
+                })
+                .collect();
+
+            let order = filter_order_streaming_server(order);
+            let reverse_string = if reverse { "DESC" } else { "ASC" };
+            if !urls.is_empty() {
+                let query = format!("SELECT {columns} FROM StreamingServers WHERE Url IN ({search}) ORDER BY {order} {reverse} LIMIT {offset},{limit}", columns = SqliteConnection::COLUMNS_STREAMING_SERVER, order = order, reverse = reverse_string, offset = offset, limit = limit, search = placeholders(urls.len()));
+                self.get_streaming_servers_query(&query, params_from_iter(urls.iter()))
+            } else {
+                Ok(vec![])
+            }
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    fn insert_streaming_servers(
+        &mut self,
+        items: Vec<DbStreamingServerNew>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut existing =
+            self.get_streaming_servers_by_url(items.iter().map(|item| item.url.clone()).collect())?;
+        let existing_urls: Vec<_> = existing.drain(..).map(|item| item.url).collect();
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        {
+            let mut stmt = transaction.prepare("INSERT INTO StreamingServers (Uuid, Url, StatusUrl, Status, CreatedAt, Error) VALUES (?,?,?,?,datetime('now'),?)")?;
+            for item in items.iter().filter(|item2| !existing_urls.contains(&item2.url)) {
+                stmt.execute(params![
+                    Uuid::new_v4().as_hyphenated().to_string(),
+                    item.url,
+                    item.statusurl,
+                    item.status,
+                    item.error,
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn update_streaming_servers(
+        &mut self,
+        items: Vec<DbStreamingServer>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        {
+            let mut stmt = transaction.prepare("UPDATE StreamingServers SET Url=?,StatusUrl=?,Status=?,Error=?,UpdatedAt=datetime('now') WHERE Id=?")?;
+            for item in items.iter() {
+                stmt.execute(params![
+                    item.url,
+                    item.statusurl,
+                    item.status,
+                    item.error,
+                    item.id,
+                ])?;
+            }
+        }
+        {
+            let mut stmt = transaction.prepare("UPDATE Station SET ServerUuid=:uuid WHERE UrlCache IS NOT NULL AND UrlCache LIKE :url || '%'")?;
+            for item in items.iter() {
+                stmt.execute(named_params! {":uuid": item.uuid, ":url": item.url})?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn get_station_by_uuid(&self, id_str: &str) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let query = format!(
+            "SELECT {columns} from Station{now_playing} WHERE StationUuid=? ORDER BY Name",
+            columns = SqliteConnection::COLUMNS, now_playing = now_playing_join()
+        );
+        self.get_list_from_query(&query, [id_str])
+    }
+
+    fn get_stations_by_uuid(&self, uuids: Vec<String>) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        if !uuids.is_empty() {
+            let query_select_stations_by_uuid = format!(
+                "SELECT {columns} FROM Station{now_playing} WHERE StationUuid IN ({items})",
+                items = placeholders(uuids.len()),
+                columns = SqliteConnection::COLUMNS, now_playing = now_playing_join()
+            );
+            self.get_list_from_query(&query_select_stations_by_uuid, params_from_iter(uuids.iter()))
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    fn get_deletable_never_working(&self, seconds: u64) -> Result<u64, Box<dyn Error>> {
+        self.get_single_column_number_params("SELECT COUNT(*) AS Items FROM Station WHERE LastCheckOkTime IS NULL AND Creation < datetime('now', ?)", [seconds_ago(seconds)])
+    }
+
+    fn get_deletable_were_working(&self, seconds: u64) -> Result<u64, Box<dyn Error>> {
+        self.get_single_column_number_params("SELECT COUNT(*) AS Items FROM Station WHERE LastCheckOK=0 AND LastCheckOkTime IS NOT NULL AND LastCheckOkTime < datetime('now', ?)", [seconds_ago(seconds)])
+    }
+
+    fn get_stations_broken(
+        &self,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        self.get_stations_query(format!(
+            "SELECT {columns} from Station{now_playing} WHERE LastCheckOK=FALSE ORDER BY RANDOM() LIMIT {offset},{limit}",
+            columns = SqliteConnection::COLUMNS, now_playing = now_playing_join(),
+            offset = offset,
+            limit = limit
+        ))
+    }
+
+    fn get_stations_topvote(
+        &self,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let hidebroken_string = if hidebroken {
+            " WHERE LastCheckOK=TRUE"
+        } else {
+            ""
+        };
+        self.get_stations_query(format!(
+            "SELECT {columns} from Station{now_playing} {where} ORDER BY Votes DESC LIMIT {offset},{limit}",
+            columns = SqliteConnection::COLUMNS, now_playing = now_playing_join(),
+            where = hidebroken_string,
+            offset = offset,
+            limit = limit
+        ))
+    }
+
+    fn get_stations_topclick(
+        &self,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let hidebroken_string = if hidebroken {
+            " WHERE LastCheckOK=TRUE"
+        } else {
+            ""
+        };
+        self.get_stations_query(format!(
+            "SELECT {columns} from Station{now_playing} {where} ORDER BY clickcount DESC LIMIT {offset},{limit}",
+            columns = SqliteConnection::COLUMNS, now_playing = now_playing_join(),
+            where = hidebroken_string,
+            offset = offset,
+            limit = limit
+        ))
+    }
+
+    fn get_stations_lastclick(
+        &self,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let hidebroken_string = if hidebroken {
+            " WHERE LastCheckOK=TRUE"
+        } else {
+            ""
+        };
+        self.get_stations_query(format!(
+            "SELECT {columns} from Station{now_playing} {where} ORDER BY ClickTimestamp DESC LIMIT {offset},{limit}",
+            columns = SqliteConnection::COLUMNS, now_playing = now_playing_join(),
+            where = hidebroken_string,
+            offset = offset,
+            limit = limit
+        ))
+    }
+
+    fn get_stations_lastchange(
+        &self,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let hidebroken_string = if hidebroken {
+            " WHERE LastCheckOK=TRUE"
+        } else {
+            ""
+        };
+        self.get_stations_query(format!(
+            "SELECT {columns} from Station{now_playing} {where} ORDER BY Creation DESC LIMIT {offset},{limit}",
+            columns = SqliteConnection::COLUMNS, now_playing = now_playing_join(),
+            where = hidebroken_string,
+            offset = offset,
+            limit = limit
+        ))
+    }
+
+    fn get_stations_by_column(
+        &self,
+        column_name: &str,
+        search: String,
+        exact: bool,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        fields: Option<&StationFields>,
+        cursor: Option<&StationCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let query = format!("SELECT {columns} from Station{now_playing} WHERE {condition}{cursor} ORDER BY {order} LIMIT {offset},{limit}",
+            columns = station_columns(fields, order), now_playing = now_playing_join(), condition = column_condition(column_name, exact, hidebroken),
+            cursor = cursor_condition(cursor, order, false), order = sql_order(filter_order(order), reverse), offset = offset, limit = limit);
+        let mut params = vec![Value::from(search.to_lowercase())];
+        if let Some(cursor) = cursor {
+            params.extend(cursor.positional_params().into_iter().map(cursor_value));
+        }
+        self.get_list_from_query(&query, params_from_iter(params.iter()))
+    }
+
+    fn count_stations_by_column(&self, column_name: &str, search: String, exact: bool, hidebroken: bool) -> Result<u64, Box<dyn Error>> {
+        let query = format!("SELECT COUNT(*) FROM Station WHERE {condition}", condition = column_condition(column_name, exact, hidebroken));
+        self.get_single_column_number_params(&query, [search.to_lowercase()])
+    }
+
+    fn get_stations_by_column_multiple(
+        &self,
+        column_name: &str,
+        search: Option<String>,
+        exact: bool,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        fields: Option<&StationFields>,
+        cursor: Option<&StationCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let query = format!("SELECT {columns} from Station{now_playing} WHERE {condition}{cursor} ORDER BY {order} LIMIT {offset},{limit}",
+            columns = station_columns(fields, order), now_playing = now_playing_join(), condition = column_multiple_condition(column_name, exact, hidebroken),
+            cursor = cursor_condition(cursor, order, true), order = sql_order(filter_order(order), reverse), offset = offset, limit = limit);
+        let mut params = vec![(String::from(":search"), Value::from(search))];
+        params.extend(cursor_named_params(cursor));
+        let params_ref: Vec<(&str, &dyn ToSql)> = params
+            .iter()
+            .map(|(key, value)| (key.as_str(), value as &dyn ToSql))
+            .collect();
+        self.get_list_from_query(&query, params_ref.as_slice())
+    }
+
+    fn count_stations_by_column_multiple(&self, column_name: &str, search: Option<String>, exact: bool, hidebroken: bool) -> Result<u64, Box<dyn Error>> {
+        let query = format!("SELECT COUNT(*) FROM Station WHERE {condition}", condition = column_multiple_condition(column_name, exact, hidebroken));
+        self.get_single_column_number_params(&query, named_params! {":search": search})
+    }
+
+    fn get_stations_by_server_uuids(
+        &self,
+        uuids: Vec<String>,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let order = filter_order(order);
+        let reverse_string = if reverse { "DESC" } else { "ASC" };
+        let hidebroken_string = if hidebroken {
+            " AND LastCheckOK=TRUE"
+        } else {
+            ""
+        };
+        let query = format!("SELECT {columns} from Station{now_playing} WHERE ServerUuid IN ({search}) {hidebroken} ORDER BY {order} {reverse} LIMIT {offset},{limit}", columns = SqliteConnection::COLUMNS, now_playing = now_playing_join(), order = order, reverse = reverse_string, hidebroken = hidebroken_string, offset = offset, limit = limit, search = placeholders(uuids.len()));
+        self.get_list_from_query(&query, params_from_iter(uuids.iter()))
+    }
+
+    fn get_stations_by_all(
+        &self,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        fields: Option<&StationFields>,
+        cursor: Option<&StationCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let hidebroken_string = if hidebroken {
+            " AND LastCheckOK=TRUE"
+        } else {
+            ""
+        };
+
+        let query = format!("SELECT {columns} from Station{now_playing} WHERE TRUE{hidebroken}{cursor} ORDER BY {order} LIMIT {offset},{limit}",
+            columns = station_columns(fields, order), now_playing = now_playing_join(), order = sql_order(filter_order(order), reverse),
+            hidebroken = hidebroken_string, cursor = cursor_condition(cursor, order, true), offset = offset, limit = limit);
+        let params = cursor_named_params(cursor);
+        let params_ref: Vec<(&str, &dyn ToSql)> = params
+            .iter()
+            .map(|(key, value)| (key.as_str(), value as &dyn ToSql))
+            .collect();
+        self.get_list_from_query(&query, params_ref.as_slice())
+    }
+
+    fn get_stations_advanced(
+        &self,
+        name: Option<String>,
+        name_exact: bool,
+        country: Option<String>,
+        country_exact: bool,
+        countrycode: Option<String>,
+        state: Option<String>,
+        state_exact: bool,
+        language: Option<String>,
+        language_exact: bool,
+        tag: Option<String>,
+        tag_exact: bool,
+        tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,
+        bitrate_max: u32,
+        has_geo_info: Option<bool>,
+        has_extended_info: Option<bool>,
+        is_https: Option<bool>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+        geo_bbox: Option<GeoBoundingBox>,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        fields: Option<&StationFields>,
+        cursor: Option<&StationCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let (condition, mut params) = advanced_condition(name, name_exact, country, country_exact, countrycode, state, state_exact,
+            language, language_exact, tag, tag_exact, tag_list, codec, bitrate_min, bitrate_max, has_geo_info, has_extended_info, is_https,
+            geo_lat, geo_long, geo_distance, geo_bbox, hidebroken);
+        params.extend(cursor_named_params(cursor));
+        let (query_offset, query_limit) = query_range(geo_lat, geo_long, geo_distance, order, offset, limit);
+        let query = format!(
+            "SELECT {columns} from Station{now_playing} WHERE{condition}{cursor} ORDER BY {order} LIMIT {offset},{limit}",
+            columns = station_columns(fields, order), now_playing = now_playing_join(),
+            condition = condition,
+            cursor = cursor_condition(cursor, order, true),
+            order = sql_order(filter_order(order), reverse),
+            offset = query_offset,
+            limit = query_limit
+        );
+        let params_ref: Vec<(&str, &dyn ToSql)> = params
+            .iter()
+            .map(|(key, value)| (key.as_str(), value as &dyn ToSql))
+            .collect();
+        let list = self.get_list_from_query(&query, params_ref.as_slice())?;
+        Ok(finish_query(list, geo_lat, geo_long, geo_distance, order, reverse, offset, limit))
+    }
+
+    fn count_stations_advanced(
+        &self,
+        name: Option<String>,
+        name_exact: bool,
+        country: Option<String>,
+        country_exact: bool,
+        countrycode: Option<String>,
+        state: Option<String>,
+        state_exact: bool,
+        language: Option<String>,
+        language_exact: bool,
+        tag: Option<String>,
+        tag_exact: bool,
+        tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,
+        bitrate_max: u32,
+        has_geo_info: Option<bool>,
+        has_extended_info: Option<bool>,
+        is_https: Option<bool>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+        geo_bbox: Option<GeoBoundingBox>,
+        hidebroken: bool,
+    ) -> Result<u64, Box<dyn Error>> {
+        let (condition, params) = advanced_condition(name, name_exact, country, country_exact, countrycode, state, state_exact,
+            language, language_exact, tag, tag_exact, tag_list, codec, bitrate_min, bitrate_max, has_geo_info, has_extended_info, is_https,
+            geo_lat, geo_long, geo_distance, geo_bbox, hidebroken);
+        self.count_stations_where("Station", &condition, params, geo_lat, geo_long, geo_distance)
+    }
+
+    fn get_stations_search(
+        &self,
+        query: &str,
+        name: Option<String>,
+        name_exact: bool,
+        country: Option<String>,
+        country_exact: bool,
+        countrycode: Option<String>,
+        state: Option<String>,
+        state_exact: bool,
+        language: Option<String>,
+        language_exact: bool,
+        tag: Option<String>,
+        tag_exact: bool,
+        tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,
+        bitrate_max: u32,
+        has_geo_info: Option<bool>,
+        has_extended_info: Option<bool>,
+        is_https: Option<bool>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+        geo_bbox: Option<GeoBoundingBox>,
+        order: &str,
+        reverse: bool,
+        hidebroken: bool,
+        cursor: Option<&StationCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DbStationItem>, Box<dyn Error>> {
+        let (condition, mut params) = advanced_condition(name, name_exact, country, country_exact, countrycode, state, state_exact,
+            language, language_exact, tag, tag_exact, tag_list, codec, bitrate_min, bitrate_max, has_geo_info, has_extended_info, is_https,
+            geo_lat, geo_long, geo_distance, geo_bbox, hidebroken);
+        let (source, search_params) = search_source(query);
+        params.extend(search_params);
+        params.extend(cursor_named_params(cursor));
+        let (query_offset, query_limit) = query_range(geo_lat, geo_long, geo_distance, order, offset, limit);
+        let query = format!(
+            "SELECT {columns},{score} AS Score from {source}{now_playing} WHERE{condition}{cursor} ORDER BY {order} LIMIT {offset},{limit}",
+            columns = SqliteConnection::COLUMNS, now_playing = now_playing_join(),
+            score = sql_score("Relevance"),
+            source = source,
+            condition = condition,
+            cursor = cursor_condition(cursor, order, true),
+            order = search_order(order, reverse),
+            offset = query_offset,
+            limit = query_limit
+        );
+        let params_ref: Vec<(&str, &dyn ToSql)> = params
+            .iter()
+            .map(|(key, value)| (key.as_str(), value as &dyn ToSql))
+            .collect();
+        let list = self.get_list_from_query(&query, params_ref.as_slice())?;
+        Ok(finish_query(list, geo_lat, geo_long, geo_distance, order, reverse, offset, limit))
+    }
+
+    fn count_stations_search(
+        &self,
+        query: &str,
+        name: Option<String>,
+        name_exact: bool,
+        country: Option<String>,
+        country_exact: bool,
+        countrycode: Option<String>,
+        state: Option<String>,
+        state_exact: bool,
+        language: Option<String>,
+        language_exact: bool,
+        tag: Option<String>,
+        tag_exact: bool,
+        tag_list: Vec<String>,
+        codec: Option<String>,
+        bitrate_min: u32,
+        bitrate_max: u32,
+        has_geo_info: Option<bool>,
+        has_extended_info: Option<bool>,
+        is_https: Option<bool>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        geo_distance: Option<f64>,
+        geo_bbox: Option<GeoBoundingBox>,
+        hidebroken: bool,
+    ) -> Result<u64, Box<dyn Error>> {
+        let (condition, mut params) = advanced_condition(name, name_exact, country, country_exact, countrycode, state, state_exact,
+            language, language_exact, tag, tag_exact, tag_list, codec, bitrate_min, bitrate_max, has_geo_info, has_extended_info, is_https,
+            geo_lat, geo_long, geo_distance, geo_bbox, hidebroken);
+        let (source, search_params) = search_source(query);
+        params.extend(search_params);
+        self.count_stations_where(&source, &condition, params, geo_lat, geo_long, geo_distance)
+    }
+
+    fn get_changes(
+        &self,
+        stationuuid: Option<String>,
+        changeuuid: Option<String>,
+        limit: u32,
+    ) -> Result<Vec<StationHistoryItem>, Box<dyn Error>> {
+        let mut params: Vec<(&str, &dyn ToSql)> = vec![];
+        let changeuuid_str = if changeuuid.is_some() {
+            params.push((":changeuuid", &changeuuid));
+            " AND StationChangeID >= IFNULL((SELECT StationChangeID FROM StationHistory WHERE ChangeUuid=:changeuuid),0)
+              AND StationChangeID <= (SELECT MAX(StationChangeID) FROM StationHistory WHERE Creation <= datetime('now','-60 seconds'))
+              AND ChangeUuid<>:changeuuid"
+        } else {
+            ""
+        };
+
+        let stationuuid_str = if stationuuid.is_some() {
+            params.push((":stationuuid", &stationuuid));
+            " AND StationUuid=:stationuuid"
+        } else {
+            ""
+        };
+        let query: String = format!("SELECT {columns} from StationHistory WHERE 1=1 {changeuuid_str} {stationuuid} ORDER BY StationChangeID ASC LIMIT {limit}", columns = SqliteConnection::COLUMNS_HISTORY, changeuuid_str = changeuuid_str, stationuuid = stationuuid_str, limit = limit);
+        self.get_list_from_query(&query, params.as_slice())
+    }
+
+    /// Select all historic changes for stations with the given uuids
+    /// ordered by creation date
+    fn get_changes_for_stations(
+        &self,
+        station_uuids: Vec<String>,
+    ) -> Result<Vec<StationHistoryItem>, Box<dyn Error>> {
+        if !station_uuids.is_empty() {
+            let query: String = format!("SELECT {columns} from StationHistory WHERE StationUuid IN ({stationuuids_str}) ORDER BY Creation ASC", columns = SqliteConnection::COLUMNS_HISTORY, stationuuids_str = placeholders(station_uuids.len()));
+            self.get_list_from_query(&query, params_from_iter(station_uuids.iter()))
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    fn add_station_opt(
+        &self,
+        name: Option<String>,
+        url: Option<String>,
+        homepage: Option<String>,
+        favicon: Option<String>,
+        countrycode: Option<String>,
+        state: Option<String>,
+        language: Option<String>,
+        languagecodes: Option<String>,
+        tags: Option<String>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+    ) -> Result<String, Box<dyn Error>> {
+        let station = check_new_station(name, url, homepage, favicon, countrycode, state, language, languagecodes, tags, geo_lat, geo_long)?;
+
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        SqliteConnection::insert_new_station(&transaction, &station)?;
+        transaction.commit()?;
+
+        Ok(station.stationuuid)
+    }
+
+    fn add_pending_station_opt(
+        &self,
+        name: Option<String>,
+        url: Option<String>,
+        homepage: Option<String>,
+        favicon: Option<String>,
+        countrycode: Option<String>,
+        state: Option<String>,
+        language: Option<String>,
+        languagecodes: Option<String>,
+        tags: Option<String>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+    ) -> Result<String, Box<dyn Error>> {
+        let station = check_new_station(name, url, homepage, favicon, countrycode, state, language, languagecodes, tags, geo_lat, geo_long)?;
+
+        let query = "INSERT INTO StationPending(StationUuid,Name,Url,Homepage,Favicon,Country,CountryCode,Subcountry,Language,LanguageCodes,Tags,GeoLat,GeoLong,Creation)
+                        VALUES(:stationuuid, :name, :url, :homepage, :favicon, :country, :countrycode, :state, :language, :languagecodes, :tags, :geo_lat, :geo_long, datetime('now'))";
+        let conn = self.connection()?;
+        conn.execute(
+            query,
+            named_params! {
+                ":stationuuid": station.stationuuid,
+                ":name": station.name,
+                ":url": station.url,
+                ":homepage": station.homepage,
+                ":favicon": station.favicon,
+                ":country": station.country,
+                ":countrycode": station.countrycode,
+                ":state": station.state,
+                ":language": station.language,
+                ":languagecodes": station.languagecodes,
+                ":tags": station.tags,
+                ":geo_lat": station.geo_lat,
+                ":geo_long": station.geo_long,
+            },
+        )?;
+
+        Ok(station.stationuuid)
+    }
+
+    fn get_pending_stations(&self, offset: u32, limit: u32) -> Result<Vec<StationAddItem>, Box<dyn Error>> {
+        let query = format!("SELECT {columns} FROM StationPending ORDER BY PendingID LIMIT :limit OFFSET :offset", columns = SqliteConnection::COLUMNS_PENDING);
+        self.get_list_from_query(&query, named_params! { ":offset": offset, ":limit": limit })
+    }
+
+    fn get_pending_stations_by_url(&self, urls: &[String]) -> Result<Vec<StationAddItem>, Box<dyn Error>> {
+        if urls.is_empty() {
+            return Ok(vec![]);
+        }
+        let items: Vec<&str> = urls.iter().map(|_| "?").collect();
+        let query = format!("SELECT {columns} FROM StationPending WHERE Url IN ({items})", columns = SqliteConnection::COLUMNS_PENDING, items = items.join(","));
+        self.get_list_from_query(&query, params_from_iter(urls.iter()))
+    }
+
+    fn approve_pending_station(&self, stationuuid: &str) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        let station: Option<StationAddItem> = {
+            let query = format!("SELECT {columns} FROM StationPending WHERE StationUuid=?", columns = SqliteConnection::COLUMNS_PENDING);
+            let mut stmt = transaction.prepare(&query)?;
+            let mut rows = stmt.query([stationuuid])?;
+            rows.next()?.map(|row| row.into())
+        };
+        let station = station.ok_or(DbError::ModerationError(String::from("station is not in the review queue")))?;
+        SqliteConnection::insert_new_station(&transaction, &station)?;
+        transaction.execute("DELETE FROM StationPending WHERE StationUuid=?", [stationuuid])?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn reject_pending_station(&self, stationuuid: &str) -> Result<(), Box<dyn Error>> {
+        let conn = self.connection()?;
+        let deleted = conn.execute("DELETE FROM StationPending WHERE StationUuid=?", [stationuuid])?;
+        if deleted == 0 {
+            return Err(Box::new(DbError::ModerationError(String::from("station is not in the review queue"))));
+        }
+        Ok(())
+    }
+
+    fn edit_station_opt(
+        &self,
+        stationuuid: &str,
+        name: Option<String>,
+        url: Option<String>,
+        homepage: Option<String>,
+        favicon: Option<String>,
+        countrycode: Option<String>,
+        state: Option<String>,
+        language: Option<String>,
+        languagecodes: Option<String>,
+        tags: Option<String>,
+        geo_lat: Option<f64>,
+        geo_long: Option<f64>,
+        source: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let mut station = self
+            .get_station_by_uuid(stationuuid)?
+            .pop()
+            .ok_or(DbError::EditStationError(String::from("station not found")))?;
+        edit_station_fields(&mut station, name, url, homepage, favicon, countrycode, state, language, languagecodes, tags, geo_lat, geo_long)?;
+
+        let query = r#"UPDATE Station SET
+                Name=:name,
+                Url=:url,
+                UrlCache=:url_resolved,
+                Homepage=:homepage,
+                Favicon=:favicon,
+                Country=:country,
+                CountryCode=:countrycode,
+                Subcountry=:state,
+                Language=:language,
+                LanguageCodes=:languagecodes,
+                Tags=:tags,
+                GeoLat=:geo_lat,
+                GeoLong=:geo_long,
+                Creation=datetime('now'),
+                ChangeUuid=:changeuuid
+            WHERE
+                StationUuid=:stationuuid"#;
+        let changeuuid = Uuid::new_v4().as_hyphenated().to_string();
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        transaction.execute(
+            query,
+            named_params! {
+                ":name": station.name,
+                ":url": station.url,
+                ":url_resolved": station.url_resolved,
+                ":homepage": station.homepage,
+                ":favicon": station.favicon,
+                ":country": station.country,
+                ":countrycode": station.countrycode,
+                ":state": station.state,
+                ":language": station.language,
+                ":languagecodes": station.languagecodes,
+                ":tags": station.tags,
+                ":geo_lat": station.geo_lat,
+                ":geo_long": station.geo_long,
+                ":changeuuid": changeuuid,
+                ":stationuuid": station.stationuuid,
+            },
+        )?;
+        SqliteConnection::backup_stations_by_uuid(
+            &transaction,
+            &(vec![station.stationuuid.clone()]),
+            source,
+        )?;
+        transaction.commit()?;
+
+        Ok(changeuuid)
+    }
+
+    fn get_pull_server_lastid(&self, server: &str) -> Result<Option<String>, Box<dyn Error>> {
+        get_pull_server_column(&*self.connection()?, "lastid", server)
+    }
+
+    fn set_pull_server_lastid(
+        &self,
+        server: &str,
+        lastid: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        set_pull_server_column(&*self.connection()?, "lastid", server, lastid)
+    }
+
+    fn get_pull_server_lastcheckid(&self, server: &str) -> Result<Option<String>, Box<dyn Error>> {
+        get_pull_server_column(&*self.connection()?, "lastcheckid", server)
+    }
+
+    fn set_pull_server_lastcheckid(
+        &self,
+        server: &str,
+        lastcheckid: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        set_pull_server_column(&*self.connection()?, "lastcheckid", server, lastcheckid)
+    }
+
+    fn get_pull_server_lastclickid(&self, server: &str) -> Result<Option<String>, Box<dyn Error>> {
+        get_pull_server_column(&*self.connection()?, "lastclickuuid", server)
+    }
+
+    fn set_pull_server_lastclickid(
+        &self,
+        server: &str,
+        lastclickuuid: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        set_pull_server_column(&*self.connection()?, "lastclickuuid", server, lastclickuuid)
+    }
+
+    fn insert_station_by_change(
+        &self,
+        list_station_changes: &[StationChangeItemNew],
+        source: &str,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+
+        let list_ids = SqliteConnection::insert_station_by_change_internal(
+            &transaction,
+            list_station_changes,
+        )?;
+        SqliteConnection::backup_stations_by_uuid(&transaction, &list_ids, source)?;
+
+        transaction.commit()?;
+        Ok(list_ids)
+    }
+
+    /// Inserts checks
+    /// Returns tupple
+    /// - existing_checks
+    /// - ignored_checks_no_station
+    /// - inserted
+    fn insert_checks(
+        &self,
+        list: Vec<StationCheckItemNew>,
+    ) -> Result<
+        (
+            Vec<StationCheckItemNew>,
+            Vec<StationCheckItemNew>,
+            Vec<StationCheckItemNew>,
+        ),
+        Box<dyn std::error::Error>,
+    > {
+        trace!("insert_checks()");
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+
+        // search for checkuuids in history table, if already added (maybe from other source)
+        let checkuuids: Vec<&String> = list.iter().filter_map(|item| item.checkuuid.as_ref()).collect();
+        let existing_checks_uuids: HashSet<String> = SqliteConnection::get_string_list(
+            &transaction,
+            &format!(
+                "SELECT CheckUuid FROM StationCheckHistory WHERE CheckUuid IN ({})",
+                placeholders(checkuuids.len())
+            ),
+            params_from_iter(checkuuids.iter()),
+        )?
+        .into_iter()
+        .collect();
+
+        trace!(
+            "Ignored checks(already existing) for insert: {}",
+            existing_checks_uuids.len()
+        );
+
+        // search for stations by stationuuid
+        let existing_stations: HashSet<String> = SqliteConnection::get_string_list(
+            &transaction,
+            &format!(
+                "SELECT StationUuid FROM Station WHERE StationUuid IN ({})",
+                placeholders(list.len())
+            ),
+            params_from_iter(list.iter().map(|item| &item.station_uuid)),
+        )?
+        .into_iter()
+        .collect();
+
+        trace!("Found stations {}", existing_stations.len());
+
+        let mut inserted: Vec<StationCheckItemNew> = vec![];
+        let mut ignored_checks_no_station: Vec<StationCheckItemNew> = vec![];
+        let mut existing_checks: Vec<StationCheckItemNew> = vec![];
+        {
+            let mut stmt = transaction.prepare("INSERT INTO StationCheckHistory(CheckUuid,CheckTime,StationUuid,Source,Codec,Bitrate,Hls,CheckOK,UrlCache,
+                MetainfoOverridesDatabase,Public,Name,Description,Tags,CountryCode,Homepage,Favicon,Loadbalancer,DoNotIndex,InsertTime,ServerSoftware,Sampling,LanguageCodes,TimingMs,CountrySubdivisionCode,GeoLat,GeoLong,AudioBitrate,AudioSampling,AudioLiveness)
+                VALUES(:checkuuid,IFNULL(datetime(:checktime),datetime('now')),:stationuuid,:source,:codec,:bitrate,:hls,:checkok,:urlcache,
+                :metainfo_overrides_database,:public,:name,:description,:tags,:countrycode,:homepage,:favicon,:loadbalancer,:do_not_index,datetime('now'),:server_software,:sampling,:languagecodes,:timing_ms,:countrysubdivisioncode,:geo_lat,:geo_long,:audio_bitrate,:audio_sampling,:audio_liveness)")?;
+            for item in list {
+                // ignore checks, where there is no station in the database
+                if !existing_stations.contains(&item.station_uuid) {
+                    ignored_checks_no_station.push(item);
+                    continue;
+                }
+                // ignore checks that are already in the database
+                if let Some(checkuuid) = &item.checkuuid {
+                    if existing_checks_uuids.contains(checkuuid) {
+                        existing_checks.push(item);
+                        continue;
+                    }
+                }
+                // reuse checkuuid or generate a new one
+                let checkuuid = item
+                    .checkuuid
+                    .clone()
+                    .unwrap_or_else(|| Uuid::new_v4().as_hyphenated().to_string());
+                stmt.execute(named_params! {
+                    ":checkuuid": checkuuid,
+                    ":checktime": item.timestamp,
+                    ":stationuuid": item.station_uuid,
+                    ":source": item.source,
+                    ":codec": item.codec,
+                    ":bitrate": item.bitrate,
+                    ":hls": item.hls,
+                    ":checkok": item.check_ok,
+                    ":urlcache": item.url,
+                    ":metainfo_overrides_database": item.metainfo_overrides_database,
+                    ":public": item.public,
+                    ":name": item.name,
+                    ":description": item.description,
+                    ":tags": item.tags,
+                    ":countrycode": item.countrycode,
+                    ":homepage": item.homepage,
+                    ":favicon": item.favicon,
+                    ":loadbalancer": item.loadbalancer,
+                    ":do_not_index": item.do_not_index,
+                    ":server_software": item.server_software,
+                    ":sampling": item.sampling,
+                    ":languagecodes": item.languagecodes,
+                    ":timing_ms": item.timing_ms as i64,
+                    ":countrysubdivisioncode": item.countrysubdivisioncode,
+                    ":geo_lat": item.geo_lat,
+                    ":geo_long": item.geo_long,
+                    ":audio_bitrate": item.audio_bitrate,
+                    ":audio_sampling": item.audio_sampling,
+                    ":audio_liveness": item.audio_liveness,
+                })?;
+                inserted.push(item);
+            }
+        }
+
+        trace!(
+            "Ignored checks(no stations) for insert: {}",
+            ignored_checks_no_station.len()
+        );
+
+        transaction.commit()?;
+
+        Ok((existing_checks, ignored_checks_no_station, inserted))
+    }
+
+    /// Select all checks that are currently in the database of a station with the given uuid
+    /// and calculate an overall status by majority vote. Ties are broken with the own vote
+    /// of the most current check
+    fn update_station_with_check_data(
+        &self,
+        list: &Vec<StationCheckItemNew>,
+        local: bool,
+        consensus: &CheckConsensus,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        trace!("update_station_with_check_data()");
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+
+        let mut majority_vote: HashMap<String, bool> = HashMap::new();
+        if !list.is_empty() {
+            // consensus over the current check of every source, most recent first
+            let mut stmt = transaction.prepare(&format!(
+                "SELECT StationUuid,CheckOK,CAST(strftime('%s','now') AS INTEGER)-CAST(strftime('%s',CheckTime) AS INTEGER) FROM StationCheck WHERE StationUuid IN ({uuids}) ORDER BY CheckID DESC",
+                uuids = placeholders(list.len())
+            ))?;
+            let mut rows = stmt.query(params_from_iter(list.iter().map(|item| &item.station_uuid)))?;
+            let mut results: HashMap<String, Vec<bool>> = HashMap::new();
+            while let Some(row) = rows.next()? {
+                let stationuuid: String = row.get(0)?;
+                let check_ok: bool = row.get(1)?;
+                let age: Option<i64> = row.get(2)?;
+                let results = results.entry(stationuuid).or_default();
+                if consensus.is_recent(results.len(), age.unwrap_or(i64::MAX)) {
+                    results.push(check_ok);
+                }
+            }
+            for (stationuuid, results) in results {
+                majority_vote.insert(stationuuid, consensus.is_working(&results));
+            }
+        }
+
+        let lastlocalchecktime = if local {
+            "LastLocalCheckTime=datetime('now'),"
+        } else {
+            ""
+        };
+        for item in list {
+            let vote = majority_vote.get(&item.station_uuid).unwrap_or(&true);
+
+            let do_not_index = item.metainfo_overrides_database && item.do_not_index.unwrap_or(false);
+            if do_not_index {
+                transaction.execute(
+                    "DELETE FROM Station WHERE StationUuid=?",
+                    [&item.station_uuid],
+                )?;
+            } else if item.check_ok {
+                let query_update_ok = format!("UPDATE Station SET ExtendedInfo=:extended_info,{lastlocalchecktime}LastCheckOkTime=datetime('now'),LastCheckTime=datetime('now'),Codec=:codec,Bitrate=:bitrate,Hls=:hls,UrlCache=:urlcache,LastCheckOk=:vote,SslError=:ssl_error WHERE StationUuid=:stationuuid",
+                    lastlocalchecktime = lastlocalchecktime,
+                );
+                transaction.execute(
+                    &query_update_ok,
+                    named_params! {
+                        ":extended_info": item.metainfo_overrides_database,
+                        ":codec": item.codec,
+                        ":bitrate": item.bitrate,
+                        ":hls": item.hls,
+                        ":urlcache": item.url,
+                        ":vote": vote,
+                        ":ssl_error": item.ssl_error,
+                        ":stationuuid": item.station_uuid,
+                    },
+                )?;
+            } else {
+                let query_update_check_ok = format!("UPDATE Station SET {lastlocalchecktime}LastCheckTime=datetime('now'),LastCheckOk=:vote WHERE StationUuid=:stationuuid",
+                    lastlocalchecktime = lastlocalchecktime,
+                );
+                transaction.execute(
+                    &query_update_check_ok,
+                    named_params! {
+                        ":vote": vote,
+                        ":stationuuid": item.station_uuid,
+                    },
+                )?;
+            }
+        }
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    fn insert_clicks(&self, list: &Vec<StationClickItemNew>) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+
+        let found_clickuuids = SqliteConnection::get_string_list(
+            &transaction,
+            &format!(
+                "SELECT ClickUuid FROM StationClick WHERE ClickUuid IN ({})",
+                placeholders(list.len())
+            ),
+            params_from_iter(list.iter().map(|item| &item.clickuuid)),
+        )?;
+
+        trace!(
+            "Ignored clicks(already existing) for insert: {}",
+            found_clickuuids.len()
+        );
+
+        let found_stationuuids = SqliteConnection::get_string_list(
+            &transaction,
+            &format!(
+                "SELECT StationUuid FROM Station WHERE StationUuid IN ({})",
+                placeholders(list.len())
+            ),
+            params_from_iter(list.iter().map(|item| &item.stationuuid)),
+        )?;
+
+        let mut ignored_clicks = 0;
+        {
+            let mut stmt = transaction.prepare("INSERT INTO StationClick(ClickUuid, StationUuid, ClickTimestamp, InsertTime) VALUES(?,?,datetime(?),datetime('now'))")?;
+            for item in list {
+                if !found_stationuuids.contains(&item.stationuuid) {
+                    ignored_clicks += 1;
+                    continue;
+                }
+                if !found_clickuuids.contains(&item.clickuuid) {
+                    stmt.execute([&item.clickuuid, &item.stationuuid, &item.clicktimestamp])?;
+                }
+            }
+        }
+
+        trace!("Ignored clicks(no stations) for insert: {}", ignored_clicks);
+
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    fn get_checks(
+        &self,
+        stationuuid: Option<String>,
+        checkuuid: Option<String>,
+        seconds: u32,
+        include_history: bool,
+        limit: u32,
+    ) -> Result<Vec<StationCheckItem>, Box<dyn Error>> {
+        let table_name = if include_history {
+            "StationCheckHistory"
+        } else {
+            "StationCheck"
+        };
+        let where_seconds = if seconds > 0 {
+            format!(
+                "AND CheckTime > datetime('now','-{seconds} seconds')",
+                seconds = seconds
+            )
+        } else {
+            String::from("")
+        };
+
+        let mut query_params: Vec<(&str, &dyn ToSql)> = vec![];
+        let where_checkuuid_str = if checkuuid.is_some() {
+            query_params.push((":checkuuid", &checkuuid));
+            " AND CheckID >= IFNULL((SELECT CheckID FROM StationCheckHistory WHERE CheckUuid=:checkuuid),0)
+              AND CheckID <= (SELECT MAX(CheckID) FROM StationCheckHistory WHERE InsertTime <= datetime('now','-60 seconds'))
+              AND CheckUuid<>:checkuuid"
+        } else {
+            ""
+        };
+        let where_stationuuid_str = if stationuuid.is_some() {
+            query_params.push((":stationuuid", &stationuuid));
+            " AND StationUuid=:stationuuid"
+        } else {
+            ""
+        };
+
+        let query = format!("SELECT {columns} FROM {table_name} WHERE 1=1 {where_stationuuid} {where_checkuuid} {where_seconds} ORDER BY CheckID LIMIT {limit}", columns = SqliteConnection::COLUMNS_CHECK, where_stationuuid = where_stationuuid_str, where_seconds = where_seconds, where_checkuuid = where_checkuuid_str, table_name = table_name, limit = limit);
+
+        trace!("get_checks() {}", query);
+        self.get_list_from_query(&query, query_params.as_slice())
+    }
+
+    fn get_check_history(&self, stationuuids: &[String], seconds: u32, limit: u32) -> Result<Vec<StationCheckItem>, Box<dyn Error>> {
+        if stationuuids.is_empty() {
+            return Ok(vec![]);
+        }
+        let query = format!("SELECT * FROM (SELECT {columns} FROM StationCheckHistory WHERE StationUuid IN ({uuids}) AND CheckTime > datetime('now','-{seconds} seconds') ORDER BY CheckID DESC LIMIT {limit}) ORDER BY CheckID", columns = SqliteConnection::COLUMNS_CHECK, uuids = placeholders(stationuuids.len()), seconds = seconds, limit = limit);
+        trace!("get_check_history() {}", query);
+        self.get_list_from_query(&query, params_from_iter(stationuuids.iter()))
+    }
+
+    fn get_clicks(
+        &self,
+        stationuuid: Option<String>,
+        clickuuid: Option<String>,
+        seconds: u32,
+        limit: u32,
+    ) -> Result<Vec<StationClickItem>, Box<dyn Error>> {
+        let where_seconds = if seconds > 0 {
+            format!(
+                "AND ClickTimestamp > datetime('now','-{seconds} seconds')",
+                seconds = seconds
+            )
+        } else {
+            String::from("")
+        };
+
+        let mut query_params: Vec<(&str, &dyn ToSql)> = vec![];
+        let where_clickuuid_str = if clickuuid.is_some() {
+            query_params.push((":clickuuid", &clickuuid));
+            " AND ClickID >= IFNULL((SELECT ClickID FROM StationClick WHERE ClickUuid=:clickuuid),0)
+              AND ClickID <= (SELECT MAX(ClickID) FROM StationClick WHERE InsertTime <= datetime('now','-60 seconds'))
+              AND ClickUuid<>:clickuuid"
+        } else {
+            ""
+        };
+        let where_stationuuid_str = if stationuuid.is_some() {
+            query_params.push((":stationuuid", &stationuuid));
+            " AND StationUuid=:stationuuid"
+        } else {
+            ""
+        };
+        let query = format!("SELECT {columns} FROM StationClick WHERE 1=1 {where_stationuuid} {where_clickuuid} {where_seconds} ORDER BY ClickID LIMIT {limit}", columns = SqliteConnection::COLUMNS_CLICK, where_stationuuid = where_stationuuid_str, where_seconds = where_seconds, where_clickuuid = where_clickuuid_str, limit = limit);
+
+        trace!("get_clicks() {}", query);
+        self.get_list_from_query(&query, query_params.as_slice())
+    }
+
+    fn get_last_changeuuid(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let list = SqliteConnection::get_string_list(&*self.connection()?, "SELECT ChangeUuid FROM StationHistory ORDER BY StationChangeID DESC LIMIT 1", [])?;
+        Ok(list.into_iter().next())
+    }
+
+    fn get_last_checkuuid(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let list = SqliteConnection::get_string_list(&*self.connection()?, "SELECT CheckUuid FROM StationCheckHistory ORDER BY CheckID DESC LIMIT 1", [])?;
+        Ok(list.into_iter().next())
+    }
+
+    fn get_last_clickuuid(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let list = SqliteConnection::get_string_list(&*self.connection()?, "SELECT ClickUuid FROM StationClick ORDER BY ClickID DESC LIMIT 1", [])?;
+        Ok(list.into_iter().next())
+    }
+
+    fn get_extra(
+        &self,
+        table_name: &str,
+        column_name: &str,
+        search: Option<String>,
+        order: String,
+        reverse: bool,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<ExtraInfo>, Box<dyn Error>> {
+        let order = filter_order_1_n(&order)?;
+        let mut params: Vec<Value> = Vec::with_capacity(1);
+        let reverse_string = if reverse { "DESC" } else { "ASC" };
+        let count_column = if hidebroken {
+            "StationCountWorking"
+        } else {
+            "StationCount"
+        };
+        let search_string = match search {
+            Some(c) => {
+                params.push((format!("%{}%", c)).into());
+                format!(" AND {} LIKE ?", column_name)
+            }
+            None => "".to_string(),
+        };
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(&format!("SELECT {column_name} AS name, {count_column} AS stationcount FROM {table_name} WHERE {column_name} <> '' {search} AND {count_column} > 0 ORDER BY {order} {reverse} LIMIT {offset},{limit}",
+            search = search_string, order = order,
+            reverse = reverse_string, count_column = count_column,
+            table_name = table_name, column_name = column_name,
+            offset = offset, limit = limit))?;
+        let items = stmt
+            .query_map(params_from_iter(params), |row| {
+                Ok(ExtraInfo::new(
+                    row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                    row.get::<_, Option<u32>>(1)?.unwrap_or(0),
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(items)
+    }
+
+    fn get_1_n(
+        &self,
+        column: &str,
+        search: Option<String>,
+        order: String,
+        reverse: bool,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<ExtraInfo>, Box<dyn Error>> {
+        let order = filter_order_1_n(&order)?;
+        let reverse_string = if reverse { "DESC" } else { "ASC" };
+        let hidebroken_string = if hidebroken {
+            " AND LastCheckOK=TRUE"
+        } else {
+            ""
+        };
+        let (search_string, params) = match search {
+            Some(value) => (format!(" AND UPPER({column}) LIKE UPPER('%' || ? || '%')", column = column), vec![value]),
+            None => (String::new(), vec![]),
+        };
+        let query = format!("SELECT {column} AS name,COUNT(*) AS stationcount FROM Station WHERE {column}<>'' {search} {hidebroken} GROUP BY {column} ORDER BY {order} {reverse} LIMIT {offset},{limit}",
+            column = column, search = search_string, order = order, reverse = reverse_string,
+            hidebroken = hidebroken_string,
+            offset = offset,
+            limit = limit,
+        );
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(&query)?;
+        let stations = stmt
+            .query_map(params_from_iter(params), |row| {
+                Ok(ExtraInfo::new(row.get(0)?, row.get(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(stations)
+    }
+
+    fn get_countries(
+        &self,
+        search: Option<String>,
+        order: String,
+        reverse: bool,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DBCountry>, Box<dyn Error>> {
+        let order = filter_order_1_n(&order)?;
+        let reverse_string = if reverse { "DESC" } else { "ASC" };
+        let hidebroken_string = if hidebroken {
+            " AND LastCheckOK=TRUE"
+        } else {
+            ""
+        };
+        let (search_string, params) = match search {
+            Some(value) => (" AND UPPER(CountryCode) LIKE UPPER('%' || ? || '%')", vec![value]),
+            None => ("", vec![]),
+        };
+        let query = format!("SELECT CountryCode AS name,COUNT(*) AS stationcount FROM Station WHERE CountryCode<>'' {search} {hidebroken} GROUP BY CountryCode ORDER BY {order} {reverse} LIMIT {offset},{limit}",
+            search = search_string, order = order, reverse = reverse_string,
+            hidebroken = hidebroken_string,
+            offset = offset,
+            limit = limit,
+        );
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(&query)?;
+        let countries = stmt
+            .query_map(params_from_iter(params), |row| {
+                Ok(DBCountry::new(row.get(0)?, row.get(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(countries)
+    }
+
+    fn get_states(
+        &self,
+        country: Option<String>,
+        search: Option<String>,
+        order: String,
+        reverse: bool,
+        hidebroken: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<State>, Box<dyn Error>> {
+        let order = filter_order_1_n(&order)?;
+        let mut params: Vec<Value> = Vec::with_capacity(1);
+        let reverse_string = if reverse { "DESC" } else { "ASC" };
+        let hidebroken_string = if hidebroken {
+            " AND LastCheckOK=TRUE"
+        } else {
+            ""
+        };
+        let country_string = match country {
+            Some(c) => {
+                params.push(c.into());
+                " AND Country=?"
+            }
+            None => "",
+        };
+        let search_string = match search {
+            Some(c) => {
+                params.push((format!("%{}%", c)).into());
+                " AND Subcountry LIKE ?"
+            }
+            None => "",
+        };
+
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(&format!(r"SELECT Subcountry AS name,Country,COUNT(*) AS stationcount FROM Station WHERE Subcountry <> '' {country} {search} {hidebroken} GROUP BY Subcountry, Country ORDER BY {order} {reverse} LIMIT {offset},{limit}",
+        hidebroken = hidebroken_string, order = order, country = country_string, reverse = reverse_string, search = search_string, limit = limit, offset = offset))?;
+        let states = stmt
+            .query_map(params_from_iter(params), |row| {
+                Ok(State::new(
+                    row.get(0)?,
+                    row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                    row.get(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(states)
+    }
+
+    /// Get items from a single column from Station table, add number of occurences
+    /// Supports columns with multiple values that are split by komma
+    fn get_stations_multi_items(
+        &self,
+        column_name: &str,
+    ) -> Result<HashMap<String, (u32, u32)>, Box<dyn Error>> {
+        let mut items = HashMap::new();
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {column_name}, LastCheckOK FROM Station",
+            column_name = column_name
+        ))?;
+        let mut rows = stmt.query([])?;
+
+        while let Some(row) = rows.next()? {
+            let tags_str: Option<String> = row.get(0)?;
+            let ok: bool = row.get(1)?;
+            let tags_str = tags_str.unwrap_or_default();
+            let tags_arr = tags_str.split(',');
+            for single_tag in tags_arr {
+                let single_tag_trimmed = single_tag.trim().to_lowercase();
+                if !single_tag_trimmed.is_empty() {
+                    let counter = items.entry(single_tag_trimmed).or_insert((0, 0));
+                    counter.0 += 1;
+                    if ok {
+                        counter.1 += 1;
+                    }
+                }
+            }
+        }
+        Ok(items)
+    }
+
+    /// Get currently cached items from table
+    fn get_cached_items(
+        &self,
+        table_name: &str,
+        column_name: &str,
+    ) -> Result<HashMap<String, (u32, u32)>, Box<dyn Error>> {
+        let mut items = HashMap::new();
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {column_name},StationCount, StationCountWorking FROM {table_name}",
+            table_name = table_name,
+            column_name = column_name
+        ))?;
+        let mut rows = stmt.query([])?;
+
+        while let Some(row) = rows.next()? {
+            let key: String = row.get(0)?;
+            let value: Option<u32> = row.get(1)?;
+            let value_working: Option<u32> = row.get(2)?;
+            items.insert(key.to_lowercase(), (value.unwrap_or(0), value_working.unwrap_or(0)));
+        }
+        Ok(items)
+    }
+
+    fn update_cache_item(
+        &self,
+        tag: &String,
+        count: u32,
+        count_working: u32,
+        table_name: &str,
+        column_name: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let query = format!(
+            r"UPDATE {table_name} SET StationCount=?, StationCountWorking=? WHERE {column_name}=?",
+            table_name = table_name,
+            column_name = column_name
+        );
+        self.connection()?
+            .execute(&query, params![count, count_working, tag])?;
+        Ok(())
+    }
+
+    fn insert_to_cache(
+        &self,
+        tags: HashMap<&String, (u32, u32)>,
+        table_name: &str,
+        column_name: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let query = format!(
+            r"INSERT INTO {table_name}({column_name},StationCount,StationCountWorking) VALUES(?,?,?)",
+            table_name = table_name,
+            column_name = column_name
+        );
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        {
+            let mut stmt = transaction.prepare(&query)?;
+            for (tag, (count, count_working)) in tags.iter() {
+                stmt.execute(params![tag, count, count_working])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn remove_from_cache(
+        &self,
+        tags: Vec<&String>,
+        table_name: &str,
+        column_name: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut query = format!(
+            "DELETE FROM {table_name} WHERE {column_name}=''",
+            table_name = table_name,
+            column_name = column_name
+        );
+        for _ in 0..tags.len() {
+            query.push_str(" OR ");
+            query.push_str(column_name);
+            query.push_str("=?");
+        }
+        self.connection()?.execute(&query, params_from_iter(tags))?;
+        Ok(())
+    }
+
+    fn vote_for_station(
+        &self,
+        ip: &str,
+        station: Option<DbStationItem>,
+    ) -> Result<String, Box<dyn Error>> {
+        match station {
+            Some(station) => {
+                let conn = self.connection()?;
+                // delete ipcheck entries after 1 day
+                conn.execute(
+                    "DELETE FROM IPVoteCheck WHERE VoteTimestamp < datetime('now','-1 day')",
+                    [],
+                )?;
+
+                // was there a vote from the ip in the last 1 day?
+                let votes = conn.query_row(
+                    "SELECT COUNT(*) FROM IPVoteCheck WHERE StationID=:id AND IP=:ip",
+                    named_params! {":id": station.id, ":ip": ip},
+                    |row| row.get::<_, u64>(0),
+                )?;
+                if votes > 0 {
+                    // do not allow vote
+                    return Err(Box::new(DbError::VoteError(
+                        "you are voting for the same station too often".to_string(),
+                    )));
+                }
+
+                // add vote entry
+                let result_3_insert_votecheck = conn.execute(
+                    "INSERT INTO IPVoteCheck(IP,StationID,VoteTimestamp) VALUES(:ip,:id,datetime('now'))",
+                    named_params! {":id": station.id, ":ip": ip},
+                )?;
+                if result_3_insert_votecheck == 0 {
+                    return Err(Box::new(DbError::VoteError(
+                        "could not insert vote check".to_string(),
+                    )));
+                }
+
+                // vote for station
+                let result_4_update_votes = conn.execute(
+                    "UPDATE Station SET Votes=Votes+1 WHERE StationID=:id",
+                    named_params! {":id": station.id},
+                )?;
+                if result_4_update_votes == 1 {
+                    Ok("voted for station successfully".to_string())
+                } else {
+                    Err(Box::new(DbError::VoteError(
+                        "could not find station with matching id".to_string(),
+                    )))
+                }
+            }
+            _ => Err(Box::new(DbError::VoteError(
+                "could not find station with matching id".to_string(),
+            ))),
+        }
+    }
+
+    fn increase_clicks(
+        &self,
+        ip: &str,
+        station: &DbStationItem,
+        seconds: u64,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let conn = self.connection()?;
+        let clicks = conn.query_row(
+            "SELECT COUNT(*) FROM StationClick WHERE StationUuid=:stationuuid AND IP=:ip AND ClickTimestamp > datetime('now', :seconds)",
+            named_params! {":stationuuid": station.stationuuid, ":ip": ip, ":seconds": seconds_ago(seconds)},
+            |row| row.get::<_, u64>(0),
+        )?;
+        if clicks > 0 {
+            return Ok(false);
+        }
+
+        let result2 = conn.execute(
+            "INSERT INTO StationClick(IP,StationUuid,ClickUuid,ClickTimestamp,InsertTime) VALUES(:ip,:stationuuid,:clickuuid,datetime('now'),datetime('now'))",
+            named_params! {
+                ":stationuuid": station.stationuuid,
+                ":ip": ip,
+                ":clickuuid": Uuid::new_v4().as_hyphenated().to_string(),
+            },
+        )?;
+
+        let result3 = conn.execute(
+            "UPDATE Station SET ClickTimestamp=datetime('now') WHERE StationUuid=:stationuuid",
+            named_params! {":stationuuid": station.stationuuid},
+        )?;
+
+        Ok(result2 == 1 && result3 == 1)
+    }
+
+    fn sync_votes(&self, list: Vec<Station>) -> Result<(), Box<dyn Error>> {
+        trace!("sync_votes() 1");
+        if list.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        // get current votes of the stations in the list
+        let mut stations_current: HashMap<String, i32> = HashMap::new();
+        {
+            let items: Vec<&str> = list.iter().map(|_| "?").collect();
+            let query = format!("SELECT StationUuid,Votes FROM Station WHERE StationUuid IN ({items})", items = items.join(","));
+            let mut stmt = transaction.prepare(&query)?;
+            let mut rows = stmt.query(params_from_iter(list.iter().map(|station| &station.stationuuid)))?;
+            while let Some(row) = rows.next()? {
+                let stationuuid: String = row.get(0)?;
+                let votes: Option<i32> = row.get(1)?;
+                stations_current.insert(stationuuid, votes.unwrap_or(0));
+            }
+        }
+        trace!("sync_votes() 2");
+        // compare and search for changes
+        let mut rows_to_update: Vec<(String, i32)> = vec![];
+        for station in list {
+            let entry = stations_current.remove_entry(&station.stationuuid);
+            if let Some(entry) = entry {
+                let (stationuuid, votes) = entry;
+                if votes != station.votes {
+                    rows_to_update.push((stationuuid, station.votes));
+                }
+            }
+        }
+        trace!("sync_votes() 3");
+        // update changed votes
+        {
+            let mut stmt = transaction.prepare(
+                "UPDATE Station SET Votes=MAX(IFNULL(Votes,0),:votes) WHERE StationUuid=:stationuuid;",
+            )?;
+            for (stationuuid, votes) in rows_to_update.iter() {
+                stmt.execute(named_params! {":votes": votes, ":stationuuid": stationuuid})?;
+            }
+        }
+        trace!("sync_votes() 4");
+        transaction.commit()?;
+        trace!("sync_votes() 5");
+        Ok(())
+    }
+
+    fn insert_station_check_steps(
+        &mut self,
+        station_check_steps: &[StationCheckStepItemNew],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        {
+            let mut stmt = transaction.prepare(
+                r"INSERT INTO StationCheckStep (StationUuid,CheckUuid,Url,UrlType,Error,StepUuid,ParentStepUuid,InsertTime)
+                  VALUES (:stationuuid, :checkuuid, :url, :urltype, :error, :stepuuid, :parentstepuuid, datetime('now'))",
+            )?;
+            for p in station_check_steps {
+                stmt.execute(named_params! {
+                    ":stationuuid": p.stationuuid,
+                    ":checkuuid": p.checkuuid,
+                    ":stepuuid": p.stepuuid,
+                    ":parentstepuuid": p.parent_stepuuid,
+                    ":url": p.url,
+                    ":urltype": p.urltype,
+                    ":error": p.error,
+                })?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn select_station_check_steps(
+        &self,
+    ) -> Result<Vec<StationCheckStepItem>, Box<dyn std::error::Error>> {
+        self.get_station_check_steps_query("SELECT Id,StationUuid,CheckUuid,Url,UrlType,Error,StepUuid,ParentStepUuid,InsertTime FROM StationCheckStep", [])
+    }
+
+    fn select_station_check_steps_by_stations(
+        &self,
+        stationuuids: &[String],
+        limit: u32,
+    ) -> Result<Vec<StationCheckStepItem>, Box<dyn std::error::Error>> {
+        if !stationuuids.is_empty() {
+            let query = format!("SELECT Id,StationUuid,CheckUuid,Url,UrlType,Error,StepUuid,ParentStepUuid,InsertTime FROM StationCheckStep WHERE StationUuid IN ({}) ORDER BY Id LIMIT {}", placeholders(stationuuids.len()), limit);
+            self.get_station_check_steps_query(&query, params_from_iter(stationuuids.iter()))
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    fn delete_old_station_check_steps(
+        &mut self,
+        seconds: u32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let delete_old_steps_query = "DELETE FROM StationCheckStep WHERE InsertTime < datetime('now', ?)";
+        let conn = self.connection()?;
+        conn.execute(delete_old_steps_query, [seconds_ago(seconds.into())])?;
+        Ok(())
+    }
+
+    fn add_webhook(&self, webhook: &WebhookItem) -> Result<(), Box<dyn Error>> {
+        let conn = self.connection()?;
+        conn.execute(
+            "INSERT INTO Webhook(WebhookUuid,Owner,Url,Secret,Events,StationUuid,Tag,CountryCode,Creation)
+              VALUES(:webhookuuid,:owner,:url,:secret,:events,:stationuuid,:tag,:countrycode,datetime('now'))",
+            named_params! {
+                ":webhookuuid": webhook.webhookuuid,
+                ":owner": webhook.owner,
+                ":url": webhook.url,
+                ":secret": webhook.secret,
+                ":events": webhook.events,
+                ":stationuuid": webhook.stationuuid,
+                ":tag": webhook.tag,
+                ":countrycode": webhook.countrycode,
+            },
+        )?;
+        Ok(())
+    }
+
+    fn get_webhooks(&self, owner: Option<&str>) -> Result<Vec<WebhookItem>, Box<dyn Error>> {
+        let query = "SELECT WebhookUuid,Owner,Url,Secret,Events,StationUuid,Tag,CountryCode,Creation FROM Webhook WHERE (:owner IS NULL OR Owner=:owner) ORDER BY WebhookID";
+        self.get_list_from_query(query, named_params! {":owner": owner})
+    }
+
+    fn delete_webhook(&self, webhookuuid: &str, owner: Option<&str>) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        let deleted = transaction.execute(
+            "DELETE FROM Webhook WHERE WebhookUuid=:webhookuuid AND (:owner IS NULL OR Owner=:owner)",
+            named_params! {":webhookuuid": webhookuuid, ":owner": owner},
+        )?;
+        if deleted == 0 {
+            return Err(Box::new(DbError::WebhookError(String::from("webhook not found"))));
+        }
+        transaction.execute("DELETE FROM WebhookDelivery WHERE WebhookUuid=?", [webhookuuid])?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn insert_webhook_delivery(&self, webhookuuid: &str, event: &str, payload: &str) -> Result<(), Box<dyn Error>> {
+        let conn = self.connection()?;
+        conn.execute(
+            "INSERT INTO WebhookDelivery(DeliveryUuid,WebhookUuid,Event,Payload,NextAttempt,Creation) VALUES(?,?,?,?,datetime('now'),datetime('now'))",
+            params![Uuid::new_v4().as_hyphenated().to_string(), webhookuuid, event, payload],
+        )?;
+        Ok(())
+    }
+
+    fn get_due_webhook_deliveries(&self, limit: u32) -> Result<Vec<WebhookDeliveryItem>, Box<dyn Error>> {
+        let query = "SELECT d.DeliveryUuid,d.WebhookUuid,w.Url,w.Secret,d.Event,d.Payload,d.Attempts FROM WebhookDelivery d
+              JOIN Webhook w ON w.WebhookUuid=d.WebhookUuid
+              WHERE d.NextAttempt <= datetime('now') ORDER BY d.DeliveryID LIMIT ?";
+        self.get_list_from_query(query, [limit])
+    }
+
+    fn delete_webhook_delivery(&self, deliveryuuid: &str) -> Result<(), Box<dyn Error>> {
+        let conn = self.connection()?;
+        conn.execute("DELETE FROM WebhookDelivery WHERE DeliveryUuid=?", [deliveryuuid])?;
+        Ok(())
+    }
+
+    fn retry_webhook_delivery(&self, deliveryuuid: &str, seconds: u64, error: &str) -> Result<(), Box<dyn Error>> {
+        let conn = self.connection()?;
+        conn.execute(
+            "UPDATE WebhookDelivery SET Attempts=Attempts+1,NextAttempt=datetime('now', ?),LastError=? WHERE DeliveryUuid=?",
+            params![format!("+{} seconds", seconds), error, deliveryuuid],
+        )?;
+        Ok(())
+    }
+
+    fn set_now_playing(&self, titles: &[(String, String)]) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.connection()?;
+        let transaction = conn.transaction()?;
+        {
+            let mut stmt = transaction.prepare("INSERT OR REPLACE INTO NowPlaying(StationUuid,Title,UpdateTime) VALUES(?,?,datetime('now'))")?;
+            for (stationuuid, title) in titles {
+                stmt.execute([stationuuid, title])?;
+            }
+        }
+        transaction.execute("DELETE FROM NowPlaying WHERE UpdateTime <= datetime('now', ?)", [format!("-{} seconds", NOW_PLAYING_MAX_AGE.as_secs())])?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn get_now_playing(&self, stationuuid: &str) -> Result<Option<NowPlayingItem>, Box<dyn Error>> {
+        let query = format!("SELECT StationUuid,Title,UpdateTime FROM NowPlaying WHERE StationUuid=? AND UpdateTime > datetime('now','-{seconds} seconds')", seconds = NOW_PLAYING_MAX_AGE.as_secs());
+        let list: Vec<NowPlayingItem> = self.get_list_from_query(&query, [stationuuid])?;
+        Ok(list.into_iter().next())
+    }
+}
+
+fn get_pull_server_column(
+    conn: &Connection,
+    column: &str,
+    server: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let query = format!("SELECT {column} FROM PullServers WHERE name=? AND {column} IS NOT NULL", column = column);
+    let list = SqliteConnection::get_string_list(conn, &query, [server])?;
+    Ok(list.into_iter().next())
+}
+
+fn set_pull_server_column(
+    conn: &Connection,
+    column: &str,
+    server: &str,
+    value: &str,
+) -> Result<(), Box<dyn Error>> {
+    let query_update = format!("UPDATE PullServers SET {column}=:value WHERE name=:name", column = column);
+    let results_update = conn.execute(&query_update, named_params! {":name": server, ":value": value})?;
+    if results_update == 0 {
+        let query_insert = format!("INSERT INTO PullServers(name, {column}) VALUES(:name,:value)", column = column);
+        conn.execute(&query_insert, named_params! {":name": server, ":value": value})?;
+    }
+    Ok(())
+}
+
+/// Comma separated list of n sql placeholders
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(",")
+}
+
+/// Modifier for sqlite datetime() to go back the given amount of seconds
+fn seconds_ago(seconds: u64) -> String {
+    format!("-{} seconds", seconds)
+}
+
+/// Time a station is due for its next check, NULL if it was never checked locally
+fn check_due(hours: u32) -> String {
+    format!("COALESCE(NextCheckTime, datetime(LastLocalCheckTime, '+{} hours'))", hours)
+}
+
+/// WHERE condition of get_stations_advanced() and count_stations_advanced() with its named parameters,
+/// so the list and its count always match the same stations
+fn advanced_condition(
+    name: Option<String>,
+    name_exact: bool,
+    country: Option<String>,
+    country_exact: bool,
+    countrycode: Option<String>,
+    state: Option<String>,
+    state_exact: bool,
+    language: Option<String>,
+    language_exact: bool,
+    tag: Option<String>,
+    tag_exact: bool,
+    tag_list: Vec<String>,
+    codec: Option<String>,
+    bitrate_min: u32,
+    bitrate_max: u32,
+    has_geo_info: Option<bool>,
+    has_extended_info: Option<bool>,
+    is_https: Option<bool>,
+    geo_lat: Option<f64>,
+    geo_long: Option<f64>,
+    geo_distance: Option<f64>,
+    geo_bbox: Option<GeoBoundingBox>,
+    hidebroken: bool,
+) -> (String, Vec<(String, Value)>) {
+    let mut query = String::new();
+    // sqlite refuses named parameters that do not appear in the query,
+    // so they are only added together with their condition
+    let mut params: Vec<(String, Value)> = vec![
+        (String::from(":bitrate_min"), Value::from(bitrate_min)),
+        (String::from(":bitrate_max"), Value::from(bitrate_max)),
+    ];
+    query.push_str(" Bitrate >= :bitrate_min AND Bitrate <= :bitrate_max");
+    if let Some(has_geo_info) = has_geo_info {
+        if has_geo_info {
+            query.push_str(" AND GeoLat IS NOT NULL AND GeoLong IS NOT NULL");
+        } else {
+            query.push_str(" AND (GeoLat IS NULL OR GeoLong IS NULL)");
+        }
+    }
+    if let Some(has_extended_info) = has_extended_info {
+        if has_extended_info {
+            query.push_str(" AND ExtendedInfo=1");
+        } else {
+            query.push_str(" AND ExtendedInfo=0");
+        }
+    }
+    if let Some(is_https) = is_https {
+        if is_https {
+            query.push_str(" AND UrlCache LIKE 'https://%'");
+        } else {
+            query.push_str(" AND UrlCache LIKE 'http://%'");
+        }
+    }
+    if let Some(name) = name {
+        if name_exact {
+            query.push_str(" AND Name=:name");
+        } else {
+            query.push_str(" AND Name LIKE '%' || :name || '%'");
+        }
+        params.push((String::from(":name"), Value::from(name)));
+    }
+    if let Some(country) = country {
+        if country_exact {
+            query.push_str(" AND Country=:country");
+        } else {
+            query.push_str(" AND Country LIKE '%' || :country || '%'");
+        }
+        params.push((String::from(":country"), Value::from(country)));
+    }
+    if let Some(countrycode) = countrycode {
+        query.push_str(" AND UPPER(CountryCode)=UPPER(:countrycode)");
+        params.push((String::from(":countrycode"), Value::from(countrycode)));
+    }
+    if let Some(state) = state {
+        if state_exact {
+            query.push_str(" AND Subcountry=:state");
+        } else {
+            query.push_str(" AND Subcountry LIKE '%' || :state || '%'");
+        }
+        params.push((String::from(":state"), Value::from(state)));
+    }
+    if let Some(language) = language {
+        if language_exact {
+            query.push_str(" AND ( Language=:language OR Language LIKE '%,' || :language || ',%' OR Language LIKE '%,' || :language OR Language LIKE :language || ',%')");
+        } else {
+            query.push_str(" AND Language LIKE '%' || :language || '%'");
+        }
+        params.push((String::from(":language"), Value::from(language)));
+    }
+    if let Some(tag) = tag {
+        if tag_exact {
+            query.push_str(" AND ( Tags=:tag OR Tags LIKE '%,' || :tag || ',%' OR Tags LIKE '%,' || :tag OR Tags LIKE :tag || ',%')");
+        } else {
+            query.push_str(" AND Tags LIKE '%' || :tag || '%'");
+        }
+        params.push((String::from(":tag"), Value::from(tag)));
+    }
+    if let Some(codec) = codec {
+        query.push_str(" AND LOWER(Codec)=LOWER(:codec)");
+        params.push((String::from(":codec"), Value::from(codec)));
+    }
+    for (i, tag) in tag_list.into_iter().enumerate() {
+        if tag_exact {
+            query.push_str(&format!(" AND ( Tags=:tag{i} OR Tags LIKE '%,' || :tag{i} || ',%' OR Tags LIKE '%,' || :tag{i} OR Tags LIKE :tag{i} || ',%')", i = i));
+        } else {
+            query.push_str(&format!(" AND Tags LIKE '%' || :tag{i} || '%'", i = i));
+        }
+        params.push((format!(":tag{i}", i = i), Value::from(tag)));
+    }
+    for (i, geo_box) in geo_boxes(geo_lat, geo_long, geo_distance, geo_bbox).iter().enumerate() {
+        let prefix = format!("geo{i}", i = i);
+        query.push_str(&geo_box.sql_condition(&prefix));
+        for (key, value) in geo_box.sql_params(&prefix) {
+            params.push((format!(":{}", key), Value::from(value)));
+        }
+    }
+    if hidebroken {
+        query.push_str(" AND LastCheckOK=TRUE");
+    }
+    (query, params)
+}
+
+/// Stations joined with the full-text index for get_stations_search() and count_stations_search(),
+/// every word of the query has to be found in name, tags, state or language.
+/// The relevance uses the same weights for the columns as the search of the memory backend.
+fn search_source(query: &str) -> (String, Vec<(String, Value)>) {
+    // the index removes accents itself, but keeps letters like "ß"
+    let (prefixes, _) = fulltext_prefixes(query, strip_accents, 1);
+    if prefixes.is_empty() {
+        // nothing to search for
+        return (String::from("(SELECT *, 0 AS Relevance FROM Station WHERE FALSE)"), vec![]);
+    }
+    let words: Vec<String> = prefixes.iter().map(|prefix| format!("\"{}\"*", prefix)).collect();
+    (
+        String::from(
+            "Station JOIN (SELECT rowid AS SearchID, -bm25(StationSearch, 1.0, 0.7, 0.5, 0.5) AS Relevance FROM StationSearch WHERE StationSearch MATCH :search) ON SearchID=StationID",
+        ),
+        vec![(String::from(":search"), Value::from(words.join(" ")))],
+    )
+}
+
+/// ORDER BY of get_stations_search(), the best score comes first
+fn search_order(order: &str, reverse: bool) -> String {
+    if order == "relevance" {
+        sql_order("Score", !reverse)
+    } else {
+        sql_order(filter_order(order), reverse)
+    }
+}
+
+/// WHERE condition of get_stations_by_column() and count_stations_by_column(), the search is the only parameter
+fn column_condition(column_name: &str, exact: bool, hidebroken: bool) -> String {
+    let hidebroken_string = if hidebroken {
+        " AND LastCheckOK=TRUE"
+    } else {
+        ""
+    };
+    if exact {
+        format!("LOWER({column_name})=?{hidebroken}", column_name = column_name, hidebroken = hidebroken_string)
+    } else {
+        format!("LOWER({column_name}) LIKE '%' || ? || '%'{hidebroken}", column_name = column_name, hidebroken = hidebroken_string)
+    }
+}
+
+/// WHERE condition of get_stations_by_column_multiple() and count_stations_by_column_multiple() with the parameter ":search"
+fn column_multiple_condition(column_name: &str, exact: bool, hidebroken: bool) -> String {
+    let hidebroken_string = if hidebroken {
+        " AND LastCheckOK=TRUE"
+    } else {
+        ""
+    };
+    if exact {
+        format!(
+            r"({column_name}=:search
+             OR {column_name} LIKE '%,' || :search || ',%'
+             OR {column_name} LIKE :search || ',%'
+             OR {column_name} LIKE '%,' || :search){hidebroken}",
+            column_name = column_name,
+            hidebroken = hidebroken_string
+        )
+    } else {
+        format!("{column_name} LIKE '%' || :search || '%'{hidebroken}", column_name = column_name, hidebroken = hidebroken_string)
+    }
+}
+
+fn cursor_condition(cursor: Option<&StationCursor>, order: &str, named: bool) -> String {
+    match cursor {
+        Some(cursor) => cursor.sql_condition(filter_order(order), named),
+        None => String::new(),
+    }
+}
+
+fn cursor_value(param: CursorParam) -> Value {
+    match param {
+        CursorParam::Text(text) => Value::from(text),
+        CursorParam::Int(number) => Value::from(number),
+    }
+}
+
+fn cursor_named_params(cursor: Option<&StationCursor>) -> Vec<(String, Value)> {
+    match cursor {
+        Some(cursor) => cursor
+            .named_params()
+            .into_iter()
+            .map(|(key, value)| (format!(":{}", key), cursor_value(value)))
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Joins the current title of the NowPlaying table to station queries, as column NowPlaying
+fn now_playing_join() -> String {
+    format!(
+        " LEFT JOIN (SELECT StationUuid AS NowPlayingUuid,Title AS NowPlaying FROM NowPlaying WHERE UpdateTime > datetime('now','-{seconds} seconds')) AS CurrentTitle ON NowPlayingUuid=StationUuid",
+        seconds = NOW_PLAYING_MAX_AGE.as_secs()
+    )
+}
+
+/// SELECT list of station queries, reduced to the columns of the requested fields if there are any
+fn station_columns(fields: Option<&StationFields>, order: &str) -> Cow<'static, str> {
+    match fields {
+        Some(fields) => Cow::Owned(fields.select(filter_order(order), |column| format!("strftime('%Y-%m-%d %H:%M:%S',{c}) AS {c}Formated", c = column))),
+        None => Cow::Borrowed(SqliteConnection::COLUMNS),
+    }
+}
+
+fn filter_order(order: &str) -> &str {
+    match order {
+        "name" => "Name",
+        "url" => "Url",
+        "homepage" => "Homepage",
+        "favicon" => "Favicon",
+        "tags" => "Tags",
+        "country" => "Country",
+        "state" => "Subcountry",
+        "language" => "Language",
+        "votes" => "Votes",
+        "codec" => "Codec",
+        "bitrate" => "Bitrate",
+        "lastcheckok" => "LastCheckOK",
+        "lastchecktime" => "LastCheckTime",
+        "clicktimestamp" => "ClickTimestamp",
+        "clickcount" => "clickcount",
+        "clicktrend" => "ClickTrend",
+        "changetimestamp" => "Creation",
+        "random" => "RANDOM()",
+        _ => "Name",
+    }
+}
+
+fn filter_order_streaming_server(order: &str) -> &str {
+    match order {
+        "url" => "Url",
+        "error" => "Error",
+        "createdat" => "CreatedAt",
+        "changedat" => "UpdatedAt",
+        "random" => "RANDOM()",
+        _ => "Id",
+    }
+}
@@ -0,0 +1,153 @@
+use rusqlite::named_params;
+use rusqlite::Connection;
+
+pub struct Migration {
+    name: String,
+    up: String,
+    down: String,
+}
+
+pub struct Migrations<'a> {
+    conn: &'a Connection,
+    migrations_wanted: Vec<Migration>,
+}
+
+impl<'a> Migrations<'a> {
+    pub fn new(conn: &Connection) -> Migrations<'_> {
+        Migrations {
+            conn,
+            migrations_wanted: vec![],
+        }
+    }
+
+    fn ensure_tables(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS __migrations(id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL, up TEXT NOT NULL, down TEXT NOT NULL);")?;
+        Ok(())
+    }
+
+    fn get_applied_migrations(&self) -> Result<Vec<Migration>, Box<dyn std::error::Error>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name,up,down FROM __migrations ORDER BY name;")?;
+        let list = stmt
+            .query_map([], |row| {
+                Ok(Migration {
+                    name: row.get(0)?,
+                    up: row.get(1)?,
+                    down: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(list)
+    }
+
+    pub fn add_migration(&mut self, name: &str, up: &str, down: &str) {
+        let m = Migration {
+            name: name.to_string(),
+            up: up.to_string(),
+            down: down.to_string(),
+        };
+        self.migrations_wanted.push(m);
+        self.migrations_wanted
+            .sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    fn apply_migration(
+        &self,
+        migration: &Migration,
+        ignore_errors: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        info!("APPLY UP '{}'", migration.name);
+        let transaction = self.conn.unchecked_transaction()?;
+        if let Err(err) = transaction.execute_batch(&migration.up) {
+            if !ignore_errors {
+                panic!("{}", err.to_string());
+            }
+        }
+        transaction.execute(
+            "INSERT INTO __migrations(name, up , down) VALUES (:name,:up,:down);",
+            named_params! {
+                ":name": migration.name,
+                ":up": migration.up,
+                ":down": migration.down,
+            },
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn unapply_migration(
+        &self,
+        migration: &Migration,
+        ignore_errors: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        info!("APPLY DOWN '{}'", migration.name);
+        let transaction = self.conn.unchecked_transaction()?;
+        if let Err(err) = transaction.execute_batch(&migration.down) {
+            if !ignore_errors {
+                panic!("{}", err.to_string());
+            }
+        }
+        transaction.execute(
+            "DELETE FROM __migrations WHERE name=:name;",
+            named_params! {
+                ":name": migration.name,
+            },
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn migrations_needed(
+        &self,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        self.ensure_tables()?;
+
+        let migrations_applied = self.get_applied_migrations()?;
+        // apply all migrations, that are not applied
+        for wanted in self.migrations_wanted.iter() {
+            if !migrations_applied.iter().any(|applied| applied.name == wanted.name) {
+                return Ok(true);
+            }
+        }
+
+        // unapply all migrations, that are not in wanted
+        for applied in migrations_applied.iter().rev() {
+            if !self.migrations_wanted.iter().any(|wanted| wanted.name == applied.name) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    pub fn do_migrations(
+        &self,
+        ignore_errors: bool,
+        allow_database_downgrade: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.ensure_tables()?;
+
+        let migrations_applied = self.get_applied_migrations()?;
+        // apply all migrations, that are not applied
+        for wanted in self.migrations_wanted.iter() {
+            if !migrations_applied.iter().any(|applied| applied.name == wanted.name) {
+                self.apply_migration(wanted, ignore_errors)?;
+            }
+        }
+
+        // unapply all migrations, that are not in wanted
+        for applied in migrations_applied.iter().rev() {
+            if !self.migrations_wanted.iter().any(|wanted| wanted.name == applied.name) {
+                if allow_database_downgrade {
+                    self.unapply_migration(applied, ignore_errors)?;
+                } else {
+                    panic!("Database downgrade would be neccessary! Please confirm if you really want to do that.")
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use crate::db::db_error::DbError;
+use crate::db::models::DbStationItem;
+use crate::db::models::StationAddItem;
+use celes::Country;
+use std::error::Error;
+use url::Url;
+use uuid::Uuid;
+
+pub fn fix_multi_field(value: &str) -> String {
+    let values: Vec<String> = value
+        .split(",")
+        .map(|v| v.trim().to_lowercase().to_string())
+        .collect();
+    values.join(",")
+}
+
+pub fn filter_order_1_n(order: &str) -> Result<&str, Box<dyn Error>> {
+    match order {
+        "name" => Ok("name"),
+        "stationcount" => Ok("stationcount"),
+        _ => Err(Box::new(DbError::IllegalOrderError(String::from(order)))),
+    }
+}
+
+pub fn fix_url(u: &str, allow_empty: bool) -> Result<String, Box<dyn std::error::Error>> {
+    let url_str = u.trim();
+    if url_str.is_empty() {
+        if allow_empty {
+            return Ok(url_str.to_string());
+        } else {
+            return Err(Box::new(DbError::AddStationError(String::from(
+                "empty url not allowed",
+            ))));
+        }
+    }
+    let url = Url::parse(url_str)?;
+    let scheme = url.scheme().to_lowercase();
+    if !scheme.eq("http") && !scheme.eq("https") {
+        return Err(Box::new(DbError::AddStationError(String::from(
+            "unknown url scheme",
+        ))));
+    }
+    let url = url.to_string();
+    Ok(url)
+}
+
+/// Checks the fields of a new station and gives it a new stationuuid
+pub fn check_new_station(
+    name: Option<String>,
+    url: Option<String>,
+    homepage: Option<String>,
+    favicon: Option<String>,
+    countrycode: Option<String>,
+    state: Option<String>,
+    language: Option<String>,
+    languagecodes: Option<String>,
+    tags: Option<String>,
+    geo_lat: Option<f64>,
+    geo_long: Option<f64>,
+) -> Result<StationAddItem, Box<dyn Error>> {
+    let countrycode: String = countrycode.unwrap_or_default().to_uppercase();
+    let country: String = String::from(
+        Country::from_alpha2(&countrycode)
+            .map(|c| c.long_name)
+            .unwrap_or(""),
+    );
+
+    let name = name.ok_or(DbError::AddStationError(String::from("name is empty")))?;
+    let url = url.map(|x| fix_url(&x, false)).transpose()?;
+    let homepage = homepage.map(|x| fix_url(&x, true)).transpose()?;
+
+    if countrycode.len() != 2 {
+        return Err(Box::new(DbError::AddStationError(String::from(
+            "countrycode does not have exactly 2 chars",
+        ))));
+    }
+
+    if name.len() > 400 {
+        return Err(Box::new(DbError::AddStationError(String::from(
+            "name is longer than 400 chars",
+        ))));
+    }
+
+    Ok(StationAddItem {
+        stationuuid: Uuid::new_v4().as_hyphenated().to_string(),
+        name,
+        url: url.unwrap_or_default(),
+        homepage: homepage.unwrap_or_default(),
+        favicon: favicon.unwrap_or_default(),
+        country,
+        countrycode,
+        state: state.unwrap_or_default(),
+        language: fix_multi_field(&language.unwrap_or_default()),
+        languagecodes: fix_multi_field(&languagecodes.unwrap_or_default()),
+        tags: fix_multi_field(&tags.unwrap_or_default()),
+        geo_lat,
+        geo_long,
+        creation_iso8601: None,
+    })
+}
+
+/// Changes the given fields of a station with the same rules as for adding a station,
+/// fields that are None stay as they are
+pub fn edit_station_fields(
+    station: &mut DbStationItem,
+    name: Option<String>,
+    url: Option<String>,
+    homepage: Option<String>,
+    favicon: Option<String>,
+    countrycode: Option<String>,
+    state: Option<String>,
+    language: Option<String>,
+    languagecodes: Option<String>,
+    tags: Option<String>,
+    geo_lat: Option<f64>,
+    geo_long: Option<f64>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(name) = name {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Box::new(DbError::EditStationError(String::from("name is empty"))));
+        }
+        if name.len() > 400 {
+            return Err(Box::new(DbError::EditStationError(String::from(
+                "name is longer than 400 chars",
+            ))));
+        }
+        station.set_name(name);
+    }
+    if let Some(url) = url {
+        let url = fix_url(&url, false)?;
+        if url != station.url {
+            // the old resolved url is useless until the next check
+            station.url_resolved = url.clone();
+            station.set_url(url);
+        }
+    }
+    if let Some(homepage) = homepage {
+        station.set_homepage(fix_url(&homepage, true)?);
+    }
+    if let Some(favicon) = favicon {
+        station.set_favicon(favicon.trim());
+    }
+    if let Some(countrycode) = countrycode {
+        let countrycode = countrycode.trim().to_uppercase();
+        if countrycode.len() != 2 {
+            return Err(Box::new(DbError::EditStationError(String::from(
+                "countrycode does not have exactly 2 chars",
+            ))));
+        }
+        station.country = String::from(
+            Country::from_alpha2(&countrycode)
+                .map(|c| c.long_name)
+                .unwrap_or(""),
+        );
+        station.set_countrycode(countrycode);
+    }
+    if let Some(state) = state {
+        station.state = state.trim().to_string();
+    }
+    if let Some(language) = language {
+        station.set_language(fix_multi_field(&language));
+    }
+    if let Some(languagecodes) = languagecodes {
+        station.set_languagecodes(fix_multi_field(&languagecodes));
+    }
+    if let Some(tags) = tags {
+        station.set_tags(fix_multi_field(&tags));
+    }
+    if geo_lat.is_some() {
+        station.geo_lat = geo_lat;
+    }
+    if geo_long.is_some() {
+        station.geo_long = geo_long;
+    }
+    Ok(())
+}
@@ -1,9 +1,22 @@
 mod db;
 mod db_mysql;
+mod db_sqlite;
+mod db_memory;
 mod db_error;
+mod db_utils;
+mod db_search;
+mod db_geo;
+mod db_cursor;
+mod db_fields;
 
 pub mod models;
 
 pub use self::db::DbConnection;
 pub use self::db_mysql::MysqlConnection;
-pub use self::db_error::DbError;
\ No newline at end of file
+pub use self::db_sqlite::SqliteConnection;
+pub use self::db_memory::MemoryConnection;
+pub use self::db_error::DbError;
+pub use self::db_geo::GeoBoundingBox;
+pub use self::db_geo::nearest_radius;
+pub use self::db_cursor::StationCursor;
+pub use self::db_fields::StationFields;
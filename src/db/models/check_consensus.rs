@@ -0,0 +1,74 @@
+/// How the current checks of the different sources decide whether a station is working.
+/// Each source only counts with its latest check, so a station that is blocked in one region
+/// does not flip for everyone. Sources that stopped checking the station are left out.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CheckConsensus {
+    /// Sources with the most recent checks that are looked at, 0 for all of them
+    pub sources: u32,
+    /// Successful checks among those sources that make the station working, 0 for the majority
+    pub ok: u32,
+    /// Seconds after which the check of a source is too old to count, 0 to count all of them
+    pub max_age: u64,
+}
+
+impl CheckConsensus {
+    pub fn new(ok: u32, sources: u32, max_age: u64) -> Self {
+        CheckConsensus { sources, ok, max_age }
+    }
+
+    /// Whether a check of this age in seconds counts, with the count of newer checks that do.
+    /// The most recent check always counts, so a station is never decided without any check.
+    pub fn is_recent(&self, newer: usize, age: i64) -> bool {
+        newer == 0 || self.max_age == 0 || age < self.max_age as i64
+    }
+
+    /// Whether the source at this position, counted from the most recent one, takes part
+    pub fn includes(&self, position: usize) -> bool {
+        self.sources == 0 || position < self.sources as usize
+    }
+
+    /// Result of the current checks of a station, one per source and the most recent first.
+    /// If there are fewer sources than successful checks are needed, all of them have to agree.
+    pub fn is_working(&self, results: &[bool]) -> bool {
+        let count = (0..results.len()).take_while(|position| self.includes(*position)).count();
+        let results = &results[..count];
+        if results.is_empty() {
+            return true;
+        }
+        let ok = results.iter().filter(|result| **result).count();
+        if self.ok == 0 {
+            ok * 2 >= results.len()
+        } else {
+            ok >= (self.ok as usize).min(results.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CheckConsensus;
+
+    #[test]
+    fn consensus_of_sources() {
+        let majority = CheckConsensus::default();
+        assert!(majority.is_working(&[]));
+        assert!(majority.is_working(&[true, false]));
+        assert!(!majority.is_working(&[true, false, false]));
+
+        let two_of_three = CheckConsensus::new(2, 3, 0);
+        assert!(two_of_three.is_working(&[false, true, true, false]));
+        assert!(!two_of_three.is_working(&[false, false, true, true]));
+        assert!(two_of_three.is_working(&[true]));
+        assert!(!two_of_three.is_working(&[true, false]));
+
+        let any = CheckConsensus::new(1, 0, 0);
+        assert!(any.is_working(&[false, false, true]));
+        assert!(!any.is_working(&[false, false]));
+
+        let day = CheckConsensus::new(0, 0, 86400);
+        assert!(day.is_recent(0, 100000));
+        assert!(day.is_recent(1, 86399));
+        assert!(!day.is_recent(1, 86400));
+        assert!(CheckConsensus::default().is_recent(1, 100000));
+    }
+}
@@ -1,7 +1,10 @@
 mod extra_info;
+mod check_consensus;
 mod station_check_item;
 mod station_check_item_new;
 mod station_item;
+mod station_add_item;
+mod station_duplicate;
 mod state;
 mod station_change_item_new;
 mod station_history_item;
@@ -12,8 +15,13 @@ mod station_check_step_item_new;
 mod streaming_server;
 mod streaming_server_new;
 mod db_country;
+mod webhook_item;
+mod now_playing_item;
 
+pub use check_consensus::CheckConsensus;
 pub use db_country::DBCountry;
+pub use now_playing_item::NowPlayingItem;
+pub use now_playing_item::NOW_PLAYING_MAX_AGE;
 pub use station_click_item::StationClickItem;
 pub use station_click_item_new::StationClickItemNew;
 pub use station_history_item::StationHistoryItem;
@@ -21,9 +29,14 @@ pub use station_change_item_new::StationChangeItemNew;
 pub use station_check_item::StationCheckItem;
 pub use station_check_item_new::StationCheckItemNew;
 pub use station_item::DbStationItem;
+pub use station_add_item::StationAddItem;
+pub use station_duplicate::DuplicateReason;
+pub use station_duplicate::StationDuplicate;
 pub use extra_info::ExtraInfo;
 pub use state::State;
 pub use station_check_step_item::StationCheckStepItem;
 pub use station_check_step_item_new::StationCheckStepItemNew;
 pub use streaming_server::DbStreamingServer;
-pub use streaming_server_new::DbStreamingServerNew;
\ No newline at end of file
+pub use streaming_server_new::DbStreamingServerNew;
+pub use webhook_item::WebhookDeliveryItem;
+pub use webhook_item::WebhookItem;
\ No newline at end of file
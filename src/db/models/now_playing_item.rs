@@ -0,0 +1,14 @@
+use chrono::DateTime;
+use chrono::Utc;
+use std::time::Duration;
+
+/// Titles older than this are not shown anymore, the station has most likely moved on
+pub const NOW_PLAYING_MAX_AGE: Duration = Duration::from_secs(15 * 60);
+
+/// Last title a station announced in its ICY metadata
+#[derive(Clone, Debug, PartialEq)]
+pub struct NowPlayingItem {
+    pub stationuuid: String,
+    pub title: String,
+    pub updatetime_iso8601: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,21 @@
+use chrono::DateTime;
+use chrono::Utc;
+
+/// Checked fields of a new station, also the content of the review queue if moderation is enabled
+#[derive(Clone, Debug, PartialEq)]
+pub struct StationAddItem {
+    pub stationuuid: String,
+    pub name: String,
+    pub url: String,
+    pub homepage: String,
+    pub favicon: String,
+    pub country: String,
+    pub countrycode: String,
+    pub state: String,
+    pub language: String,
+    pub languagecodes: String,
+    pub tags: String,
+    pub geo_lat: Option<f64>,
+    pub geo_long: Option<f64>,
+    pub creation_iso8601: Option<DateTime<Utc>>,
+}
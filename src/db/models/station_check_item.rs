@@ -35,4 +35,9 @@ pub struct StationCheckItem {
     pub ssl_error: bool,
     pub geo_lat: Option<f64>,
     pub geo_long: Option<f64>,
+
+    /// Measured by reading the stream if audio checks are enabled
+    pub audio_bitrate: Option<u32>,
+    pub audio_sampling: Option<u32>,
+    pub audio_liveness: Option<String>,
 }
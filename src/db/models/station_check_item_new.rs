@@ -28,6 +28,11 @@ pub struct StationCheckItemNew {
     pub ssl_error: bool,
     pub geo_lat: Option<f64>,
     pub geo_long: Option<f64>,
+
+    /// Measured by reading the stream if audio checks are enabled
+    pub audio_bitrate: Option<u32>,
+    pub audio_sampling: Option<u32>,
+    pub audio_liveness: Option<String>,
 }
 
 impl StationCheckItemNew {
@@ -60,6 +65,9 @@ impl StationCheckItemNew {
             ssl_error: false,
             geo_lat: None,
             geo_long: None,
+            audio_bitrate: None,
+            audio_sampling: None,
+            audio_liveness: None,
         }
     }
 
@@ -106,6 +114,9 @@ impl StationCheckItemNew {
             ssl_error: info.SslError,
             geo_lat: latlong.clone().map(|y|y.lat),
             geo_long: latlong.map(|y|y.long),
+            audio_bitrate: None,
+            audio_sampling: None,
+            audio_liveness: None,
         }
     }
 }
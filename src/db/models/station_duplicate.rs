@@ -0,0 +1,28 @@
+/// Why an existing station is thought to be the same as a new one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DuplicateReason {
+    Url,
+    UrlResolved,
+    /// Same url as a submission in the review queue
+    Pending,
+    Name,
+}
+
+impl DuplicateReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DuplicateReason::Url => "url",
+            DuplicateReason::UrlResolved => "url_resolved",
+            DuplicateReason::Pending => "pending",
+            DuplicateReason::Name => "name",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StationDuplicate {
+    pub stationuuid: String,
+    pub name: String,
+    pub url: String,
+    pub reason: DuplicateReason,
+}
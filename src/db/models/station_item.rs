@@ -40,6 +40,12 @@ pub struct DbStationItem {
     pub geo_lat: Option<f64>,
     pub geo_long: Option<f64>,
     pub has_extended_info: Option<bool>,
+    /// Relevance for the search query, only set by fuzzy search
+    pub score: Option<f64>,
+    /// Distance in meters to the point of a geo search
+    pub distance: Option<f64>,
+    /// Title from the ICY metadata of the stream
+    pub now_playing: Option<String>,
 }
 
 impl DbStationItem {
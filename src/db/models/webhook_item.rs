@@ -0,0 +1,32 @@
+use chrono::DateTime;
+use chrono::Utc;
+
+/// Registered receiver of station events. Empty filters match every station,
+/// set ones all have to match.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WebhookItem {
+    pub webhookuuid: String,
+    /// Name of the api key that registered it
+    pub owner: String,
+    pub url: String,
+    pub secret: String,
+    /// Comma separated names of the events, empty for all
+    pub events: String,
+    pub stationuuid: String,
+    pub tag: String,
+    pub countrycode: String,
+    pub creation_iso8601: Option<DateTime<Utc>>,
+}
+
+/// Event that waits to be sent to a webhook, with the receiver it belongs to
+#[derive(Clone, Debug, PartialEq)]
+pub struct WebhookDeliveryItem {
+    pub deliveryuuid: String,
+    pub webhookuuid: String,
+    pub url: String,
+    pub secret: String,
+    pub event: String,
+    pub payload: String,
+    /// Failed attempts so far
+    pub attempts: u32,
+}
@@ -10,8 +10,11 @@ extern crate prometheus;
 use crate::cli::delete_duplicate_changes;
 use crate::cli::resethistory;
 use crate::config::Config;
+use crate::db::models::CheckConsensus;
 use crate::db::DbConnection;
+use crate::db::MemoryConnection;
 use crate::db::MysqlConnection;
+use crate::db::SqliteConnection;
 use crate::pull::UuidWithTime;
 use core::fmt::Display;
 use core::fmt::Formatter;
@@ -34,6 +37,9 @@ mod db;
 mod logger;
 mod pull;
 mod refresh;
+mod webhooks;
+#[cfg(test)]
+mod test_utils;
 
 #[derive(Debug, Clone)]
 enum MainError {
@@ -71,6 +77,71 @@ where
     let mut list_deleted: Vec<UuidWithTime> = vec![];
     let client = Client::new();
 
+    // deliveries have their own thread, a long check run should not hold them back
+    let conn_webhooks = conn.clone();
+    thread::spawn(move || {
+        loop {
+            let config = config::get_config()
+                .expect("No config loaded")
+                .lock()
+                .expect("Config could not be pulled from shared memory.")
+                .clone();
+            match webhooks::deliver(
+                &conn_webhooks,
+                &config.useragent,
+                config.tcp_timeout,
+                config.webhook_retries,
+                config.webhook_private_targets,
+            ) {
+                Ok(0) => {}
+                Ok(delivered) => {
+                    debug!("Delivered {} webhook events", delivered);
+                }
+                Err(err) => {
+                    error!("Webhook delivery error: {}", err);
+                }
+            }
+            webhooks::wait_for_deliveries();
+        }
+    });
+
+    // titles change every few minutes, a long check run should not hold them back either
+    let config = config::get_config()
+        .expect("No config loaded")
+        .lock()
+        .expect("Config could not be pulled from shared memory.")
+        .clone();
+    if config.check_now_playing && !config.now_playing_interval.is_zero() {
+        let conn_now_playing = conn.clone();
+        thread::spawn(move || {
+            let client = Client::builder()
+                .user_agent(&config.useragent)
+                .timeout(config.tcp_timeout)
+                .build()
+                .expect("Unable to create http client for now playing");
+            loop {
+                let config = config::get_config()
+                    .expect("No config loaded")
+                    .lock()
+                    .expect("Config could not be pulled from shared memory.")
+                    .clone();
+                if !config.check_now_playing || config.now_playing_interval.is_zero() {
+                    thread::sleep(Duration::from_secs(10));
+                    continue;
+                }
+                match check::update_now_playing(&conn_now_playing, &client, config.now_playing_stations, config.concurrency) {
+                    Ok(count) => {
+                        debug!("Updated {} now playing titles", count);
+                    }
+                    Err(err) => {
+                        error!("Now playing error: {}", err);
+                    }
+                }
+                thread::sleep(config.now_playing_interval);
+            }
+        });
+    }
+
     thread::spawn(move || loop {
         let config = config::get_config()
             .expect("No config loaded")
@@ -106,6 +177,7 @@ where
                 config.chunk_size_changes,
                 config.chunk_size_checks,
                 config.max_duplicates,
+                &CheckConsensus::new(config.check_consensus_ok, config.check_consensus_sources, config.check_interval.as_secs()),
                 &mut list_deleted,
             );
             match result {
@@ -154,6 +226,7 @@ where
             once_check = false;
             once_refresh_caches = true;
             last_time_check = Instant::now();
+            let scheduler = check::create_scheduler(&config);
             let result = check::dbcheck(
                 conn.clone(),
                 &config.source,
@@ -168,6 +241,10 @@ where
                 config.favicon_size_min,
                 config.favicon_size_max,
                 config.favicon_size_optimum,
+                config.check_audio_duration,
+                config.check_now_playing,
+                scheduler.as_ref(),
+                &CheckConsensus::new(config.check_consensus_ok, config.check_consensus_sources, config.check_interval.as_secs()),
             );
 
             match result {
@@ -211,7 +288,10 @@ where
     });
 }
 
-fn mainloopitem(mut connection: MysqlConnection, config: Config) -> Result<(), Box<dyn Error>> {
+fn mainloopitem<C: 'static>(mut connection: C, config: Config) -> Result<(), Box<dyn Error>>
+where
+    C: DbConnection + Clone + Send + Sync,
+{
     trace!("mainloopitem()");
     if config.no_migrations {
         if connection.migrations_needed()? {
@@ -242,6 +322,43 @@ fn mainloopitem(mut connection: MysqlConnection, config: Config) -> Result<(), B
     Ok(())
 }
 
+/// Select the database backend by the scheme of the connection string
+fn mainloopconnect(config: Config, retry: bool) -> Result<(), Box<dyn Error>> {
+    if config.connection_string.starts_with("sqlite:") {
+        mainloopconnect_with(SqliteConnection::new, config, retry)
+    } else if config.connection_string.starts_with("memory:") {
+        mainloopconnect_with(|_| Ok(MemoryConnection::new()), config, retry)
+    } else {
+        mainloopconnect_with(MysqlConnection::new, config, retry)
+    }
+}
+
+fn mainloopconnect_with<C: 'static, F>(
+    connect: F,
+    config: Config,
+    retry: bool,
+) -> Result<(), Box<dyn Error>>
+where
+    C: DbConnection + Clone + Send + Sync,
+    F: Fn(&str) -> Result<C, Box<dyn Error>>,
+{
+    loop {
+        let connection = connect(&config.connection_string);
+        match connection {
+            Ok(connection) => {
+                return mainloopitem(connection, config);
+            }
+            Err(e) => {
+                error!("DB connection error: {}", e);
+                thread::sleep(time::Duration::from_millis(1000));
+                if !retry {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
 fn mainloop() -> Result<(), Box<dyn Error>> {
     // load config
     config::load_main_config()?;
@@ -260,23 +377,11 @@ fn mainloop() -> Result<(), Box<dyn Error>> {
     let config2 = config.clone();
 
     if let config::ConfigSubCommand::None = config2.sub_command {
-        thread::spawn(|| loop {
-            let connection = db::MysqlConnection::new(&config2.connection_string);
-            match connection {
-                Ok(connection) => {
-                    match mainloopitem(connection, config2) {
-                        Err(err) => {
-                            error!("Error: {}", err);
-                        }
-                        Ok(_) => {}
-                    }
-                    break;
-                }
-                Err(e) => {
-                    error!("DB connection error: {}", e);
-                    thread::sleep(time::Duration::from_millis(1000));
-                }
+        thread::spawn(|| match mainloopconnect(config2, true) {
+            Err(err) => {
+                error!("Error: {}", err);
             }
+            Ok(_) => {}
         });
         let mut signals = Signals::new(&[SIGHUP])?;
         for signal in &mut signals {
@@ -290,16 +395,7 @@ fn mainloop() -> Result<(), Box<dyn Error>> {
             }
         }
     } else {
-        let connection = db::MysqlConnection::new(&config2.connection_string);
-        match connection {
-            Ok(connection) => {
-                mainloopitem(connection, config2)?;
-            }
-            Err(e) => {
-                error!("DB connection error: {}", e);
-                thread::sleep(time::Duration::from_millis(1000));
-            }
-        }
+        mainloopconnect(config2, false)?;
     }
 
     Ok(())
@@ -4,6 +4,15 @@ mod uuid_with_time;
 pub use crate::pull::uuid_with_time::UuidWithTime;
 use std::error::Error;
 use std::convert::TryFrom;
+use std::fmt;
+use std::io::BufReader;
+use std::marker::PhantomData;
+
+use serde::de::Deserialize;
+use serde::de::Deserializer;
+use serde::de::Error as DeError;
+use serde::de::SeqAccess;
+use serde::de::Visitor;
 
 use reqwest::blocking::Client;
 use reqwest::blocking::RequestBuilder;
@@ -19,6 +28,7 @@ use crate::api::data::StationClickV0;
 use crate::api::data::Station;
 use crate::api::data::StationV0;
 use crate::db::DbConnection;
+use crate::db::models::CheckConsensus;
 use crate::db::models::StationCheckItemNew;
 use crate::db::models::StationChangeItemNew;
 use crate::db::models::StationClickItemNew;
@@ -29,10 +39,10 @@ fn add_default_request_headers(req: RequestBuilder) -> RequestBuilder {
     req.header(USER_AGENT, format!("{}/{}", pkg_name, pkg_version))
 }
 
-pub fn pull_worker<C>(client: &Client, pool: C, mirrors: &Vec<String>, chunk_size_changes: usize, chunk_size_checks: usize, max_duplicates: usize, list_deleted: &mut Vec<UuidWithTime>) -> Result<(),Box<dyn Error>> where C: DbConnection + Clone {
+pub fn pull_worker<C>(client: &Client, pool: C, mirrors: &Vec<String>, chunk_size_changes: usize, chunk_size_checks: usize, max_duplicates: usize, consensus: &CheckConsensus, list_deleted: &mut Vec<UuidWithTime>) -> Result<(),Box<dyn Error>> where C: DbConnection + Clone {
     let list_deleted_uuids = list_deleted.iter().map(|item| item.uuid.to_string()).collect();
     for server in mirrors.iter() {
-        let result = pull_server(client, pool.clone(), &server, chunk_size_changes, chunk_size_checks, &list_deleted_uuids);
+        let result = pull_server(client, pool.clone(), &server, chunk_size_changes, chunk_size_checks, consensus, &list_deleted_uuids);
         match result {
             Ok(_) => {
             },
@@ -164,27 +174,63 @@ fn pull_clicks(client: &Client, server: &str, api_version: u32, lastid: Option<S
     }
 }
 
-fn pull_stations(client: &Client, server: &str, api_version: u32) -> Result<Vec<Station>, Box<dyn Error>> {
+/// The list of all stations is parsed while it is downloaded and handed to the callback in chunks,
+/// so it never has to be in memory completely.
+fn pull_stations(client: &Client, server: &str, api_version: u32, chunk_size: usize, callback: &mut dyn FnMut(Vec<Station>) -> Result<(), Box<dyn Error>>) -> Result<(), Box<dyn Error>> {
     let path = format!("{}/json/stations",server);
     trace!("{}", path);
-    let result = add_default_request_headers(client.get(&path)).send()?;
+    let result = add_default_request_headers(client.get(&path)).send()?.error_for_status()?;
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(result));
     match api_version {
         0 => {
-            let mut list: Vec<StationV0> = result.json()?;
-            let list_current: Vec<Station> = list.drain(..).map(|x| x.into()).collect();
-            Ok(list_current)
+            deserializer.deserialize_seq(StationChunks::<StationV0>::new(chunk_size, callback))?;
         },
         1 => {
-            let list: Vec<Station> = result.json()?;
-            Ok(list)
+            deserializer.deserialize_seq(StationChunks::<Station>::new(chunk_size, callback))?;
         },
         _ => {
-            Err(Box::new(pull_error::PullError::UnknownApiVersion(api_version)))
+            return Err(Box::new(pull_error::PullError::UnknownApiVersion(api_version)));
+        }
+    }
+    deserializer.end()?;
+    Ok(())
+}
+
+struct StationChunks<'a, T> {
+    chunk_size: usize,
+    callback: &'a mut dyn FnMut(Vec<Station>) -> Result<(), Box<dyn Error>>,
+    item_type: PhantomData<T>,
+}
+
+impl<'a, T> StationChunks<'a, T> {
+    fn new(chunk_size: usize, callback: &'a mut dyn FnMut(Vec<Station>) -> Result<(), Box<dyn Error>>) -> Self {
+        StationChunks { chunk_size: chunk_size.max(1), callback, item_type: PhantomData }
+    }
+}
+
+impl<'de, 'a, T> Visitor<'de> for StationChunks<'a, T> where T: Deserialize<'de> + Into<Station> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of stations")
+    }
+
+    fn visit_seq<S>(self, mut seq: S) -> Result<(), S::Error> where S: SeqAccess<'de> {
+        let mut chunk: Vec<Station> = Vec::with_capacity(self.chunk_size);
+        while let Some(item) = seq.next_element::<T>()? {
+            chunk.push(item.into());
+            if chunk.len() >= self.chunk_size {
+                (self.callback)(std::mem::take(&mut chunk)).map_err(S::Error::custom)?;
+            }
+        }
+        if !chunk.is_empty() {
+            (self.callback)(chunk).map_err(S::Error::custom)?;
         }
+        Ok(())
     }
 }
 
-fn pull_server<C>(client: &Client, connection_new: C, server: &str, chunk_size_changes: usize, chunk_size_checks: usize, ignore_station_uuids: &Vec<String>) -> Result<(),Box<dyn std::error::Error>> where C: DbConnection {
+fn pull_server<C>(client: &Client, connection_new: C, server: &str, chunk_size_changes: usize, chunk_size_checks: usize, consensus: &CheckConsensus, ignore_station_uuids: &Vec<String>) -> Result<(),Box<dyn std::error::Error>> where C: DbConnection {
     let insert_chunksize = 2000;
     let mut station_change_count = 0;
     let mut station_check_count = 0;
@@ -230,7 +276,7 @@ fn pull_server<C>(client: &Client, connection_new: C, server: &str, chunk_size_c
             trace!("Inserted checks ({})..", inserted.len());
 
             let last = chunk.last();
-            connection_new.update_station_with_check_data(&inserted, false)?;
+            connection_new.update_station_with_check_data(&inserted, false, consensus)?;
             if let Some(last) = last {
                 let checkuuid = &last.checkuuid;
                 if let Some(checkuuid) = checkuuid {
@@ -336,8 +382,7 @@ fn pull_server<C>(client: &Client, connection_new: C, server: &str, chunk_size_c
         // but we need it to keep votes in sync
         // we could make votes more like clicks, then we could also
         // make votes incremental
-        let list_stations = pull_stations(client, server, api_version)?;
-        connection_new.sync_votes(list_stations)?;
+        pull_stations(client, server, api_version, insert_chunksize, &mut |list_stations| connection_new.sync_votes(list_stations))?;
     }
 
     debug!("Pull from '{}' OK (Added station changes: {}, Added station checks: {}, Added station clicks: {}, Added missing stations: {})", server, station_change_count, station_check_count, station_click_count, station_missing_count);
@@ -375,6 +420,9 @@ impl From<StationCheck> for StationCheckItemNew {
             ssl_error: item.ssl_error.unwrap_or(0) == 1,
             geo_lat: item.geo_lat,
             geo_long: item.geo_long,
+            audio_bitrate: item.audio_bitrate,
+            audio_sampling: item.audio_sampling,
+            audio_liveness: item.audio_liveness,
         }
     }
 }
@@ -411,4 +459,7 @@ impl From<StationClick> for StationClickItemNew {
             ip: String::from(""),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests;
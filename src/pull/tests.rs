@@ -0,0 +1,61 @@
+use super::pull_worker;
+use crate::api;
+use crate::db::models::CheckConsensus;
+use crate::db::models::StationCheckItemNew;
+use crate::db::DbConnection;
+use crate::db::MemoryConnection;
+use crate::test_utils::free_port;
+use crate::test_utils::sample_db;
+use crate::test_utils::test_config;
+use reqwest::blocking::Client;
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+/// Serve the database with the real api on a free port and return the base url
+fn start_mirror(conn: MemoryConnection) -> String {
+    let mut config = test_config();
+    let port = free_port();
+    config.listen_port = port.into();
+    thread::spawn(move || api::start(conn, config));
+    for _ in 0..100 {
+        if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    format!("http://127.0.0.1:{}", port)
+}
+
+#[test]
+fn pull_from_mirror() {
+    let source = sample_db();
+    let mut check = StationCheckItemNew::broken("s-jazz".to_string(), "k1".to_string(), "mirror".to_string(), 1);
+    check.check_ok = true;
+    check.codec = "MP3".to_string();
+    check.bitrate = 128;
+    let (_, _, inserted) = source.insert_checks(vec![check]).unwrap();
+    source.update_station_with_check_data(&inserted, true, &CheckConsensus::default()).unwrap();
+    let station = source.get_station_by_uuid("s-rock").unwrap().pop();
+    source.vote_for_station("10.0.0.1", station.clone()).unwrap();
+    source.increase_clicks("10.0.0.1", &station.unwrap(), 60).unwrap();
+    let mirror = start_mirror(source);
+
+    let target = MemoryConnection::new();
+    let client = Client::new();
+    let mut list_deleted = vec![];
+    pull_worker(&client, target.clone(), &vec![mirror.clone()], 1000, 1000, 0, &CheckConsensus::default(), &mut list_deleted).unwrap();
+
+    let stations = target.get_stations_by_all("name", false, false, None, None, 0, 100).unwrap();
+    let names: Vec<&str> = stations.iter().map(|station| station.name.as_str()).collect();
+    assert_eq!(names, vec!["Jazz Radio", "News Radio", "Rock Radio"]);
+    assert_eq!((stations[0].codec.as_str(), stations[0].bitrate), ("MP3", 128));
+    assert_eq!((stations[2].votes, stations[2].clickcount), (1, 1));
+    assert_eq!(target.get_checks(None, None, 0, true, 100).unwrap().len(), 1);
+    assert_eq!(target.get_pull_server_lastid(&mirror).unwrap(), Some("c-news".to_string()));
+
+    // a second pull does not duplicate anything
+    pull_worker(&client, target.clone(), &vec![mirror], 1000, 1000, 0, &CheckConsensus::default(), &mut list_deleted).unwrap();
+    assert_eq!(target.get_changes(None, None, 100).unwrap().len(), 3);
+    assert_eq!(target.get_clicks(None, None, 0, 100).unwrap().len(), 1);
+}
@@ -92,3 +92,6 @@ where
     );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,27 @@
+use super::refresh_cache_items;
+use crate::db::DbConnection;
+use crate::test_utils::sample_db;
+use crate::test_utils::station_change;
+
+#[test]
+fn refresh_tag_cache() {
+    let conn = sample_db();
+    let status = refresh_cache_items(conn.clone(), "TagCache", "TagName", "Tags").unwrap();
+    assert_eq!((status.old_items, status.new_items, status.changed_items), (0, 3, 0));
+    assert_eq!(conn.get_tag_count().unwrap(), 3);
+    let tags = conn
+        .get_extra("TagCache", "TagName", None, "stationcount".to_string(), true, false, 0, 10)
+        .unwrap();
+    assert_eq!((tags[0].name.as_str(), tags[0].stationcount), ("rock", 2));
+
+    conn.insert_station_by_change(
+        &[station_change("s-rock", "c-rock-2", "Rock Radio", "http://rock.example.com/live", "metal")],
+        "test",
+    )
+    .unwrap();
+    let status = refresh_cache_items(conn.clone(), "TagCache", "TagName", "Tags").unwrap();
+    assert_eq!((status.old_items, status.new_items, status.changed_items), (3, 4, 1));
+    let cached = conn.get_cached_items("TagCache", "TagName").unwrap();
+    assert_eq!(cached.get("rock"), Some(&(1, 1)));
+    assert_eq!(cached.get("metal"), Some(&(1, 1)));
+}
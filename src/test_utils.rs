@@ -0,0 +1,149 @@
+use crate::config::CacheType;
+use crate::config::CheckSchedulerType;
+use crate::config::Config;
+use crate::config::ConfigSubCommand;
+use crate::db::DbConnection;
+use crate::db::MemoryConnection;
+use crate::db::SqliteConnection;
+use crate::db::models::StationChangeItemNew;
+use std::net::TcpListener;
+use std::time::Duration;
+
+/// Configuration with the defaults of the command line parser,
+/// all background jobs disabled and no cache
+pub fn test_config() -> Config {
+    Config {
+        allow_database_downgrade: false,
+        broken_stations_never_working_timeout: Duration::from_secs(3 * 24 * 60 * 60),
+        broken_stations_timeout: Duration::from_secs(30 * 24 * 60 * 60),
+        check_stations: 10,
+        checks_timeout: Duration::from_secs(30 * 24 * 60 * 60),
+        click_valid_timeout: Duration::from_secs(24 * 60 * 60),
+        clicks_timeout: Duration::from_secs(3 * 24 * 60 * 60),
+        concurrency: 1,
+        connection_string: String::from("memory:"),
+        delete: false,
+        enable_check: false,
+        no_migrations: false,
+        ignore_migration_errors: false,
+        listen_host: String::from("127.0.0.1"),
+        listen_port: 8080,
+        log_dir: std::env::temp_dir().to_string_lossy().to_string(),
+        log_level: 0,
+        log_json: false,
+        max_depth: 5,
+        mirror_pull_interval: Duration::from_secs(5 * 60),
+        pause: Duration::from_secs(60),
+        prometheus_exporter_prefix: String::from("radio_browser_"),
+        prometheus_exporter: true,
+        retries: 0,
+        server_url: String::from("http://localhost"),
+        servers_pull: vec![],
+        source: String::from("test"),
+        server_location: String::from("Test"),
+        server_country_code: String::from("DE"),
+        static_files_dir: String::from("./static"),
+        tcp_timeout: Duration::from_secs(2),
+        threads: 2,
+        update_caches_interval: Duration::from_secs(60),
+        useragent: String::from("radiobrowser-api-rust/test"),
+        cache_type: CacheType::None,
+        cache_url: String::new(),
+        cache_ttl: Duration::from_secs(60),
+        chunk_size_changes: 10000,
+        chunk_size_checks: 10000,
+        max_duplicates: 0,
+        check_servers: false,
+        check_servers_chunksize: 100,
+        language_replace_filepath: String::new(),
+        language_to_code_filepath: String::new(),
+        tag_replace_filepath: String::new(),
+        enable_extract_favicon: false,
+        recheck_existing_favicon: false,
+        favicon_size_min: 32,
+        favicon_size_max: 256,
+        favicon_size_optimum: 128,
+        refresh_config_interval: Duration::from_secs(24 * 60 * 60),
+        cleanup_interval: Duration::from_secs(60 * 60),
+        admin_token: None,
+        api_keys: vec![],
+        moderation: false,
+        rate_limit_vote: 0,
+        rate_limit_click: 0,
+        rate_limit_add: 0,
+        rate_limit_search: 0,
+        rate_limit_check: 0,
+        rate_limit_allowlist: vec![],
+        trusted_proxies: vec![],
+        graphql_max_depth: 10,
+        graphql_max_complexity: 10000,
+        events_max_clients: 1,
+        webhook_retries: 5,
+        webhook_private_targets: false,
+        check_scheduler: CheckSchedulerType::Fixed,
+        check_interval: Duration::from_secs(24 * 60 * 60),
+        check_interval_min: Duration::from_secs(60 * 60),
+        check_interval_max: Duration::from_secs(7 * 24 * 60 * 60),
+        check_consensus_ok: 0,
+        check_consensus_sources: 0,
+        check_audio_duration: Duration::from_secs(0),
+        check_now_playing: false,
+        now_playing_interval: Duration::from_secs(0),
+        now_playing_stations: 100,
+        sub_command: ConfigSubCommand::None,
+    }
+}
+
+pub fn station_change(stationuuid: &str, changeuuid: &str, name: &str, url: &str, tags: &str) -> StationChangeItemNew {
+    StationChangeItemNew {
+        name: name.to_string(),
+        url: url.to_string(),
+        homepage: String::from("https://example.com/"),
+        favicon: String::new(),
+        country: String::from("Germany"),
+        state: String::from("Bavaria"),
+        countrycode: String::from("DE"),
+        language: String::from("german"),
+        languagecodes: String::from("de"),
+        tags: tags.to_string(),
+        votes: 0,
+        changeuuid: changeuuid.to_string(),
+        stationuuid: stationuuid.to_string(),
+        geo_lat: None,
+        geo_long: None,
+    }
+}
+
+/// Memory database with three stations, "s-jazz" and "s-rock" share the rock tag
+pub fn sample_db() -> MemoryConnection {
+    let conn = MemoryConnection::new();
+    insert_sample_stations(&conn);
+    conn
+}
+
+/// Sqlite database in a new temporary file with the same stations as sample_db()
+pub fn sample_sqlite_db() -> SqliteConnection {
+    let path = std::env::temp_dir().join(format!("radiobrowser-test-{}.db", uuid::Uuid::new_v4()));
+    let conn = SqliteConnection::new(&format!("sqlite://{}", path.display())).expect("open sqlite database");
+    conn.do_migrations(false, false).expect("migrate sqlite database");
+    insert_sample_stations(&conn);
+    conn
+}
+
+fn insert_sample_stations<C: DbConnection>(conn: &C) {
+    conn.insert_station_by_change(
+        &[
+            station_change("s-jazz", "c-jazz", "Jazz Radio", "http://jazz.example.com/live", "jazz,rock"),
+            station_change("s-rock", "c-rock", "Rock Radio", "http://rock.example.com/live", "rock"),
+            station_change("s-news", "c-news", "News Radio", "http://news.example.com/live", "news"),
+        ],
+        "test",
+    )
+    .expect("insert sample stations");
+}
+
+/// Port on localhost that nobody is listening on at the moment
+pub fn free_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind to free port");
+    listener.local_addr().expect("local address").port()
+}
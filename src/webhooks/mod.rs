@@ -0,0 +1,284 @@
+use crate::api::data::Station;
+use crate::db::models::DbStationItem;
+use crate::db::models::WebhookDeliveryItem;
+use crate::db::models::WebhookItem;
+use crate::db::DbConnection;
+use chrono::DateTime;
+use chrono::Utc;
+use hmac::Hmac;
+use hmac::Mac;
+use reqwest::blocking::Client;
+use reqwest::redirect::Policy;
+use serde::Serialize;
+use sha2::Sha256;
+use std::error::Error;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::time::Duration;
+use url::Host;
+use url::Url;
+use uuid::Uuid;
+
+/// Deliveries that are sent in one run
+const DELIVERY_BATCH: u32 = 100;
+/// Wait before the first retry, doubled for every further one
+const RETRY_BASE_SECONDS: u64 = 60;
+
+/// Set when deliveries were queued, together with WAKE it wakes up the delivery thread
+static QUEUED: Mutex<bool> = Mutex::new(false);
+static WAKE: Condvar = Condvar::new();
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StationEvent {
+    /// A check failed after the station was working
+    Broken,
+    /// A check succeeded after the station was broken
+    Working,
+    UrlChanged,
+    /// Removed by the cleanup after it was broken for too long
+    Deleted,
+}
+
+impl StationEvent {
+    pub const ALL: [StationEvent; 4] = [
+        StationEvent::Broken,
+        StationEvent::Working,
+        StationEvent::UrlChanged,
+        StationEvent::Deleted,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StationEvent::Broken => "station_broken",
+            StationEvent::Working => "station_working",
+            StationEvent::UrlChanged => "station_url_changed",
+            StationEvent::Deleted => "station_deleted",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<StationEvent> {
+        StationEvent::ALL.iter().find(|event| event.as_str() == name).copied()
+    }
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    event: &'a str,
+    time: DateTime<Utc>,
+    station: Station,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous_url: Option<&'a str>,
+}
+
+/// Registered webhooks, loaded once for a run of the check or the cleanup
+#[derive(Default)]
+pub struct Webhooks {
+    list: Vec<WebhookItem>,
+}
+
+impl Webhooks {
+    pub fn load<C: DbConnection>(conn: &C) -> Result<Webhooks, Box<dyn Error>> {
+        Ok(Webhooks {
+            list: conn.get_webhooks(None)?,
+        })
+    }
+
+    /// Queues a delivery of the event for every webhook that is interested in the station
+    pub fn notify<C: DbConnection>(
+        &self,
+        conn: &C,
+        event: StationEvent,
+        station: &DbStationItem,
+        previous_url: Option<&str>,
+    ) -> Result<(), Box<dyn Error>> {
+        let receivers: Vec<&WebhookItem> = self.list.iter().filter(|webhook| matches(webhook, event, station)).collect();
+        if receivers.is_empty() {
+            return Ok(());
+        }
+        let payload = serde_json::to_string(&Payload {
+            event: event.as_str(),
+            time: Utc::now(),
+            station: Station::from(station.clone()),
+            previous_url,
+        })?;
+        for webhook in receivers {
+            debug!("webhook {} gets {} of {}", webhook.webhookuuid, event.as_str(), station.stationuuid);
+            conn.insert_webhook_delivery(&webhook.webhookuuid, event.as_str(), &payload)?;
+        }
+        let mut queued = QUEUED.lock().unwrap_or_else(|err| err.into_inner());
+        *queued = true;
+        WAKE.notify_all();
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+}
+
+/// Blocks until deliveries were queued, or until retries may be due
+pub fn wait_for_deliveries() {
+    let queued = QUEUED.lock().unwrap_or_else(|err| err.into_inner());
+    let (mut queued, _) = WAKE
+        .wait_timeout_while(queued, Duration::from_secs(RETRY_BASE_SECONDS), |queued| !*queued)
+        .unwrap_or_else(|err| err.into_inner());
+    *queued = false;
+}
+
+/// Addresses of the host of the url. Unless private targets are allowed, all of them have to be public,
+/// so webhooks can not reach services in the network of the server or the metadata of cloud hosts.
+pub fn target_addresses(url: &str, private_targets: bool) -> Result<Vec<SocketAddr>, String> {
+    let parsed = Url::parse(url).map_err(|_| String::from("url has to be a http or https url"))?;
+    let addresses = parsed
+        .socket_addrs(|| None)
+        .map_err(|err| format!("host of the url can not be resolved: {}", err))?;
+    if addresses.is_empty() {
+        return Err(String::from("host of the url can not be resolved"));
+    }
+    if !private_targets {
+        if let Some(address) = addresses.iter().find(|address| !is_public(&address.ip())) {
+            return Err(format!("url points to the address {}, which is not public", address.ip()));
+        }
+    }
+    Ok(addresses)
+}
+
+/// Not loopback, private, link-local, shared or multicast
+fn is_public(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, _, _] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || first == 0
+                || (first == 100 && (64..128).contains(&second)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(&IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                // unique local fc00::/7 and link-local fe80::/10
+                !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || first & 0xfe00 == 0xfc00 || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Webhook from the parameters of the api, or a message what is wrong with them
+pub fn new_webhook(
+    owner: &str,
+    url: &str,
+    secret: &str,
+    events: &str,
+    stationuuid: &str,
+    tag: &str,
+    countrycode: &str,
+) -> Result<WebhookItem, String> {
+    let url = url.trim();
+    match Url::parse(url) {
+        Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => {}
+        _ => return Err(String::from("url has to be a http or https url")),
+    }
+    if secret.is_empty() {
+        return Err(String::from("secret is missing"));
+    }
+    let mut names = vec![];
+    for name in events.split(',').map(|name| name.trim().to_lowercase()).filter(|name| !name.is_empty()) {
+        if StationEvent::parse(&name).is_none() {
+            return Err(format!("unknown event '{}'", name));
+        }
+        names.push(name);
+    }
+    let countrycode = countrycode.trim().to_uppercase();
+    if !countrycode.is_empty() && countrycode.len() != 2 {
+        return Err(String::from("countrycode has to have 2 letters"));
+    }
+    Ok(WebhookItem {
+        webhookuuid: Uuid::new_v4().as_hyphenated().to_string(),
+        owner: owner.to_string(),
+        url: url.to_string(),
+        secret: secret.to_string(),
+        events: names.join(","),
+        stationuuid: stationuuid.trim().to_lowercase(),
+        tag: tag.trim().to_lowercase(),
+        countrycode,
+        creation_iso8601: None,
+    })
+}
+
+fn matches(webhook: &WebhookItem, event: StationEvent, station: &DbStationItem) -> bool {
+    let in_list = |list: &str, value: &str| list.split(',').any(|item| item.trim().eq_ignore_ascii_case(value));
+    (webhook.events.is_empty() || in_list(&webhook.events, event.as_str()))
+        && (webhook.stationuuid.is_empty() || webhook.stationuuid.eq_ignore_ascii_case(&station.stationuuid))
+        && (webhook.tag.is_empty() || in_list(&station.tags, &webhook.tag))
+        && (webhook.countrycode.is_empty() || webhook.countrycode.eq_ignore_ascii_case(&station.countrycode))
+}
+
+/// Sends the deliveries that are due. Failed ones are tried again after 1, 2, 4, .. minutes
+/// and dropped after the given count of retries. Returns the count of successful deliveries.
+pub fn deliver<C: DbConnection>(conn: &C, useragent: &str, timeout: Duration, retries: u32, private_targets: bool) -> Result<usize, Box<dyn Error>> {
+    let mut delivered = 0;
+    for delivery in conn.get_due_webhook_deliveries(DELIVERY_BATCH)? {
+        match send(&delivery, useragent, timeout, private_targets) {
+            Ok(_) => {
+                conn.delete_webhook_delivery(&delivery.deliveryuuid)?;
+                delivered += 1;
+            }
+            Err(err) if delivery.attempts >= retries => {
+                warn!("Giving up delivery {} to {}: {}", delivery.deliveryuuid, delivery.url, err);
+                conn.delete_webhook_delivery(&delivery.deliveryuuid)?;
+            }
+            Err(err) => {
+                debug!("Delivery {} to {} failed: {}", delivery.deliveryuuid, delivery.url, err);
+                let seconds = RETRY_BASE_SECONDS.saturating_mul(1 << delivery.attempts.min(16));
+                conn.retry_webhook_delivery(&delivery.deliveryuuid, seconds, &err.to_string())?;
+            }
+        }
+    }
+    Ok(delivered)
+}
+
+/// The address is checked again for every delivery, the name of the host could point somewhere else by now.
+/// The request goes to the checked address and redirects are not followed.
+fn send(delivery: &WebhookDeliveryItem, useragent: &str, timeout: Duration, private_targets: bool) -> Result<(), Box<dyn Error>> {
+    let addresses = target_addresses(&delivery.url, private_targets)?;
+    let mut builder = Client::builder().user_agent(useragent).timeout(timeout).redirect(Policy::none());
+    if let Some(Host::Domain(domain)) = Url::parse(&delivery.url)?.host() {
+        builder = builder.resolve(domain, addresses[0]);
+    }
+    let response = builder
+        .build()?
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-RadioBrowser-Event", &delivery.event)
+        .header("X-RadioBrowser-Delivery", &delivery.deliveryuuid)
+        .header("X-RadioBrowser-Signature", signature(&delivery.secret, &delivery.payload))
+        .body(delivery.payload.clone())
+        .send()?;
+    response.error_for_status()?;
+    Ok(())
+}
+
+/// Value of the signature header, the receiver calculates it over the body with its secret
+pub fn signature(secret: &str, body: &str) -> String {
+    let hex: String = hmac_sha256(secret.as_bytes(), body.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("sha256={}", hex)
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests;
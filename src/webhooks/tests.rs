@@ -0,0 +1,151 @@
+use super::deliver;
+use super::hmac_sha256;
+use super::new_webhook;
+use super::signature;
+use super::target_addresses;
+use crate::cleanup::do_cleanup;
+use crate::db::models::CheckConsensus;
+use crate::db::models::StationCheckItemNew;
+use crate::db::DbConnection;
+use crate::db::MemoryConnection;
+use crate::test_utils::sample_db;
+use std::io::Read;
+use std::io::Write;
+use std::net::TcpListener;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Answers a request with each of the statuses, the thread returns the requests as they were received
+fn serve(statuses: Vec<u16>) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let mut requests = vec![];
+        for status in statuses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = vec![];
+            let mut buffer = [0u8; 4096];
+            loop {
+                let read = stream.read(&mut buffer).unwrap();
+                received.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&received).to_string();
+                let complete = text.find("\r\n\r\n").map(|end| {
+                    let length: usize = text
+                        .lines()
+                        .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|value| value.trim().parse().unwrap()))
+                        .unwrap_or(0);
+                    received.len() >= end + 4 + length
+                });
+                if read == 0 || complete == Some(true) {
+                    break;
+                }
+            }
+            write!(stream, "HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).unwrap();
+            requests.push(String::from_utf8_lossy(&received).to_string());
+        }
+        requests
+    });
+    (url, handle)
+}
+
+/// Checks that make the station working and then broken, so the cleanup deletes it 30 days later
+fn break_station(conn: &MemoryConnection, stationuuid: &str) {
+    let mut check = StationCheckItemNew::broken(stationuuid.to_string(), format!("{}-ok", stationuuid), "test".to_string(), 1);
+    check.check_ok = true;
+    let (_, _, inserted) = conn.insert_checks(vec![check]).unwrap();
+    conn.update_station_with_check_data(&inserted, true, &CheckConsensus::default()).unwrap();
+    let check = StationCheckItemNew::broken(stationuuid.to_string(), format!("{}-broken", stationuuid), "test".to_string(), 1);
+    let (_, _, inserted) = conn.insert_checks(vec![check]).unwrap();
+    conn.update_station_with_check_data(&inserted, true, &CheckConsensus::default()).unwrap();
+}
+
+#[test]
+fn hmac_sha256_rfc4231() {
+    assert_eq!(
+        hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+    assert_eq!(
+        hex(&hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First")),
+        "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+    );
+}
+
+#[test]
+fn deleted_stations_are_delivered() {
+    let conn = sample_db();
+    let (url, server) = serve(vec![500, 200]);
+    let webhook = new_webhook("alice", &url, "top-secret", "station_deleted", "", "rock", "de").unwrap();
+    conn.add_webhook(&webhook).unwrap();
+    break_station(&conn, "s-rock");
+    break_station(&conn, "s-news");
+
+    conn.advance_clock((30 * DAY + 1) as i64);
+    do_cleanup(true, conn.clone(), DAY, 3 * DAY, 30 * DAY, 30 * DAY, 3 * DAY).unwrap();
+    assert!(conn.get_station_by_uuid("s-rock").unwrap().is_empty());
+    let due = conn.get_due_webhook_deliveries(10).unwrap();
+    assert_eq!(due.len(), 1, "only s-rock has the tag");
+    assert_eq!(due[0].event, "station_deleted");
+    assert!(due[0].payload.contains("\"stationuuid\":\"s-rock\""));
+
+    // a failed delivery waits a minute for its retry
+    let timeout = Duration::from_secs(5);
+    assert_eq!(deliver(&conn, "test", timeout, 5, true).unwrap(), 0);
+    assert!(conn.get_due_webhook_deliveries(10).unwrap().is_empty());
+    conn.advance_clock(60);
+    assert_eq!(deliver(&conn, "test", timeout, 5, true).unwrap(), 1);
+    assert!(conn.get_due_webhook_deliveries(10).unwrap().is_empty());
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests.len(), 2);
+    let expected = format!("x-radiobrowser-signature: {}", signature("top-secret", &due[0].payload));
+    assert!(requests[1].starts_with("POST /hook"));
+    assert!(requests[1].to_lowercase().contains(&expected), "{}", requests[1]);
+    assert!(requests[1].ends_with(&due[0].payload));
+
+    conn.insert_webhook_delivery(&webhook.webhookuuid, "station_deleted", "{}").unwrap();
+    conn.delete_webhook(&webhook.webhookuuid, Some("alice")).unwrap();
+    assert!(conn.get_due_webhook_deliveries(10).unwrap().is_empty(), "deliveries are removed with their webhook");
+}
+
+#[test]
+fn webhook_parameters() {
+    assert!(new_webhook("alice", "http://example.com/", "", "", "", "", "").is_err());
+    assert!(new_webhook("alice", "mailto:alice@example.com", "s", "", "", "", "").is_err());
+    assert!(new_webhook("alice", "http://example.com/", "s", "station_broken,nothing", "", "", "").is_err());
+    assert!(new_webhook("alice", "http://example.com/", "s", "", "", "", "DEU").is_err());
+    let webhook = new_webhook("alice", " https://example.com/ ", "s", "Station_Broken, station_working", "S-Rock", "", "de").unwrap();
+    assert_eq!(webhook.url, "https://example.com/");
+    assert_eq!(webhook.events, "station_broken,station_working");
+    assert_eq!(webhook.stationuuid, "s-rock");
+    assert_eq!(webhook.countrycode, "DE");
+}
+
+#[test]
+fn private_targets() {
+    for url in [
+        "http://127.0.0.1/",
+        "http://localhost:8080/",
+        "http://10.1.2.3/",
+        "http://192.168.0.1/",
+        "http://169.254.169.254/latest/meta-data/",
+        "http://100.64.0.1/",
+        "http://0.0.0.0/",
+        "http://[::1]/",
+        "http://[fd00::1]/",
+        "http://[fe80::1]/",
+        "http://[::ffff:127.0.0.1]/",
+    ] {
+        assert!(target_addresses(url, false).is_err(), "{}", url);
+    }
+    assert!(target_addresses("http://127.0.0.1/", true).is_ok());
+    assert_eq!(target_addresses("https://203.0.113.10/hook", false).unwrap()[0].to_string(), "203.0.113.10:443");
+    assert!(target_addresses("http://[2001:db8::1]:8080/", false).is_ok());
+}